@@ -6,20 +6,22 @@ use tracing::{info, info_span};
 
 use serde::Serialize;
 
-use crate::config::{Action, DeployerConfig, OutputFormat, Provider};
+use crate::config::{Action, DeployerConfig, OutputFormat};
 use crate::deployment::{
     DeploymentPackSelection, DeploymentTarget, execute_deployment_pack, resolve_deployment_pack,
 };
 use crate::error::{DeployerError, Result};
 use crate::iac::{
-    DefaultIaCCommandRunner, IaCCommandRunner, IaCTool, dry_run_commands, run_iac_destroy,
-    run_iac_plan_apply,
+    DefaultIaCCommandRunner, IAC_LOG_FILE, IaCCommandRunner, IaCTool, dry_run_commands,
+    provider_runs_iac, run_iac_apply, run_iac_destroy, run_iac_plan,
 };
+use crate::ledger::{Ledger, LedgerRecord, LedgerStatus};
 use crate::pack_introspect;
 use crate::placeholder;
 use crate::plan::{PlanContext, requirement_scope};
 use crate::providers::{ProviderArtifacts, ResolvedSecret, create_backend};
 use crate::secrets::{SecretFetchOutcome, SecretsContext};
+use crate::state_backend::BACKEND_CONFIG_FILE;
 use crate::telemetry;
 use greentic_telemetry::{TelemetryCtx, set_current_telemetry_ctx};
 use greentic_types::secrets::SecretRequirement;
@@ -27,7 +29,8 @@ use serde_json;
 use serde_yaml_bw as serde_yaml;
 
 pub async fn run(config: DeployerConfig) -> Result<()> {
-    run_with_runner(config, &DefaultIaCCommandRunner).await
+    let runner = DefaultIaCCommandRunner::new(config.iac_timeout);
+    run_with_runner(config, &runner).await
 }
 
 /// Entry point used by the CLI: builds the plan from the pack and forwards to [`run_with_plan`].
@@ -51,6 +54,38 @@ pub async fn run_with_plan(
     plan: PlanContext,
     runner: &dyn IaCCommandRunner,
 ) -> Result<()> {
+    let mut record = LedgerRecord::begin(&config, &plan);
+    let outcome = execute_plan(&config, &plan, runner, &mut record).await;
+    let (status, error) = match &outcome {
+        Ok(status) => (*status, None),
+        Err(err) => (LedgerStatus::Failed, Some(err.to_string())),
+    };
+    let deploy_dir = config.provider_output_dir();
+    record.finish(status, error, &deploy_dir);
+    let ledger = Ledger::for_config(&config);
+    if config.action == Action::Apply
+        && status == LedgerStatus::Succeeded
+        && let Err(err) = ledger.store_snapshot(&mut record, &deploy_dir, &plan)
+    {
+        tracing::warn!("failed to snapshot deployment artifacts for rollback: {err}");
+    }
+    match ledger.append(&mut record) {
+        Ok(id) => info!(
+            record = id,
+            status = status.as_str(),
+            "appended deployment ledger record"
+        ),
+        Err(err) => tracing::warn!("failed to append deployment ledger record: {err}"),
+    }
+    outcome.map(|_| ())
+}
+
+async fn execute_plan(
+    config: &DeployerConfig,
+    plan: &PlanContext,
+    runner: &dyn IaCCommandRunner,
+    record: &mut LedgerRecord,
+) -> Result<LedgerStatus> {
     let plan_summary = plan.summary();
     info!("built deployment plan: {}", plan_summary);
 
@@ -67,22 +102,25 @@ pub async fn run_with_plan(
             config.strategy
         );
     }
-    let selection = resolve_deployment_pack(&config, &plan_target)?;
+    let selection = resolve_deployment_pack(config, &plan_target)?;
     info!(
         provider = %plan_target.provider,
         strategy = %plan_target.strategy,
         pack_id = %selection.dispatch.pack_id,
         flow_id = %selection.dispatch.flow_id,
         pack_path = %selection.pack_path.display(),
+        pack_digest = %selection.pack_digest,
         origin = %selection.origin,
         candidates = ?selection.candidates,
         "resolved deployment pack"
     );
     let dispatch = &selection.dispatch;
+    record.dispatch_pack_id = Some(dispatch.pack_id.clone());
+    record.dispatch_flow_id = Some(dispatch.flow_id.clone());
 
     let deploy_dir = config.provider_output_dir();
     fs::create_dir_all(&deploy_dir)?;
-    let runtime_artifacts = persist_runtime_artifacts(&config, &plan, &selection, &deploy_dir)?;
+    let runtime_artifacts = persist_runtime_artifacts(config, plan, &selection, &deploy_dir)?;
     info!(
         plan_path = %runtime_artifacts.plan.display(),
         invoke_path = %runtime_artifacts.invoke.display(),
@@ -102,100 +140,128 @@ pub async fn run_with_plan(
             flow_id = %selection.dispatch.flow_id,
             "placeholder pack emitted deterministic artifacts"
         );
-        return Ok(());
+        return Ok(LedgerStatus::Succeeded);
     }
 
-    if execute_deployment_pack(&config, &plan, dispatch).await? {
+    if execute_deployment_pack(config, plan, dispatch).await? {
         info!("deployment plan executed via deployment pack; skipping legacy provider backend");
-        return Ok(());
+        return Ok(LedgerStatus::Succeeded);
     }
 
-    let backend = create_backend(config.provider, &config, &plan)?;
+    let backend = create_backend(config.provider, config, plan)?;
     let artifacts = backend.plan().await?;
-    write_artifacts(&config, &artifacts)?;
+    write_artifacts(config, &artifacts)?;
     let has_iac_files = !artifacts.files.is_empty();
-    let runs_iac = has_iac_files
-        && matches!(
-            config.provider,
-            Provider::Aws | Provider::Azure | Provider::Gcp
-        );
+    let runs_iac = has_iac_files && provider_runs_iac(config.provider.as_str());
 
-    let render_text = config.action != Action::Plan || matches!(config.output, OutputFormat::Text);
+    let render_text = !config.quiet
+        && (config.action != Action::Plan || matches!(config.output, OutputFormat::Text));
     if render_text {
         println!("{}", plan.summary());
         println!("Artifacts stored under {}", deploy_dir.display());
     }
 
-    let secrets_client = SecretsContext::discover(&config).await?;
+    let secrets_client = SecretsContext::discover(config).await?;
 
     match config.action {
         Action::Plan => {
-            render_plan_output(&config, &plan)?;
+            if !config.quiet {
+                render_plan_output(config, plan)?;
+            }
             if config.preview {
-                println!("Preview mode: nothing was applied.");
+                if !config.quiet {
+                    println!("Preview mode: nothing was applied.");
+                }
+                return Ok(LedgerStatus::Preview);
             }
-            Ok(())
+            Ok(LedgerStatus::Succeeded)
         }
         Action::Apply => {
             if config.preview {
-                println!("Preview mode: skipping apply.");
-                return Ok(());
+                if !config.quiet {
+                    println!("Preview mode: skipping apply.");
+                }
+                return Ok(LedgerStatus::Preview);
             }
             if config.dry_run {
-                print_dry_run_commands(config.iac_tool, false, &deploy_dir);
-                return Ok(());
+                if !config.quiet {
+                    print_dry_run_commands(config.iac_tool, false, &deploy_dir);
+                }
+                return Ok(LedgerStatus::DryRun);
             }
-            if config.yes || confirm_or_cancel("apply")? {
-                let span = stage_span("apply", &config);
-                let _enter = span.enter();
-                install_telemetry_context("apply", &config);
-                let resolved =
-                    resolve_secrets(&secrets_client, &plan.secrets, &plan, &config).await?;
-                secrets_client.push_to_provider(&resolved).await?;
-                backend.apply(&artifacts, &resolved).await?;
-                if runs_iac {
-                    run_iac_plan_apply(runner, config.iac_tool, &deploy_dir)?;
-                } else {
-                    info!(
-                        "Skipping IaC apply for provider={} (no IaC-capable artifacts)",
-                        config.provider.as_str()
-                    );
+            let span = stage_span("apply", config);
+            let _enter = span.enter();
+            install_telemetry_context("apply", config);
+            if runs_iac {
+                let changes = run_iac_plan(runner, config.iac_tool, &deploy_dir)?;
+                if !config.quiet {
+                    print!("{}", changes.render(config.output)?);
+                }
+                if changes.is_destructive() && !config.allow_destroy {
+                    return Err(DeployerError::DestructivePlan {
+                        deletes: changes.deletes.len(),
+                        replaces: changes.replaces.len(),
+                    });
+                }
+            }
+            if !(config.yes || confirm_or_cancel("apply")?) {
+                return Ok(LedgerStatus::Cancelled);
+            }
+            let resolved = resolve_secrets(&secrets_client, &plan.secrets, plan, config).await?;
+            secrets_client.push_to_provider(&resolved).await?;
+            backend.apply(&artifacts, &resolved).await?;
+            if runs_iac {
+                run_iac_apply(runner, config.iac_tool, &deploy_dir)?;
+                if !config.quiet {
+                    print_step_timings(runner, &deploy_dir);
                 }
+            } else {
+                info!(
+                    "Skipping IaC apply for provider={} (no IaC-capable artifacts)",
+                    config.provider.as_str()
+                );
             }
-            Ok(())
+            Ok(LedgerStatus::Succeeded)
         }
         Action::Destroy => {
             if config.preview {
-                println!("Preview mode: skipping destroy.");
-                return Ok(());
+                if !config.quiet {
+                    println!("Preview mode: skipping destroy.");
+                }
+                return Ok(LedgerStatus::Preview);
             }
             if config.dry_run {
-                print_dry_run_commands(config.iac_tool, true, &deploy_dir);
-                return Ok(());
+                if !config.quiet {
+                    print_dry_run_commands(config.iac_tool, true, &deploy_dir);
+                }
+                return Ok(LedgerStatus::DryRun);
             }
-            if config.yes || confirm_or_cancel("destroy")? {
-                let span = stage_span("destroy", &config);
-                let _enter = span.enter();
-                install_telemetry_context("destroy", &config);
-                let resolved =
-                    resolve_secrets(&secrets_client, &plan.secrets, &plan, &config).await?;
-                secrets_client.push_to_provider(&resolved).await?;
-                backend.destroy(&artifacts, &resolved).await?;
-                if runs_iac {
-                    run_iac_destroy(runner, config.iac_tool, &deploy_dir)?;
-                } else {
-                    info!(
-                        "Skipping IaC destroy for provider={} (no IaC-capable artifacts)",
-                        config.provider.as_str()
-                    );
+            if !(config.yes || confirm_or_cancel("destroy")?) {
+                return Ok(LedgerStatus::Cancelled);
+            }
+            let span = stage_span("destroy", config);
+            let _enter = span.enter();
+            install_telemetry_context("destroy", config);
+            let resolved = resolve_secrets(&secrets_client, &plan.secrets, plan, config).await?;
+            secrets_client.push_to_provider(&resolved).await?;
+            backend.destroy(&artifacts, &resolved).await?;
+            if runs_iac {
+                run_iac_destroy(runner, config.iac_tool, &deploy_dir)?;
+                if !config.quiet {
+                    print_step_timings(runner, &deploy_dir);
                 }
+            } else {
+                info!(
+                    "Skipping IaC destroy for provider={} (no IaC-capable artifacts)",
+                    config.provider.as_str()
+                );
             }
-            Ok(())
+            Ok(LedgerStatus::Succeeded)
         }
     }
 }
 
-fn confirm_or_cancel(action: &str) -> Result<bool> {
+pub(crate) fn confirm_or_cancel(action: &str) -> Result<bool> {
     print!("Confirm {}? [y/N]: ", action);
     io::stdout().flush()?;
     let mut buf = String::new();
@@ -220,6 +286,16 @@ fn write_artifacts(config: &DeployerConfig, artifacts: &ProviderArtifacts) -> Re
         }
         fs::write(&target, &file.contents)?;
     }
+    // A backend config left by an earlier remote-state run would still be passed to `init`.
+    let backend_config = base.join(BACKEND_CONFIG_FILE);
+    if backend_config.is_file()
+        && !artifacts
+            .files
+            .iter()
+            .any(|file| file.relative_path == Path::new(BACKEND_CONFIG_FILE))
+    {
+        fs::remove_file(backend_config)?;
+    }
 
     Ok(())
 }
@@ -235,13 +311,7 @@ fn persist_runtime_artifacts(
     selection: &DeploymentPackSelection,
     deploy_dir: &Path,
 ) -> Result<RuntimeArtifacts> {
-    let runtime_dir = config
-        .greentic
-        .paths
-        .state_dir
-        .join("runtime")
-        .join(&config.tenant)
-        .join(&config.environment);
+    let runtime_dir = config.runtime_dir();
     fs::create_dir_all(&runtime_dir)?;
 
     let plan_path = runtime_dir.join("plan.json");
@@ -258,6 +328,7 @@ fn persist_runtime_artifacts(
         pack_id: selection.dispatch.pack_id.clone(),
         flow_id: selection.dispatch.flow_id.clone(),
         pack_path: selection.pack_path.display().to_string(),
+        pack_digest: selection.pack_digest.clone(),
     };
     let invoke_path = runtime_dir.join("invoke.json");
     let invoke_file = fs::File::create(&invoke_path)?;
@@ -282,6 +353,7 @@ struct RuntimeInvocation {
     pack_id: String,
     flow_id: String,
     pack_path: String,
+    pack_digest: String,
 }
 
 #[derive(Serialize)]
@@ -357,7 +429,7 @@ fn write_runner_diagnostics(
     Ok(())
 }
 
-async fn resolve_secrets(
+pub(crate) async fn resolve_secrets(
     client: &SecretsContext,
     specs: &[SecretRequirement],
     plan: &PlanContext,
@@ -463,13 +535,29 @@ fn install_telemetry_context(stage: &str, config: &DeployerConfig) {
     set_current_telemetry_ctx(ctx);
 }
 
-fn print_dry_run_commands(tool: IaCTool, destroy: bool, deploy_dir: &Path) {
+pub(crate) fn print_step_timings(runner: &dyn IaCCommandRunner, deploy_dir: &Path) {
+    let timings = runner.timings();
+    if timings.is_empty() {
+        return;
+    }
+    let steps: Vec<String> = timings
+        .iter()
+        .map(|timing| format!("{} {:.1}s", timing.step, timing.elapsed.as_secs_f64()))
+        .collect();
+    println!(
+        "IaC step timings: {} (log: {})",
+        steps.join(", "),
+        deploy_dir.join(IAC_LOG_FILE).display()
+    );
+}
+
+pub(crate) fn print_dry_run_commands(tool: IaCTool, destroy: bool, deploy_dir: &Path) {
     println!(
         "Dry run: IaC commands for {} would execute inside {}",
         tool,
         deploy_dir.display()
     );
-    for command in dry_run_commands(destroy) {
+    for command in dry_run_commands(destroy, deploy_dir) {
         println!("{} {}", tool.binary_name(), command.join(" "));
     }
 }
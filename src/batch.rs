@@ -0,0 +1,492 @@
+//! Batch deployments across several tenant/environment targets.
+//!
+//! Targets come from a manifest or from `--tenant a,b,c` (optionally crossed with
+//! `--environment`). Each target builds its own plan and goes through [`apply::run_with_plan`],
+//! so secrets preflight, artifacts and ledger records stay per scope. A failing target never
+//! stops the others; the aggregated report lists every outcome and is the only thing written to
+//! stdout (targets run with [`DeployerConfig::quiet`] set).
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, Once};
+use std::time::Instant;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::apply;
+use crate::config::{Action, BatchArgs, DeployerConfig, OutputFormat, Provider};
+use crate::error::{DeployerError, Result};
+use crate::iac::IaCCommandRunner;
+use crate::ledger::{Ledger, LedgerStatus};
+use crate::pack_introspect;
+use crate::telemetry;
+
+/// One deployment target of a batch.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct BatchTarget {
+    pub tenant: String,
+    /// Environment name (defaults to greentic-config environment).
+    #[serde(default)]
+    pub environment: Option<String>,
+    #[serde(deserialize_with = "deserialize_provider")]
+    pub provider: Provider,
+    #[serde(default)]
+    pub strategy: Option<String>,
+    #[serde(default)]
+    pub pack: Option<PathBuf>,
+}
+
+/// Batch manifest (`--manifest <file>`, YAML or JSON).
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BatchManifest {
+    /// Pack used by targets that do not name their own.
+    #[serde(default)]
+    pub pack: Option<PathBuf>,
+    /// Strategy used by targets that do not name their own.
+    #[serde(default)]
+    pub strategy: Option<String>,
+    pub targets: Vec<BatchTarget>,
+}
+
+impl BatchManifest {
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = fs::read_to_string(path).map_err(|err| {
+            DeployerError::Config(format!(
+                "failed to read batch manifest {}: {err}",
+                path.display()
+            ))
+        })?;
+        serde_yaml_bw::from_str(&data).map_err(|err| {
+            DeployerError::Config(format!("invalid batch manifest {}: {err}", path.display()))
+        })
+    }
+}
+
+/// Accept the CLI spelling (`aws`, `k8s`, ...) for manifest providers.
+fn deserialize_provider<'de, D>(deserializer: D) -> std::result::Result<Provider, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    Provider::from_str(&value, true).map_err(serde::de::Error::custom)
+}
+
+/// Targets for a batch run, with environment, strategy and pack defaults filled in.
+///
+/// Targets without an environment get `default_environment` (the greentic-config environment).
+pub fn resolve_targets(args: &BatchArgs, default_environment: &str) -> Result<Vec<BatchTarget>> {
+    if matches!(args.action, Action::Apply | Action::Destroy)
+        && !(args.yes || args.dry_run || args.preview)
+    {
+        return Err(DeployerError::Config(format!(
+            "batch {} requires --yes (confirmations cannot be answered for concurrent targets)",
+            args.action.as_str()
+        )));
+    }
+
+    let manifest = match &args.manifest {
+        Some(path) => BatchManifest::load(path)?,
+        None => {
+            let provider = args.provider.ok_or_else(|| {
+                DeployerError::Config("--provider is required with --tenant".into())
+            })?;
+            let environments = if args.environments.is_empty() {
+                vec![None]
+            } else {
+                args.environments.iter().cloned().map(Some).collect()
+            };
+            let targets = args
+                .tenants
+                .iter()
+                .flat_map(|tenant| {
+                    environments.iter().map(move |environment| BatchTarget {
+                        tenant: tenant.clone(),
+                        environment: environment.clone(),
+                        provider,
+                        strategy: None,
+                        pack: None,
+                    })
+                })
+                .collect();
+            BatchManifest {
+                targets,
+                ..BatchManifest::default()
+            }
+        }
+    };
+    let default_pack = manifest.pack.or_else(|| args.pack.clone());
+    let default_strategy = manifest.strategy.unwrap_or_else(|| args.strategy.clone());
+
+    let mut seen = BTreeSet::new();
+    let mut targets = Vec::with_capacity(manifest.targets.len());
+    for mut target in manifest.targets {
+        if target.tenant.trim().is_empty() {
+            return Err(DeployerError::Config(
+                "batch target has an empty tenant".into(),
+            ));
+        }
+        let environment = target
+            .environment
+            .get_or_insert_with(|| default_environment.to_string());
+        // Targets sharing a tenant/environment would race on the same ledger.
+        if !seen.insert((target.tenant.clone(), environment.clone())) {
+            return Err(DeployerError::Config(format!(
+                "batch lists {} more than once",
+                target_label(&target)
+            )));
+        }
+        target
+            .strategy
+            .get_or_insert_with(|| default_strategy.clone());
+        if target.pack.is_none() {
+            target.pack = default_pack.clone();
+        }
+        if target.pack.is_none() {
+            return Err(DeployerError::Config(format!(
+                "no pack for {} (pass --pack or set `pack` in the manifest)",
+                target_label(&target)
+            )));
+        }
+        targets.push(target);
+    }
+    if targets.is_empty() {
+        return Err(DeployerError::Config("batch has no targets".into()));
+    }
+    Ok(targets)
+}
+
+fn target_label(target: &BatchTarget) -> String {
+    format!(
+        "{}/{}",
+        target.tenant,
+        target.environment.as_deref().unwrap_or("<default>")
+    )
+}
+
+/// Outcome of a single target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    Succeeded,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchTargetReport {
+    pub tenant: String,
+    pub environment: Option<String>,
+    pub provider: String,
+    pub strategy: String,
+    pub status: BatchStatus,
+    /// Ledger record appended for this target (absent when the run failed before planning).
+    pub ledger_record: Option<u64>,
+    pub ledger_status: Option<LedgerStatus>,
+    pub error: Option<String>,
+    pub elapsed_ms: u64,
+}
+
+/// Aggregated result of `greentic-deployer batch`.
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchReport {
+    pub action: String,
+    pub concurrency: usize,
+    pub targets: Vec<BatchTargetReport>,
+}
+
+impl BatchReport {
+    pub fn succeeded(&self) -> usize {
+        self.targets
+            .iter()
+            .filter(|target| target.status == BatchStatus::Succeeded)
+            .count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.targets.len() - self.succeeded()
+    }
+
+    pub fn has_failures(&self) -> bool {
+        self.failed() > 0
+    }
+}
+
+/// Run every target with at most `concurrency` targets in flight.
+///
+/// `make_config` turns a target into its deployer configuration and `make_runner` supplies the
+/// IaC runner for it; both run on the worker thread handling the target.
+pub fn run_batch<C, R>(
+    action: Action,
+    targets: Vec<BatchTarget>,
+    concurrency: usize,
+    make_config: C,
+    make_runner: R,
+) -> BatchReport
+where
+    C: Fn(&BatchTarget) -> Result<DeployerConfig> + Sync,
+    R: Fn(&DeployerConfig) -> Box<dyn IaCCommandRunner> + Sync,
+{
+    let concurrency = concurrency.clamp(1, targets.len().max(1));
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<BatchTargetReport>>> = Mutex::new(vec![None; targets.len()]);
+    let telemetry_init = Once::new();
+
+    std::thread::scope(|scope| {
+        for _ in 0..concurrency {
+            scope.spawn(|| {
+                loop {
+                    let idx = next.fetch_add(1, Ordering::SeqCst);
+                    let Some(target) = targets.get(idx) else {
+                        break;
+                    };
+                    let report = run_target(target, &make_config, &make_runner, &telemetry_init);
+                    results.lock().expect("batch results lock poisoned")[idx] = Some(report);
+                }
+            });
+        }
+    });
+
+    BatchReport {
+        action: action.as_str().to_string(),
+        concurrency,
+        targets: results
+            .into_inner()
+            .expect("batch results lock poisoned")
+            .into_iter()
+            .flatten()
+            .collect(),
+    }
+}
+
+fn run_target<C, R>(
+    target: &BatchTarget,
+    make_config: &C,
+    make_runner: &R,
+    telemetry_init: &Once,
+) -> BatchTargetReport
+where
+    C: Fn(&BatchTarget) -> Result<DeployerConfig>,
+    R: Fn(&DeployerConfig) -> Box<dyn IaCCommandRunner>,
+{
+    let started = Instant::now();
+    let mut report = BatchTargetReport {
+        tenant: target.tenant.clone(),
+        environment: target.environment.clone(),
+        provider: target.provider.as_str().to_string(),
+        strategy: target.strategy.clone().unwrap_or_default(),
+        status: BatchStatus::Failed,
+        ledger_record: None,
+        ledger_status: None,
+        error: None,
+        elapsed_ms: 0,
+    };
+
+    let outcome = make_config(target).and_then(|mut config| {
+        config.quiet = true;
+        report.environment = Some(config.environment.clone());
+        telemetry_init.call_once(|| {
+            if let Err(err) = telemetry::init(&config) {
+                tracing::warn!("failed to initialise telemetry for batch: {err}");
+            }
+        });
+        let ledger = Ledger::for_config(&config);
+        let runner = make_runner(&config);
+        let plan = pack_introspect::build_plan(&config)?;
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let outcome = runtime.block_on(apply::run_with_plan(config, plan, runner.as_ref()));
+        if let Ok(records) = ledger.records()
+            && let Some(record) = records.last()
+        {
+            report.ledger_record = Some(record.id);
+            report.ledger_status = Some(record.status);
+        }
+        outcome
+    });
+
+    match outcome {
+        Ok(()) => report.status = BatchStatus::Succeeded,
+        Err(err) => report.error = Some(err.to_string()),
+    }
+    report.elapsed_ms = started.elapsed().as_millis() as u64;
+    report
+}
+
+pub fn render_batch_report(report: &BatchReport, format: OutputFormat) -> Result<String> {
+    match format {
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(report)?),
+        OutputFormat::Yaml => {
+            serde_yaml_bw::to_string(report).map_err(|err| DeployerError::Other(err.to_string()))
+        }
+        OutputFormat::Text => {
+            let mut out = format!(
+                "Batch {}: {} target(s), {} succeeded, {} failed\n",
+                report.action,
+                report.targets.len(),
+                report.succeeded(),
+                report.failed()
+            );
+            for target in &report.targets {
+                let status = match target.status {
+                    BatchStatus::Succeeded => "ok",
+                    BatchStatus::Failed => "FAILED",
+                };
+                out.push_str(&format!(
+                    "  {status:<6} {}/{} {}/{}",
+                    target.tenant,
+                    target.environment.as_deref().unwrap_or("<default>"),
+                    target.provider,
+                    target.strategy
+                ));
+                if let (Some(id), Some(ledger_status)) =
+                    (target.ledger_record, target.ledger_status)
+                {
+                    out.push_str(&format!(" record #{id} ({})", ledger_status.as_str()));
+                }
+                out.push_str(&format!(" {:.1}s\n", target.elapsed_ms as f64 / 1000.0));
+                if let Some(error) = &target.error {
+                    out.push_str(&format!("         error: {error}\n"));
+                }
+            }
+            Ok(out)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use tempfile::tempdir;
+
+    use crate::config::{CliArgs, Command};
+    use crate::iac::DefaultIaCCommandRunner;
+
+    fn batch_args(args: &[&str]) -> BatchArgs {
+        let mut argv = vec!["greentic-deployer", "batch"];
+        argv.extend_from_slice(args);
+        match CliArgs::parse_from(argv).command {
+            Command::Batch(args) => args,
+            other => panic!("unexpected command {other:?}"),
+        }
+    }
+
+    #[test]
+    fn expands_tenants_across_environments() {
+        let args = batch_args(&[
+            "--tenant",
+            "acme,beta",
+            "--environment",
+            "dev,prod",
+            "--provider",
+            "aws",
+            "--pack",
+            "app.gtpack",
+        ]);
+        let targets = resolve_targets(&args, "dev").unwrap();
+        let labels: Vec<_> = targets.iter().map(target_label).collect();
+        assert_eq!(labels, ["acme/dev", "acme/prod", "beta/dev", "beta/prod"]);
+        assert!(
+            targets
+                .iter()
+                .all(|t| t.strategy.as_deref() == Some("iac-only"))
+        );
+    }
+
+    #[test]
+    fn loads_manifest_with_defaults_and_rejects_duplicates() {
+        let dir = tempdir().unwrap();
+        let manifest = dir.path().join("batch.yaml");
+        fs::write(
+            &manifest,
+            "pack: app.gtpack\ntargets:\n  - tenant: acme\n    environment: staging\n    provider: aws\n  - tenant: beta\n    provider: k8s\n    strategy: helm\n    pack: beta.gtpack\n",
+        )
+        .unwrap();
+        let args = batch_args(&["--manifest", manifest.to_str().unwrap()]);
+        let targets = resolve_targets(&args, "dev").unwrap();
+        assert_eq!(targets[0].pack, Some(PathBuf::from("app.gtpack")));
+        assert_eq!(targets[0].strategy.as_deref(), Some("iac-only"));
+        assert_eq!(targets[1].provider, Provider::K8s);
+        assert_eq!(targets[1].strategy.as_deref(), Some("helm"));
+        assert_eq!(targets[1].pack, Some(PathBuf::from("beta.gtpack")));
+
+        fs::write(
+            &manifest,
+            "pack: app.gtpack\ntargets:\n  - {tenant: acme, provider: aws}\n  - {tenant: acme, provider: gcp}\n",
+        )
+        .unwrap();
+        let err = resolve_targets(&args, "dev").unwrap_err();
+        assert!(err.to_string().contains("more than once"));
+
+        // A target without an environment runs in the default one.
+        fs::write(
+            &manifest,
+            "pack: app.gtpack\ntargets:\n  - {tenant: acme, provider: aws}\n  - {tenant: acme, environment: dev, provider: aws}\n",
+        )
+        .unwrap();
+        assert!(resolve_targets(&args, "staging").is_ok());
+        let err = resolve_targets(&args, "dev").unwrap_err();
+        assert!(err.to_string().contains("acme/dev more than once"), "{err}");
+    }
+
+    #[test]
+    fn batch_apply_requires_yes() {
+        let args = batch_args(&[
+            "--action",
+            "apply",
+            "--tenant",
+            "acme",
+            "--provider",
+            "aws",
+            "--pack",
+            "app.gtpack",
+        ]);
+        let err = resolve_targets(&args, "dev").unwrap_err();
+        assert!(err.to_string().contains("requires --yes"));
+    }
+
+    #[test]
+    fn isolates_failures_per_target() {
+        let targets: Vec<_> = ["acme", "beta", "gamma"]
+            .into_iter()
+            .map(|tenant| BatchTarget {
+                tenant: tenant.into(),
+                environment: Some("dev".into()),
+                provider: Provider::Aws,
+                strategy: Some("iac-only".into()),
+                pack: Some(PathBuf::from("missing.gtpack")),
+            })
+            .collect();
+        let report = run_batch(
+            Action::Plan,
+            targets,
+            2,
+            |target| {
+                Err(DeployerError::Config(format!(
+                    "no config for {}",
+                    target.tenant
+                )))
+            },
+            |config| Box::new(DefaultIaCCommandRunner::new(config.iac_timeout)),
+        );
+        assert_eq!(report.concurrency, 2);
+        assert_eq!(report.failed(), 3);
+        let tenants: Vec<_> = report.targets.iter().map(|t| t.tenant.as_str()).collect();
+        assert_eq!(tenants, ["acme", "beta", "gamma"]);
+        assert_eq!(
+            report.targets[1].error.as_deref(),
+            Some("configuration error: no config for beta")
+        );
+
+        let text = render_batch_report(&report, OutputFormat::Text).unwrap();
+        assert!(text.starts_with("Batch plan: 3 target(s), 0 succeeded, 3 failed"));
+        assert!(text.contains("FAILED acme/dev aws/iac-only"));
+        let json: serde_json::Value =
+            serde_json::from_str(&render_batch_report(&report, OutputFormat::Json).unwrap())
+                .unwrap();
+        assert_eq!(json["targets"][2]["status"], "failed");
+    }
+}
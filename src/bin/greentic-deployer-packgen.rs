@@ -6,6 +6,8 @@ use std::{
 
 use anyhow::{Context, Result, anyhow};
 use clap::{Args, Parser, Subcommand, ValueHint};
+use greentic_deployer::bootstrap::network::{NetAllowList, NetworkPolicy};
+use greentic_deployer::platform::oci::{docker_config_path, parse_oci_reference, push_oci_pack};
 use serde_json::json;
 
 #[derive(Parser, Debug)]
@@ -58,6 +60,16 @@ struct GenerateArgs {
         default_value = "oci://ghcr.io/greentic-ai/validators/deployer:latest"
     )]
     validator_pack: String,
+
+    /// Push the built .gtpack to this OCI reference (`oci://<host>/<repo>:<tag>`) using the
+    /// Docker config credentials.
+    #[arg(long)]
+    push: Option<String>,
+
+    /// Extra hosts (e.g. the registry's token service) allowed for `--push` besides the registry
+    /// itself.
+    #[arg(long, requires = "push")]
+    net_allowlist: Option<String>,
 }
 
 fn main() -> Result<()> {
@@ -90,6 +102,10 @@ impl Packgen {
 
         self.execute_commands(&context.late_commands())?;
 
+        if let Some(reference) = &self.args.push {
+            self.push(&context.gtpack_path, reference)?;
+        }
+
         if self.args.strict && !self.args.dry_run {
             println!(
                 "strict validation requested; greentic-pack doctor already ran with --validate"
@@ -119,6 +135,25 @@ impl Packgen {
         Ok(())
     }
 
+    fn push(&self, gtpack: &Path, reference: &str) -> Result<()> {
+        if self.args.verbose || self.args.dry_run {
+            println!("> push {} {reference}", gtpack.display());
+        }
+        if self.args.dry_run {
+            return Ok(());
+        }
+        let host = parse_oci_reference(reference)?.host;
+        let allowlist = match &self.args.net_allowlist {
+            Some(extra) => format!("{host},{extra}"),
+            None => host,
+        };
+        let policy = NetworkPolicy::new(true, false, NetAllowList::parse(Some(&allowlist))?);
+        let pushed = push_oci_pack(gtpack, reference, &policy, docker_config_path().as_deref())
+            .with_context(|| format!("push {} to {reference}", gtpack.display()))?;
+        println!("pushed {} ({})", pushed.reference, pushed.manifest_digest);
+        Ok(())
+    }
+
     fn execute_commands(&self, commands: &[PackCommand]) -> Result<()> {
         for command in commands {
             if self.args.verbose || self.args.dry_run {
@@ -378,6 +413,8 @@ mod tests {
             dry_run: true,
             strict: false,
             validator_pack: "oci://ghcr.io/greentic-ai/validators/deployer:latest".into(),
+            push: None,
+            net_allowlist: None,
         }
     }
 
@@ -0,0 +1,47 @@
+//! Helpers for blocking clients called from the CLI.
+//!
+//! The CLI runs under `#[tokio::main]`, and both reqwest's blocking client and nested runtimes
+//! panic on async runtime threads. Bootstrap backends therefore run such calls on a short-lived
+//! thread of their own.
+
+use std::future::Future;
+use std::sync::OnceLock;
+use std::thread;
+
+use tokio::runtime::Runtime;
+
+use crate::error::{DeployerError, Result};
+
+/// Run `task` on a dedicated thread and wait for it.
+pub fn off_runtime<T: Send>(task: impl FnOnce() -> Result<T> + Send) -> Result<T> {
+    thread::scope(|scope| {
+        scope
+            .spawn(task)
+            .join()
+            .unwrap_or_else(|_| Err(DeployerError::Other("blocking task panicked".into())))
+    })
+}
+
+/// Drive `make_future()` to completion from synchronous code.
+///
+/// Futures run on one process-wide background runtime, so async clients created by an earlier
+/// call (connection pools, secret resolvers) stay usable in later ones.
+pub fn block_on<T: Send, F: Future<Output = Result<T>>>(
+    make_future: impl FnOnce() -> F + Send,
+) -> Result<T> {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    off_runtime(|| {
+        let runtime = match RUNTIME.get() {
+            Some(runtime) => runtime,
+            None => {
+                let runtime = tokio::runtime::Builder::new_multi_thread()
+                    .worker_threads(1)
+                    .thread_name("greentic-bootstrap-io")
+                    .enable_all()
+                    .build()?;
+                RUNTIME.get_or_init(|| runtime)
+            }
+        };
+        runtime.block_on(make_future())
+    })
+}
@@ -1,8 +1,9 @@
 use crate::bootstrap::interaction::{InteractionAdapterKind, InteractionPolicy, adapters_for_mode};
 use crate::bootstrap::network::NetworkPolicy;
 use crate::config::InteractionMode;
+use serde::Serialize;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct HostCapabilities {
     pub adapters: Vec<InteractionAdapterKind>,
     pub no_listeners: bool,
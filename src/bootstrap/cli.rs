@@ -1,50 +1,91 @@
 use std::io::{BufRead, Write};
 
-use crate::bootstrap::flow_runner::{PromptAdapter, Question};
+use crate::bootstrap::flow_runner::{PromptAdapter, Question, QuestionKind};
+use crate::bootstrap::questions::collect_answers;
+use crate::bootstrap::template::Answers;
 use crate::error::{DeployerError, Result};
 
 pub struct CliPromptAdapter<R: BufRead, W: Write> {
     input: R,
     output: W,
+    mask_secrets: bool,
 }
 
 impl<R: BufRead, W: Write> CliPromptAdapter<R, W> {
     pub fn new(input: R, output: W) -> Self {
-        Self { input, output }
+        Self {
+            input,
+            output,
+            mask_secrets: false,
+        }
+    }
+
+    /// Read `secret` answers from the terminal without echo (via rpassword) instead of `input`.
+    pub fn mask_secrets(mut self, mask: bool) -> Self {
+        self.mask_secrets = mask;
+        self
     }
+
+    fn read_answer(&mut self, q: &Question, prompt: &str) -> Result<Option<String>> {
+        if self.mask_secrets && q.kind == QuestionKind::Secret {
+            return Ok(Some(rpassword::prompt_password(prompt)?));
+        }
+        write!(self.output, "{prompt}")?;
+        self.output.flush()?;
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(line.trim().to_string()))
+    }
+}
+
+fn render_prompt(q: &Question) -> String {
+    let hint = match q.kind {
+        QuestionKind::Bool => " (y/n)".to_string(),
+        QuestionKind::Enum => format!(" ({})", q.options.join("/")),
+        _ => String::new(),
+    };
+    let default = match (&q.default, q.kind) {
+        (_, QuestionKind::Secret) | (None, _) => String::new(),
+        (Some(serde_json::Value::String(d)), _) => format!(" [default: {d}]"),
+        (Some(d), _) => format!(" [default: {d}]"),
+    };
+    let optional = if q.required { "" } else { " (optional)" };
+    format!("{}{hint}{optional}{default}: ", q.prompt)
 }
 
 impl<R: BufRead, W: Write> PromptAdapter for CliPromptAdapter<R, W> {
-    fn ask(&mut self, questions: &[Question]) -> Result<serde_json::Value> {
-        let mut answers = serde_json::Map::new();
+    fn ask(&mut self, questions: &[Question], answers: &Answers) -> Result<serde_json::Value> {
+        let mut context = answers.clone();
+        let mut provided = serde_json::Map::new();
         for q in questions {
-            let prompt = format!(
-                "{}{}: ",
-                q.prompt,
-                q.default
-                    .as_ref()
-                    .map(|d| format!(" [default: {d}]"))
-                    .unwrap_or_default()
-            );
-            write!(self.output, "{prompt}")?;
-            self.output.flush()?;
-            let mut line = String::new();
-            self.input.read_line(&mut line)?;
-            let answer = line.trim().to_string();
-            if answer.is_empty() {
-                if let Some(default) = &q.default {
-                    answers.insert(q.id.clone(), serde_json::Value::String(default.clone()));
-                    continue;
-                } else {
+            if !q.is_active(&context) {
+                continue;
+            }
+            if let Some(help) = &q.help {
+                writeln!(self.output, "{help}")?;
+            }
+            let prompt = render_prompt(q);
+            // Invalid answers are explained and asked again until input runs out.
+            let value = loop {
+                let Some(answer) = self.read_answer(q, &prompt)? else {
                     return Err(DeployerError::Other(format!(
                         "no input provided for {}",
                         q.id
                     )));
+                };
+                match q.accept(Some(&serde_json::Value::String(answer))) {
+                    Ok(value) => break value,
+                    Err(err) => writeln!(self.output, "{err}")?,
                 }
+            };
+            if let Some(value) = value {
+                context.insert(q.id.clone(), value.clone());
+                provided.insert(q.id.clone(), value);
             }
-            answers.insert(q.id.clone(), serde_json::Value::String(answer));
         }
-        Ok(serde_json::Value::Object(answers))
+        Ok(serde_json::Value::Object(provided))
     }
 }
 
@@ -52,7 +93,7 @@ impl<R: BufRead, W: Write> PromptAdapter for CliPromptAdapter<R, W> {
 pub struct DenyPromptAdapter;
 
 impl PromptAdapter for DenyPromptAdapter {
-    fn ask(&mut self, _questions: &[Question]) -> Result<serde_json::Value> {
+    fn ask(&mut self, _questions: &[Question], _answers: &Answers) -> Result<serde_json::Value> {
         Err(DeployerError::Other(
             "interactive prompts are disabled by policy".into(),
         ))
@@ -75,23 +116,8 @@ impl JsonPromptAdapter {
 }
 
 impl PromptAdapter for JsonPromptAdapter {
-    fn ask(&mut self, questions: &[Question]) -> Result<serde_json::Value> {
-        let mut provided = serde_json::Map::new();
-        for q in questions {
-            let value = self
-                .answers
-                .get(&q.id)
-                .cloned()
-                .or_else(|| {
-                    q.default
-                        .as_ref()
-                        .map(|d| serde_json::Value::String(d.clone()))
-                })
-                .ok_or_else(|| {
-                    DeployerError::Config(format!("missing answer for question '{}'", q.id))
-                })?;
-            provided.insert(q.id.clone(), value);
-        }
-        Ok(serde_json::Value::Object(provided))
+    fn ask(&mut self, questions: &[Question], answers: &Answers) -> Result<serde_json::Value> {
+        let provided = serde_json::Value::Object(self.answers.clone());
+        collect_answers(questions, &provided, answers).map(serde_json::Value::Object)
     }
 }
@@ -1,7 +1,18 @@
+//! Applying installer-emitted config patches to the platform config file.
+//!
+//! A patch object is an RFC 7396 JSON merge patch and a patch array an RFC 6902 JSON Patch; both
+//! are applied to the existing file, so keys the patch does not mention are left alone. The file
+//! format follows the extension (`.toml`, `.yaml`/`.yml`, otherwise JSON). Rewriting a TOML or
+//! YAML file does not keep its comments.
+//!
+//! Before writing, [`plan_config_patch`] lists the resulting changes and, given the config as the
+//! deployer last wrote it, the changes that would overwrite keys an operator edited since.
+
+use std::fmt;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use serde_json::Value;
+use serde_json::{Map, Value};
 
 use crate::error::{DeployerError, Result};
 
@@ -34,17 +45,395 @@ pub fn snapshot_config(path: &Path) -> Result<ConfigSnapshot> {
     }
 }
 
-/// Apply a config patch by writing it to disk as JSON (no merge yet).
-pub fn apply_config_patch(path: &Path, patch: &Value) -> Result<()> {
-    if let Some(parent) = path.parent() {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl ConfigFormat {
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => ConfigFormat::Toml,
+            Some(ext) if ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml") => {
+                ConfigFormat::Yaml
+            }
+            _ => ConfigFormat::Json,
+        }
+    }
+
+    fn parse(self, content: &str) -> std::result::Result<Value, String> {
+        match self {
+            ConfigFormat::Json => serde_json::from_str(content).map_err(|err| err.to_string()),
+            ConfigFormat::Toml => toml::from_str(content).map_err(|err| err.to_string()),
+            ConfigFormat::Yaml => serde_yaml_bw::from_str(content).map_err(|err| err.to_string()),
+        }
+    }
+
+    fn render(self, document: &Value) -> std::result::Result<String, String> {
+        match self {
+            ConfigFormat::Json => {
+                serde_json::to_string_pretty(document).map_err(|err| err.to_string())
+            }
+            ConfigFormat::Toml => toml::to_string_pretty(document).map_err(|err| err.to_string()),
+            ConfigFormat::Yaml => serde_yaml_bw::to_string(document).map_err(|err| err.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for ConfigFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ConfigFormat::Json => "json",
+            ConfigFormat::Toml => "toml",
+            ConfigFormat::Yaml => "yaml",
+        })
+    }
+}
+
+/// Parsed contents of a config file; `None` when it is missing or empty.
+pub fn read_config(path: &Path) -> Result<Option<Value>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(path)?;
+    if content.trim().is_empty() {
+        return Ok(None);
+    }
+    let format = ConfigFormat::for_path(path);
+    format.parse(&content).map(Some).map_err(|err| {
+        DeployerError::Config(format!(
+            "failed to parse {format} config {}: {err}",
+            path.display()
+        ))
+    })
+}
+
+/// Apply `patch` to `document`: arrays are RFC 6902 JSON Patch operations, anything else an
+/// RFC 7396 merge patch.
+pub fn patch_document(document: &Value, patch: &Value) -> Result<Value> {
+    let mut patched = document.clone();
+    match patch {
+        Value::Array(operations) => {
+            for (index, operation) in operations.iter().enumerate() {
+                apply_operation(&mut patched, operation).map_err(|err| {
+                    DeployerError::Config(format!("JSON Patch operation {index} failed: {err}"))
+                })?;
+            }
+        }
+        patch => merge_patch(&mut patched, patch),
+    }
+    Ok(patched)
+}
+
+/// RFC 7396: objects merge recursively, `null` removes a key, anything else replaces.
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let Value::Object(target) = target else {
+        unreachable!("target was just made an object");
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+/// Merge patch that turns `from` into `to`.
+pub fn diff_merge_patch(from: &Value, to: &Value) -> Value {
+    match (from, to) {
+        (Value::Object(from), Value::Object(to)) => {
+            let mut patch = Map::new();
+            for key in from.keys().filter(|key| !to.contains_key(*key)) {
+                patch.insert(key.clone(), Value::Null);
+            }
+            for (key, value) in to {
+                match from.get(key) {
+                    Some(old) if old == value => {}
+                    Some(old) => {
+                        patch.insert(key.clone(), diff_merge_patch(old, value));
+                    }
+                    None => {
+                        patch.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+            Value::Object(patch)
+        }
+        (_, to) => to.clone(),
+    }
+}
+
+fn apply_operation(document: &mut Value, operation: &Value) -> std::result::Result<(), String> {
+    let field = |name: &str| {
+        operation[name]
+            .as_str()
+            .ok_or_else(|| format!("missing '{name}'"))
+    };
+    let value = || {
+        operation
+            .get("value")
+            .cloned()
+            .ok_or_else(|| "missing 'value'".to_string())
+    };
+    let path = field("path")?;
+    match field("op")? {
+        "add" => add_at(document, path, value()?),
+        "remove" => remove_at(document, path).map(|_| ()),
+        "replace" => {
+            remove_at(document, path)?;
+            add_at(document, path, value()?)
+        }
+        "move" => {
+            let from = field("from")?;
+            if path.starts_with(&format!("{from}/")) {
+                return Err(format!("cannot move {from} into its own child {path}"));
+            }
+            let moved = remove_at(document, from)?;
+            add_at(document, path, moved)
+        }
+        "copy" => {
+            let from = field("from")?;
+            let copied = document
+                .pointer(from)
+                .cloned()
+                .ok_or_else(|| format!("{from} does not exist"))?;
+            add_at(document, path, copied)
+        }
+        "test" => match document.pointer(path) {
+            Some(current) if *current == value()? => Ok(()),
+            _ => Err(format!("test failed at {path}")),
+        },
+        other => Err(format!("unknown op '{other}'")),
+    }
+}
+
+/// Split a JSON pointer into its parent pointer and unescaped last token.
+fn split_pointer(path: &str) -> std::result::Result<(&str, String), String> {
+    let index = path
+        .rfind('/')
+        .ok_or_else(|| format!("invalid JSON pointer '{path}'"))?;
+    let token = path[index + 1..].replace("~1", "/").replace("~0", "~");
+    Ok((&path[..index], token))
+}
+
+fn add_at(document: &mut Value, path: &str, value: Value) -> std::result::Result<(), String> {
+    if path.is_empty() {
+        *document = value;
+        return Ok(());
+    }
+    let (parent, token) = split_pointer(path)?;
+    match document.pointer_mut(parent) {
+        Some(Value::Object(map)) => {
+            map.insert(token, value);
+            Ok(())
+        }
+        Some(Value::Array(items)) => {
+            let index = if token == "-" {
+                items.len()
+            } else {
+                token
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|index| *index <= items.len())
+                    .ok_or_else(|| format!("invalid array index in {path}"))?
+            };
+            items.insert(index, value);
+            Ok(())
+        }
+        _ => Err(format!("parent of {path} does not exist")),
+    }
+}
+
+fn remove_at(document: &mut Value, path: &str) -> std::result::Result<Value, String> {
+    if path.is_empty() {
+        return Ok(std::mem::take(document));
+    }
+    let (parent, token) = split_pointer(path)?;
+    let removed = match document.pointer_mut(parent) {
+        Some(Value::Object(map)) => map.remove(&token),
+        Some(Value::Array(items)) => token
+            .parse::<usize>()
+            .ok()
+            .filter(|index| *index < items.len())
+            .map(|index| items.remove(index)),
+        _ => None,
+    };
+    removed.ok_or_else(|| format!("{path} does not exist"))
+}
+
+/// One leaf-level difference between two documents, addressed by JSON pointer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigChange {
+    pub path: String,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+impl fmt::Display for ConfigChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.old, &self.new) {
+            (None, Some(new)) => write!(f, "+ {} = {new}", self.path),
+            (Some(old), None) => write!(f, "- {} (was {old})", self.path),
+            (Some(old), Some(new)) => write!(f, "~ {}: {old} -> {new}", self.path),
+            (None, None) => write!(f, "  {}", self.path),
+        }
+    }
+}
+
+/// Leaf-level changes from `before` to `after`; objects are compared key by key, anything else
+/// as a whole.
+pub fn diff_documents(before: &Value, after: &Value) -> Vec<ConfigChange> {
+    let mut changes = Vec::new();
+    collect_changes("", Some(before), Some(after), &mut changes);
+    changes
+}
+
+fn collect_changes(
+    path: &str,
+    before: Option<&Value>,
+    after: Option<&Value>,
+    changes: &mut Vec<ConfigChange>,
+) {
+    match (before, after) {
+        (Some(Value::Object(before)), Some(Value::Object(after))) => {
+            let mut keys: Vec<&String> = before.keys().chain(after.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let child = format!("{path}/{}", key.replace('~', "~0").replace('/', "~1"));
+                collect_changes(&child, before.get(key), after.get(key), changes);
+            }
+        }
+        (before, after) if before != after => changes.push(ConfigChange {
+            path: if path.is_empty() {
+                "/".into()
+            } else {
+                path.into()
+            },
+            old: before.cloned(),
+            new: after.cloned(),
+        }),
+        _ => {}
+    }
+}
+
+/// A change the patch would make to a key whose current value differs from what the deployer
+/// last wrote.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigConflict {
+    pub path: String,
+    /// Value the deployer last wrote (absent if it did not exist).
+    pub installed: Option<Value>,
+    /// Value currently in the file (the operator's edit).
+    pub current: Option<Value>,
+    /// Value the patch would write.
+    pub patched: Option<Value>,
+}
+
+impl fmt::Display for ConfigConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |value: &Option<Value>| match value {
+            Some(value) => value.to_string(),
+            None => "<unset>".into(),
+        };
+        write!(
+            f,
+            "{}: changed from {} to {} since install; patch sets {}",
+            self.path,
+            show(&self.installed),
+            show(&self.current),
+            show(&self.patched)
+        )
+    }
+}
+
+/// What applying a patch to a config file would do.
+#[derive(Debug, Clone)]
+pub struct ConfigPlan {
+    pub path: PathBuf,
+    pub format: ConfigFormat,
+    pub before: Option<Value>,
+    pub after: Value,
+    pub changes: Vec<ConfigChange>,
+    pub conflicts: Vec<ConfigConflict>,
+}
+
+/// Work out the result of applying `patch` to the config at `path` without writing it.
+/// `installed` is the config as the deployer last wrote it; without it no conflicts are reported.
+pub fn plan_config_patch(
+    path: &Path,
+    patch: &Value,
+    installed: Option<&Value>,
+) -> Result<ConfigPlan> {
+    let before = read_config(path)?;
+    let current = before.clone().unwrap_or_else(|| Value::Object(Map::new()));
+    let after = patch_document(&current, patch)?;
+    let changes = diff_documents(&current, &after);
+    let conflicts = match installed {
+        Some(installed) => changes
+            .iter()
+            .filter_map(|change| {
+                let installed = lookup(installed, &change.path);
+                (installed != change.old).then(|| ConfigConflict {
+                    path: change.path.clone(),
+                    installed,
+                    current: change.old.clone(),
+                    patched: change.new.clone(),
+                })
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    Ok(ConfigPlan {
+        path: path.to_path_buf(),
+        format: ConfigFormat::for_path(path),
+        before,
+        after,
+        changes,
+        conflicts,
+    })
+}
+
+fn lookup(document: &Value, path: &str) -> Option<Value> {
+    if path == "/" {
+        return Some(document.clone());
+    }
+    document.pointer(path).cloned()
+}
+
+/// Write the planned config in the file's format.
+pub fn write_config(plan: &ConfigPlan) -> Result<()> {
+    if let Some(parent) = plan.path.parent() {
         fs::create_dir_all(parent)?;
     }
-    let rendered =
-        serde_json::to_string_pretty(patch).map_err(|err| DeployerError::Other(err.to_string()))?;
-    fs::write(path, rendered)?;
+    let rendered = plan.format.render(&plan.after).map_err(|err| {
+        DeployerError::Config(format!(
+            "failed to render {} config {}: {err}",
+            plan.format,
+            plan.path.display()
+        ))
+    })?;
+    fs::write(&plan.path, rendered)?;
     Ok(())
 }
 
+/// Apply a config patch to the file at `path` (see the module docs) and return the new contents.
+pub fn apply_config_patch(path: &Path, patch: &Value) -> Result<Value> {
+    let plan = plan_config_patch(path, patch, None)?;
+    write_config(&plan)?;
+    Ok(plan.after)
+}
+
 pub fn restore_config(path: &Path, snapshot: &ConfigSnapshot) -> Result<()> {
     if snapshot.existed {
         let content = snapshot
@@ -58,3 +447,67 @@ pub fn restore_config(path: &Path, snapshot: &ConfigSnapshot) -> Result<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn merge_patch_follows_rfc_7396() {
+        let mut doc = json!({"a": "b", "c": {"d": "e", "f": "g"}, "keep": [1, 2]});
+        merge_patch(
+            &mut doc,
+            &json!({"a": "z", "c": {"f": null}, "n": {"x": 1}}),
+        );
+        assert_eq!(
+            doc,
+            json!({"a": "z", "c": {"d": "e"}, "keep": [1, 2], "n": {"x": 1}})
+        );
+
+        let from = json!({"a": 1, "b": {"c": 2, "d": 3}});
+        let to = json!({"b": {"c": 2, "d": 4}, "e": true});
+        let patch = diff_merge_patch(&from, &to);
+        assert_eq!(patch, json!({"a": null, "b": {"d": 4}, "e": true}));
+        let mut reverted = from.clone();
+        merge_patch(&mut reverted, &patch);
+        assert_eq!(reverted, to);
+    }
+
+    #[test]
+    fn json_patch_follows_rfc_6902() {
+        let doc = json!({"a": {"b": [1, 2]}, "c/d": "x"});
+        let patched = patch_document(
+            &doc,
+            &json!([
+                {"op": "test", "path": "/c~1d", "value": "x"},
+                {"op": "add", "path": "/a/b/1", "value": 9},
+                {"op": "add", "path": "/a/b/-", "value": 3},
+                {"op": "move", "from": "/c~1d", "path": "/moved"},
+                {"op": "copy", "from": "/moved", "path": "/copied"},
+                {"op": "replace", "path": "/a/b/0", "value": 0},
+                {"op": "remove", "path": "/copied"}
+            ]),
+        )
+        .unwrap();
+        assert_eq!(patched, json!({"a": {"b": [0, 9, 2, 3]}, "moved": "x"}));
+
+        let err = patch_document(&doc, &json!([{"op": "test", "path": "/c~1d", "value": 1}]))
+            .unwrap_err();
+        assert!(err.to_string().contains("operation 0 failed"));
+        assert!(patch_document(&doc, &json!([{"op": "remove", "path": "/nope"}])).is_err());
+    }
+
+    #[test]
+    fn diffs_leaf_values_by_pointer() {
+        let changes = diff_documents(
+            &json!({"a": 1, "b": {"c": 2}, "x/y": true}),
+            &json!({"a": 2, "b": {"d": 3}, "x/y": true}),
+        );
+        let rendered: Vec<String> = changes.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            rendered,
+            vec!["~ /a: 1 -> 2", "- /b/c (was 2)", "+ /b/d = 3"]
+        );
+    }
+}
@@ -0,0 +1,407 @@
+//! Encrypted-at-rest file store behind the `file+enc:` bootstrap secrets backend.
+//!
+//! Records use the same `scope/key` layout as the plaintext `file:` store, and scope and metadata
+//! stay readable. Each value is sealed with ChaCha20-Poly1305 using a key derived from a key file
+//! (HKDF-SHA256) or a passphrase (PBKDF2-HMAC-SHA256). The record key is bound as associated data,
+//! so sealed values cannot be moved between records. The header carries the KDF parameters and a
+//! key check value, which makes a wrong key fail before anything is written.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use ring::aead::{Aad, CHACHA20_POLY1305, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::{hkdf, pbkdf2};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::bootstrap::secrets::StoredSecret;
+use crate::error::{DeployerError, Result};
+
+/// Environment variable holding the passphrase when no key file is configured.
+pub const PASSPHRASE_ENV: &str = "GREENTIC_SECRETS_PASSPHRASE";
+/// Environment variable holding the replacement passphrase for `platform secrets rotate-key`.
+pub const NEW_PASSPHRASE_ENV: &str = "GREENTIC_SECRETS_NEW_PASSPHRASE";
+
+const FORMAT_VERSION: u32 = 1;
+const CIPHER: &str = "chacha20-poly1305";
+const PBKDF2_ITERATIONS: u32 = 600_000;
+const SALT_LEN: usize = 16;
+const MIN_KEY_FILE_LEN: usize = 16;
+const HKDF_INFO: &[u8] = b"greentic-deployer bootstrap secrets v1";
+const KEY_CHECK_AAD: &[u8] = b"greentic-secrets-key-check";
+
+/// Where the encryption key comes from. Only the location is kept, never the key itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeySource {
+    /// Key file with at least 16 bytes of random data (e.g. `head -c 32 /dev/urandom`).
+    KeyFile(PathBuf),
+    /// Passphrase read from the named environment variable.
+    PassphraseEnv(String),
+}
+
+impl KeySource {
+    fn describe(&self) -> String {
+        match self {
+            KeySource::KeyFile(path) => format!("key file {}", path.display()),
+            KeySource::PassphraseEnv(var) => format!("passphrase from {var}"),
+        }
+    }
+
+    fn material(&self) -> Result<Vec<u8>> {
+        match self {
+            KeySource::KeyFile(path) => {
+                let bytes = fs::read(path).map_err(|err| {
+                    DeployerError::Config(format!(
+                        "failed to read secrets key file {}: {err}",
+                        path.display()
+                    ))
+                })?;
+                if bytes.len() < MIN_KEY_FILE_LEN {
+                    return Err(DeployerError::Config(format!(
+                        "secrets key file {} must hold at least {MIN_KEY_FILE_LEN} bytes",
+                        path.display()
+                    )));
+                }
+                Ok(bytes)
+            }
+            KeySource::PassphraseEnv(var) => match env::var(var) {
+                Ok(passphrase) if !passphrase.is_empty() => Ok(passphrase.into_bytes()),
+                _ => Err(DeployerError::Config(format!(
+                    "encrypted secrets backend needs --secrets-key-file or a passphrase in {var}"
+                ))),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EncryptedStore {
+    encryption: Header,
+    #[serde(default)]
+    secrets: BTreeMap<String, SealedSecret>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Header {
+    version: u32,
+    cipher: String,
+    kdf: Kdf,
+    /// Empty plaintext sealed under the key, used to detect a wrong key.
+    key_check: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "name", rename_all = "kebab-case")]
+enum Kdf {
+    HkdfSha256 { salt: String },
+    Pbkdf2Sha256 { salt: String, iterations: u32 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SealedSecret {
+    /// Base64 of nonce || ciphertext || tag.
+    value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scope: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    metadata: Option<Value>,
+}
+
+struct Cipher {
+    key: LessSafeKey,
+    kdf: Kdf,
+}
+
+impl Cipher {
+    /// Derive a key with fresh KDF parameters.
+    fn create(source: &KeySource) -> Result<Self> {
+        let salt = STANDARD.encode(random::<SALT_LEN>()?);
+        let kdf = match source {
+            KeySource::KeyFile(_) => Kdf::HkdfSha256 { salt },
+            KeySource::PassphraseEnv(_) => Kdf::Pbkdf2Sha256 {
+                salt,
+                iterations: PBKDF2_ITERATIONS,
+            },
+        };
+        Self::derive(source, kdf)
+    }
+
+    /// Derive the key a store was written with, failing if `source` does not open it.
+    fn open(source: &KeySource, header: &Header, path: &Path) -> Result<Self> {
+        if header.version != FORMAT_VERSION || header.cipher != CIPHER {
+            return Err(DeployerError::Config(format!(
+                "unsupported encrypted secrets file {} (version {}, cipher {})",
+                path.display(),
+                header.version,
+                header.cipher
+            )));
+        }
+        let cipher = Self::derive(source, header.kdf.clone())?;
+        cipher
+            .unseal(KEY_CHECK_AAD, &header.key_check)
+            .map_err(|_| {
+                DeployerError::Secret(format!(
+                    "{} does not decrypt {}",
+                    source.describe(),
+                    path.display()
+                ))
+            })?;
+        Ok(cipher)
+    }
+
+    fn derive(source: &KeySource, kdf: Kdf) -> Result<Self> {
+        let material = source.material()?;
+        let decode_salt = |salt: &str| {
+            STANDARD
+                .decode(salt)
+                .map_err(|err| DeployerError::Config(format!("invalid KDF salt: {err}")))
+        };
+        let unbound = match (&kdf, source) {
+            (Kdf::HkdfSha256 { salt }, KeySource::KeyFile(_)) => {
+                let prk =
+                    hkdf::Salt::new(hkdf::HKDF_SHA256, &decode_salt(salt)?).extract(&material);
+                let okm = prk
+                    .expand(&[HKDF_INFO], &CHACHA20_POLY1305)
+                    .map_err(|_| DeployerError::Other("key derivation failed".into()))?;
+                UnboundKey::from(okm)
+            }
+            (Kdf::Pbkdf2Sha256 { salt, iterations }, KeySource::PassphraseEnv(_)) => {
+                let iterations = NonZeroU32::new(*iterations).ok_or_else(|| {
+                    DeployerError::Config("PBKDF2 iteration count must not be zero".into())
+                })?;
+                let mut key = [0u8; 32];
+                pbkdf2::derive(
+                    pbkdf2::PBKDF2_HMAC_SHA256,
+                    iterations,
+                    &decode_salt(salt)?,
+                    &material,
+                    &mut key,
+                );
+                UnboundKey::new(&CHACHA20_POLY1305, &key)
+                    .map_err(|_| DeployerError::Other("key derivation failed".into()))?
+            }
+            (Kdf::HkdfSha256 { .. }, _) => {
+                return Err(DeployerError::Config(
+                    "secrets file is encrypted with a key file; pass --secrets-key-file".into(),
+                ));
+            }
+            (Kdf::Pbkdf2Sha256 { .. }, _) => {
+                return Err(DeployerError::Config(format!(
+                    "secrets file is encrypted with a passphrase; set {PASSPHRASE_ENV} instead of \
+                     --secrets-key-file"
+                )));
+            }
+        };
+        Ok(Self {
+            key: LessSafeKey::new(unbound),
+            kdf,
+        })
+    }
+
+    fn header(&self) -> Result<Header> {
+        Ok(Header {
+            version: FORMAT_VERSION,
+            cipher: CIPHER.into(),
+            kdf: self.kdf.clone(),
+            key_check: self.seal(KEY_CHECK_AAD, b"")?,
+        })
+    }
+
+    fn seal(&self, aad: &[u8], plaintext: &[u8]) -> Result<String> {
+        let nonce = random::<NONCE_LEN>()?;
+        let mut in_out = plaintext.to_vec();
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(aad),
+                &mut in_out,
+            )
+            .map_err(|_| DeployerError::Other("secret encryption failed".into()))?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&in_out);
+        Ok(STANDARD.encode(sealed))
+    }
+
+    fn unseal(&self, aad: &[u8], sealed: &str) -> Result<Vec<u8>> {
+        let failed = || DeployerError::Secret("secret decryption failed".into());
+        let sealed = STANDARD.decode(sealed).map_err(|_| failed())?;
+        if sealed.len() < NONCE_LEN {
+            return Err(failed());
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| failed())?;
+        let mut in_out = ciphertext.to_vec();
+        let plaintext = self
+            .key
+            .open_in_place(nonce, Aad::from(aad), &mut in_out)
+            .map_err(|_| failed())?;
+        Ok(plaintext.to_vec())
+    }
+}
+
+fn random<const N: usize>() -> Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| DeployerError::Other("system random number generator failed".into()))?;
+    Ok(bytes)
+}
+
+fn read_store(path: &Path) -> Result<Option<EncryptedStore>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(path)?;
+    if content.trim().is_empty() {
+        return Ok(None);
+    }
+    serde_json::from_str(&content).map(Some).map_err(|err| {
+        DeployerError::Config(format!(
+            "{} is not an encrypted secrets file: {err}",
+            path.display()
+        ))
+    })
+}
+
+/// Decrypt every record of the store at `path` (empty when it does not exist yet).
+fn open_records(
+    path: &Path,
+    source: &KeySource,
+) -> Result<(Option<Cipher>, BTreeMap<String, StoredSecret>)> {
+    let Some(store) = read_store(path)? else {
+        return Ok((None, BTreeMap::new()));
+    };
+    let cipher = Cipher::open(source, &store.encryption, path)?;
+    let records = store
+        .secrets
+        .into_iter()
+        .map(|(key, sealed)| {
+            let plaintext = cipher.unseal(key.as_bytes(), &sealed.value).map_err(|_| {
+                DeployerError::Secret(format!(
+                    "secret '{key}' in {} could not be decrypted",
+                    path.display()
+                ))
+            })?;
+            let value = String::from_utf8(plaintext)
+                .map_err(|_| DeployerError::Secret(format!("secret '{key}' is not valid UTF-8")))?;
+            let record = StoredSecret {
+                value,
+                scope: sealed.scope,
+                metadata: sealed.metadata,
+            };
+            Ok((key, record))
+        })
+        .collect::<Result<_>>()?;
+    Ok((Some(cipher), records))
+}
+
+/// Seal `records` and replace the file atomically (owner-only permissions on Unix).
+fn write_records(
+    path: &Path,
+    cipher: &Cipher,
+    records: &BTreeMap<String, StoredSecret>,
+) -> Result<()> {
+    let secrets = records
+        .iter()
+        .map(|(key, record)| {
+            let sealed = SealedSecret {
+                value: cipher.seal(key.as_bytes(), record.value.as_bytes())?,
+                scope: record.scope.clone(),
+                metadata: record.metadata.clone(),
+            };
+            Ok((key.clone(), sealed))
+        })
+        .collect::<Result<_>>()?;
+    let store = EncryptedStore {
+        encryption: cipher.header()?,
+        secrets,
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    fs::write(&tmp, serde_json::to_string_pretty(&store)?)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&tmp, fs::Permissions::from_mode(0o600))?;
+    }
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Merge `records` into the store at `path`, creating it with fresh KDF parameters if needed.
+pub(crate) fn write_secrets(
+    path: &Path,
+    source: &KeySource,
+    records: impl IntoIterator<Item = (String, StoredSecret)>,
+) -> Result<()> {
+    let (cipher, mut existing) = open_records(path, source)?;
+    let cipher = match cipher {
+        Some(cipher) => cipher,
+        None => Cipher::create(source)?,
+    };
+    existing.extend(records);
+    write_records(path, &cipher, &existing)
+}
+
+/// Fail unless `source` opens the store at `path` (a missing store always passes).
+pub(crate) fn check_key(path: &Path, source: &KeySource) -> Result<()> {
+    if let Some(store) = read_store(path)? {
+        Cipher::open(source, &store.encryption, path)?;
+    }
+    Ok(())
+}
+
+/// Decrypted records of the store at `path`, in the same `{value, scope, metadata}` shape as the
+/// plaintext `file:` store.
+pub fn load_encrypted_file(path: &Path, source: &KeySource) -> Result<Map<String, Value>> {
+    let (_, records) = open_records(path, source)?;
+    records
+        .into_iter()
+        .map(|(key, record)| Ok((key, serde_json::to_value(record)?)))
+        .collect()
+}
+
+/// Re-encrypt every record of the store at `path` under `new` (fresh salt and nonces).
+/// Returns the number of secrets re-encrypted.
+pub fn rotate_file_key(path: &Path, current: &KeySource, new: &KeySource) -> Result<usize> {
+    let (cipher, records) = open_records(path, current)?;
+    if cipher.is_none() {
+        return Err(DeployerError::Config(format!(
+            "no encrypted secrets file at {}",
+            path.display()
+        )));
+    }
+    let new_cipher = Cipher::create(new)?;
+    write_records(path, &new_cipher, &records)?;
+    Ok(records.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seals_values_bound_to_their_record_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_path = dir.path().join("key");
+        fs::write(&key_path, [7u8; 32]).unwrap();
+        let cipher = Cipher::create(&KeySource::KeyFile(key_path)).unwrap();
+
+        let sealed = cipher.seal(b"dev/api_key", b"supersecret").unwrap();
+        assert_eq!(
+            cipher.unseal(b"dev/api_key", &sealed).unwrap(),
+            b"supersecret"
+        );
+        assert!(cipher.unseal(b"dev/other", &sealed).is_err());
+        assert_ne!(sealed, cipher.seal(b"dev/api_key", b"supersecret").unwrap());
+    }
+}
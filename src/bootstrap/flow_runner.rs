@@ -1,7 +1,10 @@
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use serde_json::Value;
 
+use crate::bootstrap::installer::InstallerRuntime;
 use crate::bootstrap::output::BootstrapOutput;
+use crate::bootstrap::questions::{collect_answers, validate_questions};
+use crate::bootstrap::template::{Answers, render_output};
 use crate::error::{DeployerError, Result};
 
 #[derive(Debug, Deserialize)]
@@ -16,18 +19,21 @@ struct BootstrapStep {
     result: Option<Value>,
     #[serde(default)]
     questions: Option<Vec<Question>>,
-}
-
-#[derive(Debug, Deserialize, Clone, Serialize)]
-pub struct Question {
-    pub id: String,
-    pub prompt: String,
+    /// Extra input forwarded to the installer component.
     #[serde(default)]
-    pub default: Option<String>,
+    input: Option<Value>,
 }
 
+pub use crate::bootstrap::questions::{Condition, Question, QuestionKind};
+
 pub trait PromptAdapter {
-    fn ask(&mut self, questions: &[Question]) -> Result<Value>;
+    /// Ask the questions of one prompt step. `answers` holds the answers of earlier steps so
+    /// `when` conditions can be evaluated; see [`crate::bootstrap::questions`].
+    fn ask(&mut self, questions: &[Question], answers: &Answers) -> Result<Value>;
+
+    /// Called for every entry appended to the status history, so interactive adapters can show
+    /// progress while the flow runs.
+    fn status(&mut self, _status: &str) {}
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -36,41 +42,84 @@ pub struct FlowExecutionResult {
     pub status_history: Vec<String>,
 }
 
+/// Executes a minimal bootstrap flow definition without an installer component.
+///
+/// See [`run_bootstrap_flow_with_installer`]; `installer_call` steps must carry a static `result`.
+pub fn run_bootstrap_flow(
+    bytes: &[u8],
+    prompt_adapter: &mut dyn PromptAdapter,
+) -> Result<FlowExecutionResult> {
+    run_bootstrap_flow_with_installer(bytes, prompt_adapter, None)
+}
+
 /// Executes a minimal bootstrap flow definition.
 ///
 /// Allowed steps:
-/// - kind="installer_call": captures `result` as the flow output (last one wins)
-/// - kind="prompt": prompts via adapter (answers are not yet fed back to installer)
+/// - kind="installer_call": with a static `result`, captures it as the flow output with the
+///   answers collected so far substituted into it (see [`crate::bootstrap::template`]);
+///   without one, calls the installer component with the answers and the step's `input`.
+///   The last installer_call wins.
+/// - kind="prompt": prompts via adapter, validates the answers against the questions and
+///   accumulates them (later steps override)
 ///
 /// Everything else is denied in bootstrap mode.
-pub fn run_bootstrap_flow(
+pub fn run_bootstrap_flow_with_installer(
     bytes: &[u8],
     prompt_adapter: &mut dyn PromptAdapter,
+    mut installer: Option<&mut dyn InstallerRuntime>,
 ) -> Result<FlowExecutionResult> {
     let flow: BootstrapFlow = serde_json::from_slice(bytes)
         .map_err(|err| DeployerError::Other(format!("invalid ygtc format: {err}")))?;
+    validate_questions(
+        flow.steps
+            .iter()
+            .filter(|step| step.kind == "prompt")
+            .filter_map(|step| step.questions.as_deref()),
+    )?;
 
     let mut statuses: Vec<String> = Vec::new();
-    statuses.push("waiting_for_answers".into());
+    record(&mut statuses, prompt_adapter, "waiting_for_answers");
 
+    let mut answers = Answers::new();
     let mut output: Option<BootstrapOutput> = None;
     for step in flow.steps {
         match step.kind.as_str() {
             "installer_call" => {
-                statuses.push("deploying".into());
-                let raw = step
-                    .result
-                    .ok_or_else(|| DeployerError::Other("installer_call missing result".into()))?;
-                let parsed: BootstrapOutput = serde_json::from_value(raw).map_err(|err| {
-                    DeployerError::Other(format!("invalid bootstrap output: {err}"))
-                })?;
+                record(&mut statuses, prompt_adapter, "deploying");
+                let parsed = match (step.result, installer.as_deref_mut()) {
+                    (Some(raw), _) => {
+                        let mut parsed: BootstrapOutput =
+                            serde_json::from_value(raw).map_err(|err| {
+                                DeployerError::Other(format!("invalid bootstrap output: {err}"))
+                            })?;
+                        render_output(&mut parsed, &answers)?;
+                        parsed
+                    }
+                    (None, Some(installer)) => installer.call(&answers, step.input.as_ref())?,
+                    (None, None) => {
+                        return Err(DeployerError::Other(
+                            "installer_call missing result and no installer component is available"
+                                .into(),
+                        ));
+                    }
+                };
                 output = Some(parsed);
             }
             "prompt" => {
-                statuses.push("validating".into());
+                record(&mut statuses, prompt_adapter, "validating");
                 let questions = step.questions.unwrap_or_default();
-                prompt_adapter.ask(&questions)?;
-                statuses.push("applying_config".into());
+                let provided = match prompt_adapter.ask(&questions, &answers)? {
+                    Value::Null => Value::Object(Default::default()),
+                    provided @ Value::Object(_) => provided,
+                    other => {
+                        return Err(DeployerError::Other(format!(
+                            "prompt adapter returned non-object answers: {other}"
+                        )));
+                    }
+                };
+                let accepted = collect_answers(&questions, &provided, &answers)?;
+                answers.extend(accepted);
+                record(&mut statuses, prompt_adapter, "applying_config");
             }
             other => {
                 return Err(DeployerError::Other(format!(
@@ -83,14 +132,20 @@ pub fn run_bootstrap_flow(
     let final_output = output.ok_or_else(|| {
         DeployerError::Other("bootstrap flow produced no installer_call output".into())
     })?;
-    statuses.push(if final_output.ready {
-        "completed".into()
+    let last = if final_output.ready {
+        "completed"
     } else {
-        "failed".into()
-    });
+        "failed"
+    };
+    record(&mut statuses, prompt_adapter, last);
 
     Ok(FlowExecutionResult {
         output: final_output,
         status_history: statuses,
     })
 }
+
+fn record(statuses: &mut Vec<String>, adapter: &mut dyn PromptAdapter, status: &str) {
+    adapter.status(status);
+    statuses.push(status.to_string());
+}
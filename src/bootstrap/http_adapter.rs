@@ -1,158 +1,608 @@
+//! HTTP interaction adapter: a small wizard server for operators and automation.
+//!
+//! Every endpoint requires the one-time access token generated at bind time (see
+//! [`HttpPromptAdapter::wizard_url`]):
+//! - `GET /?token=<token>` trades the token, once, for an `HttpOnly`, `SameSite=Strict` cookie
+//!   (`Secure` with TLS) holding a fresh random session id, and redirects to `/`, which renders
+//!   the waiting prompt step as an HTML form. Later `?token=` exchanges are refused.
+//! - `POST /answers` with a form body needs the session cookie and the page's CSRF token, and is
+//!   rejected when `Origin` names another host.
+//! - `GET /schema`, `GET /status` and `POST /answers` with a JSON body serve automation with
+//!   `Authorization: Bearer <token>`, which browsers never attach on their own.
+//!
+//! Answers are validated with [`check_answers`]; invalid submissions are reported (HTML page or
+//! `422`) and may be corrected until the interaction timeout expires.
+
 use std::io::{Read, Write};
-use std::net::{SocketAddr, TcpListener};
-use std::sync::mpsc;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::thread;
+use std::time::Duration;
 
-use serde_json::{Value, json};
+use base64::Engine as _;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+use serde_json::{Map, Value, json};
 
-use crate::bootstrap::flow_runner::{PromptAdapter, Question};
+use crate::bootstrap::flow_runner::{PromptAdapter, Question, QuestionKind};
+use crate::bootstrap::http_wizard::{WizardView, parse_form, render_message, render_page};
+use crate::bootstrap::questions::check_answers;
+use crate::bootstrap::template::Answers;
 use crate::error::{DeployerError, Result};
 
+const SESSION_COOKIE: &str = "greentic_wizard";
+const MAX_HEADER_BYTES: usize = 16 * 1024;
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
 pub struct HttpPromptAdapter {
-    listener: TcpListener,
     bind_addr: SocketAddr,
-    timeout: std::time::Duration,
+    timeout: Duration,
+    tls: bool,
+    access_token: String,
+    state: Arc<Mutex<WizardState>>,
+    answers: Receiver<Map<String, Value>>,
+    shutdown: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+struct WizardState {
+    step: usize,
+    /// Questions of the prompt step waiting for answers.
+    questions: Option<Vec<Question>>,
+    previous: Answers,
+    /// Last rejected submission (secrets removed) and its problems, for re-rendering the form.
+    submitted: Map<String, Value>,
+    errors: Vec<String>,
+    history: Vec<String>,
+    /// Session id minted by the first `?token=` exchange; `None` until then.
+    session: Option<String>,
 }
 
 impl HttpPromptAdapter {
-    pub fn bind(addr: &str, timeout: std::time::Duration) -> Result<Self> {
+    pub fn bind(addr: &str, timeout: Duration) -> Result<Self> {
+        Self::start(addr, timeout, None)
+    }
+
+    /// Like [`HttpPromptAdapter::bind`], serving HTTPS with a PEM certificate chain and key.
+    pub fn bind_with_tls(addr: &str, timeout: Duration, cert: &Path, key: &Path) -> Result<Self> {
+        Self::start(addr, timeout, Some(server_tls_config(cert, key)?))
+    }
+
+    fn start(addr: &str, timeout: Duration, tls: Option<Arc<ServerConfig>>) -> Result<Self> {
         let listener = TcpListener::bind(addr)?;
         let bind_addr = listener.local_addr()?;
+        listener.set_nonblocking(true)?;
+        let access_token = random_token()?;
+        let state = Arc::new(Mutex::new(WizardState::default()));
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let (answers_tx, answers) = mpsc::channel();
+        let is_tls = tls.is_some();
+        let server = Arc::new(WizardServer {
+            token: access_token.clone(),
+            csrf: random_token()?,
+            tls: is_tls,
+            state: state.clone(),
+            answers: answers_tx,
+        });
+        let stop = shutdown.clone();
+        thread::Builder::new()
+            .name("http-wizard".into())
+            .spawn(move || accept_loop(listener, tls, server, stop))?;
         Ok(Self {
-            listener,
             bind_addr,
             timeout,
+            tls: is_tls,
+            access_token,
+            state,
+            answers,
+            shutdown,
         })
     }
 
     pub fn bound_addr(&self) -> SocketAddr {
         self.bind_addr
     }
+
+    /// Token required by every endpoint (query parameter once for a session cookie, or bearer
+    /// header).
+    pub fn access_token(&self) -> &str {
+        &self.access_token
+    }
+
+    /// URL operators open to reach the wizard; it carries the access token.
+    pub fn wizard_url(&self) -> String {
+        let scheme = if self.tls { "https" } else { "http" };
+        format!("{scheme}://{}/?token={}", self.bind_addr, self.access_token)
+    }
+}
+
+impl Drop for HttpPromptAdapter {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+    }
 }
 
 impl PromptAdapter for HttpPromptAdapter {
-    fn ask(&mut self, questions: &[Question]) -> Result<Value> {
-        let questions_json = json!({ "questions": questions });
-        let (tx, rx) = mpsc::channel();
-
-        let listener = self
-            .listener
-            .try_clone()
-            .map_err(|err| DeployerError::Other(err.to_string()))?;
-        let questions_clone: Vec<Question> = questions.to_vec();
-        let timeout = self.timeout;
-        let start = std::time::Instant::now();
-
-        thread::spawn(move || {
-            for stream in listener.incoming() {
-                if start.elapsed() > timeout {
-                    break;
+    fn ask(&mut self, questions: &[Question], answers: &Answers) -> Result<Value> {
+        {
+            let mut state = lock(&self.state);
+            state.step += 1;
+            state.questions = Some(questions.to_vec());
+            state.previous = answers.clone();
+            state.submitted.clear();
+            state.errors.clear();
+        }
+        let received = self.answers.recv_timeout(self.timeout);
+        if received.is_err() {
+            lock(&self.state).questions = None;
+        }
+        received
+            .map(Value::Object)
+            .map_err(|err| DeployerError::Other(format!("no answers received: {err}")))
+    }
+
+    fn status(&mut self, status: &str) {
+        lock(&self.state).history.push(status.to_string());
+    }
+}
+
+fn lock(state: &Mutex<WizardState>) -> MutexGuard<'_, WizardState> {
+    state
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+struct WizardServer {
+    token: String,
+    csrf: String,
+    tls: bool,
+    state: Arc<Mutex<WizardState>>,
+    answers: Sender<Map<String, Value>>,
+}
+
+/// Outcome of submitting answers for the waiting step.
+enum Submission {
+    Accepted,
+    Invalid(Vec<String>),
+    NotWaiting,
+}
+
+impl WizardServer {
+    fn handle(&self, request: &Request) -> Response {
+        let bearer = request
+            .header("authorization")
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|token| same_secret(token.trim(), &self.token));
+        let session = request.cookie(SESSION_COOKIE).is_some_and(|id| {
+            lock(&self.state)
+                .session
+                .as_deref()
+                .is_some_and(|session| same_secret(id, session))
+        });
+
+        match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/") => {
+                if let Some(token) = parse_form(&request.query).get("token") {
+                    if !same_secret(token.as_str().unwrap_or_default(), &self.token) {
+                        return Response::html(
+                            "403 Forbidden",
+                            render_message("Invalid access token", "Check the wizard URL."),
+                        );
+                    }
+                    return self.start_session();
                 }
-                match stream {
-                    Ok(mut stream) => {
-                        let _ = stream.set_read_timeout(Some(timeout));
-                        // Read until headers and (optional) body are available or until timeout.
-                        let mut buffer = Vec::new();
-                        let mut header_end: Option<usize> = None;
-                        let mut content_length: usize = 0;
-                        loop {
-                            let mut chunk = [0u8; 1024];
-                            match stream.read(&mut chunk) {
-                                Ok(0) => break,
-                                Ok(n) => {
-                                    buffer.extend_from_slice(&chunk[..n]);
-                                    if header_end.is_none()
-                                        && let Some(pos) =
-                                            buffer.windows(4).position(|w| w == b"\r\n\r\n")
-                                    {
-                                        header_end = Some(pos + 4);
-                                        let headers = String::from_utf8_lossy(&buffer[..pos + 4]);
-                                        for line in headers.lines() {
-                                            if let Some(value) =
-                                                line.strip_prefix("Content-Length:")
-                                            {
-                                                content_length =
-                                                    value.trim().parse::<usize>().unwrap_or(0);
-                                            }
-                                        }
-                                    }
-                                    if let Some(h_end) = header_end
-                                        && buffer.len() >= h_end + content_length
-                                    {
-                                        break;
-                                    }
-                                    if n < chunk.len() {
-                                        break;
-                                    }
-                                }
-                                Err(ref e)
-                                    if e.kind() == std::io::ErrorKind::WouldBlock
-                                        || e.kind() == std::io::ErrorKind::TimedOut =>
-                                {
-                                    break;
-                                }
-                                Err(_) => break,
-                            }
-                            if start.elapsed() > timeout {
-                                break;
-                            }
-                        }
-
-                        let request = String::from_utf8_lossy(&buffer);
-                        let mut lines = request.lines();
-                        let request_line = lines.next().unwrap_or_default();
-                        if request_line.starts_with("GET /schema") {
-                            let body = serde_json::to_string(&questions_json).unwrap_or_default();
-                            let response = format!(
-                                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
-                                body.len(),
-                                body
-                            );
-                            let _ = stream.write_all(response.as_bytes());
-                            let _ = stream.flush();
-                        } else if request_line.starts_with("POST /answers") {
-                            // read body
-                            let body = request.split("\r\n\r\n").nth(1).unwrap_or_default();
-                            if let Ok(value) = serde_json::from_str::<Value>(body) {
-                                let _ = tx.send(value);
-                                let response = "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n";
-                                let _ = stream.write_all(response.as_bytes());
-                                let _ = stream.flush();
-                                break;
-                            } else {
-                                let response =
-                                    "HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n";
-                                let _ = stream.write_all(response.as_bytes());
-                                let _ = stream.flush();
+                if !session {
+                    return Response::html(
+                        "401 Unauthorized",
+                        render_message(
+                            "Access token required",
+                            "Open the wizard URL printed by greentic-deployer; it includes ?token=.",
+                        ),
+                    );
+                }
+                let state = lock(&self.state);
+                Response::html(
+                    "200 OK",
+                    render_page(&WizardView {
+                        step: state.step,
+                        questions: state.questions.as_deref(),
+                        submitted: &state.submitted,
+                        errors: &state.errors,
+                        history: &state.history,
+                        csrf: &self.csrf,
+                    }),
+                )
+            }
+            ("GET", "/schema") if bearer || session => {
+                let state = lock(&self.state);
+                Response::json(
+                    "200 OK",
+                    &json!({
+                        "step": state.step,
+                        "waiting": state.questions.is_some(),
+                        "questions": state.questions.clone().unwrap_or_default(),
+                        "errors": state.errors,
+                    }),
+                )
+            }
+            ("GET", "/status") if bearer || session => {
+                let state = lock(&self.state);
+                Response::json(
+                    "200 OK",
+                    &json!({
+                        "step": state.step,
+                        "waiting": state.questions.is_some(),
+                        "status_history": state.history,
+                    }),
+                )
+            }
+            ("POST", "/answers") => {
+                let content_type = request.header("content-type").unwrap_or_default();
+                if content_type.starts_with("application/x-www-form-urlencoded") {
+                    self.handle_form(request, session)
+                } else if !bearer {
+                    Response::json(
+                        "401 Unauthorized",
+                        &json!({ "errors": ["missing or invalid bearer token"] }),
+                    )
+                } else {
+                    match serde_json::from_slice::<Value>(&request.body) {
+                        Ok(answers) => match self.submit(&answers) {
+                            Submission::Accepted => {
+                                Response::json("200 OK", &json!({ "status": "accepted" }))
                             }
-                        } else {
-                            let response = "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n";
-                            let _ = stream.write_all(response.as_bytes());
-                            let _ = stream.flush();
-                        }
+                            Submission::Invalid(errors) => Response::json(
+                                "422 Unprocessable Entity",
+                                &json!({ "errors": errors }),
+                            ),
+                            Submission::NotWaiting => Response::json(
+                                "409 Conflict",
+                                &json!({ "errors": ["no questions are waiting for answers"] }),
+                            ),
+                        },
+                        Err(err) => Response::json(
+                            "400 Bad Request",
+                            &json!({ "errors": [format!("invalid JSON: {err}")] }),
+                        ),
                     }
-                    Err(_) => continue,
                 }
             }
-        });
+            (_, "/schema" | "/status") => Response::json(
+                "401 Unauthorized",
+                &json!({ "errors": ["missing or invalid access token"] }),
+            ),
+            _ => Response::json("404 Not Found", &json!({ "errors": ["not found"] })),
+        }
+    }
+
+    /// Trade the (valid) access token for a new session cookie; only the first exchange succeeds.
+    fn start_session(&self) -> Response {
+        let mut state = lock(&self.state);
+        if state.session.is_some() {
+            return Response::html(
+                "403 Forbidden",
+                render_message(
+                    "Access token already used",
+                    "The wizard URL works once; continue in the browser that opened it first.",
+                ),
+            );
+        }
+        let id = match random_token() {
+            Ok(id) => id,
+            Err(err) => {
+                return Response::html(
+                    "500 Internal Server Error",
+                    render_message("Session unavailable", &err.to_string()),
+                );
+            }
+        };
+        let secure = if self.tls { "; Secure" } else { "" };
+        let cookie = format!("{SESSION_COOKIE}={id}; Path=/; HttpOnly; SameSite=Strict{secure}");
+        state.session = Some(id);
+        Response::redirect("/").with_header("Set-Cookie", cookie)
+    }
+
+    /// Browser form submission: session cookie, CSRF token and same-origin are all required.
+    fn handle_form(&self, request: &Request, session: bool) -> Response {
+        let form = parse_form(&String::from_utf8_lossy(&request.body));
+        let csrf_ok = form
+            .get("csrf")
+            .and_then(Value::as_str)
+            .is_some_and(|csrf| same_secret(csrf, &self.csrf));
+        let origin_ok = match (request.header("origin"), request.header("host")) {
+            (Some(origin), Some(host)) => {
+                origin.split_once("://").map(|(_, rest)| rest) == Some(host)
+            }
+            (Some(_), None) => false,
+            (None, _) => true,
+        };
+        if !session || !csrf_ok || !origin_ok {
+            return Response::html(
+                "403 Forbidden",
+                render_message(
+                    "Request rejected",
+                    "The form was not submitted from this wizard session; reload the page and try again.",
+                ),
+            );
+        }
+        // Accepted or not, send the browser back to the wizard page (post/redirect/get).
+        match self.submit(&Value::Object(form)) {
+            Submission::Accepted | Submission::Invalid(_) | Submission::NotWaiting => {
+                Response::redirect("/")
+            }
+        }
+    }
+
+    fn submit(&self, answers: &Value) -> Submission {
+        let mut state = lock(&self.state);
+        let Some(questions) = state.questions.clone() else {
+            return Submission::NotWaiting;
+        };
+        match check_answers(&questions, answers, &state.previous) {
+            Ok(accepted) => {
+                state.questions = None;
+                state.submitted.clear();
+                state.errors.clear();
+                let _ = self.answers.send(accepted);
+                Submission::Accepted
+            }
+            Err(errors) => {
+                state.submitted = questions
+                    .iter()
+                    .filter(|question| question.kind != QuestionKind::Secret)
+                    .filter_map(|question| {
+                        answers
+                            .get(&question.id)
+                            .map(|value| (question.id.clone(), value.clone()))
+                    })
+                    .collect();
+                state.errors = errors.clone();
+                Submission::Invalid(errors)
+            }
+        }
+    }
+}
+
+fn accept_loop(
+    listener: TcpListener,
+    tls: Option<Arc<ServerConfig>>,
+    server: Arc<WizardServer>,
+    shutdown: Arc<AtomicBool>,
+) {
+    while !shutdown.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let tls = tls.clone();
+                let server = server.clone();
+                thread::spawn(move || serve(stream, tls, &server));
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(20));
+            }
+            Err(err) => {
+                tracing::debug!("http wizard accept failed: {err}");
+                thread::sleep(Duration::from_millis(20));
+            }
+        }
+    }
+}
+
+fn serve(stream: TcpStream, tls: Option<Arc<ServerConfig>>, server: &WizardServer) {
+    if stream.set_nonblocking(false).is_err()
+        || stream
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .is_err()
+    {
+        return;
+    }
+    match tls {
+        Some(config) => {
+            let Ok(connection) = ServerConnection::new(config) else {
+                return;
+            };
+            let mut stream = StreamOwned::new(connection, stream);
+            exchange(&mut stream, server);
+            stream.conn.send_close_notify();
+            let _ = stream.flush();
+        }
+        None => {
+            let mut stream = stream;
+            exchange(&mut stream, server);
+        }
+    }
+}
+
+/// Serve one request per connection.
+fn exchange(stream: &mut (impl Read + Write), server: &WizardServer) {
+    let response = match read_request(stream) {
+        Ok(request) => server.handle(&request),
+        Err(Some(response)) => response,
+        Err(None) => return,
+    };
+    let _ = stream.write_all(&response.into_bytes());
+    let _ = stream.flush();
+}
+
+struct Request {
+    method: String,
+    path: String,
+    query: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn cookie(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .filter(|(key, _)| key.eq_ignore_ascii_case("cookie"))
+            .flat_map(|(_, value)| value.split(';'))
+            .filter_map(|pair| pair.trim().split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+    }
+}
 
-        let answers = rx
-            .recv_timeout(self.timeout)
-            .map_err(|err| DeployerError::Other(format!("no answers received: {err}")))?;
+/// Read one request; `Err(Some(..))` carries an error response, `Err(None)` means the client
+/// went away.
+fn read_request(stream: &mut impl Read) -> std::result::Result<Request, Option<Response>> {
+    let too_large = || {
+        Some(Response::json(
+            "413 Payload Too Large",
+            &json!({ "errors": ["request too large"] }),
+        ))
+    };
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let header_end = loop {
+        if let Some(pos) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        if buffer.len() > MAX_HEADER_BYTES {
+            return Err(too_large());
+        }
+        match stream.read(&mut chunk) {
+            Ok(0) | Err(_) => return Err(None),
+            Ok(n) => buffer.extend_from_slice(&chunk[..n]),
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).into_owned();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default().to_string();
+    let target = request_line.next().unwrap_or_default();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect();
+    let content_length = headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    if content_length > MAX_BODY_BYTES {
+        return Err(too_large());
+    }
+
+    let mut body = buffer[header_end..].to_vec();
+    while body.len() < content_length {
+        match stream.read(&mut chunk) {
+            Ok(0) | Err(_) => return Err(None),
+            Ok(n) => body.extend_from_slice(&chunk[..n]),
+        }
+    }
+    body.truncate(content_length);
+
+    Ok(Request {
+        method,
+        path: path.to_string(),
+        query: query.to_string(),
+        headers,
+        body,
+    })
+}
 
-        // Ensure required answers exist
-        let mut provided = serde_json::Map::new();
-        for q in questions_clone {
-            let value = answers
-                .get(&q.id)
-                .cloned()
-                .or_else(|| q.default.as_ref().map(|d| Value::String(d.clone())))
-                .ok_or_else(|| {
-                    DeployerError::Config(format!("missing answer for question '{}'", q.id))
-                })?;
-            provided.insert(q.id, value);
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    headers: Vec<(&'static str, String)>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn json(status: &'static str, body: &Value) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            headers: Vec::new(),
+            body: serde_json::to_vec(body).unwrap_or_default(),
         }
+    }
 
-        Ok(Value::Object(provided))
+    fn html(status: &'static str, body: String) -> Self {
+        Self {
+            status,
+            content_type: "text/html; charset=utf-8",
+            headers: Vec::new(),
+            body: body.into_bytes(),
+        }
     }
+
+    fn redirect(location: &str) -> Self {
+        Self::html("303 See Other", String::new()).with_header("Location", location.to_string())
+    }
+
+    fn with_header(mut self, name: &'static str, value: String) -> Self {
+        self.headers.push((name, value));
+        self
+    }
+
+    /// Every connection serves one request, so responses ask clients not to reuse it.
+    fn into_bytes(self) -> Vec<u8> {
+        let mut head = format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\
+             Cache-Control: no-store\r\nX-Frame-Options: DENY\r\nReferrer-Policy: no-referrer\r\n\
+             Content-Security-Policy: default-src 'none'; style-src 'unsafe-inline'; form-action 'self'\r\n",
+            self.status,
+            self.content_type,
+            self.body.len()
+        );
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{name}: {value}\r\n"));
+        }
+        head.push_str("\r\n");
+        let mut bytes = head.into_bytes();
+        bytes.extend(self.body);
+        bytes
+    }
+}
+
+fn random_token() -> Result<String> {
+    let mut bytes = [0u8; 24];
+    getrandom::fill(&mut bytes)
+        .map_err(|err| DeployerError::Other(format!("failed to generate access token: {err}")))?;
+    Ok(URL_SAFE_NO_PAD.encode(bytes))
+}
+
+/// Compare secrets without an early exit on the first differing byte.
+fn same_secret(candidate: &str, secret: &str) -> bool {
+    candidate.len() == secret.len()
+        && candidate
+            .bytes()
+            .zip(secret.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+fn server_tls_config(cert: &Path, key: &Path) -> Result<Arc<ServerConfig>> {
+    let certs = CertificateDer::pem_file_iter(cert)
+        .and_then(|certs| certs.collect::<std::result::Result<Vec<_>, _>>())
+        .map_err(|err| {
+            DeployerError::Config(format!(
+                "failed to read TLS certificate {}: {err}",
+                cert.display()
+            ))
+        })?;
+    let key = PrivateKeyDer::from_pem_file(key).map_err(|err| {
+        DeployerError::Config(format!("failed to read TLS key {}: {err}", key.display()))
+    })?;
+    ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+        .map(Arc::new)
+        .map_err(|err| DeployerError::Config(format!("invalid TLS configuration: {err}")))
 }
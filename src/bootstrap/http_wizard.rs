@@ -0,0 +1,270 @@
+//! HTML rendering and form decoding for the HTTP bootstrap wizard.
+//!
+//! Pages are plain server-rendered HTML (no scripts); every form carries the session's CSRF
+//! token. Form values are strings and are validated/normalized like any other answers.
+
+use serde_json::{Map, Value};
+
+use crate::bootstrap::flow_runner::{Question, QuestionKind};
+
+/// What the wizard page shows.
+pub struct WizardView<'a> {
+    pub step: usize,
+    /// Questions still waiting for answers, if a prompt step is active.
+    pub questions: Option<&'a [Question]>,
+    /// Answers submitted for the active step (re-filled after validation errors, secrets excluded).
+    pub submitted: &'a Map<String, Value>,
+    pub errors: &'a [String],
+    pub history: &'a [String],
+    pub csrf: &'a str,
+}
+
+pub fn render_page(view: &WizardView<'_>) -> String {
+    let mut body = String::new();
+    match view.questions {
+        Some(questions) => {
+            body.push_str(&format!("<h2>Step {}</h2>\n", view.step));
+            if !view.errors.is_empty() {
+                body.push_str("<ul class=\"errors\">\n");
+                for error in view.errors {
+                    body.push_str(&format!("<li>{}</li>\n", escape(error)));
+                }
+                body.push_str("</ul>\n");
+            }
+            body.push_str("<form method=\"post\" action=\"/answers\">\n");
+            body.push_str(&format!(
+                "<input type=\"hidden\" name=\"csrf\" value=\"{}\">\n",
+                escape(view.csrf)
+            ));
+            for question in questions {
+                body.push_str(&render_field(question, view.submitted.get(&question.id)));
+            }
+            body.push_str("<button type=\"submit\">Continue</button>\n</form>\n");
+        }
+        None => body.push_str(
+            "<p>No questions are waiting right now; this page refreshes automatically.</p>\n",
+        ),
+    }
+    if !view.history.is_empty() {
+        body.push_str("<h3>Progress</h3>\n<ol class=\"history\">\n");
+        for status in view.history {
+            body.push_str(&format!("<li>{}</li>\n", escape(status)));
+        }
+        body.push_str("</ol>\n");
+    }
+    let refresh = if view.questions.is_none() {
+        "<meta http-equiv=\"refresh\" content=\"2\">\n"
+    } else {
+        ""
+    };
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n{refresh}<title>Greentic platform install</title>\n<style>{STYLE}</style>\n</head>\n<body>\n<h1>Greentic platform install</h1>\n{body}</body>\n</html>\n"
+    )
+}
+
+/// Minimal page for errors and unauthenticated visits.
+pub fn render_message(title: &str, message: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n<p>{}</p>\n</body>\n</html>\n",
+        escape(message),
+        title = escape(title)
+    )
+}
+
+const STYLE: &str = "body{font-family:sans-serif;max-width:40em;margin:2em auto;padding:0 1em}\
+label{display:block;margin-top:1em;font-weight:bold}\
+input,select{width:100%;padding:.4em;margin-top:.2em}\
+small{display:block;color:#555}\
+button{margin-top:1.5em;padding:.5em 2em}\
+.errors{color:#b00020}";
+
+fn render_field(question: &Question, submitted: Option<&Value>) -> String {
+    let id = escape(&question.id);
+    let mut out = format!(
+        "<label for=\"{id}\">{}{}</label>\n",
+        escape(&question.prompt),
+        if question.required { "" } else { " (optional)" }
+    );
+    if let Some(help) = &question.help {
+        out.push_str(&format!("<small>{}</small>\n", escape(help)));
+    }
+    if let Some(condition) = &question.when {
+        out.push_str(&format!(
+            "<small>Only needed depending on the answer to '{}'.</small>\n",
+            escape(&condition.answer)
+        ));
+    }
+    let current = match question.kind {
+        QuestionKind::Secret => None,
+        _ => submitted.or(question.default.as_ref()).map(value_text),
+    };
+    match question.kind {
+        QuestionKind::Bool | QuestionKind::Enum => {
+            let options: Vec<(String, String)> = if question.kind == QuestionKind::Bool {
+                vec![("true".into(), "Yes".into()), ("false".into(), "No".into())]
+            } else {
+                question
+                    .options
+                    .iter()
+                    .map(|option| (option.clone(), option.clone()))
+                    .collect()
+            };
+            out.push_str(&format!("<select id=\"{id}\" name=\"{id}\">\n"));
+            if current.is_none() {
+                out.push_str("<option value=\"\"></option>\n");
+            }
+            for (value, label) in options {
+                let selected = if current.as_deref() == Some(value.as_str()) {
+                    " selected"
+                } else {
+                    ""
+                };
+                out.push_str(&format!(
+                    "<option value=\"{}\"{selected}>{}</option>\n",
+                    escape(&value),
+                    escape(&label)
+                ));
+            }
+            out.push_str("</select>\n");
+        }
+        kind => {
+            let input_type = match kind {
+                QuestionKind::Int => "number",
+                QuestionKind::Secret => "password",
+                _ => "text",
+            };
+            let mut attrs = String::new();
+            if let Some(value) = &current {
+                attrs.push_str(&format!(" value=\"{}\"", escape(value)));
+            }
+            let (min_attr, max_attr) = if kind == QuestionKind::Int {
+                ("min", "max")
+            } else {
+                ("minlength", "maxlength")
+            };
+            if let Some(min) = question.min {
+                attrs.push_str(&format!(" {min_attr}=\"{min}\""));
+            }
+            if let Some(max) = question.max {
+                attrs.push_str(&format!(" {max_attr}=\"{max}\""));
+            }
+            if question.required && question.default.is_none() && question.when.is_none() {
+                attrs.push_str(" required");
+            }
+            out.push_str(&format!(
+                "<input type=\"{input_type}\" id=\"{id}\" name=\"{id}\"{attrs}>\n"
+            ));
+        }
+    }
+    out
+}
+
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// Decode an `application/x-www-form-urlencoded` body (also used for query strings).
+pub fn parse_form(body: &str) -> Map<String, Value> {
+    body.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), Value::String(percent_decode(value)))
+        })
+        .collect()
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'+' => out.push(b' '),
+            b'%' if idx + 2 < bytes.len() => {
+                match std::str::from_utf8(&bytes[idx + 1..idx + 3])
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    Some(byte) => {
+                        out.push(byte);
+                        idx += 2;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            byte => out.push(byte),
+        }
+        idx += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn renders_typed_fields_and_escapes_text() {
+        let questions: Vec<Question> = serde_json::from_value(json!([
+            { "id": "region", "prompt": "Region <primary>", "default": "eu-west-1",
+              "help": "Where the control plane runs" },
+            { "id": "replicas", "prompt": "Replicas", "type": "int", "min": 1, "max": 5 },
+            { "id": "tier", "prompt": "Tier", "type": "enum", "options": ["edge", "cloud"] },
+            { "id": "token", "prompt": "Token", "type": "secret" }
+        ]))
+        .unwrap();
+        let submitted = json!({ "tier": "cloud", "token": "hunter2" })
+            .as_object()
+            .cloned()
+            .unwrap();
+        let errors = vec!["invalid answer for question 'replicas': expected an integer".into()];
+        let history = vec!["waiting_for_answers".to_string()];
+        let page = render_page(&WizardView {
+            step: 1,
+            questions: Some(&questions),
+            submitted: &submitted,
+            errors: &errors,
+            history: &history,
+            csrf: "csrf-123",
+        });
+        assert!(page.contains("Region &lt;primary&gt;"));
+        assert!(page.contains("name=\"csrf\" value=\"csrf-123\""));
+        assert!(page.contains("value=\"eu-west-1\""));
+        assert!(
+            page.contains("type=\"number\" id=\"replicas\" name=\"replicas\" min=\"1\" max=\"5\"")
+        );
+        assert!(page.contains("<option value=\"cloud\" selected>"));
+        assert!(page.contains("type=\"password\" id=\"token\" name=\"token\" required>"));
+        assert!(!page.contains("hunter2"));
+        assert!(page.contains("&#39;replicas&#39;: expected an integer"));
+        assert!(page.contains("<li>waiting_for_answers</li>"));
+    }
+
+    #[test]
+    fn decodes_form_bodies() {
+        let form = parse_form("csrf=abc&name=edge+01&url=https%3A%2F%2Fexample.com%2F&bad=%zz");
+        assert_eq!(form["name"], json!("edge 01"));
+        assert_eq!(form["url"], json!("https://example.com/"));
+        assert_eq!(form["bad"], json!("%zz"));
+    }
+}
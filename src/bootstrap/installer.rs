@@ -0,0 +1,447 @@
+//! Minimal wasmtime host for platform `installer.wasm` components.
+//!
+//! `installer_call` steps without a static `result` invoke the pack's installer component
+//! (resolved through [`resolve_bootstrap`]). The component exports
+//! `run-installer: func(input: string) -> result<string, string>`; the input is a JSON document
+//! with the accumulated `answers`, the host `capabilities` and the step's optional `input`, and
+//! the ok value must be a [`BootstrapOutput`] JSON document.
+//!
+//! The component gets no WASI: no filesystem, clock, environment or sockets. The only host
+//! interface is `greentic:platform-installer/host@1.0.0` with `log` and `http-get`, the latter
+//! checked against the [`NetworkPolicy`] before any request is made. Execution is bounded by a
+//! fuel budget.
+
+use std::path::Path;
+use std::time::Duration;
+
+use greentic_types::pack_manifest::PackManifest;
+use serde::Serialize;
+use serde_json::Value;
+use wasmtime::component::{Component, Linker};
+use wasmtime::{Config as WasmtimeConfig, Engine, Store, StoreContextMut};
+
+use crate::bootstrap::capabilities::HostCapabilities;
+use crate::bootstrap::network::NetworkPolicy;
+use crate::bootstrap::output::BootstrapOutput;
+use crate::bootstrap::template::Answers;
+use crate::error::{DeployerError, Result};
+use crate::pack_introspect::read_entry_from_gtpack;
+use crate::platform::flow::resolve_bootstrap;
+
+/// Host interface imported by installer components.
+pub const INSTALLER_HOST_INTERFACE: &str = "greentic:platform-installer/host@1.0.0";
+/// Export invoked for `installer_call` steps.
+pub const INSTALLER_EXPORT: &str = "run-installer";
+
+const INSTALLER_FUEL: u64 = 5_000_000_000;
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_REDIRECTS: usize = 10;
+
+/// Executes `installer_call` steps that have no static `result`.
+pub trait InstallerRuntime {
+    fn call(&mut self, answers: &Answers, input: Option<&Value>) -> Result<BootstrapOutput>;
+}
+
+#[derive(Serialize)]
+struct InstallerInput<'a> {
+    answers: &'a Answers,
+    capabilities: &'a HostCapabilities,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    input: Option<&'a Value>,
+}
+
+/// Installer component loaded from a platform pack.
+pub struct WasmInstaller {
+    component_id: String,
+    wasm: Vec<u8>,
+    capabilities: HostCapabilities,
+    network_policy: NetworkPolicy,
+    /// Messages logged by the component during the last call.
+    pub logs: Vec<String>,
+}
+
+impl WasmInstaller {
+    pub fn new(
+        component_id: impl Into<String>,
+        wasm: Vec<u8>,
+        capabilities: HostCapabilities,
+        network_policy: NetworkPolicy,
+    ) -> Self {
+        Self {
+            component_id: component_id.into(),
+            wasm,
+            capabilities,
+            network_policy,
+            logs: Vec::new(),
+        }
+    }
+
+    /// Load the installer component named by the manifest's bootstrap block from a `.gtpack`.
+    ///
+    /// The component is looked up as `components/<component id>.wasm`, then
+    /// `components/<installer>.wasm` and `<installer>.wasm`.
+    pub fn from_pack(
+        pack_path: &Path,
+        manifest: &PackManifest,
+        capabilities: HostCapabilities,
+        network_policy: NetworkPolicy,
+    ) -> Result<Self> {
+        let installer = resolve_bootstrap(manifest)
+            .map_err(|err| DeployerError::Pack(format!("bootstrap resolution error: {err}")))?
+            .installer_component;
+        let component_id = manifest
+            .components
+            .iter()
+            .map(|component| component.id.to_string())
+            .find(|id| *id == installer || id.ends_with(&format!(".{installer}")))
+            .unwrap_or_else(|| installer.clone());
+        let candidates = [
+            Path::new("components").join(format!("{component_id}.wasm")),
+            Path::new("components").join(format!("{installer}.wasm")),
+            Path::new(&format!("{installer}.wasm")).to_path_buf(),
+        ];
+        let wasm = candidates
+            .iter()
+            .find_map(|entry| read_entry_from_gtpack(pack_path, entry).ok())
+            .ok_or_else(|| {
+                DeployerError::Pack(format!(
+                    "installer component '{installer}' not found in {} (tried {:?})",
+                    pack_path.display(),
+                    candidates
+                ))
+            })?;
+        Ok(Self::new(component_id, wasm, capabilities, network_policy))
+    }
+}
+
+/// Stands in for an installer component that failed to load, so `installer_call` steps that
+/// need one report the load error rather than just its absence.
+pub struct UnavailableInstaller {
+    reason: String,
+}
+
+impl UnavailableInstaller {
+    pub fn new(reason: impl Into<String>) -> Self {
+        Self {
+            reason: reason.into(),
+        }
+    }
+}
+
+impl InstallerRuntime for UnavailableInstaller {
+    fn call(&mut self, _answers: &Answers, _input: Option<&Value>) -> Result<BootstrapOutput> {
+        Err(DeployerError::Other(format!(
+            "installer_call missing result and no installer component is available: {}",
+            self.reason
+        )))
+    }
+}
+
+impl InstallerRuntime for WasmInstaller {
+    fn call(&mut self, answers: &Answers, input: Option<&Value>) -> Result<BootstrapOutput> {
+        let request = serde_json::to_string(&InstallerInput {
+            answers,
+            capabilities: &self.capabilities,
+            input,
+        })?;
+        let id = &self.component_id;
+
+        let mut config = WasmtimeConfig::new();
+        config.wasm_component_model(true);
+        config.consume_fuel(true);
+        let engine = Engine::new(&config)
+            .map_err(|err| DeployerError::Other(format!("wasmtime init failed: {err}")))?;
+        let component = Component::from_binary(&engine, &self.wasm).map_err(|err| {
+            DeployerError::Pack(format!("invalid installer component {id}: {err}"))
+        })?;
+        let mut store = Store::new(
+            &engine,
+            HostState {
+                network_policy: self.network_policy.clone(),
+                logs: Vec::new(),
+            },
+        );
+        store
+            .set_fuel(INSTALLER_FUEL)
+            .map_err(|err| DeployerError::Other(format!("failed to set fuel: {err}")))?;
+        let instance = build_linker(&engine)?
+            .instantiate(&mut store, &component)
+            .map_err(|err| {
+                DeployerError::Other(format!("failed to instantiate installer {id}: {err}"))
+            })?;
+        let func = instance
+            .get_typed_func::<(String,), (std::result::Result<String, String>,)>(
+                &mut store,
+                INSTALLER_EXPORT,
+            )
+            .map_err(|err| {
+                DeployerError::Pack(format!(
+                    "installer {id} export '{INSTALLER_EXPORT}' lookup failed: {err}"
+                ))
+            })?;
+        let (result,) = func
+            .call(&mut store, (request,))
+            .map_err(|err| DeployerError::Other(format!("installer {id} trapped: {err}")))?;
+        func.post_return(&mut store).map_err(|err| {
+            DeployerError::Other(format!("installer {id} post-return failed: {err}"))
+        })?;
+        self.logs = store.into_data().logs;
+
+        let output = result
+            .map_err(|message| DeployerError::Other(format!("installer {id} failed: {message}")))?;
+        serde_json::from_str(&output).map_err(|err| {
+            DeployerError::Other(format!(
+                "installer {id} returned invalid bootstrap output: {err}"
+            ))
+        })
+    }
+}
+
+struct HostState {
+    network_policy: NetworkPolicy,
+    logs: Vec<String>,
+}
+
+fn build_linker(engine: &Engine) -> Result<Linker<HostState>> {
+    let mut linker = Linker::<HostState>::new(engine);
+    let map_err = |err: wasmtime::Error| {
+        DeployerError::Other(format!(
+            "failed to register installer host interface: {err}"
+        ))
+    };
+    let mut host = linker.instance(INSTALLER_HOST_INTERFACE).map_err(map_err)?;
+    host.func_wrap(
+        "log",
+        |mut store: StoreContextMut<'_, HostState>, (message,): (String,)| {
+            tracing::info!(installer = %message, "installer log");
+            store.data_mut().logs.push(message);
+            Ok(())
+        },
+    )
+    .map_err(map_err)?;
+    host.func_wrap(
+        "http-get",
+        |store: StoreContextMut<'_, HostState>, (url,): (String,)| {
+            let policy = &store.data().network_policy;
+            let result = policy
+                .enforce(&url)
+                .map_err(|err| err.to_string())
+                .and_then(|()| http_get(&url, policy));
+            Ok((result,))
+        },
+    )
+    .map_err(map_err)?;
+    Ok(linker)
+}
+
+/// Blocking GET on a dedicated thread so it is safe to call from within a tokio runtime. Every
+/// redirect hop is checked against the network policy, not just the first URL.
+fn http_get(url: &str, policy: &NetworkPolicy) -> std::result::Result<String, String> {
+    let url = url.to_string();
+    let policy = policy.clone();
+    std::thread::spawn(move || {
+        let redirects = reqwest::redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                return attempt.error("too many redirects");
+            }
+            match policy.enforce(attempt.url().as_str()) {
+                Ok(()) => attempt.follow(),
+                Err(err) => attempt.error(err.to_string()),
+            }
+        });
+        let client = reqwest::blocking::Client::builder()
+            .timeout(HTTP_TIMEOUT)
+            .redirect(redirects)
+            .build()
+            .map_err(|err| err.to_string())?;
+        let response = client
+            .get(&url)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| error_chain(&err))?;
+        response.text().map_err(|err| error_chain(&err))
+    })
+    .join()
+    .unwrap_or_else(|_| Err("http-get worker panicked".into()))
+}
+
+/// `err` followed by its sources, so a refused redirect reports the policy reason.
+fn error_chain(err: &dyn std::error::Error) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(cause) = source {
+        message.push_str(&format!(": {cause}"));
+        source = cause.source();
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bootstrap::capabilities::build_host_capabilities;
+    use crate::bootstrap::network::NetAllowList;
+    use crate::config::InteractionMode;
+    use serde_json::json;
+
+    const OUTPUT: &str = r#"{"output_version":"v1","config_patch":{"installed":true},"secrets_writes":[],"warnings":[],"ready":true}"#;
+
+    /// Installer that logs its input, optionally calls `http-get`, and returns [`OUTPUT`]
+    /// (or the `http-get` error).
+    fn installer_wat(fetch: bool) -> String {
+        let url = "https://example.com/config";
+        let fetch_code = if fetch {
+            format!(
+                r#"
+      i32.const 100
+      i32.const {url_len}
+      i32.const 500
+      call $http
+      i32.const 500
+      i32.load8_u
+      if
+        i32.const 600
+        i32.const 1
+        i32.store8
+        i32.const 604
+        i32.const 504
+        i32.load
+        i32.store
+        i32.const 608
+        i32.const 508
+        i32.load
+        i32.store
+        i32.const 600
+        return
+      end"#,
+                url_len = url.len()
+            )
+        } else {
+            String::new()
+        };
+        format!(
+            r#"
+(component
+  (import "greentic:platform-installer/host@1.0.0" (instance $host
+    (export "log" (func (param "message" string)))
+    (export "http-get" (func (param "url" string) (result (result string (error string)))))
+  ))
+  (core module $libc
+    (memory (export "memory") 1)
+    (global $heap (mut i32) (i32.const 4096))
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (local $ret i32)
+      global.get $heap
+      i32.const 7
+      i32.add
+      i32.const -8
+      i32.and
+      local.tee $ret
+      local.get 3
+      i32.add
+      global.set $heap
+      local.get $ret))
+  (core instance $libc (instantiate $libc))
+  (alias core export $libc "memory" (core memory $mem))
+  (alias core export $libc "realloc" (core func $realloc))
+  (core func $log (canon lower (func $host "log") (memory $mem) (realloc $realloc)))
+  (core func $http (canon lower (func $host "http-get") (memory $mem) (realloc $realloc)))
+  (core module $main
+    (import "env" "memory" (memory 1))
+    (import "host" "log" (func $log (param i32 i32)))
+    (import "host" "http-get" (func $http (param i32 i32 i32)))
+    (data (i32.const 100) "{url}")
+    (data (i32.const 1024) "{output}")
+    (func (export "run-installer") (param i32 i32) (result i32)
+      local.get 0
+      local.get 1
+      call $log{fetch_code}
+      i32.const 600
+      i32.const 0
+      i32.store8
+      i32.const 604
+      i32.const 1024
+      i32.store
+      i32.const 608
+      i32.const {output_len}
+      i32.store
+      i32.const 600))
+  (core instance $main (instantiate $main
+    (with "env" (instance (export "memory" (memory $mem))))
+    (with "host" (instance
+      (export "log" (func $log))
+      (export "http-get" (func $http))))))
+  (func (export "run-installer") (param "input" string) (result (result string (error string)))
+    (canon lift (core func $main "run-installer") (memory $mem) (realloc $realloc)))
+)
+"#,
+            output = OUTPUT.replace('"', "\\\""),
+            output_len = OUTPUT.len(),
+        )
+    }
+
+    fn installer(fetch: bool) -> WasmInstaller {
+        let policy = NetworkPolicy::new(false, false, NetAllowList::parse(None).unwrap());
+        let capabilities = build_host_capabilities(InteractionMode::Json, false, &policy);
+        let wasm = wat::parse_str(installer_wat(fetch)).expect("installer wat");
+        WasmInstaller::new("test.installer", wasm, capabilities, policy)
+    }
+
+    #[test]
+    fn runs_installer_with_answers_and_capabilities() {
+        let mut installer = installer(false);
+        let answers = json!({"region": "eu-west-1"}).as_object().cloned().unwrap();
+        let output = installer
+            .call(&answers, Some(&json!({"mode": "install"})))
+            .expect("installer runs");
+        assert!(output.ready);
+        assert_eq!(output.config_patch["installed"], json!(true));
+
+        let logged: Value = serde_json::from_str(&installer.logs[0]).unwrap();
+        assert_eq!(logged["answers"]["region"], json!("eu-west-1"));
+        assert_eq!(logged["input"]["mode"], json!("install"));
+        assert_eq!(logged["capabilities"]["no_listeners"], json!(true));
+    }
+
+    #[test]
+    fn network_policy_gates_http_get() {
+        let mut installer = installer(true);
+        let err = installer.call(&Answers::new(), None).unwrap_err();
+        assert!(err.to_string().contains("network access disabled"));
+    }
+
+    #[test]
+    fn http_get_checks_redirect_targets() {
+        use std::io::{Read, Write};
+        let Ok(listener) = std::net::TcpListener::bind("127.0.0.1:0") else {
+            return;
+        };
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            if let Ok((mut stream, _)) = listener.accept() {
+                let _ = stream.read(&mut [0u8; 1024]);
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 302 Found\r\nLocation: http://localhost:{port}/elsewhere\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                );
+            }
+        });
+        let policy =
+            NetworkPolicy::new(true, false, NetAllowList::parse(Some("127.0.0.1")).unwrap());
+
+        let err = http_get(&format!("http://127.0.0.1:{port}/config"), &policy).unwrap_err();
+        assert!(err.contains("allowlist"), "{err}");
+    }
+
+    #[test]
+    fn unavailable_installer_reports_load_error() {
+        let mut installer = UnavailableInstaller::new("installer component 'setup' not found");
+        let err = installer.call(&Answers::new(), None).unwrap_err();
+        assert!(
+            err.to_string().contains(
+                "no installer component is available: installer component 'setup' not found"
+            ),
+            "{err}"
+        );
+    }
+}
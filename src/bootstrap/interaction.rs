@@ -1,6 +1,9 @@
+use serde::Serialize;
+
 use crate::config::InteractionMode;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum InteractionAdapterKind {
     Cli,
     Json,
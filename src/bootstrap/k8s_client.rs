@@ -0,0 +1,373 @@
+//! Minimal Kubernetes API access shared by the k8s bootstrap backends.
+//!
+//! Credentials come from a kubeconfig (token, tokenFile or client certificate users) or the
+//! in-cluster service account; requests are plain JSON calls against core/v1 resources.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD;
+use reqwest::Method;
+use reqwest::blocking::Client;
+use reqwest::redirect::Policy;
+use serde::Deserialize;
+use serde::de::IgnoredAny;
+use serde_json::Value;
+
+use crate::bootstrap::blocking::off_runtime;
+use crate::error::{DeployerError, Result};
+
+const SERVICE_ACCOUNT_DIR: &str = "/var/run/secrets/kubernetes.io/serviceaccount";
+
+/// API server endpoint and credentials.
+#[derive(Debug, Clone, Default)]
+pub struct K8sConnection {
+    pub server: String,
+    pub token: Option<String>,
+    /// PEM bundle trusted for the API server certificate.
+    pub ca_pem: Option<Vec<u8>>,
+    /// PEM client certificate followed by its private key.
+    pub identity_pem: Option<Vec<u8>>,
+    pub insecure_skip_tls_verify: bool,
+}
+
+impl K8sConnection {
+    /// Use `kubeconfig` when given, else the in-cluster service account, else `$KUBECONFIG` or
+    /// `~/.kube/config`.
+    pub fn discover(kubeconfig: Option<&Path>) -> Result<Self> {
+        if let Some(path) = kubeconfig {
+            return Self::from_kubeconfig(path);
+        }
+        if let Some(connection) = Self::in_cluster()? {
+            return Ok(connection);
+        }
+        let path = env::var_os("KUBECONFIG")
+            .and_then(|value| env::split_paths(&value).next())
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".kube/config")))
+            .filter(|path| path.exists())
+            .ok_or_else(|| {
+                DeployerError::Config(
+                    "no Kubernetes credentials found: not running in a cluster and no kubeconfig \
+                     (set --k8s-kubeconfig or KUBECONFIG)"
+                        .into(),
+                )
+            })?;
+        Self::from_kubeconfig(&path)
+    }
+
+    /// Service account credentials mounted into pods, if running inside a cluster.
+    pub fn in_cluster() -> Result<Option<Self>> {
+        let (Ok(host), Ok(port)) = (
+            env::var("KUBERNETES_SERVICE_HOST"),
+            env::var("KUBERNETES_SERVICE_PORT"),
+        ) else {
+            return Ok(None);
+        };
+        let dir = Path::new(SERVICE_ACCOUNT_DIR);
+        let token_path = dir.join("token");
+        if !token_path.exists() {
+            return Ok(None);
+        }
+        let host = if host.contains(':') {
+            format!("[{host}]")
+        } else {
+            host
+        };
+        let ca_path = dir.join("ca.crt");
+        Ok(Some(Self {
+            server: format!("https://{host}:{port}"),
+            token: Some(fs::read_to_string(&token_path)?.trim().to_string()),
+            ca_pem: ca_path.exists().then(|| fs::read(&ca_path)).transpose()?,
+            ..Self::default()
+        }))
+    }
+
+    /// Resolve the current context of a kubeconfig file. Token and client-certificate users are
+    /// supported; exec and auth-provider plugins are not.
+    pub fn from_kubeconfig(path: &Path) -> Result<Self> {
+        let invalid = |reason: String| {
+            DeployerError::Config(format!("kubeconfig {}: {reason}", path.display()))
+        };
+        let text = fs::read_to_string(path).map_err(|err| invalid(err.to_string()))?;
+        let config: KubeConfig =
+            serde_yaml_bw::from_str(&text).map_err(|err| invalid(err.to_string()))?;
+        let base = path.parent().unwrap_or_else(|| Path::new("."));
+
+        let context_name = config
+            .current_context
+            .as_deref()
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| invalid("no current-context set".into()))?;
+        let context = config
+            .contexts
+            .iter()
+            .find(|entry| entry.name == context_name)
+            .map(|entry| &entry.context)
+            .ok_or_else(|| invalid(format!("context '{context_name}' not found")))?;
+        let cluster = config
+            .clusters
+            .iter()
+            .find(|entry| entry.name == context.cluster)
+            .map(|entry| &entry.cluster)
+            .ok_or_else(|| invalid(format!("cluster '{}' not found", context.cluster)))?;
+        let user = match context.user.as_deref() {
+            Some(name) => config
+                .users
+                .iter()
+                .find(|entry| entry.name == name)
+                .map(|entry| &entry.user)
+                .ok_or_else(|| invalid(format!("user '{name}' not found")))?,
+            None => &KubeUser::default(),
+        };
+
+        let token = match (&user.token, &user.token_file) {
+            (Some(token), _) => Some(token.clone()),
+            (None, Some(file)) => Some(
+                fs::read_to_string(base.join(file))
+                    .map_err(|err| invalid(format!("tokenFile {}: {err}", file.display())))?
+                    .trim()
+                    .to_string(),
+            ),
+            (None, None) => None,
+        };
+        let certificate = read_material(
+            base,
+            &user.client_certificate_data,
+            &user.client_certificate,
+        )
+        .map_err(|err| invalid(format!("client certificate: {err}")))?;
+        let key = read_material(base, &user.client_key_data, &user.client_key)
+            .map_err(|err| invalid(format!("client key: {err}")))?;
+        let identity_pem = match (certificate, key) {
+            (Some(mut certificate), Some(key)) => {
+                certificate.push(b'\n');
+                certificate.extend(key);
+                Some(certificate)
+            }
+            _ => None,
+        };
+        if token.is_none()
+            && identity_pem.is_none()
+            && (user.exec.is_some() || user.auth_provider.is_some())
+        {
+            return Err(invalid(format!(
+                "user of context '{context_name}' uses an exec/auth-provider plugin, which is not \
+                 supported; use a token or client certificate"
+            )));
+        }
+
+        Ok(Self {
+            server: cluster.server.clone(),
+            token,
+            ca_pem: read_material(
+                base,
+                &cluster.certificate_authority_data,
+                &cluster.certificate_authority,
+            )
+            .map_err(|err| invalid(format!("certificate authority: {err}")))?,
+            identity_pem,
+            insecure_skip_tls_verify: cluster.insecure_skip_tls_verify,
+        })
+    }
+
+    /// Send a JSON request to `path` on the API server and return the status and JSON body
+    /// (`null` when the body is not JSON).
+    pub fn request(&self, method: Method, path: &str, body: Option<Value>) -> Result<(u16, Value)> {
+        off_runtime(|| self.send_blocking(method, path, body))
+    }
+
+    fn send_blocking(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> Result<(u16, Value)> {
+        let connection = self;
+        let url = format!("{}{path}", connection.server.trim_end_matches('/'));
+        let client_error = |err: reqwest::Error| {
+            DeployerError::Other(format!(
+                "kubernetes API client for {}: {err}",
+                connection.server
+            ))
+        };
+        let mut builder = Client::builder()
+            .timeout(Duration::from_secs(15))
+            .redirect(Policy::none());
+        if let Some(ca) = &connection.ca_pem {
+            for certificate in reqwest::Certificate::from_pem_bundle(ca).map_err(client_error)? {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        if let Some(identity) = &connection.identity_pem {
+            builder =
+                builder.identity(reqwest::Identity::from_pem(identity).map_err(client_error)?);
+        }
+        if connection.insecure_skip_tls_verify {
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+        let client = builder.build().map_err(client_error)?;
+
+        let mut request = client
+            .request(method, &url)
+            .header(reqwest::header::ACCEPT, "application/json");
+        if let Some(token) = &connection.token {
+            request = request.bearer_auth(token);
+        }
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+        let response = request.send().map_err(|err| {
+            DeployerError::Other(format!("kubernetes API request to {url} failed: {err}"))
+        })?;
+        let status = response.status().as_u16();
+        let body = response
+            .text()
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or(Value::Null);
+        Ok((status, body))
+    }
+}
+
+/// Inline base64 data wins over a file path (relative to the kubeconfig directory).
+fn read_material(
+    base: &Path,
+    data: &Option<String>,
+    file: &Option<PathBuf>,
+) -> std::result::Result<Option<Vec<u8>>, String> {
+    match (data, file) {
+        (Some(data), _) => STANDARD
+            .decode(data.trim())
+            .map(Some)
+            .map_err(|err| err.to_string()),
+        (None, Some(file)) => fs::read(base.join(file))
+            .map(Some)
+            .map_err(|err| format!("{}: {err}", file.display())),
+        (None, None) => Ok(None),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct KubeConfig {
+    current_context: Option<String>,
+    #[serde(default)]
+    contexts: Vec<NamedContext>,
+    #[serde(default)]
+    clusters: Vec<NamedCluster>,
+    #[serde(default)]
+    users: Vec<NamedUser>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NamedContext {
+    name: String,
+    context: KubeContext,
+}
+
+#[derive(Debug, Deserialize)]
+struct KubeContext {
+    cluster: String,
+    user: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NamedCluster {
+    name: String,
+    cluster: KubeCluster,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct KubeCluster {
+    server: String,
+    certificate_authority: Option<PathBuf>,
+    certificate_authority_data: Option<String>,
+    #[serde(default)]
+    insecure_skip_tls_verify: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct NamedUser {
+    name: String,
+    #[serde(default)]
+    user: KubeUser,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct KubeUser {
+    token: Option<String>,
+    #[serde(rename = "tokenFile")]
+    token_file: Option<PathBuf>,
+    client_certificate: Option<PathBuf>,
+    client_certificate_data: Option<String>,
+    client_key: Option<PathBuf>,
+    client_key_data: Option<String>,
+    exec: Option<IgnoredAny>,
+    auth_provider: Option<IgnoredAny>,
+}
+
+/// Message of a Kubernetes `Status` error body.
+pub fn status_message(body: &Value) -> &str {
+    body["message"].as_str().unwrap_or("no details")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_current_context_from_kubeconfig() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("token"), "file-token\n").unwrap();
+        let path = dir.path().join("config");
+        fs::write(
+            &path,
+            format!(
+                r#"apiVersion: v1
+kind: Config
+current-context: edge
+contexts:
+  - name: other
+    context: {{ cluster: other, user: plugin }}
+  - name: edge
+    context: {{ cluster: edge-cluster, user: installer }}
+clusters:
+  - name: edge-cluster
+    cluster:
+      server: https://10.0.0.1:6443
+      certificate-authority-data: {}
+users:
+  - name: installer
+    user:
+      tokenFile: token
+  - name: plugin
+    user:
+      exec: {{ command: aws }}
+"#,
+                STANDARD.encode("-----BEGIN CERTIFICATE-----")
+            ),
+        )
+        .unwrap();
+
+        let connection = K8sConnection::from_kubeconfig(&path).expect("kubeconfig");
+        assert_eq!(connection.server, "https://10.0.0.1:6443");
+        assert_eq!(connection.token.as_deref(), Some("file-token"));
+        assert_eq!(
+            connection.ca_pem.as_deref(),
+            Some(b"-----BEGIN CERTIFICATE-----".as_slice())
+        );
+
+        let text = fs::read_to_string(&path)
+            .unwrap()
+            .replace("current-context: edge", "current-context: other")
+            .replace("name: edge-cluster", "name: other");
+        fs::write(&path, text).unwrap();
+        let err = K8sConnection::from_kubeconfig(&path).expect_err("exec plugin");
+        assert!(err.to_string().contains("exec/auth-provider"));
+    }
+}
@@ -0,0 +1,217 @@
+//! Kubernetes bootstrap state backend.
+//!
+//! [`BootstrapState`] is stored as JSON under the `state.json` key of a ConfigMap or Secret
+//! (`--k8s-namespace`/`--k8s-state-name`). Writes carry the `resourceVersion` observed by the
+//! last read, so the API server rejects a save when another installer changed the object in the
+//! meantime instead of silently overwriting it. The object is owned by the deployer: saves replace
+//! its data and labels.
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD;
+use reqwest::Method;
+use serde_json::{Value, json};
+
+use crate::bootstrap::k8s_client::{K8sConnection, status_message};
+use crate::bootstrap::state::BootstrapState;
+use crate::config::K8sStateKind;
+use crate::error::{DeployerError, Result};
+
+const STATE_KEY: &str = "state.json";
+
+/// Which object holds the state and how to reach the cluster.
+#[derive(Debug, Clone)]
+pub struct K8sStateTarget {
+    pub namespace: String,
+    pub name: String,
+    pub kind: K8sStateKind,
+    pub kubeconfig: Option<PathBuf>,
+}
+
+/// What the last read or write saw of the state object.
+#[derive(Debug, Clone)]
+enum Observed {
+    Unknown,
+    Missing,
+    Version(String),
+}
+
+pub struct K8sStateStore {
+    connection: K8sConnection,
+    namespace: String,
+    name: String,
+    kind: K8sStateKind,
+    observed: Mutex<Observed>,
+}
+
+impl K8sStateStore {
+    pub fn new(connection: K8sConnection, target: &K8sStateTarget) -> Self {
+        Self {
+            connection,
+            namespace: target.namespace.clone(),
+            name: target.name.clone(),
+            kind: target.kind,
+            observed: Mutex::new(Observed::Unknown),
+        }
+    }
+
+    /// Connect using [`K8sConnection::discover`].
+    pub fn open(target: &K8sStateTarget) -> Result<Self> {
+        let connection = K8sConnection::discover(target.kubeconfig.as_deref())?;
+        Ok(Self::new(connection, target))
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "{} {}/{}",
+            self.kind.object_kind(),
+            self.namespace,
+            self.name
+        )
+    }
+
+    pub fn load(&self) -> Result<Option<BootstrapState>> {
+        let (status, body) = self
+            .connection
+            .request(Method::GET, &self.object_path(), None)?;
+        match status {
+            404 => {
+                self.observe(Observed::Missing);
+                Ok(None)
+            }
+            200 => {
+                self.observe(resource_version(&body));
+                self.decode(&body)
+            }
+            _ => Err(self.api_error("read", status, &body)),
+        }
+    }
+
+    /// Save `state`, failing if the object changed since this store last read or wrote it.
+    /// Without an earlier [`K8sStateStore::load`] the current object is read first.
+    pub fn save(&self, state: &BootstrapState) -> Result<()> {
+        if matches!(self.observed(), Observed::Unknown) {
+            self.load()?;
+        }
+        let data = serde_json::to_string_pretty(state)
+            .map_err(|err| DeployerError::Other(err.to_string()))?;
+        let (status, body) = match self.observed() {
+            Observed::Version(version) => self.connection.request(
+                Method::PUT,
+                &self.object_path(),
+                Some(self.object(&data, Some(&version))),
+            )?,
+            _ => self.connection.request(
+                Method::POST,
+                &self.collection_path(),
+                Some(self.object(&data, None)),
+            )?,
+        };
+        match status {
+            200 | 201 => {
+                self.observe(resource_version(&body));
+                Ok(())
+            }
+            // 409: stale resourceVersion or created concurrently; 404: deleted since it was read.
+            404 | 409 => Err(DeployerError::Other(format!(
+                "bootstrap state {} was changed by another installer since it was read; \
+                 re-run once it has finished",
+                self.describe()
+            ))),
+            _ => Err(self.api_error("write", status, &body)),
+        }
+    }
+
+    fn observed(&self) -> Observed {
+        self.observed
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    fn observe(&self, observed: Observed) {
+        *self
+            .observed
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = observed;
+    }
+
+    fn collection_path(&self) -> String {
+        format!(
+            "/api/v1/namespaces/{}/{}",
+            self.namespace,
+            self.kind.resource()
+        )
+    }
+
+    fn object_path(&self) -> String {
+        format!("{}/{}", self.collection_path(), self.name)
+    }
+
+    fn object(&self, data: &str, resource_version: Option<&str>) -> Value {
+        let mut metadata = json!({
+            "name": self.name,
+            "namespace": self.namespace,
+            "labels": { "app.kubernetes.io/managed-by": "greentic-deployer" }
+        });
+        if let Some(version) = resource_version {
+            metadata["resourceVersion"] = json!(version);
+        }
+        match self.kind {
+            K8sStateKind::ConfigMap => json!({
+                "apiVersion": "v1",
+                "kind": "ConfigMap",
+                "metadata": metadata,
+                "data": { STATE_KEY: data }
+            }),
+            K8sStateKind::Secret => json!({
+                "apiVersion": "v1",
+                "kind": "Secret",
+                "type": "Opaque",
+                "metadata": metadata,
+                "data": { STATE_KEY: STANDARD.encode(data) }
+            }),
+        }
+    }
+
+    fn decode(&self, object: &Value) -> Result<Option<BootstrapState>> {
+        let Some(raw) = object["data"][STATE_KEY].as_str() else {
+            return Ok(None);
+        };
+        let invalid = |reason: String| {
+            DeployerError::Other(format!(
+                "invalid bootstrap state in {}: {reason}",
+                self.describe()
+            ))
+        };
+        let text = match self.kind {
+            K8sStateKind::ConfigMap => raw.to_string(),
+            K8sStateKind::Secret => STANDARD
+                .decode(raw)
+                .map_err(|err| invalid(err.to_string()))
+                .and_then(|bytes| {
+                    String::from_utf8(bytes).map_err(|err| invalid(err.to_string()))
+                })?,
+        };
+        serde_json::from_str(&text)
+            .map(Some)
+            .map_err(|err| invalid(err.to_string()))
+    }
+
+    fn api_error(&self, action: &str, status: u16, body: &Value) -> DeployerError {
+        DeployerError::Other(format!(
+            "failed to {action} bootstrap state {}: HTTP {status}: {}",
+            self.describe(),
+            status_message(body)
+        ))
+    }
+}
+
+fn resource_version(object: &Value) -> Observed {
+    match object["metadata"]["resourceVersion"].as_str() {
+        Some(version) => Observed::Version(version.to_string()),
+        None => Observed::Unknown,
+    }
+}
@@ -1,11 +1,22 @@
+pub mod blocking;
 pub mod capabilities;
 pub mod cli;
 pub mod config_patch;
+pub mod encrypted_file;
 pub mod flow_runner;
 pub mod http_adapter;
+pub mod http_wizard;
+pub mod installer;
 pub mod interaction;
+pub mod k8s_client;
+pub mod k8s_state;
 pub mod mqtt_adapter;
+pub mod mqtt_client;
 pub mod network;
 pub mod output;
+pub mod questions;
+pub mod releases;
 pub mod secrets;
 pub mod state;
+pub mod template;
+pub mod vault_client;
@@ -1,11 +1,15 @@
 use std::collections::HashMap;
+use std::sync::mpsc::Receiver;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use serde_json::{Value, json};
 
 use crate::bootstrap::flow_runner::{PromptAdapter, Question};
+use crate::bootstrap::mqtt_client::{BrokerTransport, MqttBrokerOptions};
 use crate::bootstrap::network::NetworkPolicy;
+use crate::bootstrap::questions::check_answers;
+use crate::bootstrap::template::Answers;
 use crate::error::{DeployerError, Result};
 
 type SubscriberMap = HashMap<String, Vec<std::sync::mpsc::Sender<Vec<u8>>>>;
@@ -34,23 +38,94 @@ impl MockBroker {
     }
 }
 
-pub struct MqttPromptAdapter {
+/// Message transport behind [`MqttPromptAdapter`]: the in-process [`MockBroker`] or a real
+/// broker connection ([`crate::bootstrap::mqtt_client::BrokerTransport`]).
+pub trait MqttTransport: Send {
+    /// Subscribe to `topic`; returns once the subscription is active.
+    fn subscribe(&mut self, topic: &str) -> Result<()>;
+    fn publish(&mut self, topic: &str, payload: &[u8]) -> Result<()>;
+    /// Next message on any subscribed topic, or `None` when `timeout` expires first.
+    fn recv(&mut self, timeout: Duration) -> Result<Option<(String, Vec<u8>)>>;
+}
+
+/// [`MqttTransport`] over a [`MockBroker`].
+struct MockTransport {
     broker: MockBroker,
+    subscriptions: Vec<(String, Receiver<Vec<u8>>)>,
+}
+
+impl MqttTransport for MockTransport {
+    fn subscribe(&mut self, topic: &str) -> Result<()> {
+        let rx = self.broker.subscribe(topic);
+        self.subscriptions.push((topic.to_string(), rx));
+        Ok(())
+    }
+
+    fn publish(&mut self, topic: &str, payload: &[u8]) -> Result<()> {
+        self.broker.publish(topic, payload);
+        Ok(())
+    }
+
+    fn recv(&mut self, timeout: Duration) -> Result<Option<(String, Vec<u8>)>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            for (topic, rx) in &self.subscriptions {
+                if let Ok(payload) = rx.try_recv() {
+                    return Ok(Some((topic.clone(), payload)));
+                }
+            }
+            if Instant::now() >= deadline {
+                return Ok(None);
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+}
+
+pub struct MqttPromptAdapter {
+    transport: Box<dyn MqttTransport>,
     device_id: String,
     topic_prefix: String,
     timeout: Duration,
     network_policy: Option<(NetworkPolicy, String)>,
+    subscribed: bool,
 }
 
 impl MqttPromptAdapter {
-    pub fn new_mock(broker: MockBroker, device_id: String, topic_prefix: String) -> Result<Self> {
-        Ok(Self {
-            broker,
+    pub fn new(transport: Box<dyn MqttTransport>, device_id: String, topic_prefix: String) -> Self {
+        Self {
+            transport,
             device_id,
             topic_prefix,
             timeout: Duration::from_secs(5),
             network_policy: None,
-        })
+            subscribed: false,
+        }
+    }
+
+    pub fn new_mock(broker: MockBroker, device_id: String, topic_prefix: String) -> Result<Self> {
+        let transport = MockTransport {
+            broker,
+            subscriptions: Vec::new(),
+        };
+        Ok(Self::new(Box::new(transport), device_id, topic_prefix))
+    }
+
+    /// Connect to a real broker; the broker host is checked against `policy` before any
+    /// connection attempt and again before every prompt.
+    pub fn connect(
+        options: &MqttBrokerOptions,
+        policy: NetworkPolicy,
+        device_id: String,
+        topic_prefix: String,
+        timeout: Duration,
+    ) -> Result<Self> {
+        let endpoint = options.endpoint()?;
+        policy.enforce(&endpoint.host)?;
+        let transport = BrokerTransport::connect(options, timeout)?;
+        Self::new(Box::new(transport), device_id, topic_prefix)
+            .with_timeout(timeout)
+            .with_network_policy(policy, endpoint.host)
     }
 
     pub fn with_timeout(mut self, timeout: Duration) -> Self {
@@ -68,55 +143,73 @@ impl MqttPromptAdapter {
         Ok(self)
     }
 
-    fn schema_topic(&self) -> String {
-        format!("{}/{}/schema", self.topic_prefix, self.device_id)
+    pub fn questions_topic(&self) -> String {
+        format!("{}/{}/questions", self.topic_prefix, self.device_id)
     }
 
-    fn answers_topic(&self) -> String {
+    pub fn answers_topic(&self) -> String {
         format!("{}/{}/answers", self.topic_prefix, self.device_id)
     }
 
-    fn status_topic(&self) -> String {
+    pub fn status_topic(&self) -> String {
         format!("{}/{}/status", self.topic_prefix, self.device_id)
     }
+
+    fn publish_status(&mut self, status: &Value) {
+        if let Ok(bytes) = serde_json::to_vec(status) {
+            let topic = self.status_topic();
+            if let Err(err) = self.transport.publish(&topic, &bytes) {
+                tracing::warn!("failed to publish mqtt status: {err}");
+            }
+        }
+    }
 }
 
 impl PromptAdapter for MqttPromptAdapter {
-    fn ask(&mut self, questions: &[Question]) -> Result<Value> {
+    fn ask(&mut self, questions: &[Question], answers: &Answers) -> Result<Value> {
         if let Some((policy, host)) = &self.network_policy {
             policy.enforce(host)?;
         }
-        // publish schema
-        let payload = serde_json::to_vec(&json!({ "questions": questions }))
-            .map_err(|err| DeployerError::Other(format!("failed to encode schema: {err}")))?;
-        self.broker.publish(&self.schema_topic(), &payload);
-
-        // subscribe to answers
-        let rx = self.broker.subscribe(&self.answers_topic());
-        let answers_payload = rx
-            .recv_timeout(self.timeout)
-            .map_err(|_| DeployerError::Other("timeout waiting for MQTT answers".into()))?;
-
-        let answers: Value = serde_json::from_slice(&answers_payload)
-            .map_err(|err| DeployerError::Other(format!("invalid MQTT answers payload: {err}")))?;
-
-        let mut provided = serde_json::Map::new();
-        for q in questions {
-            let value = answers
-                .get(&q.id)
-                .cloned()
-                .or_else(|| q.default.as_ref().map(|d| Value::String(d.clone())))
-                .ok_or_else(|| {
-                    DeployerError::Config(format!("missing answer for question '{}'", q.id))
-                })?;
-            provided.insert(q.id.clone(), value);
+        // subscribe to answers before the questions go out so a fast client is not missed
+        let answers_topic = self.answers_topic();
+        if !self.subscribed {
+            self.transport.subscribe(&answers_topic)?;
+            self.subscribed = true;
         }
 
+        // publish questions
+        let payload = serde_json::to_vec(&json!({ "questions": questions }))
+            .map_err(|err| DeployerError::Other(format!("failed to encode questions: {err}")))?;
+        let questions_topic = self.questions_topic();
+        self.transport.publish(&questions_topic, &payload)?;
+
+        // invalid answers are reported on the status topic and may be resent until the timeout
+        let deadline = Instant::now() + self.timeout;
+        let provided = loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let Some((topic, answers_payload)) = self.transport.recv(remaining)? else {
+                return Err(DeployerError::Other(
+                    "timeout waiting for MQTT answers".into(),
+                ));
+            };
+            if topic != answers_topic {
+                continue;
+            }
+
+            let received: Value = serde_json::from_slice(&answers_payload).map_err(|err| {
+                DeployerError::Other(format!("invalid MQTT answers payload: {err}"))
+            })?;
+
+            match check_answers(questions, &received, answers) {
+                Ok(provided) => break provided,
+                Err(errors) => {
+                    self.publish_status(&json!({"status": "invalid_answers", "errors": errors}))
+                }
+            }
+        };
+
         // publish status update for observers
-        let status = json!({"status": "answers_received"});
-        if let Ok(bytes) = serde_json::to_vec(&status) {
-            self.broker.publish(&self.status_topic(), &bytes);
-        }
+        self.publish_status(&json!({"status": "answers_received"}));
 
         Ok(Value::Object(provided))
     }
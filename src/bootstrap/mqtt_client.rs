@@ -0,0 +1,428 @@
+//! Broker connection for the MQTT prompt adapter (MQTT 3.1.1 and 5 via rumqttc).
+//!
+//! The rumqttc event loop runs on its own thread (it drives a private runtime, which must not
+//! block inside the deployer's async context) and forwards connection events over a channel.
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rumqttc::{TlsConfiguration, Transport};
+use rustls::pki_types::CertificateDer;
+use rustls::pki_types::pem::PemObject;
+
+use crate::bootstrap::mqtt_adapter::MqttTransport;
+use crate::config::MqttProtocol;
+use crate::error::{DeployerError, Result};
+
+/// Connection settings for a real MQTT broker.
+#[derive(Debug, Clone)]
+pub struct MqttBrokerOptions {
+    /// `mqtt://host[:port]` (default port 1883) or `mqtts://host[:port]` for TLS (8883).
+    pub url: String,
+    pub client_id: String,
+    pub protocol: MqttProtocol,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// PEM bundle trusted for `mqtts://` brokers instead of the system roots.
+    pub ca_file: Option<PathBuf>,
+    pub keep_alive: Duration,
+}
+
+impl MqttBrokerOptions {
+    pub fn new(url: impl Into<String>, client_id: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            client_id: client_id.into(),
+            protocol: MqttProtocol::V311,
+            username: None,
+            password: None,
+            ca_file: None,
+            keep_alive: Duration::from_secs(30),
+        }
+    }
+
+    pub fn endpoint(&self) -> Result<BrokerEndpoint> {
+        BrokerEndpoint::parse(&self.url)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokerEndpoint {
+    pub host: String,
+    pub port: u16,
+    pub tls: bool,
+}
+
+impl BrokerEndpoint {
+    pub fn parse(url: &str) -> Result<Self> {
+        let invalid = |reason: &str| {
+            DeployerError::Config(format!("invalid MQTT broker URL '{url}': {reason}"))
+        };
+        let (scheme, rest) = url
+            .split_once("://")
+            .ok_or_else(|| invalid("expected mqtt://host[:port] or mqtts://host[:port]"))?;
+        let (tls, default_port) = match scheme {
+            "mqtt" | "tcp" => (false, 1883),
+            "mqtts" | "ssl" => (true, 8883),
+            other => return Err(invalid(&format!("unsupported scheme '{other}'"))),
+        };
+        let authority = rest.trim_end_matches('/');
+        if authority.is_empty() || authority.contains('/') || authority.contains('@') {
+            return Err(invalid("expected only host and optional port"));
+        }
+        let (host, port) = match authority.strip_prefix('[') {
+            Some(bracketed) => {
+                let (host, after) = bracketed
+                    .split_once(']')
+                    .ok_or_else(|| invalid("unterminated IPv6 address"))?;
+                (host, after.strip_prefix(':'))
+            }
+            None => match authority.rsplit_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            },
+        };
+        let port = match port {
+            Some(port) => port.parse().map_err(|_| invalid("invalid port"))?,
+            None => default_port,
+        };
+        if host.is_empty() {
+            return Err(invalid("missing host"));
+        }
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            tls,
+        })
+    }
+}
+
+enum Notice {
+    Connected,
+    Subscribed(bool),
+    Message(String, Vec<u8>),
+    Failed(String),
+}
+
+enum BrokerClient {
+    V311(rumqttc::Client),
+    V5(rumqttc::v5::Client),
+}
+
+/// [`MqttTransport`] over a broker connection; publishes and subscribes with QoS 1.
+pub struct BrokerTransport {
+    client: BrokerClient,
+    notices: Receiver<Notice>,
+    pending: VecDeque<(String, Vec<u8>)>,
+    timeout: Duration,
+}
+
+impl BrokerTransport {
+    /// Connect and wait (up to `timeout`) for the broker to accept the session.
+    pub fn connect(options: &MqttBrokerOptions, timeout: Duration) -> Result<Self> {
+        let endpoint = options.endpoint()?;
+        let transport = if endpoint.tls {
+            Transport::tls_with_config(TlsConfiguration::Rustls(Arc::new(tls_config(
+                options.ca_file.as_ref(),
+            )?)))
+        } else {
+            Transport::tcp()
+        };
+        let (notice_tx, notices) = mpsc::channel();
+        let client = match options.protocol {
+            MqttProtocol::V311 => {
+                let mut mqtt = rumqttc::MqttOptions::new(
+                    options.client_id.clone(),
+                    endpoint.host.clone(),
+                    endpoint.port,
+                );
+                mqtt.set_keep_alive(options.keep_alive);
+                mqtt.set_transport(transport);
+                if let Some(username) = &options.username {
+                    mqtt.set_credentials(username, options.password.clone().unwrap_or_default());
+                }
+                BrokerClient::V311(spawn_v311(mqtt, notice_tx)?)
+            }
+            MqttProtocol::V5 => {
+                let mut mqtt = rumqttc::v5::MqttOptions::new(
+                    options.client_id.clone(),
+                    endpoint.host.clone(),
+                    endpoint.port,
+                );
+                mqtt.set_keep_alive(options.keep_alive);
+                mqtt.set_transport(transport);
+                if let Some(username) = &options.username {
+                    mqtt.set_credentials(username, options.password.clone().unwrap_or_default());
+                }
+                BrokerClient::V5(spawn_v5(mqtt, notice_tx)?)
+            }
+        };
+        let mut transport = Self {
+            client,
+            notices,
+            pending: VecDeque::new(),
+            timeout,
+        };
+        transport
+            .wait_for(|notice| matches!(notice, Notice::Connected).then_some(()))
+            .map_err(|err| {
+                DeployerError::Other(format!(
+                    "MQTT connection to {}:{} failed: {err}",
+                    endpoint.host, endpoint.port
+                ))
+            })?;
+        Ok(transport)
+    }
+
+    /// Wait for a notice selected by `select`, queueing messages that arrive meanwhile.
+    fn wait_for<T>(&mut self, select: impl Fn(&Notice) -> Option<T>) -> Result<T> {
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let notice = self
+                .notices
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                .map_err(|err| match err {
+                    RecvTimeoutError::Timeout => DeployerError::Other("timed out".into()),
+                    RecvTimeoutError::Disconnected => {
+                        DeployerError::Other("connection closed".into())
+                    }
+                })?;
+            if let Some(selected) = select(&notice) {
+                return Ok(selected);
+            }
+            match notice {
+                Notice::Message(topic, payload) => self.pending.push_back((topic, payload)),
+                Notice::Failed(err) => return Err(DeployerError::Other(err)),
+                Notice::Connected | Notice::Subscribed(_) => {}
+            }
+        }
+    }
+}
+
+impl MqttTransport for BrokerTransport {
+    fn subscribe(&mut self, topic: &str) -> Result<()> {
+        let requested = match &self.client {
+            BrokerClient::V311(client) => client
+                .subscribe(topic, rumqttc::QoS::AtLeastOnce)
+                .map_err(|err| err.to_string()),
+            BrokerClient::V5(client) => client
+                .subscribe(topic, rumqttc::v5::mqttbytes::QoS::AtLeastOnce)
+                .map_err(|err| err.to_string()),
+        };
+        requested.map_err(|err| DeployerError::Other(format!("MQTT subscribe failed: {err}")))?;
+        let granted = self
+            .wait_for(|notice| match notice {
+                Notice::Subscribed(granted) => Some(*granted),
+                _ => None,
+            })
+            .map_err(|err| {
+                DeployerError::Other(format!("MQTT subscribe to {topic} failed: {err}"))
+            })?;
+        if !granted {
+            return Err(DeployerError::Other(format!(
+                "MQTT broker refused subscription to {topic}"
+            )));
+        }
+        Ok(())
+    }
+
+    fn publish(&mut self, topic: &str, payload: &[u8]) -> Result<()> {
+        let published = match &self.client {
+            BrokerClient::V311(client) => client
+                .publish(topic, rumqttc::QoS::AtLeastOnce, false, payload.to_vec())
+                .map_err(|err| err.to_string()),
+            BrokerClient::V5(client) => client
+                .publish(
+                    topic,
+                    rumqttc::v5::mqttbytes::QoS::AtLeastOnce,
+                    false,
+                    payload.to_vec(),
+                )
+                .map_err(|err| err.to_string()),
+        };
+        published
+            .map_err(|err| DeployerError::Other(format!("MQTT publish to {topic} failed: {err}")))
+    }
+
+    fn recv(&mut self, timeout: Duration) -> Result<Option<(String, Vec<u8>)>> {
+        if let Some(message) = self.pending.pop_front() {
+            return Ok(Some(message));
+        }
+        let deadline = Instant::now() + timeout;
+        loop {
+            match self
+                .notices
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            {
+                Ok(Notice::Message(topic, payload)) => return Ok(Some((topic, payload))),
+                Ok(Notice::Failed(err)) => {
+                    return Err(DeployerError::Other(format!("MQTT connection lost: {err}")));
+                }
+                Ok(Notice::Connected | Notice::Subscribed(_)) => {}
+                Err(RecvTimeoutError::Timeout) => return Ok(None),
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(DeployerError::Other("MQTT connection closed".into()));
+                }
+            }
+        }
+    }
+}
+
+impl Drop for BrokerTransport {
+    fn drop(&mut self) {
+        let _ = match &self.client {
+            BrokerClient::V311(client) => client.try_disconnect().map_err(|err| err.to_string()),
+            BrokerClient::V5(client) => client.try_disconnect().map_err(|err| err.to_string()),
+        };
+    }
+}
+
+/// Run a 3.1.1 event loop on its own thread. The loop stops on the first connection error
+/// (rumqttc would otherwise reconnect forever) or once the transport is dropped.
+fn spawn_v311(options: rumqttc::MqttOptions, notices: Sender<Notice>) -> Result<rumqttc::Client> {
+    use rumqttc::{Event, Packet, SubscribeReasonCode};
+
+    let (client_tx, client_rx) = mpsc::channel();
+    thread::Builder::new()
+        .name("mqtt-v311".into())
+        .spawn(move || {
+            let (client, mut connection) = rumqttc::Client::new(options, 16);
+            if client_tx.send(client).is_err() {
+                return;
+            }
+            for event in connection.iter() {
+                let notice = match event {
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => Notice::Connected,
+                    Ok(Event::Incoming(Packet::SubAck(ack))) => Notice::Subscribed(
+                        ack.return_codes
+                            .iter()
+                            .all(|code| matches!(code, SubscribeReasonCode::Success(_))),
+                    ),
+                    Ok(Event::Incoming(Packet::Publish(publish))) => {
+                        Notice::Message(publish.topic, publish.payload.to_vec())
+                    }
+                    Ok(_) => continue,
+                    Err(err) => {
+                        let _ = notices.send(Notice::Failed(err.to_string()));
+                        break;
+                    }
+                };
+                if notices.send(notice).is_err() {
+                    break;
+                }
+            }
+        })?;
+    client_rx
+        .recv()
+        .map_err(|_| DeployerError::Other("MQTT client thread exited".into()))
+}
+
+/// MQTT 5 counterpart of [`spawn_v311`].
+fn spawn_v5(
+    options: rumqttc::v5::MqttOptions,
+    notices: Sender<Notice>,
+) -> Result<rumqttc::v5::Client> {
+    use rumqttc::v5::Event;
+    use rumqttc::v5::mqttbytes::v5::{Packet, SubscribeReasonCode};
+
+    let (client_tx, client_rx) = mpsc::channel();
+    thread::Builder::new()
+        .name("mqtt-v5".into())
+        .spawn(move || {
+            let (client, mut connection) = rumqttc::v5::Client::new(options, 16);
+            if client_tx.send(client).is_err() {
+                return;
+            }
+            for event in connection.iter() {
+                let notice = match event {
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => Notice::Connected,
+                    Ok(Event::Incoming(Packet::SubAck(ack))) => Notice::Subscribed(
+                        ack.return_codes
+                            .iter()
+                            .all(|code| matches!(code, SubscribeReasonCode::Success(_))),
+                    ),
+                    Ok(Event::Incoming(Packet::Publish(publish))) => Notice::Message(
+                        String::from_utf8_lossy(&publish.topic).into_owned(),
+                        publish.payload.to_vec(),
+                    ),
+                    Ok(_) => continue,
+                    Err(err) => {
+                        let _ = notices.send(Notice::Failed(err.to_string()));
+                        break;
+                    }
+                };
+                if notices.send(notice).is_err() {
+                    break;
+                }
+            }
+        })?;
+    client_rx
+        .recv()
+        .map_err(|_| DeployerError::Other("MQTT client thread exited".into()))
+}
+
+/// TLS client configuration trusting `ca_file` or, without one, the system roots.
+fn tls_config(ca_file: Option<&PathBuf>) -> Result<rustls::ClientConfig> {
+    let mut roots = rustls::RootCertStore::empty();
+    let certs: Vec<CertificateDer<'static>> = match ca_file {
+        Some(path) => CertificateDer::pem_file_iter(path)
+            .and_then(|certs| certs.collect())
+            .map_err(|err| {
+                DeployerError::Config(format!(
+                    "failed to read MQTT CA bundle {}: {err}",
+                    path.display()
+                ))
+            })?,
+        None => rustls_native_certs::load_native_certs().certs,
+    };
+    for cert in certs {
+        roots
+            .add(cert)
+            .map_err(|err| DeployerError::Config(format!("invalid MQTT CA certificate: {err}")))?;
+    }
+    if roots.is_empty() {
+        return Err(DeployerError::Config(
+            "no trusted CA certificates for the MQTT broker; pass --mqtt-ca-file".into(),
+        ));
+    }
+    rustls::ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map(|builder| builder.with_root_certificates(roots).with_no_client_auth())
+        .map_err(|err| DeployerError::Other(format!("failed to configure MQTT TLS: {err}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_broker_urls() {
+        assert_eq!(
+            BrokerEndpoint::parse("mqtt://broker.example.com").unwrap(),
+            BrokerEndpoint {
+                host: "broker.example.com".into(),
+                port: 1883,
+                tls: false
+            }
+        );
+        assert_eq!(
+            BrokerEndpoint::parse("mqtts://10.0.0.5:8884").unwrap(),
+            BrokerEndpoint {
+                host: "10.0.0.5".into(),
+                port: 8884,
+                tls: true
+            }
+        );
+        assert_eq!(
+            BrokerEndpoint::parse("mqtt://[::1]:1884").unwrap().host,
+            "::1"
+        );
+        assert!(BrokerEndpoint::parse("http://broker").is_err());
+        assert!(BrokerEndpoint::parse("mqtt://user@broker").is_err());
+        assert!(BrokerEndpoint::parse("broker:1883").is_err());
+    }
+}
@@ -0,0 +1,349 @@
+//! Question model for bootstrap `prompt` steps and the validation every prompt adapter shares.
+//!
+//! A question has a type (`string`, `int`, `bool`, `enum`, `secret`), optional `pattern`
+//! (full-match regex for string-like answers), `min`/`max` (value bounds for `int`, length
+//! bounds for `string`/`secret`), `required` (default `true`), `help` text and a `when`
+//! condition on an earlier answer. Answers are normalized to the question's JSON type, so the
+//! CLI's text input and the JSON/HTTP/MQTT payloads end up identical.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::bootstrap::template::Answers;
+use crate::error::{DeployerError, Result};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuestionKind {
+    #[default]
+    String,
+    Int,
+    Bool,
+    Enum,
+    /// String answer that adapters must not echo (masked in the CLI).
+    Secret,
+}
+
+/// Condition on an earlier answer; without a comparison the answer must be present and truthy.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Condition {
+    pub answer: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub equals: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_equals: Option<Value>,
+    #[serde(default, rename = "in", skip_serializing_if = "Option::is_none")]
+    pub one_of: Option<Vec<Value>>,
+}
+
+impl Condition {
+    pub fn matches(&self, answers: &Answers) -> bool {
+        let answer = answers.get(&self.answer);
+        if let Some(expected) = &self.equals {
+            return answer == Some(expected);
+        }
+        if let Some(unexpected) = &self.not_equals {
+            return answer != Some(unexpected);
+        }
+        if let Some(options) = &self.one_of {
+            return answer.is_some_and(|value| options.contains(value));
+        }
+        match answer {
+            None | Some(Value::Null) | Some(Value::Bool(false)) => false,
+            Some(Value::String(text)) => !text.is_empty(),
+            Some(_) => true,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct Question {
+    pub id: String,
+    pub prompt: String,
+    #[serde(default, rename = "type")]
+    pub kind: QuestionKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
+    #[serde(default = "default_required")]
+    pub required: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub help: Option<String>,
+    /// Allowed values for `enum` questions.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<Condition>,
+}
+
+fn default_required() -> bool {
+    true
+}
+
+impl Question {
+    /// Whether the question applies given the answers collected so far.
+    pub fn is_active(&self, answers: &Answers) -> bool {
+        self.when
+            .as_ref()
+            .is_none_or(|condition| condition.matches(answers))
+    }
+
+    /// Validate and normalize a raw answer.
+    ///
+    /// Missing, `null` and empty-string answers fall back to the default; without one, required
+    /// questions fail and optional ones yield `None`.
+    pub fn accept(&self, raw: Option<&Value>) -> Result<Option<Value>> {
+        let raw = raw.filter(|value| !value.is_null() && value.as_str() != Some(""));
+        match raw.or(self.default.as_ref()) {
+            Some(value) => self.coerce(value).map(Some),
+            None if self.required => Err(self.invalid("an answer is required")),
+            None => Ok(None),
+        }
+    }
+
+    /// Check the question definition itself: enum options, pattern syntax, bounds and default.
+    pub fn validate_definition(&self) -> Result<()> {
+        let definition = |reason: String| {
+            DeployerError::Config(format!("invalid question '{}': {reason}", self.id))
+        };
+        if self.kind == QuestionKind::Enum && self.options.is_empty() {
+            return Err(definition("enum questions need options".into()));
+        }
+        if self.pattern.is_some() {
+            self.regex().map_err(|err| definition(err.to_string()))?;
+        }
+        if let (Some(min), Some(max)) = (self.min, self.max)
+            && min > max
+        {
+            return Err(definition(format!("min {min} is greater than max {max}")));
+        }
+        if let Some(default) = &self.default {
+            self.coerce(default)
+                .map_err(|err| definition(format!("default rejected: {err}")))?;
+        }
+        Ok(())
+    }
+
+    fn coerce(&self, value: &Value) -> Result<Value> {
+        let normalized = match (self.kind, value) {
+            (QuestionKind::Int, Value::Number(number)) if number.is_i64() => value.clone(),
+            (QuestionKind::Int, Value::String(text)) => text
+                .trim()
+                .parse::<i64>()
+                .map(Value::from)
+                .map_err(|_| self.invalid("expected an integer"))?,
+            (QuestionKind::Int, _) => return Err(self.invalid("expected an integer")),
+            (QuestionKind::Bool, Value::Bool(_)) => value.clone(),
+            (QuestionKind::Bool, Value::String(text)) => {
+                match text.trim().to_ascii_lowercase().as_str() {
+                    "true" | "yes" | "y" => Value::Bool(true),
+                    "false" | "no" | "n" => Value::Bool(false),
+                    _ => return Err(self.invalid("expected true/false or yes/no")),
+                }
+            }
+            (QuestionKind::Bool, _) => return Err(self.invalid("expected a boolean")),
+            (QuestionKind::Enum, Value::String(text)) if self.options.contains(text) => {
+                value.clone()
+            }
+            (QuestionKind::Enum, _) => {
+                return Err(self.invalid(&format!("expected one of: {}", self.options.join(", "))));
+            }
+            (QuestionKind::String | QuestionKind::Secret, Value::String(_)) => value.clone(),
+            (QuestionKind::String | QuestionKind::Secret, _) => {
+                return Err(self.invalid("expected a string"));
+            }
+        };
+        self.check_bounds(&normalized)?;
+        Ok(normalized)
+    }
+
+    fn check_bounds(&self, value: &Value) -> Result<()> {
+        let (measure, unit) = match value {
+            Value::Number(number) => (number.as_i64().unwrap_or_default(), ""),
+            Value::String(text) => (text.chars().count() as i64, " characters"),
+            _ => return Ok(()),
+        };
+        if let Some(min) = self.min
+            && measure < min
+        {
+            return Err(self.invalid(&format!("must be at least {min}{unit}")));
+        }
+        if let Some(max) = self.max
+            && measure > max
+        {
+            return Err(self.invalid(&format!("must be at most {max}{unit}")));
+        }
+        if let (Some(pattern), Value::String(text)) = (&self.pattern, value)
+            && !self.regex()?.is_match(text)
+        {
+            return Err(self.invalid(&format!("must match pattern {pattern}")));
+        }
+        Ok(())
+    }
+
+    fn regex(&self) -> Result<Regex> {
+        let pattern = self.pattern.as_deref().unwrap_or_default();
+        Regex::new(&format!("^(?:{pattern})$")).map_err(|err| {
+            DeployerError::Config(format!("invalid pattern for question '{}': {err}", self.id))
+        })
+    }
+
+    fn invalid(&self, reason: &str) -> DeployerError {
+        DeployerError::Config(format!(
+            "invalid answer for question '{}': {reason}",
+            self.id
+        ))
+    }
+}
+
+/// Validate the questions of every prompt step up front; `when` may only reference questions
+/// asked earlier in the flow.
+pub fn validate_questions<'a>(steps: impl IntoIterator<Item = &'a [Question]>) -> Result<()> {
+    let mut asked: Vec<&str> = Vec::new();
+    for questions in steps {
+        for question in questions {
+            question.validate_definition()?;
+            if let Some(condition) = &question.when
+                && !asked.contains(&condition.answer.as_str())
+            {
+                return Err(DeployerError::Config(format!(
+                    "question '{}' has a condition on '{}', which is not asked before it",
+                    question.id, condition.answer
+                )));
+            }
+            asked.push(&question.id);
+        }
+    }
+    Ok(())
+}
+
+/// Validate a batch of answers (JSON, HTTP and MQTT adapters) against a prompt step.
+///
+/// Questions are evaluated in order so `when` sees both earlier steps' answers and the answers
+/// of this step accepted so far; answers to inactive questions are dropped. Every invalid
+/// answer is reported in one error.
+pub fn collect_answers(
+    questions: &[Question],
+    provided: &Value,
+    previous: &Answers,
+) -> Result<Map<String, Value>> {
+    check_answers(questions, provided, previous)
+        .map_err(|problems| DeployerError::Config(problems.join("; ")))
+}
+
+/// Like [`collect_answers`], returning each problem separately for adapters that report them
+/// back to the client.
+pub fn check_answers(
+    questions: &[Question],
+    provided: &Value,
+    previous: &Answers,
+) -> std::result::Result<Map<String, Value>, Vec<String>> {
+    let mut context = previous.clone();
+    let mut accepted = Map::new();
+    let mut problems = Vec::new();
+    for question in questions {
+        if !question.is_active(&context) {
+            continue;
+        }
+        match question.accept(provided.get(&question.id)) {
+            Ok(Some(value)) => {
+                context.insert(question.id.clone(), value.clone());
+                accepted.insert(question.id.clone(), value);
+            }
+            Ok(None) => {}
+            Err(err) => problems.push(err.to_string()),
+        }
+    }
+    if problems.is_empty() {
+        Ok(accepted)
+    } else {
+        Err(problems)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn questions() -> Vec<Question> {
+        serde_json::from_value(json!([
+            { "id": "env", "prompt": "Environment", "type": "enum", "options": ["dev", "prod"] },
+            { "id": "replicas", "prompt": "Replicas", "type": "int", "min": 1, "max": 9, "default": 2 },
+            { "id": "ha", "prompt": "Enable HA?", "type": "bool",
+              "when": { "answer": "env", "equals": "prod" } },
+            { "id": "name", "prompt": "Cluster name", "pattern": "[a-z][a-z0-9-]*", "max": 12 },
+            { "id": "token", "prompt": "Token", "type": "secret", "required": false }
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn normalizes_answers_and_skips_inactive_questions() {
+        let questions = questions();
+        validate_questions([questions.as_slice()]).unwrap();
+
+        let answers = collect_answers(
+            &questions,
+            &json!({ "env": "dev", "replicas": "3", "ha": "yes", "name": "edge-1" }),
+            &Answers::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            Value::Object(answers),
+            json!({ "env": "dev", "replicas": 3, "name": "edge-1" })
+        );
+
+        let answers = collect_answers(
+            &questions,
+            &json!({ "env": "prod", "ha": "y", "name": "edge-1", "token": "s3cr3t" }),
+            &Answers::new(),
+        )
+        .unwrap();
+        assert_eq!(answers["ha"], json!(true));
+        assert_eq!(answers["replicas"], json!(2));
+        assert_eq!(answers["token"], json!("s3cr3t"));
+    }
+
+    #[test]
+    fn reports_every_invalid_answer() {
+        let err = collect_answers(
+            &questions(),
+            &json!({ "env": "qa", "replicas": 12, "name": "Edge_1" }),
+            &Answers::new(),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("'env': expected one of: dev, prod"));
+        assert!(err.contains("'replicas': must be at most 9"));
+        assert!(err.contains("'name': must match pattern"));
+
+        let err = collect_answers(&questions(), &json!({ "env": "prod" }), &Answers::new())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("'ha': an answer is required"));
+    }
+
+    #[test]
+    fn rejects_bad_definitions_and_forward_conditions() {
+        let bad: Vec<Question> = serde_json::from_value(json!([
+            { "id": "size", "prompt": "Size", "type": "enum" }
+        ]))
+        .unwrap();
+        assert!(validate_questions([bad.as_slice()]).is_err());
+
+        let forward: Vec<Question> = serde_json::from_value(json!([
+            { "id": "ha", "prompt": "HA?", "type": "bool", "when": { "answer": "env" } },
+            { "id": "env", "prompt": "Environment" }
+        ]))
+        .unwrap();
+        let err = validate_questions([forward.as_slice()]).unwrap_err();
+        assert!(err.to_string().contains("not asked before it"));
+    }
+}
@@ -0,0 +1,194 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::error::{DeployerError, Result};
+
+const PACK_FILE: &str = "pack.gtpack";
+const CONFIG_PATCH_FILE: &str = "config_patch.json";
+const APPLIED_CONFIG_FILE: &str = "applied_config.json";
+
+/// Parsed `BootstrapState.rollback_ref` (`version=<v>,digest=<d>`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RollbackRef {
+    pub version: String,
+    pub digest: String,
+}
+
+impl RollbackRef {
+    pub fn new(version: Option<&str>, digest: Option<&str>) -> Self {
+        Self {
+            version: version.unwrap_or("unknown").to_string(),
+            digest: digest.unwrap_or("unknown").to_string(),
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self> {
+        let mut version = None;
+        let mut digest = None;
+        for part in value.split(',') {
+            match part.split_once('=') {
+                Some(("version", v)) => version = Some(v.trim().to_string()),
+                Some(("digest", d)) => digest = Some(d.trim().to_string()),
+                _ => {}
+            }
+        }
+        match (version, digest) {
+            (Some(version), Some(digest)) => Ok(Self { version, digest }),
+            _ => Err(DeployerError::Other(format!(
+                "invalid rollback_ref '{value}' (expected version=<v>,digest=<d>)"
+            ))),
+        }
+    }
+
+    /// Directory name used for this release under the releases archive.
+    fn key(&self) -> String {
+        format!("{}-{}", self.version, self.digest)
+            .chars()
+            .map(|ch| {
+                if ch.is_ascii_alphanumeric() || matches!(ch, '.' | '-' | '_') {
+                    ch
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for RollbackRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "version={},digest={}", self.version, self.digest)
+    }
+}
+
+/// Platform release archived after a successful install/upgrade.
+#[derive(Debug, Clone)]
+pub struct ArchivedRelease {
+    pub dir: PathBuf,
+    /// Copy of the platform pack (absent when the pack was installed from a directory).
+    pub pack_path: Option<PathBuf>,
+    pub config_patch: Value,
+    /// Config file contents as written by this release (absent in older archives); the baseline
+    /// for detecting operator edits.
+    pub applied_config: Option<Value>,
+}
+
+/// Releases archive (sibling to bootstrap state).
+pub fn releases_dir(state_path: &Path) -> PathBuf {
+    match state_path.parent() {
+        Some(dir) if dir != Path::new("") => dir.join("releases"),
+        _ => PathBuf::from("releases"),
+    }
+}
+
+/// Keep the applied pack, config patch and resulting config so a later `platform rollback` can
+/// restore them.
+pub fn archive_release(
+    state_path: &Path,
+    release: &RollbackRef,
+    pack_path: &Path,
+    config_patch: &Value,
+    applied_config: &Value,
+) -> Result<ArchivedRelease> {
+    let dir = releases_dir(state_path).join(release.key());
+    fs::create_dir_all(&dir)?;
+    let archived_pack = if pack_path.is_file() {
+        let dest = dir.join(PACK_FILE);
+        fs::copy(pack_path, &dest)?;
+        Some(dest)
+    } else {
+        None
+    };
+    let rendered = serde_json::to_string_pretty(config_patch)
+        .map_err(|err| DeployerError::Other(err.to_string()))?;
+    fs::write(dir.join(CONFIG_PATCH_FILE), rendered)?;
+    let rendered = serde_json::to_string_pretty(applied_config)
+        .map_err(|err| DeployerError::Other(err.to_string()))?;
+    fs::write(dir.join(APPLIED_CONFIG_FILE), rendered)?;
+    Ok(ArchivedRelease {
+        dir,
+        pack_path: archived_pack,
+        config_patch: config_patch.clone(),
+        applied_config: Some(applied_config.clone()),
+    })
+}
+
+/// Load the archived release named by a rollback reference.
+pub fn load_release(state_path: &Path, release: &RollbackRef) -> Result<ArchivedRelease> {
+    let dir = releases_dir(state_path).join(release.key());
+    let patch_path = dir.join(CONFIG_PATCH_FILE);
+    if !patch_path.is_file() {
+        return Err(DeployerError::Other(format!(
+            "no archived platform release for {release} at {}",
+            dir.display()
+        )));
+    }
+    let data = fs::read_to_string(&patch_path)?;
+    let config_patch =
+        serde_json::from_str(&data).map_err(|err| DeployerError::Other(err.to_string()))?;
+    let applied_path = dir.join(APPLIED_CONFIG_FILE);
+    let applied_config = if applied_path.is_file() {
+        let data = fs::read_to_string(&applied_path)?;
+        Some(serde_json::from_str(&data).map_err(|err| DeployerError::Other(err.to_string()))?)
+    } else {
+        None
+    };
+    let pack = dir.join(PACK_FILE);
+    Ok(ArchivedRelease {
+        pack_path: pack.is_file().then_some(pack),
+        dir,
+        config_patch,
+        applied_config,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    #[test]
+    fn parses_and_renders_rollback_ref() {
+        let parsed = RollbackRef::parse("version=1.2.0,digest=sha256:abc").unwrap();
+        assert_eq!(parsed.version, "1.2.0");
+        assert_eq!(parsed.digest, "sha256:abc");
+        assert_eq!(parsed.to_string(), "version=1.2.0,digest=sha256:abc");
+        assert!(RollbackRef::parse("1.2.0").is_err());
+    }
+
+    #[test]
+    fn archives_and_loads_release() {
+        let dir = tempdir().unwrap();
+        let state_path = dir.path().join("bootstrap/state.json");
+        let pack = dir.path().join("platform.gtpack");
+        fs::write(&pack, b"pack-bytes").unwrap();
+        let release = RollbackRef::new(Some("1.0.0"), Some("sha256:abc"));
+        let missing = load_release(&state_path, &release).unwrap_err();
+        assert!(missing.to_string().contains("no archived platform release"));
+
+        archive_release(
+            &state_path,
+            &release,
+            &pack,
+            &json!({"mode": "v1"}),
+            &json!({"mode": "v1", "operator": true}),
+        )
+        .unwrap();
+        let loaded = load_release(&state_path, &release).unwrap();
+        assert_eq!(loaded.config_patch, json!({"mode": "v1"}));
+        assert_eq!(
+            loaded.applied_config,
+            Some(json!({"mode": "v1", "operator": true}))
+        );
+        assert_eq!(fs::read(loaded.pack_path.unwrap()).unwrap(), b"pack-bytes");
+        assert!(
+            loaded
+                .dir
+                .starts_with(dir.path().join("bootstrap/releases"))
+        );
+    }
+}
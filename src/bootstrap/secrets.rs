@@ -1,20 +1,86 @@
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
 use base64::{Engine as _, engine::general_purpose};
+use greentic_secrets::core::{
+    DefaultResolver, Error as CoreError, ResolverConfig, Scope, SecretUri, SecretsError,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value, json};
 use std::env;
-use std::sync::Mutex;
 use std::sync::OnceLock;
+use std::sync::{Arc, Mutex};
 
+use reqwest::Method;
+
+use crate::bootstrap::blocking::block_on;
+use crate::bootstrap::encrypted_file::{self, KeySource, PASSPHRASE_ENV};
+use crate::bootstrap::k8s_client::{K8sConnection, status_message};
 use crate::bootstrap::output::SecretWrite;
+use crate::bootstrap::vault_client::VaultClient;
 use crate::error::{DeployerError, Result};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SecretsBackend {
     File(PathBuf),
-    K8s { namespace: String, name: String },
+    /// File store with each value encrypted at rest (see `encrypted_file`).
+    EncryptedFile {
+        path: PathBuf,
+        key: KeySource,
+    },
+    /// Kubernetes Secret written through the API (credentials from `kubeconfig`, else discovered).
+    /// With `dry_run` the writes are rendered as Secret YAML files instead.
+    K8s {
+        namespace: String,
+        name: String,
+        kubeconfig: Option<PathBuf>,
+        dry_run: bool,
+    },
+    /// greentic-secrets store, through the same resolver as `SecretsContext`. A write scope of
+    /// `env`, `env/tenant` or `env/tenant/team` overrides the leading parts of this default scope.
+    Greentic {
+        env: String,
+        tenant: String,
+        team: Option<String>,
+    },
+    /// HashiCorp Vault KV v2 engine at `mount`; each write is stored at `<prefix>/<scope>/<key>`.
+    /// `addr` falls back to `VAULT_ADDR`.
+    Vault {
+        addr: Option<String>,
+        mount: String,
+        prefix: Option<String>,
+    },
+}
+
+impl SecretsBackend {
+    /// Apply `--k8s-kubeconfig` and `--secrets-dry-run`; non-k8s backends are returned unchanged.
+    pub fn with_k8s_options(self, kubeconfig: Option<PathBuf>, dry_run: bool) -> Self {
+        match self {
+            SecretsBackend::K8s {
+                namespace, name, ..
+            } => SecretsBackend::K8s {
+                namespace,
+                name,
+                kubeconfig,
+                dry_run,
+            },
+            other => other,
+        }
+    }
+
+    /// Apply `--secrets-key-file` to an encrypted file backend; others are returned unchanged.
+    pub fn with_key_file(self, key_file: Option<PathBuf>) -> Self {
+        match (self, key_file) {
+            (SecretsBackend::EncryptedFile { path, .. }, Some(key_file)) => {
+                SecretsBackend::EncryptedFile {
+                    path,
+                    key: KeySource::KeyFile(key_file),
+                }
+            }
+            (backend, _) => backend,
+        }
+    }
 }
 
 static K8S_SECRET_DIR_OVERRIDE: OnceLock<Mutex<Option<PathBuf>>> = OnceLock::new();
@@ -24,9 +90,17 @@ pub fn set_k8s_secret_dir_override(path: Option<PathBuf>) {
     *lock.lock().unwrap() = path;
 }
 
+/// Snapshot value for a Vault path that exists but whose latest version is soft-deleted.
+pub const VAULT_DELETED: &str = "deleted";
+
+/// Backend contents captured before bootstrap writes so a failed install can be rolled back.
 #[derive(Debug, Clone)]
 pub struct SecretsSnapshot {
     pub backend: SecretsBackend,
+    /// File (or rendered YAML) contents, or the JSON `data` map of the Kubernetes Secret;
+    /// `None` when nothing existed yet. Key/value stores (greentic, Vault) record a JSON map of
+    /// each location about to be written to its prior value (`null` if it did not exist, and
+    /// [`VAULT_DELETED`] for a Vault path whose latest version is soft-deleted).
     pub content: Option<String>,
 }
 
@@ -40,6 +114,18 @@ pub fn parse_backend(input: &str) -> Result<SecretsBackend> {
         return Ok(SecretsBackend::File(PathBuf::from(path)));
     }
 
+    if let Some(path) = input.strip_prefix("file+enc:") {
+        if path.is_empty() {
+            return Err(DeployerError::Config(
+                "encrypted file secrets backend requires a path".into(),
+            ));
+        }
+        return Ok(SecretsBackend::EncryptedFile {
+            path: PathBuf::from(path),
+            key: KeySource::PassphraseEnv(PASSPHRASE_ENV.into()),
+        });
+    }
+
     if let Some(rest) = input.strip_prefix("k8s:") {
         if rest.contains('/') {
             let mut parts = rest.splitn(2, '/');
@@ -52,6 +138,8 @@ pub fn parse_backend(input: &str) -> Result<SecretsBackend> {
             return Ok(SecretsBackend::K8s {
                 namespace: ns.to_string(),
                 name: name.to_string(),
+                kubeconfig: None,
+                dry_run: false,
             });
         } else if rest.contains('=') {
             // format: k8s:namespace=ns,name=secret
@@ -76,6 +164,8 @@ pub fn parse_backend(input: &str) -> Result<SecretsBackend> {
             return Ok(SecretsBackend::K8s {
                 namespace: ns,
                 name,
+                kubeconfig: None,
+                dry_run: false,
             });
         } else {
             return Err(DeployerError::Config(
@@ -84,40 +174,139 @@ pub fn parse_backend(input: &str) -> Result<SecretsBackend> {
         }
     }
 
+    if let Some(rest) = input.strip_prefix("greentic:") {
+        let parts: Vec<&str> = rest.split('/').collect();
+        return match parts.as_slice() {
+            [env, tenant] | [env, tenant, _] if !env.is_empty() && !tenant.is_empty() => {
+                Ok(SecretsBackend::Greentic {
+                    env: env.to_string(),
+                    tenant: tenant.to_string(),
+                    team: parts
+                        .get(2)
+                        .filter(|team| !team.is_empty())
+                        .map(|team| team.to_string()),
+                })
+            }
+            _ => Err(DeployerError::Config(
+                "greentic backend expects greentic:<env>/<tenant>[/<team>]".into(),
+            )),
+        };
+    }
+
+    if let Some(rest) = input.strip_prefix("vault:") {
+        let (addr, path) = match rest.split_once("://") {
+            Some((scheme, remainder)) => {
+                let (host, path) = remainder.split_once('/').unwrap_or((remainder, ""));
+                (Some(format!("{scheme}://{host}")), path)
+            }
+            None => (None, rest),
+        };
+        let path = path.trim_matches('/');
+        let (mount, prefix) = path.split_once('/').unwrap_or((path, ""));
+        if mount.is_empty() {
+            return Err(DeployerError::Config(
+                "vault backend expects vault:[http(s)://host:port/]<mount>[/<prefix>]".into(),
+            ));
+        }
+        return Ok(SecretsBackend::Vault {
+            addr,
+            mount: mount.to_string(),
+            prefix: (!prefix.is_empty()).then(|| prefix.to_string()),
+        });
+    }
+
     Err(DeployerError::Config(format!(
         "unsupported secrets backend: {input}"
     )))
 }
 
-pub fn snapshot_backend(backend: &SecretsBackend) -> Option<SecretsSnapshot> {
-    match backend {
-        SecretsBackend::File(path) => {
-            if path.exists() {
-                match fs::read_to_string(path) {
-                    Ok(content) => Some(SecretsSnapshot {
-                        backend: backend.clone(),
-                        content: Some(content),
-                    }),
-                    Err(_) => None,
+/// Capture what `writes` are about to change in `backend`.
+pub fn snapshot_backend(
+    backend: &SecretsBackend,
+    writes: &[SecretWrite],
+) -> Result<SecretsSnapshot> {
+    let content = match backend {
+        SecretsBackend::File(path) => read_optional(path)?,
+        // The ciphertext is kept as is; checking the key first means a wrong key fails before
+        // any write instead of half-way through the install.
+        SecretsBackend::EncryptedFile { path, key } => {
+            encrypted_file::check_key(path, key)?;
+            read_optional(path)?
+        }
+        SecretsBackend::K8s {
+            namespace,
+            name,
+            dry_run: true,
+            ..
+        } => read_optional(&rendered_secret_path(namespace, name))?,
+        SecretsBackend::K8s {
+            namespace,
+            name,
+            kubeconfig,
+            dry_run: false,
+        } => {
+            let connection = K8sConnection::discover(kubeconfig.as_deref())?;
+            read_k8s_secret(&connection, namespace, name)?
+                .map(|secret| serde_json::to_string(&secret["data"]))
+                .transpose()?
+        }
+        SecretsBackend::Greentic { .. } => {
+            let uris = writes
+                .iter()
+                .map(|write| greentic_uri(backend, write))
+                .collect::<Result<Vec<_>>>()?;
+            let prior = block_on(|| async {
+                let mut prior = Map::new();
+                for uri in uris {
+                    let resolver = greentic_resolver(&uri).await?;
+                    let value = match resolver.get_json::<Value>(&uri.to_string()).await {
+                        Ok(value) => value,
+                        Err(err) if is_not_found(&err) => Value::Null,
+                        Err(err) => return Err(DeployerError::Secret(err.to_string())),
+                    };
+                    prior.insert(uri.to_string(), value);
                 }
-            } else {
-                Some(SecretsSnapshot {
-                    backend: backend.clone(),
-                    content: None,
-                })
+                Ok(prior)
+            })?;
+            Some(serde_json::to_string(&prior)?)
+        }
+        SecretsBackend::Vault { addr, mount, .. } => {
+            let client = VaultClient::from_env(addr.as_deref())?;
+            let mut prior = Map::new();
+            for write in writes {
+                let path = vault_path(backend, write);
+                let prior_value = match client.read_kv2(mount, &path)? {
+                    Some(data) => Value::Object(data),
+                    // Reads of a soft-deleted latest version 404 like missing paths do.
+                    None if client.exists_kv2(mount, &path)? => VAULT_DELETED.into(),
+                    None => Value::Null,
+                };
+                prior.insert(path, prior_value);
             }
+            Some(serde_json::to_string(&prior)?)
         }
-        SecretsBackend::K8s { .. } => None,
+    };
+    Ok(SecretsSnapshot {
+        backend: backend.clone(),
+        content,
+    })
+}
+
+fn read_optional(path: &Path) -> Result<Option<String>> {
+    if path.exists() {
+        Ok(Some(fs::read_to_string(path)?))
+    } else {
+        Ok(None)
     }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct StoredSecret {
-    value: String,
+pub(crate) struct StoredSecret {
+    pub(crate) value: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    scope: Option<String>,
+    pub(crate) scope: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    metadata: Option<Value>,
+    pub(crate) metadata: Option<Value>,
 }
 
 pub fn execute_writes(backend: &SecretsBackend, writes: &[SecretWrite]) -> Result<()> {
@@ -126,10 +315,69 @@ pub fn execute_writes(backend: &SecretsBackend, writes: &[SecretWrite]) -> Resul
     }
     match backend {
         SecretsBackend::File(path) => write_file_backend(path, writes),
-        SecretsBackend::K8s { namespace, name } => write_k8s_backend(namespace, name, writes),
+        SecretsBackend::EncryptedFile { path, key } => {
+            let records = writes
+                .iter()
+                .map(|write| Ok((storage_key(write), stored_record(write)?)))
+                .collect::<Result<Vec<_>>>()?;
+            encrypted_file::write_secrets(path, key, records)
+        }
+        SecretsBackend::K8s {
+            namespace,
+            name,
+            dry_run: true,
+            ..
+        } => render_k8s_secret(namespace, name, writes),
+        SecretsBackend::K8s {
+            namespace,
+            name,
+            kubeconfig,
+            dry_run: false,
+        } => write_k8s_secret(kubeconfig.as_deref(), namespace, name, writes),
+        SecretsBackend::Greentic { .. } => {
+            let entries = writes
+                .iter()
+                .map(|write| Ok((greentic_uri(backend, write)?, stored_record(write)?.value)))
+                .collect::<Result<Vec<_>>>()?;
+            // Stored like `SecretsContext::push_to_provider` stores resolved secrets.
+            block_on(|| async {
+                for (uri, value) in entries {
+                    greentic_resolver(&uri)
+                        .await?
+                        .put_json(&uri.to_string(), &value)
+                        .await
+                        .map_err(|err| DeployerError::Secret(err.to_string()))?;
+                }
+                Ok(())
+            })
+        }
+        SecretsBackend::Vault { addr, mount, .. } => {
+            let client = VaultClient::from_env(addr.as_deref())?;
+            for write in writes {
+                let Value::Object(data) = serde_json::to_value(stored_record(write)?)? else {
+                    unreachable!("secret records serialize to objects");
+                };
+                client.write_kv2(mount, &vault_path(backend, write), &data)?;
+            }
+            Ok(())
+        }
     }
 }
 
+fn stored_record(write: &SecretWrite) -> Result<StoredSecret> {
+    let value = write.value.clone().ok_or_else(|| {
+        DeployerError::Config(format!(
+            "secret write '{}' missing value (installers must supply values in bootstrap mode)",
+            write.key
+        ))
+    })?;
+    Ok(StoredSecret {
+        value,
+        scope: write.scope.clone(),
+        metadata: write.metadata.clone(),
+    })
+}
+
 fn write_file_backend(path: &Path, writes: &[SecretWrite]) -> Result<()> {
     let mut store: Map<String, Value> = if path.exists() {
         let content = fs::read_to_string(path)?;
@@ -147,19 +395,10 @@ fn write_file_backend(path: &Path, writes: &[SecretWrite]) -> Result<()> {
     }
 
     for write in writes {
-        let value = write.value.clone().ok_or_else(|| {
-            DeployerError::Config(format!(
-                "secret write '{}' missing value (installers must supply values in bootstrap mode)",
-                write.key
-            ))
-        })?;
-        let storage_key = storage_key(write);
-        let record = StoredSecret {
-            value,
-            scope: write.scope.clone(),
-            metadata: write.metadata.clone(),
-        };
-        store.insert(storage_key, serde_json::to_value(record)?);
+        store.insert(
+            storage_key(write),
+            serde_json::to_value(stored_record(write)?)?,
+        );
     }
 
     let serialized = serde_json::to_string_pretty(&store)?;
@@ -169,23 +408,170 @@ fn write_file_backend(path: &Path, writes: &[SecretWrite]) -> Result<()> {
 
 pub fn restore_backend(snapshot: &SecretsSnapshot) -> Result<()> {
     match &snapshot.backend {
-        SecretsBackend::File(path) => {
-            if let Some(content) = &snapshot.content {
-                if let Some(parent) = path.parent() {
-                    fs::create_dir_all(parent)?;
+        SecretsBackend::File(path) | SecretsBackend::EncryptedFile { path, .. } => {
+            restore_file(path, snapshot.content.as_deref())
+        }
+        SecretsBackend::K8s {
+            namespace,
+            name,
+            dry_run: true,
+            ..
+        } => restore_file(
+            &rendered_secret_path(namespace, name),
+            snapshot.content.as_deref(),
+        ),
+        SecretsBackend::K8s {
+            namespace,
+            name,
+            kubeconfig,
+            dry_run: false,
+        } => {
+            let prior: Option<Map<String, Value>> = snapshot
+                .content
+                .as_deref()
+                .map(serde_json::from_str::<Value>)
+                .transpose()?
+                .map(|data| data.as_object().cloned().unwrap_or_default());
+            let connection = K8sConnection::discover(kubeconfig.as_deref())?;
+            update_k8s_secret(&connection, namespace, name, |_| prior.clone())
+        }
+        SecretsBackend::Greentic { .. } => {
+            let prior = snapshot_entries(snapshot)?;
+            block_on(|| async {
+                for (uri, value) in prior {
+                    let parsed = SecretUri::try_from(uri.as_str())
+                        .map_err(|err| DeployerError::Secret(err.to_string()))?;
+                    let resolver = greentic_resolver(&parsed).await?;
+                    let result = match value {
+                        Value::Null => match resolver.delete(&uri).await {
+                            Err(err) if is_not_found(&err) => Ok(()),
+                            other => other,
+                        },
+                        value => resolver.put_json(&uri, &value).await.map(|_| ()),
+                    };
+                    result.map_err(|err| DeployerError::Secret(err.to_string()))?;
+                }
+                Ok(())
+            })
+        }
+        SecretsBackend::Vault { addr, mount, .. } => {
+            let client = VaultClient::from_env(addr.as_deref())?;
+            for (path, data) in snapshot_entries(snapshot)? {
+                match data {
+                    Value::Object(data) => client.write_kv2(mount, &path, &data)?,
+                    // Keep the version history; only hide the version the install wrote.
+                    Value::String(marker) if marker == VAULT_DELETED => {
+                        client.delete_kv2(mount, &path)?
+                    }
+                    _ => client.destroy_kv2(mount, &path)?,
                 }
-                fs::write(path, content)?;
-            } else if path.exists() {
-                fs::remove_file(path)?;
             }
             Ok(())
         }
-        SecretsBackend::K8s { .. } => Err(DeployerError::Other(
-            "k8s secrets backend restore not implemented in stub".into(),
+    }
+}
+
+/// Re-encrypt an encrypted file backend under `new_key`; returns the number of secrets.
+pub fn rotate_key(backend: &SecretsBackend, new_key: &KeySource) -> Result<usize> {
+    match backend {
+        SecretsBackend::EncryptedFile { path, key } => {
+            encrypted_file::rotate_file_key(path, key, new_key)
+        }
+        _ => Err(DeployerError::Config(
+            "key rotation needs an encrypted file secrets backend (file+enc:<path>)".into(),
         )),
     }
 }
 
+fn snapshot_entries(snapshot: &SecretsSnapshot) -> Result<Map<String, Value>> {
+    match snapshot.content.as_deref() {
+        Some(content) => Ok(serde_json::from_str(content)?),
+        None => Ok(Map::new()),
+    }
+}
+
+/// Resolvers only accept their own tenant, so one is kept per tenant for the whole process:
+/// snapshot, writes and restore must all see the same store.
+async fn greentic_resolver(uri: &SecretUri) -> Result<Arc<DefaultResolver>> {
+    static RESOLVERS: OnceLock<tokio::sync::Mutex<HashMap<String, Arc<DefaultResolver>>>> =
+        OnceLock::new();
+    let tenant = uri.scope().tenant().to_string();
+    let mut resolvers = RESOLVERS.get_or_init(Default::default).lock().await;
+    if let Some(resolver) = resolvers.get(&tenant) {
+        return Ok(resolver.clone());
+    }
+    let resolver = DefaultResolver::from_config(
+        ResolverConfig::from_env()
+            .tenant(tenant.clone())
+            .dev_fallback(false),
+    )
+    .await
+    .map_err(|err| DeployerError::Secret(err.to_string()))?;
+    let resolver = Arc::new(resolver);
+    resolvers.insert(tenant, resolver.clone());
+    Ok(resolver)
+}
+
+fn is_not_found(err: &SecretsError) -> bool {
+    matches!(err, SecretsError::Core(CoreError::NotFound { .. }))
+}
+
+fn greentic_uri(backend: &SecretsBackend, write: &SecretWrite) -> Result<SecretUri> {
+    let SecretsBackend::Greentic { env, tenant, team } = backend else {
+        unreachable!("greentic_uri is only used for the greentic backend");
+    };
+    let (env, tenant, team) = match write.scope.as_deref() {
+        None => (env.clone(), tenant.clone(), team.clone()),
+        Some(scope) => match scope.split('/').collect::<Vec<_>>().as_slice() {
+            [scope_env] => (scope_env.to_string(), tenant.clone(), team.clone()),
+            [scope_env, scope_tenant] => (scope_env.to_string(), scope_tenant.to_string(), None),
+            [scope_env, scope_tenant, scope_team] => (
+                scope_env.to_string(),
+                scope_tenant.to_string(),
+                Some(scope_team.to_string()),
+            ),
+            _ => {
+                return Err(DeployerError::Config(format!(
+                    "secret write '{}' has scope '{scope}'; expected env[/tenant[/team]]",
+                    write.key
+                )));
+            }
+        },
+    };
+    let scope =
+        Scope::new(env, tenant, team).map_err(|err| DeployerError::Secret(err.to_string()))?;
+    SecretUri::new(scope, "configs", &write.key)
+        .map_err(|err| DeployerError::Secret(err.to_string()))
+}
+
+fn vault_path(backend: &SecretsBackend, write: &SecretWrite) -> String {
+    let prefix = match backend {
+        SecretsBackend::Vault {
+            prefix: Some(prefix),
+            ..
+        } => Some(prefix.as_str()),
+        _ => None,
+    };
+    prefix
+        .into_iter()
+        .chain(write.scope.as_deref())
+        .chain([write.key.as_str()])
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn restore_file(path: &Path, content: Option<&str>) -> Result<()> {
+    if let Some(content) = content {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, content)?;
+    } else if path.exists() {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
 fn storage_key(write: &SecretWrite) -> String {
     match write.scope.as_ref() {
         Some(scope) => format!("{}/{}", scope, write.key),
@@ -193,7 +579,135 @@ fn storage_key(write: &SecretWrite) -> String {
     }
 }
 
-fn write_k8s_backend(namespace: &str, name: &str, writes: &[SecretWrite]) -> Result<()> {
+/// Secret data keys must match `[-._a-zA-Z0-9]+`: the scope separator becomes `.` and any other
+/// character `_` (e.g. scope `dev`, key `api_key` is stored as `dev.api_key`).
+fn k8s_key(write: &SecretWrite) -> String {
+    storage_key(write)
+        .chars()
+        .map(|ch| match ch {
+            '/' => '.',
+            ch if ch.is_ascii_alphanumeric() || matches!(ch, '-' | '.' | '_') => ch,
+            _ => '_',
+        })
+        .collect()
+}
+
+/// Base64-encoded Secret `data` entries for `writes`.
+fn k8s_entries(writes: &[SecretWrite]) -> Result<Map<String, Value>> {
+    writes
+        .iter()
+        .map(|write| {
+            let value = write.value.as_ref().ok_or_else(|| {
+                DeployerError::Config(format!(
+                    "secret write '{}' missing value (installers must supply values in bootstrap mode)",
+                    write.key
+                ))
+            })?;
+            Ok((
+                k8s_key(write),
+                Value::String(general_purpose::STANDARD.encode(value.as_bytes())),
+            ))
+        })
+        .collect()
+}
+
+fn write_k8s_secret(
+    kubeconfig: Option<&Path>,
+    namespace: &str,
+    name: &str,
+    writes: &[SecretWrite],
+) -> Result<()> {
+    let entries = k8s_entries(writes)?;
+    let connection = K8sConnection::discover(kubeconfig)?;
+    update_k8s_secret(&connection, namespace, name, |data| {
+        let mut data = data.unwrap_or_default();
+        data.extend(entries.clone());
+        Some(data)
+    })
+}
+
+fn secret_path(namespace: &str, name: &str) -> String {
+    format!("/api/v1/namespaces/{namespace}/secrets/{name}")
+}
+
+fn read_k8s_secret(
+    connection: &K8sConnection,
+    namespace: &str,
+    name: &str,
+) -> Result<Option<Value>> {
+    let (status, body) = connection.request(Method::GET, &secret_path(namespace, name), None)?;
+    match status {
+        200 => Ok(Some(body)),
+        404 => Ok(None),
+        _ => Err(DeployerError::Other(format!(
+            "failed to read secret {namespace}/{name}: HTTP {status}: {}",
+            status_message(&body)
+        ))),
+    }
+}
+
+/// Read-modify-write of the Secret's `data`: `change` maps the current data (`None` when the
+/// Secret does not exist) to the new data (`None` deletes the Secret). Unrelated keys are kept
+/// by `change`, and the write carries the read `resourceVersion`, so it is retried from a fresh
+/// read when someone else updated the Secret in between.
+fn update_k8s_secret(
+    connection: &K8sConnection,
+    namespace: &str,
+    name: &str,
+    change: impl Fn(Option<Map<String, Value>>) -> Option<Map<String, Value>>,
+) -> Result<()> {
+    const ATTEMPTS: usize = 3;
+    let path = secret_path(namespace, name);
+    for _ in 0..ATTEMPTS {
+        let current = read_k8s_secret(connection, namespace, name)?;
+        let data = current
+            .as_ref()
+            .map(|secret| secret["data"].as_object().cloned().unwrap_or_default());
+        let (method, target, body) = match (current, change(data)) {
+            (None, None) => return Ok(()),
+            (Some(_), None) => (Method::DELETE, path.clone(), None),
+            (Some(mut secret), Some(data)) => {
+                secret["data"] = Value::Object(data);
+                (Method::PUT, path.clone(), Some(secret))
+            }
+            (None, Some(data)) => (
+                Method::POST,
+                format!("/api/v1/namespaces/{namespace}/secrets"),
+                Some(json!({
+                    "apiVersion": "v1",
+                    "kind": "Secret",
+                    "type": "Opaque",
+                    "metadata": {
+                        "name": name,
+                        "namespace": namespace,
+                        "annotations": { "managed-by": "greentic-deployer" }
+                    },
+                    "data": data
+                })),
+            ),
+        };
+        let deleting = method == Method::DELETE;
+        let (status, body) = connection.request(method, &target, body)?;
+        match status {
+            200..=299 => return Ok(()),
+            404 if deleting => return Ok(()),
+            // Changed, created or deleted concurrently: start over from a fresh read.
+            404 | 409 => continue,
+            _ => {
+                return Err(DeployerError::Other(format!(
+                    "failed to write secret {namespace}/{name}: HTTP {status}: {}",
+                    status_message(&body)
+                )));
+            }
+        }
+    }
+    Err(DeployerError::Other(format!(
+        "secret {namespace}/{name} kept changing concurrently; gave up after {ATTEMPTS} attempts"
+    )))
+}
+
+/// Location of the Secret YAML rendered in dry-run mode.
+fn rendered_secret_path(namespace: &str, name: &str) -> PathBuf {
     let override_dir = K8S_SECRET_DIR_OVERRIDE
         .get()
         .and_then(|lock| lock.lock().ok().and_then(|v| v.clone()));
@@ -201,22 +715,17 @@ fn write_k8s_backend(namespace: &str, name: &str, writes: &[SecretWrite]) -> Res
         .map(|p| p.to_string_lossy().to_string())
         .or_else(|| env::var("GREENTIC_K8S_SECRET_DIR").ok())
         .unwrap_or_else(|| "/tmp/greentic-k8s-secrets".to_string());
-    let secret_dir = Path::new(&base_dir).join(namespace);
-    fs::create_dir_all(&secret_dir)?;
-    let path = secret_dir.join(format!("{name}.yaml"));
+    Path::new(&base_dir)
+        .join(namespace)
+        .join(format!("{name}.yaml"))
+}
 
-    let mut data = serde_json::Map::new();
-    for write in writes {
-        let value = write.value.clone().ok_or_else(|| {
-            DeployerError::Config(format!(
-                "secret write '{}' missing value (installers must supply values in bootstrap mode)",
-                write.key
-            ))
-        })?;
-        let key = storage_key(write);
-        let encoded = general_purpose::STANDARD.encode(value.as_bytes());
-        data.insert(key, Value::String(encoded));
+fn render_k8s_secret(namespace: &str, name: &str, writes: &[SecretWrite]) -> Result<()> {
+    let path = rendered_secret_path(namespace, name);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
     }
+    let data = k8s_entries(writes)?;
 
     // Build a minimal Secret manifest
     let manifest = json!({
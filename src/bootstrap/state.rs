@@ -1,11 +1,13 @@
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use semver::Version;
 use serde::{Deserialize, Serialize};
 
-use crate::config::BootstrapStateBackend;
+use crate::bootstrap::k8s_state::{K8sStateStore, K8sStateTarget};
+use crate::bootstrap::releases::RollbackRef;
+use crate::config::{BootstrapStateBackend, K8sStateKind};
 use crate::error::{DeployerError, Result};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
@@ -45,6 +47,18 @@ impl BootstrapState {
             rollback_ref,
         }
     }
+
+    /// State after `platform rollback` restored `target`; the new `rollback_ref` points back at
+    /// the release that was replaced.
+    pub fn rolled_back_to(current: &BootstrapState, target: &RollbackRef) -> Self {
+        let replaced = RollbackRef::new(current.version.as_deref(), current.digest.as_deref());
+        Self::upgraded_from(
+            current,
+            Some(target.version.clone()),
+            (target.digest != "unknown").then(|| target.digest.clone()),
+            Some(replaced.to_string()),
+        )
+    }
 }
 
 pub fn load_state(path: &Path) -> Result<Option<BootstrapState>> {
@@ -67,32 +81,78 @@ pub fn save_state(path: &Path, state: &BootstrapState) -> Result<()> {
     Ok(())
 }
 
+/// Bootstrap state storage selected by `--bootstrap-state-backend`.
+pub enum StateStore {
+    File(PathBuf),
+    K8s(K8sStateStore),
+}
+
+impl StateStore {
+    /// Open the backend; for k8s this resolves cluster credentials (see
+    /// [`crate::bootstrap::k8s_client::K8sConnection::discover`]).
+    pub fn open(
+        backend: BootstrapStateBackend,
+        file_path: &Path,
+        k8s: &K8sStateTarget,
+    ) -> Result<Self> {
+        match backend {
+            BootstrapStateBackend::File => Ok(Self::File(file_path.to_path_buf())),
+            BootstrapStateBackend::K8s => K8sStateStore::open(k8s).map(Self::K8s),
+        }
+    }
+
+    pub fn load(&self) -> Result<Option<BootstrapState>> {
+        match self {
+            Self::File(path) => load_state(path),
+            Self::K8s(store) => store.load(),
+        }
+    }
+
+    /// Persist `state`. The k8s backend refuses to overwrite changes made by another installer
+    /// since this store last loaded the state.
+    pub fn save(&self, state: &BootstrapState) -> Result<()> {
+        match self {
+            Self::File(path) => save_state(path, state),
+            Self::K8s(store) => store.save(state),
+        }
+    }
+
+    /// Human-readable location for messages.
+    pub fn describe(&self) -> String {
+        match self {
+            Self::File(path) => path.display().to_string(),
+            Self::K8s(store) => store.describe(),
+        }
+    }
+}
+
+/// One-shot load using a ConfigMap and discovered credentials for the k8s backend.
 pub fn load_state_backend(
     backend: BootstrapStateBackend,
     file_path: &Path,
-    _namespace: &str,
-    _name: &str,
+    namespace: &str,
+    name: &str,
 ) -> Result<Option<BootstrapState>> {
-    match backend {
-        BootstrapStateBackend::File => load_state(file_path),
-        BootstrapStateBackend::K8s => Err(DeployerError::Other(
-            "k8s bootstrap state backend not available in this build".into(),
-        )),
-    }
+    StateStore::open(backend, file_path, &default_target(namespace, name))?.load()
 }
 
+/// One-shot save using a ConfigMap and discovered credentials for the k8s backend.
 pub fn save_state_backend(
     backend: BootstrapStateBackend,
     file_path: &Path,
-    _namespace: &str,
-    _name: &str,
+    namespace: &str,
+    name: &str,
     state: &BootstrapState,
 ) -> Result<()> {
-    match backend {
-        BootstrapStateBackend::File => save_state(file_path, state),
-        BootstrapStateBackend::K8s => Err(DeployerError::Other(
-            "k8s bootstrap state backend not available in this build".into(),
-        )),
+    StateStore::open(backend, file_path, &default_target(namespace, name))?.save(state)
+}
+
+fn default_target(namespace: &str, name: &str) -> K8sStateTarget {
+    K8sStateTarget {
+        namespace: namespace.to_string(),
+        name: name.to_string(),
+        kind: K8sStateKind::ConfigMap,
+        kubeconfig: None,
     }
 }
 
@@ -0,0 +1,251 @@
+//! Substitution of prompt answers into `installer_call` results.
+//!
+//! Syntax:
+//! - `{{id}}` (surrounding whitespace allowed, e.g. `{{ id }}`) is replaced by the answer to
+//!   question `id`; `{{a.b}}` walks into object answers.
+//! - Ids may contain ASCII letters, digits, `_` and `-`; `.` separates path segments.
+//! - A config patch value consisting of exactly one placeholder takes the answer's JSON type
+//!   (numbers, booleans, objects); anywhere else non-string answers are rendered as JSON text.
+//! - `\{{` produces a literal `{{` (written `"\\{{"` inside JSON documents).
+//! - Placeholders without an answer are reported together as an error; an unterminated `{{` is
+//!   an error as well.
+
+use serde_json::{Map, Value};
+
+use crate::bootstrap::output::BootstrapOutput;
+use crate::error::{DeployerError, Result};
+
+/// Answers accumulated across prompt steps, keyed by question id.
+pub type Answers = Map<String, Value>;
+
+enum Segment<'a> {
+    Literal(String),
+    Placeholder(&'a str),
+}
+
+/// Substitute answers into the config patch, secret write values/scopes and warnings.
+pub fn render_output(output: &mut BootstrapOutput, answers: &Answers) -> Result<()> {
+    let mut renderer = Renderer {
+        answers,
+        unresolved: Vec::new(),
+    };
+    renderer.render_value(&mut output.config_patch, "config_patch".into())?;
+    for (idx, write) in output.secrets_writes.iter_mut().enumerate() {
+        if let Some(value) = &mut write.value {
+            *value = renderer.render_str(value, &format!("secrets_writes[{idx}].value"))?;
+        }
+        if let Some(scope) = &mut write.scope {
+            *scope = renderer.render_str(scope, &format!("secrets_writes[{idx}].scope"))?;
+        }
+    }
+    for (idx, warning) in output.warnings.iter_mut().enumerate() {
+        *warning = renderer.render_str(warning, &format!("warnings[{idx}]"))?;
+    }
+    if renderer.unresolved.is_empty() {
+        Ok(())
+    } else {
+        Err(DeployerError::Other(format!(
+            "unresolved placeholder(s) in installer_call result: {}",
+            renderer.unresolved.join(", ")
+        )))
+    }
+}
+
+struct Renderer<'a> {
+    answers: &'a Answers,
+    unresolved: Vec<String>,
+}
+
+impl Renderer<'_> {
+    fn render_value(&mut self, value: &mut Value, location: String) -> Result<()> {
+        match value {
+            Value::String(template) => {
+                let segments = parse(template, &location)?;
+                if let [Segment::Placeholder(name)] = segments.as_slice() {
+                    if let Some(answer) = self.lookup(name, &location) {
+                        *value = answer.clone();
+                    }
+                } else {
+                    *value = Value::String(self.join(&segments, &location));
+                }
+            }
+            Value::Array(items) => {
+                for (idx, item) in items.iter_mut().enumerate() {
+                    self.render_value(item, format!("{location}[{idx}]"))?;
+                }
+            }
+            Value::Object(map) => {
+                for (key, item) in map.iter_mut() {
+                    self.render_value(item, format!("{location}.{key}"))?;
+                }
+            }
+            Value::Null | Value::Bool(_) | Value::Number(_) => {}
+        }
+        Ok(())
+    }
+
+    fn render_str(&mut self, template: &str, location: &str) -> Result<String> {
+        let segments = parse(template, location)?;
+        Ok(self.join(&segments, location))
+    }
+
+    fn join(&mut self, segments: &[Segment<'_>], location: &str) -> String {
+        let mut out = String::new();
+        for segment in segments {
+            match segment {
+                Segment::Literal(text) => out.push_str(text),
+                Segment::Placeholder(name) => match self.lookup(name, location) {
+                    Some(Value::String(text)) => out.push_str(text),
+                    Some(other) => out.push_str(&other.to_string()),
+                    None => {}
+                },
+            }
+        }
+        out
+    }
+
+    fn lookup(&mut self, name: &str, location: &str) -> Option<&Value> {
+        let mut parts = name.split('.');
+        let mut current = parts.next().and_then(|first| self.answers.get(first));
+        for part in parts {
+            current = current.and_then(|value| value.get(part));
+        }
+        if current.is_none() {
+            self.unresolved
+                .push(format!("{{{{{name}}}}} at {location}"));
+        }
+        current
+    }
+}
+
+fn parse<'a>(template: &'a str, location: &str) -> Result<Vec<Segment<'a>>> {
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        if rest[..start].ends_with('\\') {
+            literal.push_str(&rest[..start - 1]);
+            literal.push_str("{{");
+            rest = &rest[start + 2..];
+            continue;
+        }
+        literal.push_str(&rest[..start]);
+        let body = &rest[start + 2..];
+        let end = body.find("}}").ok_or_else(|| {
+            DeployerError::Other(format!(
+                "unterminated placeholder at {location}: {template}"
+            ))
+        })?;
+        let name = body[..end].trim();
+        let valid = !name.is_empty()
+            && name.split('.').all(|part| {
+                !part.is_empty()
+                    && part
+                        .chars()
+                        .all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-')
+            });
+        if !valid {
+            return Err(DeployerError::Other(format!(
+                "invalid placeholder '{{{{{}}}}}' at {location}",
+                &body[..end]
+            )));
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(std::mem::take(&mut literal)));
+        }
+        segments.push(Segment::Placeholder(name));
+        rest = &body[end + 2..];
+    }
+    literal.push_str(rest);
+    if !literal.is_empty() {
+        segments.push(Segment::Literal(literal));
+    }
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bootstrap::output::SecretWrite;
+    use serde_json::json;
+
+    fn answers() -> Answers {
+        json!({
+            "region": "eu-west-1",
+            "replicas": 3,
+            "db": { "host": "db.internal" }
+        })
+        .as_object()
+        .cloned()
+        .unwrap()
+    }
+
+    fn output(config_patch: Value) -> BootstrapOutput {
+        BootstrapOutput::new(
+            config_patch,
+            vec![SecretWrite {
+                key: "platform/token".into(),
+                value: Some("token-{{region}}".into()),
+                scope: Some("{{ region }}".into()),
+                metadata: None,
+            }],
+            vec!["Deployed {{replicas}} replicas in {{region}}".into()],
+            true,
+        )
+    }
+
+    #[test]
+    fn substitutes_answers_with_types_and_paths() {
+        let mut out = output(json!({
+            "region": "{{region}}",
+            "replicas": "{{replicas}}",
+            "url": "postgres://{{db.host}}:5432",
+            "list": ["{{ region }}", "static"],
+            "escaped": "\\{{region}}"
+        }));
+        render_output(&mut out, &answers()).unwrap();
+        assert_eq!(out.config_patch["region"], json!("eu-west-1"));
+        assert_eq!(out.config_patch["replicas"], json!(3));
+        assert_eq!(
+            out.config_patch["url"],
+            json!("postgres://db.internal:5432")
+        );
+        assert_eq!(out.config_patch["list"], json!(["eu-west-1", "static"]));
+        assert_eq!(out.config_patch["escaped"], json!("{{region}}"));
+        assert_eq!(
+            out.secrets_writes[0].value.as_deref(),
+            Some("token-eu-west-1")
+        );
+        assert_eq!(out.secrets_writes[0].scope.as_deref(), Some("eu-west-1"));
+        assert_eq!(out.warnings[0], "Deployed 3 replicas in eu-west-1");
+    }
+
+    #[test]
+    fn reports_every_unresolved_placeholder() {
+        let mut out = output(json!({ "zone": "{{zone}}", "name": "{{db.name}}" }));
+        let err = render_output(&mut out, &Answers::new()).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("{{zone}} at config_patch.zone"));
+        assert!(message.contains("{{db.name}} at config_patch.name"));
+        assert!(message.contains("{{region}} at secrets_writes[0].value"));
+        assert!(message.contains("{{replicas}} at warnings[0]"));
+    }
+
+    #[test]
+    fn rejects_malformed_placeholders() {
+        let mut out = output(json!({ "bad": "{{region" }));
+        assert!(
+            render_output(&mut out, &answers())
+                .unwrap_err()
+                .to_string()
+                .contains("unterminated placeholder")
+        );
+        let mut out = output(json!({ "bad": "{{ two words }}" }));
+        assert!(
+            render_output(&mut out, &answers())
+                .unwrap_err()
+                .to_string()
+                .contains("invalid placeholder")
+        );
+    }
+}
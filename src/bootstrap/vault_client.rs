@@ -0,0 +1,177 @@
+//! Minimal HashiCorp Vault client for the KV version 2 secrets engine.
+//!
+//! Connection settings follow the Vault CLI environment: `VAULT_ADDR` (unless the backend URI
+//! names the address), `VAULT_TOKEN`, `VAULT_NAMESPACE` and `VAULT_CACERT`.
+
+use std::env;
+use std::fs;
+use std::time::Duration;
+
+use reqwest::Method;
+use reqwest::blocking::Client;
+use reqwest::redirect::Policy;
+use serde_json::{Map, Value, json};
+
+use crate::bootstrap::blocking::off_runtime;
+use crate::error::{DeployerError, Result};
+
+#[derive(Debug, Clone, Default)]
+pub struct VaultClient {
+    pub addr: String,
+    pub token: Option<String>,
+    pub namespace: Option<String>,
+    /// PEM bundle trusted for the Vault server certificate.
+    pub ca_pem: Option<Vec<u8>>,
+}
+
+impl VaultClient {
+    /// Build a client from the Vault CLI environment; `addr` overrides `VAULT_ADDR`.
+    pub fn from_env(addr: Option<&str>) -> Result<Self> {
+        let addr = match addr {
+            Some(addr) => addr.to_string(),
+            None => env::var("VAULT_ADDR").map_err(|_| {
+                DeployerError::Config(
+                    "vault secrets backend needs an address (vault:https://host:8200/<mount>/... or VAULT_ADDR)"
+                        .into(),
+                )
+            })?,
+        };
+        let ca_pem = env::var_os("VAULT_CACERT")
+            .map(|path| {
+                fs::read(&path).map_err(|err| {
+                    DeployerError::Config(format!(
+                        "failed to read VAULT_CACERT {}: {err}",
+                        path.to_string_lossy()
+                    ))
+                })
+            })
+            .transpose()?;
+        Ok(Self {
+            addr,
+            token: env::var("VAULT_TOKEN")
+                .ok()
+                .filter(|token| !token.is_empty()),
+            namespace: env::var("VAULT_NAMESPACE").ok().filter(|ns| !ns.is_empty()),
+            ca_pem,
+        })
+    }
+
+    /// Current data of `path` in the KV v2 engine at `mount`, or `None` if it does not exist
+    /// (or its latest version is deleted).
+    pub fn read_kv2(&self, mount: &str, path: &str) -> Result<Option<Map<String, Value>>> {
+        let (status, body) = self.request(Method::GET, &format!("{mount}/data/{path}"), None)?;
+        match status {
+            200 => Ok(body["data"]["data"].as_object().cloned()),
+            404 => Ok(None),
+            _ => Err(self.error("read", mount, path, status, &body)),
+        }
+    }
+
+    /// Whether `path` has metadata, i.e. exists even if its latest version is deleted.
+    pub fn exists_kv2(&self, mount: &str, path: &str) -> Result<bool> {
+        let (status, body) =
+            self.request(Method::GET, &format!("{mount}/metadata/{path}"), None)?;
+        match status {
+            200 => Ok(true),
+            404 => Ok(false),
+            _ => Err(self.error("read", mount, path, status, &body)),
+        }
+    }
+
+    /// Write a new version of `path`.
+    pub fn write_kv2(&self, mount: &str, path: &str, data: &Map<String, Value>) -> Result<()> {
+        let (status, body) = self.request(
+            Method::POST,
+            &format!("{mount}/data/{path}"),
+            Some(json!({ "data": data })),
+        )?;
+        match status {
+            200 | 204 => Ok(()),
+            _ => Err(self.error("write", mount, path, status, &body)),
+        }
+    }
+
+    /// Soft-delete the latest version of `path`; earlier versions and metadata are kept.
+    pub fn delete_kv2(&self, mount: &str, path: &str) -> Result<()> {
+        let (status, body) = self.request(Method::DELETE, &format!("{mount}/data/{path}"), None)?;
+        match status {
+            200 | 204 | 404 => Ok(()),
+            _ => Err(self.error("delete", mount, path, status, &body)),
+        }
+    }
+
+    /// Remove `path` with all of its versions.
+    pub fn destroy_kv2(&self, mount: &str, path: &str) -> Result<()> {
+        let (status, body) =
+            self.request(Method::DELETE, &format!("{mount}/metadata/{path}"), None)?;
+        match status {
+            200 | 204 | 404 => Ok(()),
+            _ => Err(self.error("delete", mount, path, status, &body)),
+        }
+    }
+
+    fn error(
+        &self,
+        action: &str,
+        mount: &str,
+        path: &str,
+        status: u16,
+        body: &Value,
+    ) -> DeployerError {
+        let details = body["errors"]
+            .as_array()
+            .map(|errors| {
+                errors
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            })
+            .filter(|details| !details.is_empty())
+            .unwrap_or_else(|| "no details".into());
+        DeployerError::Other(format!(
+            "failed to {action} vault secret {mount}/{path}: HTTP {status}: {details}"
+        ))
+    }
+
+    fn request(&self, method: Method, path: &str, body: Option<Value>) -> Result<(u16, Value)> {
+        off_runtime(|| {
+            let url = format!("{}/v1/{path}", self.addr.trim_end_matches('/'));
+            let client_error = |err: reqwest::Error| {
+                DeployerError::Other(format!("vault client for {}: {err}", self.addr))
+            };
+            let mut builder = Client::builder()
+                .timeout(Duration::from_secs(15))
+                .redirect(Policy::none());
+            if let Some(ca) = &self.ca_pem {
+                for certificate in
+                    reqwest::Certificate::from_pem_bundle(ca).map_err(client_error)?
+                {
+                    builder = builder.add_root_certificate(certificate);
+                }
+            }
+            let client = builder.build().map_err(client_error)?;
+
+            let mut request = client.request(method, &url);
+            if let Some(token) = &self.token {
+                request = request.header("X-Vault-Token", token);
+            }
+            if let Some(namespace) = &self.namespace {
+                request = request.header("X-Vault-Namespace", namespace);
+            }
+            if let Some(body) = body {
+                request = request.json(&body);
+            }
+            let response = request.send().map_err(|err| {
+                DeployerError::Other(format!("vault request to {url} failed: {err}"))
+            })?;
+            let status = response.status().as_u16();
+            let body = response
+                .text()
+                .ok()
+                .and_then(|text| serde_json::from_str(&text).ok())
+                .unwrap_or(Value::Null);
+            Ok((status, body))
+        })
+    }
+}
@@ -1,5 +1,6 @@
 use std::fs;
 use std::path::PathBuf;
+use std::time::Duration;
 
 use clap::{Args, Parser, Subcommand, ValueEnum};
 use greentic_config::{ConfigFileFormat, ConfigLayer, ConfigResolver, ProvenanceMap};
@@ -11,9 +12,11 @@ use serde::{Deserialize, Serialize};
 
 use crate::error::{DeployerError, Result};
 use crate::iac::{IaCTool, IacToolArg, resolve_iac_tool};
+use crate::platform::{PackVerification, VerificationPolicy};
+use crate::state_backend::StateBackendConfig;
 
 /// Available CLI actions.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Action {
     Plan,
     Apply,
@@ -130,10 +133,34 @@ pub struct ActionArgs {
     #[arg(long, default_value_t = false)]
     pub dry_run: bool,
 
+    /// Allow applies whose IaC plan deletes or replaces resources.
+    #[arg(long, default_value_t = false)]
+    pub allow_destroy: bool,
+
+    /// Kill any IaC step (init/plan/apply/destroy) running longer than this many seconds.
+    #[arg(long, value_name = "SECONDS")]
+    pub iac_timeout: Option<u64>,
+
+    /// YAML/JSON file describing the Terraform/OpenTofu state backend (defaults to local state).
+    #[arg(long, env = "GREENTIC_STATE_BACKEND")]
+    pub state_backend: Option<PathBuf>,
+
     /// IaC tool to use (tf/terraform or tofu/opentofu).
     #[arg(long, value_enum)]
     pub iac_tool: Option<IacToolArg>,
 
+    /// Verify application and deployment pack signatures (warnings on missing signatures).
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+    pub verify: bool,
+
+    /// Fail when pack signatures are missing or invalid, or signed by unknown or expired keys.
+    #[arg(long, default_value_t = false)]
+    pub strict_verify: bool,
+
+    /// Trust store of publisher keys (defaults to /var/lib/greentic/bootstrap/trusted_keys.json).
+    #[arg(long, env = "GREENTIC_TRUST_STORE")]
+    pub trust_store: Option<PathBuf>,
+
     /// Output format for plan/rendering (text|json|yaml).
     #[arg(long, value_enum, default_value = "text")]
     pub output: OutputFormat,
@@ -181,13 +208,317 @@ pub enum Command {
     Apply(ActionArgs),
     /// Destroy resources created by apply.
     Destroy(ActionArgs),
-    /// Platform bootstrap commands (install/upgrade/status).
-    Platform(PlatformArgs),
+    /// Platform bootstrap commands (install/upgrade/status/rollback).
+    Platform(Box<PlatformArgs>),
     /// Provider onboarding commands.
     Provider {
         #[command(subcommand)]
         command: ProviderArgs,
     },
+    /// List or inspect the deployment ledger for a tenant/environment.
+    History(HistoryArgs),
+    /// Compare the plan built from a pack with the last persisted plan (or two plan files).
+    Diff(DiffArgs),
+    /// Restore the artifacts of a previous successful apply and re-run the IaC apply.
+    Rollback(RollbackArgs),
+    /// Detect infrastructure drift (refresh-only plan) and pack drift for an applied environment.
+    Drift(DriftArgs),
+    /// Run plan/apply/destroy for several tenant/environment targets with bounded concurrency.
+    Batch(BatchArgs),
+    /// Apply the exact pack deployed in one environment to another environment.
+    Promote(PromoteArgs),
+    /// Pack distribution commands.
+    Pack {
+        #[command(subcommand)]
+        command: PackArgs,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct DiffArgs {
+    /// Compare two persisted plan JSON files instead of building a plan from a pack.
+    #[arg(long, num_args = 2, value_names = ["FROM", "TO"], conflicts_with = "pack")]
+    pub files: Option<Vec<PathBuf>>,
+    /// Deployment target used to build the candidate plan.
+    #[arg(long, value_enum, required_unless_present = "files")]
+    pub provider: Option<Provider>,
+    /// Deployment strategy identifier.
+    #[arg(long, default_value = "iac-only")]
+    pub strategy: String,
+    /// Tenant identifier (e.g. acme).
+    #[arg(long, required_unless_present = "files")]
+    pub tenant: Option<String>,
+    /// Environment name (defaults to greentic-config environment).
+    #[arg(long)]
+    pub environment: Option<String>,
+    /// Path to a .greentic-pack archive or a pack directory.
+    #[arg(long, required_unless_present = "files")]
+    pub pack: Option<PathBuf>,
+    /// Baseline plan file (defaults to `runtime/<tenant>/<env>/plan.json` under the state dir).
+    #[arg(long, conflicts_with = "files")]
+    pub against: Option<PathBuf>,
+    /// Output format (text|json|yaml).
+    #[arg(long, value_enum, default_value = "text")]
+    pub output: OutputFormat,
+}
+
+impl DiffArgs {
+    /// Plan arguments for building the candidate plan (None when comparing two files).
+    pub fn action_args(&self) -> Option<ActionArgs> {
+        Some(ActionArgs::preview(
+            self.provider?,
+            self.strategy.clone(),
+            self.tenant.clone()?,
+            self.environment.clone(),
+            self.pack.clone()?,
+            self.output,
+        ))
+    }
+}
+
+impl ActionArgs {
+    /// Arguments for building a plan in preview mode from a pack with default discovery paths.
+    pub fn preview(
+        provider: Provider,
+        strategy: String,
+        tenant: String,
+        environment: Option<String>,
+        pack: PathBuf,
+        output: OutputFormat,
+    ) -> Self {
+        Self {
+            provider,
+            strategy,
+            tenant,
+            environment,
+            pack,
+            providers_dir: PathBuf::from("providers/deployer"),
+            packs_dir: PathBuf::from("packs"),
+            provider_pack: None,
+            pack_id: None,
+            pack_version: None,
+            pack_digest: None,
+            distributor_url: None,
+            distributor_token: None,
+            yes: false,
+            preview: true,
+            dry_run: false,
+            allow_destroy: false,
+            iac_timeout: None,
+            state_backend: None,
+            iac_tool: None,
+            verify: true,
+            strict_verify: false,
+            trust_store: None,
+            output,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct DriftArgs {
+    /// Tenant identifier (e.g. acme).
+    #[arg(long)]
+    pub tenant: String,
+    /// Environment name (defaults to greentic-config environment).
+    #[arg(long)]
+    pub environment: Option<String>,
+    /// Pack to compare against the applied plan (enables pack drift detection).
+    #[arg(long)]
+    pub pack: Option<PathBuf>,
+    /// Deployment strategy used to build the pack plan (defaults to the applied record's).
+    #[arg(long)]
+    pub strategy: Option<String>,
+    /// IaC tool to use (tf/terraform or tofu/opentofu).
+    #[arg(long, value_enum)]
+    pub iac_tool: Option<IacToolArg>,
+    /// Kill any IaC step running longer than this many seconds.
+    #[arg(long, value_name = "SECONDS")]
+    pub iac_timeout: Option<u64>,
+    /// Output format (text|json|yaml).
+    #[arg(long, value_enum, default_value = "text")]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct BatchArgs {
+    /// Action to run for every target (plan|apply|destroy).
+    #[arg(long, value_enum, default_value = "plan")]
+    pub action: Action,
+    /// YAML/JSON manifest listing tenant/environment/provider/strategy targets.
+    #[arg(long, conflicts_with = "tenants")]
+    pub manifest: Option<PathBuf>,
+    /// Comma-separated tenants (e.g. `--tenant a,b,c`); requires --provider.
+    #[arg(
+        long = "tenant",
+        value_delimiter = ',',
+        required_unless_present = "manifest",
+        requires = "provider"
+    )]
+    pub tenants: Vec<String>,
+    /// Comma-separated environments; every tenant is deployed to each (defaults to greentic-config environment).
+    #[arg(
+        long = "environment",
+        value_delimiter = ',',
+        conflicts_with = "manifest"
+    )]
+    pub environments: Vec<String>,
+    /// Deployment target used with --tenant.
+    #[arg(long, value_enum, conflicts_with = "manifest")]
+    pub provider: Option<Provider>,
+    /// Deployment strategy identifier (manifest targets may override it).
+    #[arg(long, default_value = "iac-only")]
+    pub strategy: String,
+    /// Path to a .greentic-pack archive or a pack directory (manifest targets may override it).
+    #[arg(long)]
+    pub pack: Option<PathBuf>,
+    /// Maximum number of targets processed at the same time.
+    #[arg(long, default_value_t = 4)]
+    pub concurrency: usize,
+    /// Skip interactive confirmations; required for batch apply/destroy.
+    #[arg(long, default_value_t = false)]
+    pub yes: bool,
+    /// Treat the operation as a preview/dry-run.
+    #[arg(long, default_value_t = false)]
+    pub preview: bool,
+    /// Generate IaC artifacts but do not execute them.
+    #[arg(long, default_value_t = false)]
+    pub dry_run: bool,
+    /// Allow applies whose IaC plan deletes or replaces resources.
+    #[arg(long, default_value_t = false)]
+    pub allow_destroy: bool,
+    /// IaC tool to use (tf/terraform or tofu/opentofu).
+    #[arg(long, value_enum)]
+    pub iac_tool: Option<IacToolArg>,
+    /// Kill any IaC step running longer than this many seconds.
+    #[arg(long, value_name = "SECONDS")]
+    pub iac_timeout: Option<u64>,
+    /// YAML/JSON file describing the Terraform/OpenTofu state backend (defaults to local state).
+    #[arg(long, env = "GREENTIC_STATE_BACKEND")]
+    pub state_backend: Option<PathBuf>,
+    /// Output format for the aggregated report (text|json|yaml).
+    #[arg(long, value_enum, default_value = "text")]
+    pub output: OutputFormat,
+}
+
+impl BatchArgs {
+    /// Per-target action arguments sharing this batch's run flags.
+    pub fn action_args(
+        &self,
+        provider: Provider,
+        strategy: String,
+        tenant: String,
+        environment: Option<String>,
+        pack: PathBuf,
+    ) -> ActionArgs {
+        ActionArgs {
+            yes: self.yes,
+            preview: self.preview,
+            dry_run: self.dry_run,
+            allow_destroy: self.allow_destroy,
+            iac_timeout: self.iac_timeout,
+            state_backend: self.state_backend.clone(),
+            iac_tool: self.iac_tool,
+            ..ActionArgs::preview(
+                provider,
+                strategy,
+                tenant,
+                environment,
+                pack,
+                OutputFormat::Text,
+            )
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct PromoteArgs {
+    /// Tenant identifier (e.g. acme).
+    #[arg(long)]
+    pub tenant: String,
+    /// Environment whose current deployment is promoted.
+    #[arg(long)]
+    pub from: String,
+    /// Environment receiving the promoted pack.
+    #[arg(long)]
+    pub to: String,
+    /// Local pack to promote; its digest must match the one recorded in the source environment.
+    #[arg(long)]
+    pub pack: Option<PathBuf>,
+    /// Distributor base URL used when the pack is resolved by reference.
+    #[arg(long)]
+    pub distributor_url: Option<String>,
+    /// Optional auth token for the distributor.
+    #[arg(long)]
+    pub distributor_token: Option<String>,
+    /// Skip interactive confirmations (defaults to false).
+    #[arg(long, default_value_t = false)]
+    pub yes: bool,
+    /// Generate IaC artifacts but do not execute them.
+    #[arg(long, default_value_t = false)]
+    pub dry_run: bool,
+    /// Allow applies whose IaC plan deletes or replaces resources.
+    #[arg(long, default_value_t = false)]
+    pub allow_destroy: bool,
+    /// IaC tool to use (tf/terraform or tofu/opentofu).
+    #[arg(long, value_enum)]
+    pub iac_tool: Option<IacToolArg>,
+    /// Kill any IaC step running longer than this many seconds.
+    #[arg(long, value_name = "SECONDS")]
+    pub iac_timeout: Option<u64>,
+    /// YAML/JSON file describing the Terraform/OpenTofu state backend (defaults to local state).
+    #[arg(long, env = "GREENTIC_STATE_BACKEND")]
+    pub state_backend: Option<PathBuf>,
+    /// Output format for the plan diff and apply output (text|json|yaml).
+    #[arg(long, value_enum, default_value = "text")]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct HistoryArgs {
+    /// Tenant identifier (e.g. acme).
+    #[arg(long)]
+    pub tenant: String,
+    /// Environment name (defaults to greentic-config environment).
+    #[arg(long)]
+    pub environment: Option<String>,
+    /// Show a single ledger record (including artifact hashes).
+    #[arg(long)]
+    pub record: Option<u64>,
+    /// Only list the most recent N records.
+    #[arg(long)]
+    pub limit: Option<usize>,
+    /// Output format (text|json|yaml).
+    #[arg(long, value_enum, default_value = "text")]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct RollbackArgs {
+    /// Tenant identifier (e.g. acme).
+    #[arg(long)]
+    pub tenant: String,
+    /// Environment name (defaults to greentic-config environment).
+    #[arg(long)]
+    pub environment: Option<String>,
+    /// Ledger record to restore (defaults to the deployment before the current one).
+    #[arg(long)]
+    pub to: Option<u64>,
+    /// Skip interactive confirmations (defaults to false).
+    #[arg(long, default_value_t = false)]
+    pub yes: bool,
+    /// Show what would be restored without touching the deployment.
+    #[arg(long, default_value_t = false)]
+    pub dry_run: bool,
+    /// Allow rollbacks whose IaC plan deletes or replaces resources.
+    #[arg(long, default_value_t = false)]
+    pub allow_destroy: bool,
+    /// IaC tool to use (tf/terraform or tofu/opentofu).
+    #[arg(long, value_enum)]
+    pub iac_tool: Option<IacToolArg>,
+    /// Kill any IaC step running longer than this many seconds.
+    #[arg(long, value_name = "SECONDS")]
+    pub iac_timeout: Option<u64>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -198,6 +529,116 @@ pub enum PlatformCommand {
     Upgrade(PlatformActionArgs),
     /// Show bootstrap state/status.
     Status,
+    /// Restore the platform release named by the bootstrap state's rollback_ref.
+    Rollback,
+    /// Manage the bootstrap secrets backend.
+    Secrets {
+        #[command(subcommand)]
+        command: PlatformSecretsCommand,
+    },
+    /// Manage the publisher keys trusted to sign platform packs.
+    Trust {
+        #[command(subcommand)]
+        command: PlatformTrustCommand,
+    },
+    /// Inspect and maintain the cache of packs resolved from oci:// references.
+    Cache {
+        #[command(subcommand)]
+        command: PlatformCacheCommand,
+    },
+    /// Move packs to air-gapped hosts as a single tarball.
+    Bundle {
+        #[command(subcommand)]
+        command: PlatformBundleCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PlatformCacheCommand {
+    /// List cached references with their digests and sizes.
+    List,
+    /// Remove index entries without a file and files no entry points at.
+    Prune {
+        /// Empty the cache completely.
+        #[arg(long, default_value_t = false)]
+        all: bool,
+    },
+    /// Re-hash cached packs and report missing or corrupted files.
+    Verify,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PlatformBundleCommand {
+    /// Resolve oci:// references and deployment packs into a bundle tarball.
+    Export(BundleExportArgs),
+    /// Seed the pack cache and packs directory from a bundle tarball.
+    Import(BundleImportArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct BundleExportArgs {
+    /// Platform pack references (`oci://<host>/<repo>[:<tag>][@sha256:<digest>]`).
+    pub references: Vec<String>,
+    /// Deployment pack to include (oci:// reference or local .gtpack); repeatable.
+    #[arg(long = "deployment-pack")]
+    pub deployment_packs: Vec<String>,
+    /// Output tarball.
+    #[arg(long)]
+    pub out: PathBuf,
+    /// Docker config with registry credentials (defaults to $DOCKER_CONFIG/config.json or
+    /// ~/.docker/config.json).
+    #[arg(long)]
+    pub docker_config: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct BundleImportArgs {
+    /// Bundle tarball created by `platform bundle export`.
+    pub bundle: PathBuf,
+    /// Directory receiving deployment packs (the `--packs-dir` searched by plan/apply).
+    #[arg(long, default_value = "packs")]
+    pub packs_dir: PathBuf,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PlatformTrustCommand {
+    /// Trust a publisher public key (PEM, DER or raw Ed25519 / ECDSA P-256).
+    Add(TrustAddArgs),
+    /// List trusted keys.
+    List,
+    /// Stop trusting a key.
+    Remove {
+        /// Key id as it appears in pack signatures.
+        key_id: String,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct TrustAddArgs {
+    /// Key id as it appears in pack signatures.
+    pub key_id: String,
+    /// File holding the public key.
+    #[arg(long)]
+    pub public_key: PathBuf,
+    /// Publisher the key belongs to (informational).
+    #[arg(long)]
+    pub publisher: Option<String>,
+    /// Reject signatures from this key after the given number of days.
+    #[arg(long, value_name = "DAYS")]
+    pub expires_in_days: Option<u64>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PlatformSecretsCommand {
+    /// Re-encrypt a file+enc: secrets backend under a new key file or passphrase.
+    RotateKey(RotateKeyArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct RotateKeyArgs {
+    /// New key file; without it the new passphrase is read from GREENTIC_SECRETS_NEW_PASSPHRASE.
+    #[arg(long)]
+    pub new_key_file: Option<PathBuf>,
 }
 
 #[derive(Debug, Args)]
@@ -208,9 +649,15 @@ pub struct PlatformActionArgs {
     /// Enable signature verification (warnings on missing signatures).
     #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
     pub verify: bool,
-    /// Fail when signatures are missing or invalid.
+    /// Fail when signatures are missing or invalid, or signed by unknown or expired keys.
     #[arg(long, default_value_t = false)]
     pub strict_verify: bool,
+    /// Print the config changes (and conflicts) the install would make, then stop without writing.
+    #[arg(long, default_value_t = false)]
+    pub config_preview: bool,
+    /// Apply the config patch even where it overwrites keys edited since the last install.
+    #[arg(long, default_value_t = false)]
+    pub force_config: bool,
 }
 
 #[derive(Args, Debug)]
@@ -225,6 +672,10 @@ pub struct PlatformArgs {
         env = "GREENTIC_BOOTSTRAP_STATE"
     )]
     pub bootstrap_state: PathBuf,
+    /// Trust store of publisher keys for pack signature verification (defaults beside the
+    /// bootstrap state as trusted_keys.json).
+    #[arg(long, global = true, env = "GREENTIC_TRUST_STORE")]
+    pub trust_store: Option<PathBuf>,
     /// Bootstrap state backend (file|k8s).
     #[arg(long, global = true, value_enum, default_value = "file")]
     pub bootstrap_state_backend: BootstrapStateBackend,
@@ -234,6 +685,13 @@ pub struct PlatformArgs {
     /// Kubernetes ConfigMap/Secret name for bootstrap state (when using k8s backend).
     #[arg(long, global = true, default_value = "greentic-bootstrap")]
     pub k8s_state_name: String,
+    /// Kubernetes object kind holding bootstrap state (configmap|secret).
+    #[arg(long, global = true, value_enum, default_value = "configmap")]
+    pub k8s_state_kind: K8sStateKind,
+    /// Kubeconfig for the k8s state backend (defaults to the in-cluster service account, then
+    /// KUBECONFIG or ~/.kube/config).
+    #[arg(long, global = true)]
+    pub k8s_kubeconfig: Option<PathBuf>,
     /// Interaction mode for bootstrap (cli|json|auto).
     #[arg(long, global = true, value_enum, default_value = "auto")]
     pub interaction: InteractionMode,
@@ -252,7 +710,9 @@ pub struct PlatformArgs {
     /// Force offline-only bootstrap (no network/remote fetches).
     #[arg(long, global = true, default_value_t = false)]
     pub offline_only: bool,
-    /// Secrets backend URI for bootstrap secret writes (e.g., file:/var/lib/greentic/secrets.db).
+    /// Secrets backend URI for bootstrap secret writes (`file:<path>`, `file+enc:<path>`,
+    /// `k8s:<ns>/<name>`, `greentic:<env>/<tenant>[/<team>]` or
+    /// `vault:[<addr>/]<mount>[/<prefix>]`).
     #[arg(
         long,
         global = true,
@@ -260,6 +720,14 @@ pub struct PlatformArgs {
         env = "GREENTIC_SECRETS_BACKEND"
     )]
     pub secrets_backend: String,
+    /// Key file for a file+enc: secrets backend (otherwise the passphrase is read from
+    /// GREENTIC_SECRETS_PASSPHRASE).
+    #[arg(long, global = true, env = "GREENTIC_SECRETS_KEY_FILE")]
+    pub secrets_key_file: Option<PathBuf>,
+    /// Render k8s secrets backend writes as Secret YAML under GREENTIC_K8S_SECRET_DIR
+    /// (default /tmp/greentic-k8s-secrets) instead of calling the Kubernetes API.
+    #[arg(long, global = true, default_value_t = false)]
+    pub secrets_dry_run: bool,
     /// Answers JSON for non-interactive bootstrap (use @- for stdin).
     #[arg(long, global = true)]
     pub answers: Option<String>,
@@ -272,6 +740,38 @@ pub struct PlatformArgs {
     /// Interaction timeout in seconds (applies to HTTP/MQTT adapters).
     #[arg(long, global = true, default_value_t = 30)]
     pub interaction_timeout: u64,
+    /// PEM certificate chain to serve the HTTP wizard over HTTPS (requires --http-tls-key).
+    #[arg(long, global = true, requires = "http_tls_key")]
+    pub http_tls_cert: Option<PathBuf>,
+    /// PEM private key for --http-tls-cert.
+    #[arg(long, global = true, requires = "http_tls_cert")]
+    pub http_tls_key: Option<PathBuf>,
+    /// MQTT broker for --interaction mqtt (`mqtt://host[:1883]` or `mqtts://host[:8883]`).
+    #[arg(long, global = true)]
+    pub mqtt_broker: Option<String>,
+    /// Device id used in MQTT topics (`<prefix>/<device_id>/questions|answers|status`).
+    #[arg(long, global = true)]
+    pub mqtt_device_id: Option<String>,
+    /// MQTT topic prefix.
+    #[arg(long, global = true, default_value = "greentic/bootstrap")]
+    pub mqtt_topic_prefix: String,
+    /// MQTT protocol version (v311|v5).
+    #[arg(long, global = true, value_enum, default_value = "v311")]
+    pub mqtt_protocol: MqttProtocol,
+    /// MQTT username.
+    #[arg(long, global = true)]
+    pub mqtt_username: Option<String>,
+    /// MQTT password.
+    #[arg(
+        long,
+        global = true,
+        env = "GREENTIC_MQTT_PASSWORD",
+        hide_env_values = true
+    )]
+    pub mqtt_password: Option<String>,
+    /// PEM CA bundle for mqtts:// brokers (defaults to the system roots).
+    #[arg(long, global = true)]
+    pub mqtt_ca_file: Option<PathBuf>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -280,6 +780,27 @@ pub enum ProviderArgs {
     Onboard(ProviderOnboardArgs),
 }
 
+#[derive(Subcommand, Debug)]
+pub enum PackArgs {
+    /// Push a .gtpack to an OCI registry and print the manifest digest.
+    Push(PackPushArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct PackPushArgs {
+    /// Path to the .gtpack archive.
+    pub gtpack: PathBuf,
+    /// Target reference (`oci://<host>/<repo>:<tag>`).
+    pub reference: String,
+    /// Extra hosts (e.g. the registry's token service) allowed besides the registry itself.
+    #[arg(long)]
+    pub net_allowlist: Option<String>,
+    /// Docker config with registry credentials (defaults to $DOCKER_CONFIG/config.json or
+    /// ~/.docker/config.json).
+    #[arg(long)]
+    pub docker_config: Option<PathBuf>,
+}
+
 #[derive(Debug, Args)]
 pub struct ProviderOnboardArgs {
     /// Path to a provider pack (.gtpack) or unpacked directory.
@@ -314,12 +835,45 @@ pub enum InteractionMode {
     Mqtt,
 }
 
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MqttProtocol {
+    /// MQTT 3.1.1
+    V311,
+    /// MQTT 5
+    V5,
+}
+
 #[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BootstrapStateBackend {
     File,
     K8s,
 }
 
+/// Kubernetes object holding bootstrap state for the k8s backend.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum K8sStateKind {
+    #[value(name = "configmap")]
+    ConfigMap,
+    Secret,
+}
+
+impl K8sStateKind {
+    pub fn object_kind(&self) -> &'static str {
+        match self {
+            K8sStateKind::ConfigMap => "configmap",
+            K8sStateKind::Secret => "secret",
+        }
+    }
+
+    /// Resource name in API paths.
+    pub fn resource(&self) -> &'static str {
+        match self {
+            K8sStateKind::ConfigMap => "configmaps",
+            K8sStateKind::Secret => "secrets",
+        }
+    }
+}
+
 /// Complete configuration used by the deployer.
 #[derive(Debug, Clone)]
 pub struct DeployerConfig {
@@ -338,6 +892,9 @@ pub struct DeployerConfig {
     pub yes: bool,
     pub preview: bool,
     pub dry_run: bool,
+    pub allow_destroy: bool,
+    pub iac_timeout: Option<Duration>,
+    pub state_backend: StateBackendConfig,
     pub iac_tool: IaCTool,
     pub output: OutputFormat,
     pub greentic: GreenticConfig,
@@ -346,6 +903,11 @@ pub struct DeployerConfig {
     pub explain_config: bool,
     pub explain_config_json: bool,
     pub allow_remote_in_offline: bool,
+    /// Signature verification for the application and deployment packs.
+    pub pack_verification: PackVerification,
+    /// Keep per-run rendering (plan summary, change listing, status lines) off stdout; set by
+    /// `batch` so stdout only carries the aggregated report.
+    pub quiet: bool,
 }
 
 impl DeployerConfig {
@@ -354,18 +916,27 @@ impl DeployerConfig {
             Command::Plan(args) => (Action::Plan, args),
             Command::Apply(args) => (Action::Apply, args),
             Command::Destroy(args) => (Action::Destroy, args),
-            Command::Platform(_) | Command::Provider { .. } => {
+            Command::Platform(_)
+            | Command::Provider { .. }
+            | Command::History(_)
+            | Command::Diff(_)
+            | Command::Rollback(_)
+            | Command::Drift(_)
+            | Command::Batch(_)
+            | Command::Promote(_)
+            | Command::Pack { .. } => {
                 return Err(DeployerError::Config(
-                    "platform/provider commands do not use DeployerConfig".into(),
+                    "platform/provider/history/diff/rollback/drift/batch/promote/pack commands do not use DeployerConfig"
+                        .into(),
                 ));
             }
         };
+        Self::from_action_args(action, args, &cli.global)
+    }
 
-        let mut resolver = ConfigResolver::new();
-        if let Some(layer) = load_explicit_config(cli.global.config.as_ref())? {
-            resolver = resolver.with_cli_overrides(layer);
-        }
-        let resolved = resolver
+    /// Build the configuration for an action from already parsed arguments.
+    pub fn from_action_args(action: Action, args: ActionArgs, global: &GlobalArgs) -> Result<Self> {
+        let resolved = config_resolver(global)?
             .load()
             .map_err(|err| DeployerError::Config(err.to_string()))?;
         let greentic = resolved.config;
@@ -384,6 +955,10 @@ impl DeployerConfig {
         )?;
 
         let iac_tool = resolve_iac_tool(args.iac_tool, None)?;
+        let state_backend = match &args.state_backend {
+            Some(path) => StateBackendConfig::load(path)?,
+            None => StateBackendConfig::default(),
+        };
         let pack_ref = build_pack_ref(&args)?;
 
         let distributor_url = args.distributor_url;
@@ -393,7 +968,7 @@ impl DeployerConfig {
             greentic.environment.connection.as_ref(),
             &pack_ref,
             distributor_url.as_deref(),
-            cli.global.allow_remote_in_offline,
+            global.allow_remote_in_offline,
         )?;
 
         Ok(Self {
@@ -412,14 +987,27 @@ impl DeployerConfig {
             yes: args.yes,
             preview: args.preview,
             dry_run: args.dry_run,
+            allow_destroy: args.allow_destroy,
+            iac_timeout: args.iac_timeout.map(Duration::from_secs),
+            state_backend,
             iac_tool,
             output: args.output,
             greentic,
             provenance: resolved.provenance,
             config_warnings: resolved.warnings,
-            explain_config: cli.global.explain_config,
-            explain_config_json: cli.global.explain_config_json,
-            allow_remote_in_offline: cli.global.allow_remote_in_offline,
+            explain_config: global.explain_config,
+            explain_config_json: global.explain_config_json,
+            allow_remote_in_offline: global.allow_remote_in_offline,
+            pack_verification: PackVerification {
+                policy: VerificationPolicy {
+                    verify: args.verify,
+                    strict: args.strict_verify,
+                },
+                trust_store: args
+                    .trust_store
+                    .unwrap_or_else(|| PackVerification::default().trust_store),
+            },
+            quiet: false,
         })
     }
 
@@ -434,6 +1022,16 @@ impl DeployerConfig {
             .join(&self.environment)
     }
 
+    /// Directory holding the last persisted `plan.json`/`invoke.json` for this tenant/env.
+    pub fn runtime_dir(&self) -> PathBuf {
+        self.greentic
+            .paths
+            .state_dir
+            .join("runtime")
+            .join(&self.tenant)
+            .join(&self.environment)
+    }
+
     pub fn telemetry_config(&self) -> &TelemetryConfig {
         &self.greentic.telemetry
     }
@@ -443,6 +1041,22 @@ impl DeployerConfig {
     }
 }
 
+/// Resolve greentic-config (honouring `--config`) for commands without a full [`DeployerConfig`].
+pub fn load_greentic_config(global: &GlobalArgs) -> Result<GreenticConfig> {
+    let resolved = config_resolver(global)?
+        .load()
+        .map_err(|err| DeployerError::Config(err.to_string()))?;
+    Ok(resolved.config)
+}
+
+fn config_resolver(global: &GlobalArgs) -> Result<ConfigResolver> {
+    let mut resolver = ConfigResolver::new();
+    if let Some(layer) = load_explicit_config(global.config.as_ref())? {
+        resolver = resolver.with_cli_overrides(layer);
+    }
+    Ok(resolver)
+}
+
 fn load_explicit_config(path: Option<&PathBuf>) -> Result<Option<ConfigLayer>> {
     let Some(path) = path else {
         return Ok(None);
@@ -654,4 +1268,160 @@ kind = "none"
             "expected offline validation error, got {err}"
         );
     }
+
+    #[test]
+    fn parses_secrets_rotate_key_command() {
+        let cli = CliArgs::parse_from([
+            "greentic-deployer",
+            "platform",
+            "secrets",
+            "rotate-key",
+            "--secrets-backend",
+            "file+enc:/var/lib/greentic/secrets.enc",
+            "--secrets-key-file",
+            "/etc/greentic/old.key",
+            "--new-key-file",
+            "/etc/greentic/new.key",
+        ]);
+        let Command::Platform(args) = cli.command else {
+            panic!("expected platform command");
+        };
+        let PlatformCommand::Secrets {
+            command: PlatformSecretsCommand::RotateKey(rotate),
+        } = args.command
+        else {
+            panic!("expected secrets rotate-key");
+        };
+        assert_eq!(
+            args.secrets_backend,
+            "file+enc:/var/lib/greentic/secrets.enc"
+        );
+        assert_eq!(
+            args.secrets_key_file,
+            Some(PathBuf::from("/etc/greentic/old.key"))
+        );
+        assert_eq!(
+            rotate.new_key_file,
+            Some(PathBuf::from("/etc/greentic/new.key"))
+        );
+    }
+
+    #[test]
+    fn parses_trust_add_command() {
+        let cli = CliArgs::parse_from([
+            "greentic-deployer",
+            "platform",
+            "trust",
+            "add",
+            "greentic-release-2026",
+            "--public-key",
+            "/etc/greentic/release.pub",
+            "--expires-in-days",
+            "365",
+            "--trust-store",
+            "/etc/greentic/trust.json",
+        ]);
+        let Command::Platform(args) = cli.command else {
+            panic!("expected platform command");
+        };
+        let PlatformCommand::Trust {
+            command: PlatformTrustCommand::Add(add),
+        } = args.command
+        else {
+            panic!("expected trust add");
+        };
+        assert_eq!(add.key_id, "greentic-release-2026");
+        assert_eq!(add.public_key, PathBuf::from("/etc/greentic/release.pub"));
+        assert_eq!(add.expires_in_days, Some(365));
+        assert_eq!(
+            args.trust_store,
+            Some(PathBuf::from("/etc/greentic/trust.json"))
+        );
+    }
+
+    #[test]
+    fn parses_bundle_export_command() {
+        let cli = CliArgs::parse_from([
+            "greentic-deployer",
+            "platform",
+            "bundle",
+            "export",
+            "oci://ghcr.io/greentic-ai/platform:1.0.0",
+            "--deployment-pack",
+            "dist/deploy-k8s.gtpack",
+            "--out",
+            "usb/bundle.tar",
+            "--allow-network",
+        ]);
+        let Command::Platform(args) = cli.command else {
+            panic!("expected platform command");
+        };
+        let PlatformCommand::Bundle {
+            command: PlatformBundleCommand::Export(export),
+        } = args.command
+        else {
+            panic!("expected bundle export");
+        };
+        assert_eq!(
+            export.references,
+            ["oci://ghcr.io/greentic-ai/platform:1.0.0"]
+        );
+        assert_eq!(export.deployment_packs, ["dist/deploy-k8s.gtpack"]);
+        assert_eq!(export.out, PathBuf::from("usb/bundle.tar"));
+        assert!(args.allow_network);
+    }
+
+    #[test]
+    fn parses_pack_push_command() {
+        let cli = CliArgs::parse_from([
+            "greentic-deployer",
+            "pack",
+            "push",
+            "dist/platform.gtpack",
+            "oci://ghcr.io/greentic-ai/platform:1.0.0",
+            "--net-allowlist",
+            "auth.ghcr.io",
+        ]);
+        let Command::Pack {
+            command: PackArgs::Push(push),
+        } = cli.command
+        else {
+            panic!("expected pack push");
+        };
+        assert_eq!(push.gtpack, PathBuf::from("dist/platform.gtpack"));
+        assert_eq!(push.reference, "oci://ghcr.io/greentic-ai/platform:1.0.0");
+        assert_eq!(push.net_allowlist.as_deref(), Some("auth.ghcr.io"));
+        assert!(push.docker_config.is_none());
+    }
+
+    #[test]
+    fn parses_history_command() {
+        let cli = CliArgs::parse_from([
+            "greentic-deployer",
+            "history",
+            "--tenant",
+            "acme",
+            "--environment",
+            "staging",
+            "--record",
+            "3",
+            "--output",
+            "json",
+        ]);
+        let Command::History(args) = cli.command else {
+            panic!("expected history command");
+        };
+        assert_eq!(args.tenant, "acme");
+        assert_eq!(args.environment.as_deref(), Some("staging"));
+        assert_eq!(args.record, Some(3));
+        assert_eq!(args.output, OutputFormat::Json);
+        let err = DeployerConfig::from_env_and_args(CliArgs::parse_from([
+            "greentic-deployer",
+            "history",
+            "--tenant",
+            "acme",
+        ]))
+        .unwrap_err();
+        assert!(format!("{err}").contains("history"));
+    }
 }
@@ -8,10 +8,13 @@ use crate::config::DeployerConfig;
 use crate::error::{DeployerError, Result};
 use crate::pack_introspect::{read_manifest_from_directory, read_manifest_from_gtpack};
 use crate::plan::PlanContext;
+use crate::platform::{pack_content_digest, pack_digest};
 use async_trait::async_trait;
 use greentic_types::pack_manifest::PackManifest;
 use once_cell::sync::Lazy;
 
+pub mod wasm;
+
 /// Logical deployment target keyed by provider + strategy.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct DeploymentTarget {
@@ -24,6 +27,8 @@ pub struct DeploymentTarget {
 pub struct DeploymentDispatch {
     pub pack_id: String,
     pub flow_id: String,
+    /// Pinned pack digest (`sha256:<hex>`); candidates with another digest are never selected.
+    pub digest: Option<String>,
 }
 
 /// Resolved deployment pack selection including discovered manifest.
@@ -32,6 +37,8 @@ pub struct DeploymentPackSelection {
     pub dispatch: DeploymentDispatch,
     pub pack_path: PathBuf,
     pub manifest: PackManifest,
+    /// Digest of the selected pack (see [`crate::platform::pack_digest`]).
+    pub pack_digest: String,
     pub origin: String,
     pub candidates: Vec<String>,
 }
@@ -47,6 +54,7 @@ pub fn default_dispatch_table() -> HashMap<DeploymentTarget, DeploymentDispatch>
         DeploymentDispatch {
             pack_id: "greentic.demo.deploy.aws".into(),
             flow_id: "deploy_aws_iac".into(),
+            digest: None,
         },
     );
     map.insert(
@@ -57,6 +65,7 @@ pub fn default_dispatch_table() -> HashMap<DeploymentTarget, DeploymentDispatch>
         DeploymentDispatch {
             pack_id: "greentic.demo.deploy.local".into(),
             flow_id: "deploy_local_iac".into(),
+            digest: None,
         },
     );
     map.insert(
@@ -67,6 +76,7 @@ pub fn default_dispatch_table() -> HashMap<DeploymentTarget, DeploymentDispatch>
         DeploymentDispatch {
             pack_id: "greentic.demo.deploy.azure".into(),
             flow_id: "deploy_azure_iac".into(),
+            digest: None,
         },
     );
     map.insert(
@@ -77,6 +87,7 @@ pub fn default_dispatch_table() -> HashMap<DeploymentTarget, DeploymentDispatch>
         DeploymentDispatch {
             pack_id: "greentic.demo.deploy.gcp".into(),
             flow_id: "deploy_gcp_iac".into(),
+            digest: None,
         },
     );
     map.insert(
@@ -87,6 +98,7 @@ pub fn default_dispatch_table() -> HashMap<DeploymentTarget, DeploymentDispatch>
         DeploymentDispatch {
             pack_id: "greentic.demo.deploy.k8s".into(),
             flow_id: "deploy_k8s_iac".into(),
+            digest: None,
         },
     );
     map.insert(
@@ -97,6 +109,7 @@ pub fn default_dispatch_table() -> HashMap<DeploymentTarget, DeploymentDispatch>
         DeploymentDispatch {
             pack_id: "greentic.demo.deploy.generic".into(),
             flow_id: "deploy_generic_iac".into(),
+            digest: None,
         },
     );
     map
@@ -112,12 +125,28 @@ pub fn resolve_deployment_pack(
     target: &DeploymentTarget,
 ) -> Result<DeploymentPackSelection> {
     let dispatch = resolve_dispatch(target)?;
+    select_deployment_pack(config, target, dispatch)
+}
+
+/// Locate the pack for an already resolved dispatch entry, verify its signatures and ensure its
+/// flow exists.
+pub fn select_deployment_pack(
+    config: &DeployerConfig,
+    target: &DeploymentTarget,
+    dispatch: DeploymentDispatch,
+) -> Result<DeploymentPackSelection> {
     let discovery = find_pack_for_dispatch(config, target, &dispatch)?;
+    config.pack_verification.verify(
+        &discovery.pack_path.display().to_string(),
+        &discovery.manifest,
+        &pack_content_digest(&discovery.pack_path)?,
+    )?;
     ensure_flow_available(&dispatch, &discovery.manifest)?;
     Ok(DeploymentPackSelection {
         dispatch,
         pack_path: discovery.pack_path,
         manifest: discovery.manifest,
+        pack_digest: discovery.digest,
         origin: discovery.origin,
         candidates: discovery.candidates,
     })
@@ -132,7 +161,8 @@ where
     }
 
     let mut defaults = default_dispatch_table();
-    if let Some(dispatch) = defaults.remove(target) {
+    if let Some(mut dispatch) = defaults.remove(target) {
+        dispatch.digest = digest_override(target, &get_env);
         return Ok(dispatch);
     }
 
@@ -161,6 +191,27 @@ where
     env_override_with_prefix(&provider_prefix, get_env)
 }
 
+/// `DEPLOY_TARGET_<PROVIDER>[_<STRATEGY>]_PACK_DIGEST` pinning the built-in mapping's pack.
+fn digest_override<F>(target: &DeploymentTarget, get_env: &F) -> Option<String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let provider_prefix = format!("DEPLOY_TARGET_{}", sanitize_key(&target.provider));
+    let strategy_prefix = format!("{provider_prefix}_{}", sanitize_key(&target.strategy));
+    get_env(&format!("{strategy_prefix}_PACK_DIGEST"))
+        .or_else(|| get_env(&format!("{provider_prefix}_PACK_DIGEST")))
+        .map(normalize_digest)
+}
+
+fn normalize_digest(digest: String) -> String {
+    let digest = digest.trim().to_ascii_lowercase();
+    if digest.contains(':') {
+        digest
+    } else {
+        format!("sha256:{digest}")
+    }
+}
+
 fn env_override_with_prefix<F>(prefix: &str, get_env: &F) -> Result<Option<DeploymentDispatch>>
 where
     F: Fn(&str) -> Option<String>,
@@ -170,7 +221,11 @@ where
     let pack = get_env(&pack_key);
     let flow = get_env(&flow_key);
     match (pack, flow) {
-        (Some(pack_id), Some(flow_id)) => Ok(Some(DeploymentDispatch { pack_id, flow_id })),
+        (Some(pack_id), Some(flow_id)) => Ok(Some(DeploymentDispatch {
+            pack_id,
+            flow_id,
+            digest: get_env(&format!("{prefix}_PACK_DIGEST")).map(normalize_digest),
+        })),
         (None, None) => Ok(None),
         (Some(_), None) | (None, Some(_)) => Err(DeployerError::Config(format!(
             "Incomplete deployment mapping overrides. Both {pack_key} and {flow_key} must be set."
@@ -186,6 +241,7 @@ struct SearchPath {
 struct PackDiscovery {
     pack_path: PathBuf,
     manifest: PackManifest,
+    digest: String,
     origin: String,
     candidates: Vec<String>,
 }
@@ -206,62 +262,83 @@ fn find_pack_for_dispatch(
                 dispatch.pack_id
             )));
         }
+        let digest = pack_digest(override_path)?;
+        if let Some(pinned) = dispatch.digest.as_deref()
+            && pinned != digest
+        {
+            return Err(DeployerError::Config(format!(
+                "explicit deployment pack {} has digest {} (dispatch mapping pins {})",
+                override_path.display(),
+                digest,
+                pinned
+            )));
+        }
         return Ok(PackDiscovery {
             pack_path: override_path.clone(),
             manifest,
+            digest,
             origin: format!("override -> {}", override_path.display()),
             candidates: vec![format!("{} (override {})", actual, override_path.display())],
         });
     }
 
-    if let Some((direct_path, manifest)) =
-        resolve_direct_pack_path(config, target).and_then(|direct_path| {
-            if !direct_path.exists() {
-                return None;
-            }
-            match load_manifest(&direct_path) {
-                Ok(manifest) if manifest.pack_id.to_string() == dispatch.pack_id => {
-                    Some((direct_path, manifest))
-                }
-                _ => None,
-            }
-        })
+    let mut candidates = Vec::new();
+    if let Some(direct_path) = resolve_direct_pack_path(config, target)
+        && let Ok(manifest) = load_manifest(&direct_path)
+        && manifest.pack_id.to_string() == dispatch.pack_id
     {
         let candidate_display = direct_path.display().to_string();
+        let digest = pack_digest(&direct_path)?;
         let entry = format!("{} ({})", manifest.pack_id, candidate_display);
-        return Ok(PackDiscovery {
-            pack_path: direct_path.clone(),
-            manifest,
-            origin: format!("providers-dir -> {}", candidate_display),
-            candidates: vec![entry],
-        });
+        if pinned_digest_matches(dispatch, &digest) {
+            return Ok(PackDiscovery {
+                pack_path: direct_path,
+                manifest,
+                digest,
+                origin: format!("providers-dir -> {}", candidate_display),
+                candidates: vec![entry],
+            });
+        }
+        candidates.push(format!("{entry} digest {digest} does not match pin"));
     }
 
     let search_paths = build_search_paths(config);
-    let mut candidates = Vec::new();
     for search in &search_paths {
         for candidate in gather_candidates(&search.path) {
             if let Ok(manifest) = load_manifest(&candidate) {
                 let entry = format!("{} ({})", manifest.pack_id, candidate.display());
-                candidates.push(entry.clone());
-                if manifest.pack_id.to_string() == dispatch.pack_id {
-                    let candidate_display = candidate.display().to_string();
-                    let pack_path = candidate.clone();
-                    return Ok(PackDiscovery {
-                        pack_path,
-                        manifest,
-                        origin: format!("{} -> {}", search.label, candidate_display),
-                        candidates,
-                    });
+                if manifest.pack_id.to_string() != dispatch.pack_id {
+                    candidates.push(entry);
+                    continue;
+                }
+                let digest = pack_digest(&candidate)?;
+                if !pinned_digest_matches(dispatch, &digest) {
+                    candidates.push(format!("{entry} digest {digest} does not match pin"));
+                    continue;
                 }
+                candidates.push(entry);
+                let candidate_display = candidate.display().to_string();
+                return Ok(PackDiscovery {
+                    pack_path: candidate,
+                    manifest,
+                    digest,
+                    origin: format!("{} -> {}", search.label, candidate_display),
+                    candidates,
+                });
             }
         }
     }
 
     let summary = build_search_summary(&search_paths);
+    let pin = dispatch
+        .digest
+        .as_deref()
+        .map(|digest| format!(" with digest {digest}"))
+        .unwrap_or_default();
     Err(DeployerError::Config(format!(
-        "Deployment pack {} not found; searched {} (candidates: {})",
+        "Deployment pack {}{} not found; searched {} (candidates: {})",
         dispatch.pack_id,
+        pin,
         summary,
         if candidates.is_empty() {
             "none".into()
@@ -271,6 +348,13 @@ fn find_pack_for_dispatch(
     )))
 }
 
+fn pinned_digest_matches(dispatch: &DeploymentDispatch, digest: &str) -> bool {
+    dispatch
+        .digest
+        .as_deref()
+        .is_none_or(|pinned| pinned == digest)
+}
+
 fn ensure_flow_available(dispatch: &DeploymentDispatch, manifest: &PackManifest) -> Result<()> {
     let available: Vec<String> = manifest
         .flows
@@ -367,7 +451,7 @@ fn sanitize_key(input: &str) -> String {
         .collect()
 }
 
-/// Placeholder hook for future deployment-pack execution via greentic-runner.
+/// Run the deployment pack through the registered executor (see [`wasm::WasmDeploymentExecutor`]).
 ///
 /// Returns `Ok(true)` when the plan was executed via a deployment pack,
 /// `Ok(false)` when the legacy provider shim should be used, and `Err` on fatal failures.
@@ -496,6 +580,64 @@ mod tests {
         assert!(format!("{err}").contains("Incomplete deployment mapping overrides"));
     }
 
+    #[test]
+    fn pins_default_mapping_digest_from_env() {
+        let target = DeploymentTarget {
+            provider: "k8s".into(),
+            strategy: "iac-only".into(),
+        };
+        let dispatch = resolve_dispatch_with_env(&target, |key| {
+            (key == "DEPLOY_TARGET_K8S_PACK_DIGEST").then(|| "ABC123".to_string())
+        })
+        .expect("default mapping");
+        assert_eq!(dispatch.pack_id, "greentic.demo.deploy.k8s");
+        assert_eq!(dispatch.digest.as_deref(), Some("sha256:abc123"));
+    }
+
+    #[test]
+    fn pinned_digest_skips_stray_pack_with_same_id() {
+        let base = env::current_dir().expect("cwd").join("target/tmp-tests");
+        std::fs::create_dir_all(&base).expect("create tmp base");
+        let dir = tempfile::tempdir_in(base).expect("temp dir");
+        let packs_dir = dir.path().join("packs");
+        let manifest =
+            encode_pack_manifest(&test_manifest("test.deploy.pinned")).expect("encode manifest");
+        for (name, flow) in [("stray", "id: stray\n"), ("trusted", "id: trusted\n")] {
+            let pack = packs_dir.join(name);
+            std::fs::create_dir_all(pack.join("flows")).expect("create pack dir");
+            std::fs::write(pack.join("manifest.cbor"), &manifest).expect("write manifest");
+            std::fs::write(pack.join("flows/deploy.ygtc"), flow).expect("write flow");
+        }
+        let trusted = packs_dir.join("trusted");
+        let mut config = test_config(PathBuf::from("unused"));
+        config.providers_dir = dir.path().join("providers");
+        config.packs_dir = packs_dir;
+        let target = DeploymentTarget {
+            provider: "pinned".into(),
+            strategy: "iac-only".into(),
+        };
+        let mut dispatch = DeploymentDispatch {
+            pack_id: "test.deploy.pinned".into(),
+            flow_id: "deploy_flow".into(),
+            digest: Some(pack_digest(&trusted).expect("digest")),
+        };
+
+        let found = find_pack_for_dispatch(&config, &target, &dispatch).expect("pinned pack");
+        assert_eq!(found.pack_path, trusted);
+        assert_eq!(Some(found.digest), dispatch.digest);
+
+        dispatch.digest = Some("sha256:0000".into());
+        let err = find_pack_for_dispatch(&config, &target, &dispatch)
+            .err()
+            .expect("no pack matches the pin");
+        let message = err.to_string();
+        assert!(
+            message.contains("with digest sha256:0000 not found"),
+            "{message}"
+        );
+        assert!(message.contains("does not match pin"), "{message}");
+    }
+
     struct TestExecutor {
         hits: Arc<AtomicUsize>,
     }
@@ -519,7 +661,34 @@ mod tests {
         let hits = Arc::new(AtomicUsize::new(0));
         set_deployment_executor(Arc::new(TestExecutor { hits: hits.clone() }));
         let pack_path = write_test_pack();
-        let config = DeployerConfig {
+        let config = test_config(pack_path);
+        let plan = pack_introspect::build_plan(&config).expect("plan builds");
+        let dispatch = DeploymentDispatch {
+            pack_id: "test.pack".into(),
+            flow_id: "deploy_flow".into(),
+            digest: None,
+        };
+        let ran = execute_deployment_pack(&config, &plan, &dispatch)
+            .await
+            .expect("executor runs");
+        assert!(ran);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+        clear_deployment_executor();
+    }
+
+    #[allow(deprecated)]
+    fn write_test_pack() -> PathBuf {
+        let base = env::current_dir().expect("cwd").join("target/tmp-tests");
+        std::fs::create_dir_all(&base).expect("create tmp base");
+        let dir = tempfile::tempdir_in(base).expect("temp dir");
+        let manifest = test_manifest("dev.greentic.sample");
+        let bytes = encode_pack_manifest(&manifest).expect("encode manifest");
+        std::fs::write(dir.path().join("manifest.cbor"), bytes).expect("write manifest");
+        dir.into_path()
+    }
+
+    fn test_config(pack_path: PathBuf) -> DeployerConfig {
+        DeployerConfig {
             action: Action::Plan,
             provider: Provider::Aws,
             strategy: "iac-only".into(),
@@ -535,6 +704,9 @@ mod tests {
             yes: true,
             preview: false,
             dry_run: false,
+            allow_destroy: false,
+            iac_timeout: None,
+            state_backend: Default::default(),
             iac_tool: IaCTool::Terraform,
             output: crate::config::OutputFormat::Text,
             greentic: greentic_config::ConfigResolver::new()
@@ -546,28 +718,15 @@ mod tests {
             explain_config: false,
             explain_config_json: false,
             allow_remote_in_offline: false,
-        };
-        let plan = pack_introspect::build_plan(&config).expect("plan builds");
-        let dispatch = DeploymentDispatch {
-            pack_id: "test.pack".into(),
-            flow_id: "deploy_flow".into(),
-        };
-        let ran = execute_deployment_pack(&config, &plan, &dispatch)
-            .await
-            .expect("executor runs");
-        assert!(ran);
-        assert_eq!(hits.load(Ordering::SeqCst), 1);
-        clear_deployment_executor();
+            pack_verification: Default::default(),
+            quiet: false,
+        }
     }
 
-    #[allow(deprecated)]
-    fn write_test_pack() -> PathBuf {
-        let base = env::current_dir().expect("cwd").join("target/tmp-tests");
-        std::fs::create_dir_all(&base).expect("create tmp base");
-        let dir = tempfile::tempdir_in(base).expect("temp dir");
-        let manifest = PackManifest {
+    fn test_manifest(pack_id: &str) -> PackManifest {
+        PackManifest {
             schema_version: "pack-v1".to_string(),
-            pack_id: PackId::try_from("dev.greentic.sample").unwrap(),
+            pack_id: PackId::try_from(pack_id).unwrap(),
             version: Version::new(0, 1, 0),
             kind: PackKind::Application,
             publisher: "greentic".to_string(),
@@ -591,9 +750,6 @@ mod tests {
             signatures: Default::default(),
             bootstrap: None,
             extensions: None,
-        };
-        let bytes = encode_pack_manifest(&manifest).expect("encode manifest");
-        std::fs::write(dir.path().join("manifest.cbor"), bytes).expect("write manifest");
-        dir.into_path()
+        }
     }
 }
@@ -0,0 +1,760 @@
+//! Built-in deployment executor that runs deployment-pack flows with wasmtime.
+//!
+//! Flow nodes are visited from the ingress node following `Next` routing. Nodes bound to
+//! `greentic.host.iac-write-files` are handled natively; every other node must reference a
+//! pack component implementing `greentic:deploy-plan@1.0.0`, which is instantiated with the
+//! `plan-api` and `iac-write-files` host interfaces and has its operation export invoked.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component as PathComponent, Path, PathBuf};
+
+use async_trait::async_trait;
+use greentic_types::flow::{Flow, Node, Routing};
+use greentic_types::pack_manifest::PackManifest;
+use serde::Deserialize;
+use wasmtime::component::{Component, ComponentType, Lift, Linker, Lower};
+use wasmtime::{Config as WasmtimeConfig, Engine, Store, StoreContextMut};
+
+use super::{
+    DeploymentDispatch, DeploymentExecutor, DeploymentPackSelection, DeploymentTarget,
+    select_deployment_pack,
+};
+use crate::config::DeployerConfig;
+use crate::error::{DeployerError, Result};
+use crate::path_safety::normalize_under_root;
+use crate::plan::PlanContext;
+
+/// Host interface serving the deployment plan to deploy-plan components.
+pub const PLAN_API_INTERFACE: &str = "greentic:deploy-plan/plan-api@1.0.0";
+/// Host interface used by components to emit IaC files.
+pub const IAC_WRITE_FILES_INTERFACE: &str = "greentic:host/iac-write-files@1.0.0";
+/// Component id used by flows to call the host write-files contract directly.
+pub const IAC_WRITE_FILES_COMPONENT: &str = "greentic.host.iac-write-files";
+
+const DEPLOY_PLAN_WORLD_PREFIX: &str = "greentic:deploy-plan";
+const DEFAULT_OPERATION: &str = "run";
+
+/// File specification exchanged over `greentic:host/iac-write-files@1.0.0`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, ComponentType, Lift, Lower)]
+#[component(record)]
+pub struct FileSpec {
+    pub path: String,
+    pub content: String,
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+/// Error returned by the write-files host contract.
+#[derive(Debug, Clone, PartialEq, Eq, ComponentType, Lift, Lower)]
+#[component(record)]
+pub struct WriteError {
+    pub code: u32,
+    pub message: String,
+    pub path: Option<String>,
+}
+
+impl std::fmt::Display for WriteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "write error [{}]: {}", self.code, self.message)?;
+        if let Some(path) = &self.path {
+            write!(f, " ({path})")?;
+        }
+        Ok(())
+    }
+}
+
+/// Summary of a deployment-pack flow execution.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecutionReport {
+    /// Node ids in the order they ran.
+    pub nodes: Vec<String>,
+    /// Files written under the provider output directory (relative paths).
+    pub files: Vec<String>,
+    /// Status messages emitted by components via `emit-status`.
+    pub statuses: Vec<String>,
+}
+
+/// First-party [`DeploymentExecutor`] backed by wasmtime.
+pub struct WasmDeploymentExecutor {
+    engine: Engine,
+}
+
+impl WasmDeploymentExecutor {
+    pub fn new() -> Result<Self> {
+        let mut config = WasmtimeConfig::new();
+        config.wasm_component_model(true);
+        let engine = Engine::new(&config)
+            .map_err(|err| DeployerError::Other(format!("wasmtime init failed: {err}")))?;
+        Ok(Self { engine })
+    }
+
+    /// Run the dispatch flow from an already resolved pack selection.
+    pub fn run_selection(
+        &self,
+        selection: &DeploymentPackSelection,
+        plan: &PlanContext,
+        out_dir: &Path,
+    ) -> Result<ExecutionReport> {
+        let flow = find_flow(&selection.manifest, &selection.dispatch)?;
+        if flow.is_empty() {
+            return Err(DeployerError::Pack(format!(
+                "flow {} in deployment pack {} has no nodes to execute",
+                selection.dispatch.flow_id, selection.dispatch.pack_id
+            )));
+        }
+        fs::create_dir_all(out_dir)?;
+        let plan_json = serde_json::to_string(&plan.plan)?;
+        let mut report = ExecutionReport::default();
+        for node in ordered_nodes(flow)? {
+            tracing::info!(
+                pack_id = %selection.dispatch.pack_id,
+                flow_id = %selection.dispatch.flow_id,
+                node = %node.id,
+                component = %node.component.id,
+                "executing deployment flow node"
+            );
+            if is_write_files_node(node) {
+                let files = inline_files(node)?;
+                let written = write_files(out_dir, &files).map_err(|err| {
+                    DeployerError::Pack(format!("node {} failed: {err}", node.id))
+                })?;
+                report.files.extend(written);
+            } else {
+                let outcome = self.run_component(selection, node, &plan_json, out_dir)?;
+                report.files.extend(outcome.written);
+                report.statuses.extend(outcome.statuses);
+            }
+            report.nodes.push(node.id.to_string());
+        }
+        Ok(report)
+    }
+
+    fn run_component(
+        &self,
+        selection: &DeploymentPackSelection,
+        node: &Node,
+        plan_json: &str,
+        out_dir: &Path,
+    ) -> Result<HostState> {
+        let component_id = node.component.id.to_string();
+        let manifest_entry = selection
+            .manifest
+            .components
+            .iter()
+            .find(|component| component.id.to_string() == component_id)
+            .ok_or_else(|| {
+                DeployerError::Pack(format!(
+                    "node {} references component {} which is not part of pack {}",
+                    node.id, component_id, selection.dispatch.pack_id
+                ))
+            })?;
+        if !manifest_entry.world.starts_with(DEPLOY_PLAN_WORLD_PREFIX) {
+            return Err(DeployerError::Pack(format!(
+                "component {} targets world {} (expected {DEPLOY_PLAN_WORLD_PREFIX}@1.0.0)",
+                component_id, manifest_entry.world
+            )));
+        }
+
+        let wasm_path = Path::new("components").join(format!("{component_id}.wasm"));
+        let wasm_bytes = read_pack_entry(&selection.pack_path, &wasm_path)?;
+        let component = Component::from_binary(&self.engine, &wasm_bytes).map_err(|err| {
+            DeployerError::Pack(format!("invalid component {component_id}: {err}"))
+        })?;
+
+        let mut store = Store::new(
+            &self.engine,
+            HostState {
+                plan_json: plan_json.to_string(),
+                out_dir: out_dir.to_path_buf(),
+                written: Vec::new(),
+                statuses: Vec::new(),
+            },
+        );
+        let linker = build_linker(&self.engine)?;
+        let instance = linker.instantiate(&mut store, &component).map_err(|err| {
+            DeployerError::Other(format!("failed to instantiate {component_id}: {err}"))
+        })?;
+
+        let operation = node
+            .component
+            .operation
+            .as_deref()
+            .unwrap_or(DEFAULT_OPERATION);
+        let func = instance
+            .get_typed_func::<(), (std::result::Result<(), String>,)>(&mut store, operation)
+            .map_err(|err| {
+                DeployerError::Pack(format!(
+                    "component {component_id} export '{operation}' lookup failed: {err}"
+                ))
+            })?;
+        let (result,) = func.call(&mut store, ()).map_err(|err| {
+            DeployerError::Other(format!("{component_id}.{operation} trapped: {err}"))
+        })?;
+        func.post_return(&mut store).map_err(|err| {
+            DeployerError::Other(format!(
+                "{component_id}.{operation} post-return failed: {err}"
+            ))
+        })?;
+        result.map_err(|message| {
+            DeployerError::Pack(format!(
+                "component {component_id}.{operation} failed: {message}"
+            ))
+        })?;
+        Ok(store.into_data())
+    }
+}
+
+#[async_trait]
+impl DeploymentExecutor for WasmDeploymentExecutor {
+    async fn execute(
+        &self,
+        config: &DeployerConfig,
+        plan: &PlanContext,
+        dispatch: &DeploymentDispatch,
+    ) -> Result<()> {
+        let target = DeploymentTarget {
+            provider: plan.deployment.provider.clone(),
+            strategy: plan.deployment.strategy.clone(),
+        };
+        let selection = select_deployment_pack(config, &target, dispatch.clone())?;
+        let out_dir = config.provider_output_dir();
+        let report = self.run_selection(&selection, plan, &out_dir)?;
+        if config.quiet {
+            return Ok(());
+        }
+        println!(
+            "Executed deployment flow {}::{} ({} node(s)); wrote {} file(s) to {}",
+            dispatch.pack_id,
+            dispatch.flow_id,
+            report.nodes.len(),
+            report.files.len(),
+            out_dir.display()
+        );
+        for status in &report.statuses {
+            println!("  status: {status}");
+        }
+        Ok(())
+    }
+}
+
+struct HostState {
+    plan_json: String,
+    out_dir: PathBuf,
+    written: Vec<String>,
+    statuses: Vec<String>,
+}
+
+fn build_linker(engine: &Engine) -> Result<Linker<HostState>> {
+    let mut linker = Linker::<HostState>::new(engine);
+    let map_err = |err: wasmtime::Error| {
+        DeployerError::Other(format!("failed to register host interface: {err}"))
+    };
+
+    let mut plan_api = linker.instance(PLAN_API_INTERFACE).map_err(map_err)?;
+    plan_api
+        .func_wrap(
+            "get-deployment-plan",
+            |store: StoreContextMut<'_, HostState>, (): ()| Ok((store.data().plan_json.clone(),)),
+        )
+        .map_err(map_err)?;
+    plan_api
+        .func_wrap(
+            "emit-status",
+            |mut store: StoreContextMut<'_, HostState>, (message,): (String,)| {
+                tracing::info!(status = %message, "deployment component status");
+                store.data_mut().statuses.push(message);
+                Ok(())
+            },
+        )
+        .map_err(map_err)?;
+
+    let mut iac = linker
+        .instance(IAC_WRITE_FILES_INTERFACE)
+        .map_err(map_err)?;
+    iac.func_wrap(
+        "write-files",
+        |mut store: StoreContextMut<'_, HostState>, (files,): (Vec<FileSpec>,)| {
+            let state = store.data_mut();
+            let result = write_files(&state.out_dir, &files);
+            if let Ok(written) = &result {
+                state.written.extend(written.iter().cloned());
+            }
+            Ok((result,))
+        },
+    )
+    .map_err(map_err)?;
+
+    Ok(linker)
+}
+
+fn find_flow<'a>(manifest: &'a PackManifest, dispatch: &DeploymentDispatch) -> Result<&'a Flow> {
+    manifest
+        .flows
+        .iter()
+        .find(|entry| entry.id.to_string() == dispatch.flow_id)
+        .map(|entry| &entry.flow)
+        .ok_or_else(|| {
+            DeployerError::Config(format!(
+                "Flow {} not found in {}",
+                dispatch.flow_id, dispatch.pack_id
+            ))
+        })
+}
+
+/// Walk the flow from its ingress node following `Next` routing (and `Branch` defaults).
+fn ordered_nodes(flow: &Flow) -> Result<Vec<&Node>> {
+    let mut ordered = Vec::new();
+    let mut visited = HashSet::new();
+    let mut current = flow.ingress().map(|(_, node)| node);
+    while let Some(node) = current {
+        if !visited.insert(node.id.to_string()) {
+            return Err(DeployerError::Pack(format!(
+                "flow {} loops back to node {}",
+                flow.id, node.id
+            )));
+        }
+        ordered.push(node);
+        let next = match &node.routing {
+            Routing::Next { node_id } => Some(node_id),
+            Routing::Branch { default, .. } => default.as_ref(),
+            Routing::End | Routing::Reply | Routing::Custom(_) => None,
+        };
+        current = match next {
+            Some(node_id) => Some(flow.nodes.get(node_id).ok_or_else(|| {
+                DeployerError::Pack(format!(
+                    "node {} routes to unknown node {} in flow {}",
+                    node.id, node_id, flow.id
+                ))
+            })?),
+            None => None,
+        };
+    }
+    Ok(ordered)
+}
+
+fn is_write_files_node(node: &Node) -> bool {
+    let id = node.component.id.to_string();
+    id == IAC_WRITE_FILES_COMPONENT || id == format!("{IAC_WRITE_FILES_COMPONENT}.write-files")
+}
+
+fn inline_files(node: &Node) -> Result<Vec<FileSpec>> {
+    let files = node
+        .input
+        .mapping
+        .get("files")
+        .cloned()
+        .unwrap_or_else(|| serde_json::Value::Array(Vec::new()));
+    serde_json::from_value(files).map_err(|err| {
+        DeployerError::Pack(format!(
+            "node {} has invalid write-files input: {err}",
+            node.id
+        ))
+    })
+}
+
+/// Write files under `out_dir`, mirroring the `iac-write-files` component semantics.
+pub fn write_files(
+    out_dir: &Path,
+    files: &[FileSpec],
+) -> std::result::Result<Vec<String>, WriteError> {
+    let mut written = Vec::with_capacity(files.len());
+    for spec in files {
+        let relative = sanitize_rel_path(&spec.path).map_err(|message| WriteError {
+            code: 2,
+            message,
+            path: Some(spec.path.clone()),
+        })?;
+        let target = out_dir.join(&relative);
+        if !spec.overwrite && target.exists() {
+            return Err(WriteError {
+                code: 3,
+                message: "file exists and overwrite is false".to_string(),
+                path: Some(relative.display().to_string()),
+            });
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|err| WriteError {
+                code: 4,
+                message: format!("failed to create parent directories: {err}"),
+                path: Some(parent.display().to_string()),
+            })?;
+        }
+        fs::write(&target, spec.content.as_bytes()).map_err(|err| WriteError {
+            code: 5,
+            message: format!("failed to write file: {err}"),
+            path: Some(relative.display().to_string()),
+        })?;
+        written.push(relative.display().to_string());
+    }
+    Ok(written)
+}
+
+fn sanitize_rel_path(path: &str) -> std::result::Result<PathBuf, String> {
+    let candidate = Path::new(path);
+    if path.is_empty() {
+        return Err("empty paths are not allowed".into());
+    }
+    if candidate.is_absolute() {
+        return Err("absolute paths are not allowed".into());
+    }
+    if candidate
+        .components()
+        .any(|comp| !matches!(comp, PathComponent::Normal(_) | PathComponent::CurDir))
+    {
+        return Err("path traversal is not allowed".into());
+    }
+    Ok(candidate.to_path_buf())
+}
+
+fn read_pack_entry(pack_path: &Path, relative: &Path) -> Result<Vec<u8>> {
+    if pack_path.is_dir() {
+        let path = normalize_under_root(pack_path, relative)
+            .map_err(|err| DeployerError::Pack(err.to_string()))?;
+        Ok(fs::read(path)?)
+    } else {
+        crate::pack_introspect::read_entry_from_gtpack(pack_path, relative)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan::{DeploymentHints, MessagingContext, PlanContext, Target, TelemetryContext};
+    use greentic_types::cbor::encode_pack_manifest;
+    use greentic_types::component::{ComponentCapabilities, ComponentManifest, ComponentProfiles};
+    use greentic_types::deployment::DeploymentPlan;
+    use greentic_types::flow::{ComponentRef, FlowKind, FlowMetadata, InputMapping, OutputMapping};
+    use greentic_types::pack_manifest::{PackFlowEntry, PackKind};
+    use greentic_types::{ComponentId, FlowId, NodeId, PackId};
+    use semver::Version;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    const PLAN_COMPONENT_WAT: &str = r#"
+(component
+  (import "greentic:deploy-plan/plan-api@1.0.0" (instance $plan
+    (export "get-deployment-plan" (func (result string)))
+    (export "emit-status" (func (param "message" string)))
+  ))
+  (import "greentic:host/iac-write-files@1.0.0" (instance $iac
+    (type $fs (record (field "path" string) (field "content" string) (field "overwrite" bool)))
+    (export "file-spec" (type $fs-e (eq $fs)))
+    (type $we (record (field "code" u32) (field "message" string) (field "path" (option string))))
+    (export "write-error" (type $we-e (eq $we)))
+    (export "write-files" (func (param "files" (list $fs-e)) (result (result (list string) (error $we-e)))))
+  ))
+  (core module $libc
+    (memory (export "memory") 1)
+    (global $heap (mut i32) (i32.const 4096))
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (local $ret i32)
+      global.get $heap
+      i32.const 7
+      i32.add
+      i32.const -8
+      i32.and
+      local.tee $ret
+      local.get 3
+      i32.add
+      global.set $heap
+      local.get $ret))
+  (core instance $libc (instantiate $libc))
+  (alias core export $libc "memory" (core memory $mem))
+  (alias core export $libc "realloc" (core func $realloc))
+  (core func $get-plan (canon lower (func $plan "get-deployment-plan") (memory $mem) (realloc $realloc)))
+  (core func $emit (canon lower (func $plan "emit-status") (memory $mem) (realloc $realloc)))
+  (core func $write (canon lower (func $iac "write-files") (memory $mem) (realloc $realloc)))
+  (core module $main
+    (import "env" "memory" (memory 1))
+    (import "host" "get-plan" (func $get_plan (param i32)))
+    (import "host" "emit" (func $emit (param i32 i32)))
+    (import "host" "write" (func $write (param i32 i32 i32)))
+    (data (i32.const 100) "plan.json")
+    (data (i32.const 200) "rendered")
+    (func (export "run") (result i32)
+      ;; fetch the plan into (ptr, len) at 400
+      i32.const 400
+      call $get_plan
+      ;; file-spec at 300: path="plan.json", content=<plan>, overwrite=true
+      i32.const 300
+      i32.const 100
+      i32.store
+      i32.const 304
+      i32.const 9
+      i32.store
+      i32.const 308
+      i32.const 400
+      i32.load
+      i32.store
+      i32.const 312
+      i32.const 404
+      i32.load
+      i32.store
+      i32.const 316
+      i32.const 1
+      i32.store8
+      i32.const 300
+      i32.const 1
+      i32.const 500
+      call $write
+      i32.const 200
+      i32.const 8
+      call $emit
+      ;; result<_, string> at 600: ok when write-files succeeded
+      i32.const 600
+      i32.const 500
+      i32.load8_u
+      i32.store8
+      i32.const 604
+      i32.const 200
+      i32.store
+      i32.const 608
+      i32.const 8
+      i32.store
+      i32.const 600))
+  (core instance $main (instantiate $main
+    (with "env" (instance (export "memory" (memory $mem))))
+    (with "host" (instance
+      (export "get-plan" (func $get-plan))
+      (export "emit" (func $emit))
+      (export "write" (func $write))))))
+  (func (export "run") (result (result (error string)))
+    (canon lift (core func $main "run") (memory $mem) (realloc $realloc)))
+)
+"#;
+
+    fn node(id: &str, component: &str, mapping: serde_json::Value, next: Option<&str>) -> Node {
+        Node {
+            id: NodeId::try_from(id).unwrap(),
+            component: ComponentRef {
+                id: ComponentId::try_from(component).unwrap(),
+                pack_alias: None,
+                operation: None,
+            },
+            input: InputMapping { mapping },
+            output: OutputMapping {
+                mapping: serde_json::Value::Null,
+            },
+            routing: match next {
+                Some(next) => Routing::Next {
+                    node_id: NodeId::try_from(next).unwrap(),
+                },
+                None => Routing::End,
+            },
+            telemetry: Default::default(),
+        }
+    }
+
+    fn selection(dir: &Path, nodes: Vec<Node>) -> DeploymentPackSelection {
+        let mut flow_nodes = indexmap::IndexMap::default();
+        for node in nodes {
+            flow_nodes.insert(node.id.clone(), node);
+        }
+        let flow_id = FlowId::try_from("deploy_flow").unwrap();
+        let manifest = PackManifest {
+            schema_version: "pack-v1".to_string(),
+            pack_id: PackId::try_from("test.deploy").unwrap(),
+            version: Version::new(0, 1, 0),
+            kind: PackKind::Application,
+            publisher: "greentic".to_string(),
+            secret_requirements: Vec::new(),
+            components: vec![ComponentManifest {
+                id: ComponentId::try_from("test.plan-renderer").unwrap(),
+                version: Version::new(0, 1, 0),
+                supports: Vec::new(),
+                world: "greentic:deploy-plan/deployer@1.0.0".to_string(),
+                profiles: ComponentProfiles::default(),
+                capabilities: ComponentCapabilities::default(),
+                configurators: None,
+                operations: Vec::new(),
+                config_schema: None,
+                resources: Default::default(),
+                dev_flows: Default::default(),
+            }],
+            flows: vec![PackFlowEntry {
+                id: flow_id.clone(),
+                kind: FlowKind::ComponentConfig,
+                flow: Flow {
+                    schema_version: "flow-v1".into(),
+                    id: flow_id,
+                    kind: FlowKind::ComponentConfig,
+                    entrypoints: BTreeMap::new(),
+                    nodes: flow_nodes,
+                    metadata: FlowMetadata::default(),
+                },
+                tags: Vec::new(),
+                entrypoints: Vec::new(),
+            }],
+            dependencies: Vec::new(),
+            capabilities: Vec::new(),
+            signatures: Default::default(),
+            bootstrap: None,
+            extensions: None,
+        };
+        let bytes = encode_pack_manifest(&manifest).expect("encode manifest");
+        fs::write(dir.join("manifest.cbor"), bytes).expect("write manifest");
+        DeploymentPackSelection {
+            dispatch: DeploymentDispatch {
+                pack_id: "test.deploy".into(),
+                flow_id: "deploy_flow".into(),
+                digest: None,
+            },
+            pack_path: dir.to_path_buf(),
+            manifest,
+            pack_digest: "sha256:test".into(),
+            origin: "test".into(),
+            candidates: Vec::new(),
+        }
+    }
+
+    fn plan_context() -> PlanContext {
+        let plan = DeploymentPlan {
+            pack_id: "test.app".into(),
+            pack_version: Version::new(0, 1, 0),
+            tenant: "acme".into(),
+            environment: "dev".into(),
+            runners: Vec::new(),
+            messaging: None,
+            channels: Vec::new(),
+            secrets: Vec::new(),
+            oauth: Vec::new(),
+            telemetry: None,
+            extra: json!({}),
+        };
+        PlanContext {
+            plan,
+            target: Target::Aws,
+            external_components: Vec::new(),
+            components: Vec::new(),
+            messaging: MessagingContext {
+                logical_cluster: "nats-dev".into(),
+                replicas: 1,
+                admin_url: "nats://localhost".into(),
+            },
+            telemetry: TelemetryContext {
+                otlp_endpoint: "http://localhost:4317".into(),
+                resource_attributes: BTreeMap::new(),
+            },
+            channels: Vec::new(),
+            secrets: Vec::new(),
+            deployment: DeploymentHints {
+                target: Target::Aws,
+                provider: "aws".into(),
+                strategy: "iac-only".into(),
+            },
+        }
+    }
+
+    #[test]
+    fn host_write_files_node_writes_under_output_dir() {
+        let pack = tempfile::tempdir().expect("pack dir");
+        let out = tempfile::tempdir().expect("out dir");
+        let selection = selection(
+            pack.path(),
+            vec![node(
+                "emit",
+                IAC_WRITE_FILES_COMPONENT,
+                json!({"files": [
+                    {"path": "main.tf", "content": "# tf", "overwrite": true},
+                    {"path": "nested/vars.tf", "content": "# vars"}
+                ]}),
+                None,
+            )],
+        );
+        let executor = WasmDeploymentExecutor::new().expect("engine");
+        let report = executor
+            .run_selection(&selection, &plan_context(), out.path())
+            .expect("flow runs");
+        assert_eq!(report.nodes, vec!["emit".to_string()]);
+        assert_eq!(report.files.len(), 2);
+        assert_eq!(
+            fs::read_to_string(out.path().join("nested/vars.tf")).unwrap(),
+            "# vars"
+        );
+    }
+
+    #[test]
+    fn runs_deploy_plan_component_with_host_interfaces() {
+        let pack = tempfile::tempdir().expect("pack dir");
+        let out = tempfile::tempdir().expect("out dir");
+        fs::create_dir_all(pack.path().join("components")).unwrap();
+        let wasm = wat::parse_str(PLAN_COMPONENT_WAT).expect("component wat");
+        fs::write(pack.path().join("components/test.plan-renderer.wasm"), wasm).unwrap();
+        let selection = selection(
+            pack.path(),
+            vec![
+                node("render", "test.plan-renderer", json!({}), Some("readme")),
+                node(
+                    "readme",
+                    IAC_WRITE_FILES_COMPONENT,
+                    json!({"files": [{"path": "README.md", "content": "hi"}]}),
+                    None,
+                ),
+            ],
+        );
+        let executor = WasmDeploymentExecutor::new().expect("engine");
+        let report = executor
+            .run_selection(&selection, &plan_context(), out.path())
+            .expect("flow runs");
+        assert_eq!(
+            report.nodes,
+            vec!["render".to_string(), "readme".to_string()]
+        );
+        assert_eq!(report.statuses, vec!["rendered".to_string()]);
+        let written: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(out.path().join("plan.json")).unwrap())
+                .expect("plan json");
+        assert_eq!(written["pack_id"], "test.app");
+        assert!(out.path().join("README.md").exists());
+    }
+
+    #[test]
+    fn rejects_paths_escaping_output_dir() {
+        let out = tempfile::tempdir().expect("out dir");
+        for path in ["../escape.tf", "/etc/passwd", ""] {
+            let err = write_files(
+                out.path(),
+                &[FileSpec {
+                    path: path.into(),
+                    content: String::new(),
+                    overwrite: true,
+                }],
+            )
+            .expect_err("path rejected");
+            assert_eq!(err.code, 2);
+        }
+    }
+
+    #[test]
+    fn refuses_to_overwrite_without_flag() {
+        let out = tempfile::tempdir().expect("out dir");
+        fs::write(out.path().join("main.tf"), "old").unwrap();
+        let err = write_files(
+            out.path(),
+            &[FileSpec {
+                path: "main.tf".into(),
+                content: "new".into(),
+                overwrite: false,
+            }],
+        )
+        .expect_err("overwrite refused");
+        assert_eq!(err.code, 3);
+    }
+
+    #[test]
+    fn rejects_components_outside_deploy_plan_world() {
+        let pack = tempfile::tempdir().expect("pack dir");
+        let out = tempfile::tempdir().expect("out dir");
+        let mut selection = selection(
+            pack.path(),
+            vec![node("render", "test.plan-renderer", json!({}), None)],
+        );
+        selection.manifest.components[0].world = "greentic:test/world".into();
+        let executor = WasmDeploymentExecutor::new().expect("engine");
+        let err = executor
+            .run_selection(&selection, &plan_context(), out.path())
+            .expect_err("world mismatch");
+        assert!(format!("{err}").contains("greentic:deploy-plan"));
+    }
+}
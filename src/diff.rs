@@ -0,0 +1,400 @@
+//! Semantic diff between two deployment plans.
+//!
+//! Compares runners, channels (plan entry + ingress/OAuth hints), secrets, OAuth clients,
+//! component role/profile mappings and telemetry so reviewers can see how a pack's
+//! deployment shape changes before running `apply`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::config::OutputFormat;
+use crate::error::{DeployerError, Result};
+use crate::plan::PlanContext;
+
+/// Field-level change for an entry present in both plans.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntryChange {
+    pub name: String,
+    pub changes: Vec<String>,
+}
+
+/// Added/removed/changed entries for one section of the plan.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SectionDiff {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub added: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changed: Vec<EntryChange>,
+}
+
+impl SectionDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Semantic differences between a baseline plan and a candidate plan.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanDiff {
+    pub runners: SectionDiff,
+    pub channels: SectionDiff,
+    pub secrets: SectionDiff,
+    pub oauth: SectionDiff,
+    pub components: SectionDiff,
+    pub telemetry: SectionDiff,
+}
+
+impl PlanDiff {
+    pub fn is_empty(&self) -> bool {
+        self.sections()
+            .iter()
+            .all(|(_, section)| section.is_empty())
+    }
+
+    fn sections(&self) -> [(&'static str, &SectionDiff); 6] {
+        [
+            ("runners", &self.runners),
+            ("channels", &self.channels),
+            ("secrets", &self.secrets),
+            ("oauth", &self.oauth),
+            ("components", &self.components),
+            ("telemetry", &self.telemetry),
+        ]
+    }
+}
+
+/// Load a plan previously persisted as JSON (e.g. `runtime/<tenant>/<env>/plan.json`).
+pub fn load_plan_file(path: &Path) -> Result<PlanContext> {
+    let bytes = fs::read(path).map_err(|err| {
+        DeployerError::Config(format!("failed to read plan {}: {err}", path.display()))
+    })?;
+    serde_json::from_slice(&bytes).map_err(|err| {
+        DeployerError::Config(format!("invalid plan file {}: {err}", path.display()))
+    })
+}
+
+/// Compute the semantic diff from `old` to `new`.
+pub fn diff_plans(old: &PlanContext, new: &PlanContext) -> PlanDiff {
+    PlanDiff {
+        runners: diff_entries(&runner_entries(old), &runner_entries(new)),
+        channels: diff_entries(&channel_entries(old), &channel_entries(new)),
+        secrets: diff_entries(&secret_entries(old), &secret_entries(new)),
+        oauth: diff_entries(&oauth_entries(old), &oauth_entries(new)),
+        components: diff_entries(&component_entries(old), &component_entries(new)),
+        telemetry: diff_entries(&telemetry_entries(old), &telemetry_entries(new)),
+    }
+}
+
+/// Render a diff as text, JSON or YAML.
+pub fn render_diff(diff: &PlanDiff, format: OutputFormat) -> Result<String> {
+    match format {
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(diff)?),
+        OutputFormat::Yaml => {
+            serde_yaml_bw::to_string(diff).map_err(|err| DeployerError::Other(err.to_string()))
+        }
+        OutputFormat::Text => {
+            if diff.is_empty() {
+                return Ok("No deployment shape changes.\n".to_string());
+            }
+            let mut out = String::new();
+            for (name, section) in diff.sections() {
+                if section.is_empty() {
+                    continue;
+                }
+                out.push_str(&format!("{name}:\n"));
+                for added in &section.added {
+                    out.push_str(&format!("  + {added}\n"));
+                }
+                for removed in &section.removed {
+                    out.push_str(&format!("  - {removed}\n"));
+                }
+                for changed in &section.changed {
+                    out.push_str(&format!(
+                        "  ~ {}: {}\n",
+                        changed.name,
+                        changed.changes.join("; ")
+                    ));
+                }
+            }
+            Ok(out)
+        }
+    }
+}
+
+type Entries = BTreeMap<String, Value>;
+
+fn diff_entries(old: &Entries, new: &Entries) -> SectionDiff {
+    let mut diff = SectionDiff::default();
+    for (name, new_value) in new {
+        match old.get(name) {
+            None => diff.added.push(name.clone()),
+            Some(old_value) if old_value != new_value => diff.changed.push(EntryChange {
+                name: name.clone(),
+                changes: field_changes(old_value, new_value),
+            }),
+            Some(_) => {}
+        }
+    }
+    diff.removed = old
+        .keys()
+        .filter(|name| !new.contains_key(*name))
+        .cloned()
+        .collect();
+    diff
+}
+
+fn field_changes(old: &Value, new: &Value) -> Vec<String> {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            keys.into_iter()
+                .filter_map(|key| {
+                    let before = old.get(key).unwrap_or(&Value::Null);
+                    let after = new.get(key).unwrap_or(&Value::Null);
+                    (before != after)
+                        .then(|| format!("{key} {} -> {}", compact(before), compact(after)))
+                })
+                .collect()
+        }
+        _ => vec![format!("{} -> {}", compact(old), compact(new))],
+    }
+}
+
+fn compact(value: &Value) -> String {
+    match value {
+        Value::Null => "(none)".to_string(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn to_value<T: Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+fn runner_entries(plan: &PlanContext) -> Entries {
+    plan.plan
+        .runners
+        .iter()
+        .map(|runner| (runner.name.clone(), to_value(runner)))
+        .collect()
+}
+
+fn channel_entries(plan: &PlanContext) -> Entries {
+    let mut entries: Entries = plan
+        .plan
+        .channels
+        .iter()
+        .map(|channel| (channel.name.clone(), to_value(channel)))
+        .collect();
+    for hints in &plan.channels {
+        let entry = entries
+            .entry(hints.name.clone())
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let Value::Object(map) = entry {
+            map.insert("ingress".into(), to_value(&hints.ingress));
+            map.insert("oauth_required".into(), Value::Bool(hints.oauth_required));
+        }
+    }
+    entries
+}
+
+fn secret_entries(plan: &PlanContext) -> Entries {
+    plan.secrets
+        .iter()
+        .map(|secret| (secret.key.as_str().to_string(), to_value(secret)))
+        .collect()
+}
+
+fn oauth_entries(plan: &PlanContext) -> Entries {
+    plan.plan
+        .oauth
+        .iter()
+        .map(|client| {
+            (
+                format!("{}/{}", client.provider_id, client.logical_client_id),
+                to_value(client),
+            )
+        })
+        .collect()
+}
+
+fn component_entries(plan: &PlanContext) -> Entries {
+    plan.components
+        .iter()
+        .map(|component| {
+            (
+                component.id.clone(),
+                serde_json::json!({
+                    "role": component.role.as_str(),
+                    "profile": component.profile.as_str(),
+                    "target": component.target.as_str(),
+                    "infra": component.infra.summary,
+                }),
+            )
+        })
+        .collect()
+}
+
+fn telemetry_entries(plan: &PlanContext) -> Entries {
+    let mut entries = Entries::new();
+    entries.insert(
+        "otlp_endpoint".into(),
+        Value::String(plan.telemetry.otlp_endpoint.clone()),
+    );
+    for (key, value) in &plan.telemetry.resource_attributes {
+        entries.insert(format!("attribute.{key}"), Value::String(value.clone()));
+    }
+    if let Some(telemetry) = &plan.plan.telemetry {
+        entries.insert("required".into(), Value::Bool(telemetry.required));
+        if let Some(endpoint) = &telemetry.suggested_endpoint {
+            entries.insert("suggested_endpoint".into(), Value::String(endpoint.clone()));
+        }
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan::{
+        ComponentRole, DeploymentHints, DeploymentProfile, InfraPlan, MessagingContext,
+        PlannedComponent, Target, TelemetryContext,
+    };
+    use greentic_types::deployment::{ChannelPlan, DeploymentPlan, OAuthPlan, RunnerPlan};
+    use semver::Version;
+    use serde_json::json;
+
+    fn component(id: &str, profile: DeploymentProfile) -> PlannedComponent {
+        PlannedComponent {
+            id: id.into(),
+            role: ComponentRole::Worker,
+            profile: profile.clone(),
+            target: Target::Aws,
+            infra: InfraPlan {
+                target: Target::Aws,
+                profile,
+                summary: "ecs service".into(),
+                resources: Vec::new(),
+                notes: None,
+            },
+            inference: None,
+        }
+    }
+
+    fn plan() -> PlanContext {
+        PlanContext {
+            plan: DeploymentPlan {
+                pack_id: "dev.greentic.sample".into(),
+                pack_version: Version::new(0, 1, 0),
+                tenant: "acme".into(),
+                environment: "staging".into(),
+                runners: vec![RunnerPlan {
+                    name: "main".into(),
+                    replicas: 1,
+                    capabilities: json!({}),
+                }],
+                messaging: None,
+                channels: vec![ChannelPlan {
+                    name: "chat".into(),
+                    flow_id: "chat_flow".into(),
+                    kind: "messaging".into(),
+                    config: json!({}),
+                }],
+                secrets: Vec::new(),
+                oauth: vec![OAuthPlan {
+                    provider_id: "google".into(),
+                    logical_client_id: "chat".into(),
+                    redirect_path: "/oauth/google".into(),
+                    extra: json!({}),
+                }],
+                telemetry: None,
+                extra: json!({}),
+            },
+            target: Target::Aws,
+            external_components: Vec::new(),
+            components: vec![component(
+                "dev.greentic.msg",
+                DeploymentProfile::LongLivedService,
+            )],
+            messaging: MessagingContext {
+                logical_cluster: "nats".into(),
+                replicas: 1,
+                admin_url: "nats://localhost".into(),
+            },
+            telemetry: TelemetryContext {
+                otlp_endpoint: "http://collector:4317".into(),
+                resource_attributes: BTreeMap::new(),
+            },
+            channels: Vec::new(),
+            secrets: Vec::new(),
+            deployment: DeploymentHints {
+                target: Target::Aws,
+                provider: "aws".into(),
+                strategy: "iac-only".into(),
+            },
+        }
+    }
+
+    #[test]
+    fn identical_plans_have_no_diff() {
+        let diff = diff_plans(&plan(), &plan());
+        assert!(diff.is_empty());
+        assert_eq!(
+            render_diff(&diff, OutputFormat::Text).unwrap(),
+            "No deployment shape changes.\n"
+        );
+    }
+
+    #[test]
+    fn reports_added_removed_and_changed_entries() {
+        let old = plan();
+        let mut new = plan();
+        new.plan.runners[0].replicas = 3;
+        new.plan.runners.push(RunnerPlan {
+            name: "worker".into(),
+            replicas: 1,
+            capabilities: json!({}),
+        });
+        new.plan.oauth.clear();
+        new.components[0] = component("dev.greentic.msg", DeploymentProfile::HttpEndpoint);
+        new.telemetry.otlp_endpoint = "http://otel:4317".into();
+
+        let diff = diff_plans(&old, &new);
+        assert_eq!(diff.runners.added, vec!["worker".to_string()]);
+        assert_eq!(
+            diff.runners.changed[0].changes,
+            vec!["replicas 1 -> 3".to_string()]
+        );
+        assert_eq!(diff.oauth.removed, vec!["google/chat".to_string()]);
+        assert!(diff.components.changed[0].changes[0].contains("profile"));
+        assert!(diff.channels.is_empty());
+        assert_eq!(diff.telemetry.changed[0].name, "otlp_endpoint");
+
+        let text = render_diff(&diff, OutputFormat::Text).unwrap();
+        assert!(text.contains("runners:\n  + worker\n  ~ main: replicas 1 -> 3"));
+        assert!(text.contains("oauth:\n  - google/chat"));
+
+        let json: Value = serde_json::from_str(&render_diff(&diff, OutputFormat::Json).unwrap())
+            .expect("json diff");
+        assert_eq!(json["runners"]["added"][0], "worker");
+    }
+
+    #[test]
+    fn round_trips_persisted_plan_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("plan.json");
+        fs::write(&path, serde_json::to_vec_pretty(&plan()).unwrap()).unwrap();
+        let loaded = load_plan_file(&path).expect("plan loads");
+        assert!(diff_plans(&plan(), &loaded).is_empty());
+        assert!(load_plan_file(&dir.path().join("missing.json")).is_err());
+    }
+}
@@ -0,0 +1,209 @@
+//! Drift detection for an applied tenant/environment.
+//!
+//! Infra drift comes from a refresh-only IaC plan of the deploy directory; pack drift compares
+//! the plan built from the current pack against the plan stored with the applied ledger record.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::config::OutputFormat;
+use crate::diff::{PlanDiff, diff_plans, render_diff};
+use crate::error::{DeployerError, Result};
+use crate::iac::{
+    IaCCommandRunner, IaCTool, PlanChangeSummary, provider_runs_iac, run_iac_drift_check,
+};
+use crate::ledger::{Ledger, LedgerRecord};
+use crate::plan::PlanContext;
+
+/// Result of `greentic-deployer drift`.
+#[derive(Debug, Clone, Serialize)]
+pub struct DriftReport {
+    pub tenant: String,
+    pub environment: String,
+    pub provider: String,
+    /// Ledger record describing what is currently deployed.
+    pub record: u64,
+    pub pack_id: String,
+    pub pack_version: String,
+    /// Resources changed outside IaC; `None` when the infrastructure matches state or no IaC
+    /// check was run (see `infra_checked`).
+    pub infra_drift: Option<PlanChangeSummary>,
+    pub infra_checked: bool,
+    /// Plan changes between the applied record and the current pack; `None` without `--pack`.
+    pub pack_drift: Option<PlanDiff>,
+}
+
+impl DriftReport {
+    pub fn has_infra_drift(&self) -> bool {
+        self.infra_drift.is_some()
+    }
+
+    pub fn has_pack_drift(&self) -> bool {
+        self.pack_drift
+            .as_ref()
+            .is_some_and(|diff| !diff.is_empty())
+    }
+
+    pub fn has_drift(&self) -> bool {
+        self.has_infra_drift() || self.has_pack_drift()
+    }
+}
+
+/// Currently deployed record for the ledger, or an error when nothing is applied.
+pub fn applied_record(ledger: &Ledger) -> Result<LedgerRecord> {
+    ledger.current()?.ok_or_else(|| {
+        DeployerError::Config(format!(
+            "nothing is currently deployed according to {}; drift requires a successful apply",
+            ledger.records_path().display()
+        ))
+    })
+}
+
+/// Deploy directory holding the applied artifacts for a record.
+pub fn deploy_dir(state_dir: &Path, record: &LedgerRecord) -> PathBuf {
+    state_dir
+        .join("deploy")
+        .join(&record.provider)
+        .join(&record.tenant)
+        .join(&record.environment)
+}
+
+/// Check infra and (optionally) pack drift for the applied record.
+pub fn check_drift(
+    ledger: &Ledger,
+    record: &LedgerRecord,
+    deploy_dir: &Path,
+    tool: IaCTool,
+    runner: &dyn IaCCommandRunner,
+    candidate: Option<&PlanContext>,
+) -> Result<DriftReport> {
+    let infra_checked = !record.artifacts.is_empty() && provider_runs_iac(&record.provider);
+    let infra_drift = if infra_checked {
+        run_iac_drift_check(runner, tool, deploy_dir)?
+    } else {
+        None
+    };
+    let pack_drift = match candidate {
+        Some(candidate) => Some(diff_plans(&applied_plan(ledger, record)?, candidate)),
+        None => None,
+    };
+    Ok(DriftReport {
+        tenant: record.tenant.clone(),
+        environment: record.environment.clone(),
+        provider: record.provider.clone(),
+        record: record.id,
+        pack_id: record.pack_id.clone(),
+        pack_version: record.pack_version.clone(),
+        infra_drift,
+        infra_checked,
+        pack_drift,
+    })
+}
+
+/// Plan stored with an applied ledger record.
+fn applied_plan(ledger: &Ledger, record: &LedgerRecord) -> Result<PlanContext> {
+    let sha = record.plan_sha256.as_deref().ok_or_else(|| {
+        DeployerError::Config(format!(
+            "ledger record {} has no stored plan; re-apply to enable pack drift checks",
+            record.id
+        ))
+    })?;
+    Ok(serde_json::from_slice(&ledger.read_blob(sha)?)?)
+}
+
+pub fn render_drift(report: &DriftReport, format: OutputFormat) -> Result<String> {
+    match format {
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(report)?),
+        OutputFormat::Yaml => {
+            serde_yaml_bw::to_string(report).map_err(|err| DeployerError::Other(err.to_string()))
+        }
+        OutputFormat::Text => {
+            let mut out = format!(
+                "Drift report for {}/{} ({}; record #{}, {}@{})\n",
+                report.tenant,
+                report.environment,
+                report.provider,
+                report.record,
+                report.pack_id,
+                report.pack_version
+            );
+            out.push_str("Infra drift: ");
+            match (&report.infra_drift, report.infra_checked) {
+                (_, false) => out.push_str("not checked (no IaC artifacts)\n"),
+                (None, true) => out.push_str("none\n"),
+                (Some(drift), true) => {
+                    out.push_str("detected\n");
+                    for (marker, label, addresses) in [
+                        ("~", "changed", &drift.updates),
+                        ("-", "deleted", &drift.deletes),
+                        ("+", "created", &drift.creates),
+                        ("-/+", "replaced", &drift.replaces),
+                    ] {
+                        for address in addresses {
+                            out.push_str(&format!("  {marker} {address} ({label} outside IaC)\n"));
+                        }
+                    }
+                }
+            }
+            out.push_str("Pack drift: ");
+            match &report.pack_drift {
+                None => out.push_str("not checked (pass --pack)\n"),
+                Some(diff) if diff.is_empty() => out.push_str("none\n"),
+                Some(diff) => {
+                    out.push_str("detected\n");
+                    out.push_str(&render_diff(diff, OutputFormat::Text)?);
+                }
+            }
+            Ok(out)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> DriftReport {
+        DriftReport {
+            tenant: "acme".into(),
+            environment: "staging".into(),
+            provider: "aws".into(),
+            record: 4,
+            pack_id: "dev.greentic.sample".into(),
+            pack_version: "0.1.0".into(),
+            infra_drift: None,
+            infra_checked: true,
+            pack_drift: Some(PlanDiff::default()),
+        }
+    }
+
+    #[test]
+    fn reports_clean_environment() {
+        let report = report();
+        assert!(!report.has_drift());
+        let text = render_drift(&report, OutputFormat::Text).unwrap();
+        assert!(text.contains("Infra drift: none"));
+        assert!(text.contains("Pack drift: none"));
+    }
+
+    #[test]
+    fn reports_infra_drift_separately_from_pack_drift() {
+        let mut report = report();
+        report.infra_drift = Some(PlanChangeSummary {
+            updates: vec!["aws_ecs_service.runner".into()],
+            ..PlanChangeSummary::default()
+        });
+        assert!(report.has_infra_drift());
+        assert!(!report.has_pack_drift());
+        let text = render_drift(&report, OutputFormat::Text).unwrap();
+        assert!(text.contains("~ aws_ecs_service.runner (changed outside IaC)"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&render_drift(&report, OutputFormat::Json).unwrap()).unwrap();
+        assert_eq!(
+            json["infra_drift"]["updates"][0],
+            serde_json::json!("aws_ecs_service.runner")
+        );
+    }
+}
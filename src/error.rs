@@ -57,6 +57,25 @@ pub enum DeployerError {
         stderr: String,
     },
 
+    #[error("IaC tool '{tool}' command '{step}' timed out after {timeout_secs}s and was killed")]
+    IaCTimeout {
+        tool: String,
+        step: String,
+        timeout_secs: u64,
+    },
+
+    #[error(
+        "IaC plan deletes {deletes} and replaces {replaces} resource(s); re-run with --allow-destroy to apply it"
+    )]
+    DestructivePlan { deletes: usize, replaces: usize },
+
+    #[error("pack digest mismatch for {pack}: expected {expected}, got {actual}")]
+    PackDigestMismatch {
+        pack: String,
+        expected: String,
+        actual: String,
+    },
+
     #[error("deployment packs not wired yet for provider={provider}, strategy={strategy}")]
     DeploymentPackUnsupported { provider: String, strategy: String },
 
@@ -1,11 +1,33 @@
+use std::collections::HashSet;
 use std::fmt;
-use std::path::Path;
-use std::process::Command;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use clap::ValueEnum;
-use tracing::{debug, warn};
+use serde::{Deserialize, Serialize};
+use tracing::{Span, debug, info, info_span, warn};
 
+use crate::config::OutputFormat;
 use crate::error::DeployerError;
+use crate::ledger::{format_timestamp, now_ts};
+use crate::state_backend::BACKEND_CONFIG_FILE;
+
+/// Saved plan file produced by `plan -out` inside the deploy directory.
+pub const PLAN_FILE: &str = "plan.tfplan";
+
+/// Refresh-only plan written by [`run_iac_drift_check`].
+pub const DRIFT_PLAN_FILE: &str = "drift.tfplan";
+
+/// Combined stdout/stderr of the IaC steps of the latest run, kept in the deploy directory.
+pub const IAC_LOG_FILE: &str = "iac.log";
+
+/// How long output readers may keep draining after a timed-out step was killed.
+const READER_DRAIN_GRACE: Duration = Duration::from_secs(2);
 
 /// Supported IaC tools.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -58,6 +80,11 @@ impl From<IacToolArg> for IaCTool {
     }
 }
 
+/// Whether deployments to `provider` (`Provider::as_str` name) are driven by an IaC tool.
+pub fn provider_runs_iac(provider: &str) -> bool {
+    matches!(provider, "aws" | "azure" | "gcp")
+}
+
 /// Resolve which IaC tool to use based on CLI flag, env, or PATH.
 pub fn resolve_iac_tool(
     cli_arg: Option<IacToolArg>,
@@ -90,93 +117,486 @@ pub fn resolve_iac_tool(
 
 /// Runner responsible for executing IaC commands.
 pub trait IaCCommandRunner: Send + Sync {
-    fn run(&self, tool: IaCTool, dir: &Path, args: &[&str]) -> Result<(), DeployerError>;
+    /// Run the command inside `dir`, returning its captured stdout.
+    fn run(&self, tool: IaCTool, dir: &Path, args: &[&str]) -> Result<String, DeployerError>;
+
+    /// Wall time of the steps executed so far (runners that do not time steps return nothing).
+    fn timings(&self) -> Vec<IaCStepTiming> {
+        Vec::new()
+    }
 }
 
-pub struct DefaultIaCCommandRunner;
+/// Wall time recorded for a single IaC step (init/plan/show/apply/destroy).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IaCStepTiming {
+    pub step: String,
+    pub elapsed: Duration,
+    pub succeeded: bool,
+}
+
+/// Runs the IaC binary, streaming stdout/stderr lines to the terminal (stderr), tracing and
+/// [`IAC_LOG_FILE`] inside the working directory.
+#[derive(Default)]
+pub struct DefaultIaCCommandRunner {
+    step_timeout: Option<Duration>,
+    timings: Mutex<Vec<IaCStepTiming>>,
+    logs_started: Mutex<HashSet<PathBuf>>,
+}
+
+impl DefaultIaCCommandRunner {
+    /// Runner that kills any step exceeding `step_timeout`.
+    pub fn new(step_timeout: Option<Duration>) -> Self {
+        Self {
+            step_timeout,
+            ..Self::default()
+        }
+    }
+
+    /// Open the log for `dir`, truncating it the first time this runner writes there.
+    fn open_log(&self, dir: &Path) -> std::io::Result<fs::File> {
+        let path = dir.join(IAC_LOG_FILE);
+        let fresh = self
+            .logs_started
+            .lock()
+            .expect("iac log registry poisoned")
+            .insert(path.clone());
+        OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(fresh)
+            .append(!fresh)
+            .open(path)
+    }
+}
 
 impl IaCCommandRunner for DefaultIaCCommandRunner {
-    fn run(&self, tool: IaCTool, dir: &Path, args: &[&str]) -> Result<(), DeployerError> {
+    fn run(&self, tool: IaCTool, dir: &Path, args: &[&str]) -> Result<String, DeployerError> {
         let binary = tool.binary_name();
+        let step = args.first().copied().unwrap_or("command").to_string();
         let mut command = Command::new(binary);
-        command
-            .args(args)
-            .current_dir(dir)
-            .stdin(std::process::Stdio::null());
-        let output = command.output();
-        match output {
-            Ok(output) => {
-                if output.status.success() {
-                    Ok(())
-                } else {
-                    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-                    let step = args.first().copied().unwrap_or("command").to_string();
-                    debug!(tool = ?tool, step = %step, stderr = %stderr);
-                    Err(DeployerError::IaCTool {
-                        tool: tool.to_string(),
-                        step,
-                        status: output.status.code(),
-                        stderr,
-                    })
-                }
+        command.args(args).current_dir(dir);
+        let mut log = self.open_log(dir)?;
+        writeln!(
+            log,
+            "==> {binary} {} ({})",
+            args.join(" "),
+            format_timestamp(now_ts())
+        )?;
+        // Machine-readable output (e.g. `show -json`) is captured but not echoed.
+        let echo = !args.contains(&"-json");
+        let span = info_span!("iac_step", tool = %tool, step = %step);
+        let output = match run_streaming(command, &span, echo, log, self.step_timeout) {
+            Ok(output) => output,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Err(DeployerError::IaCToolMissing {
+                    tool: tool.to_string(),
+                    binary,
+                });
             }
-            Err(err) => {
-                if err.kind() == std::io::ErrorKind::NotFound {
-                    Err(DeployerError::IaCToolMissing {
-                        tool: tool.to_string(),
-                        binary,
-                    })
-                } else {
-                    Err(DeployerError::Io(err))
-                }
+            Err(err) => return Err(DeployerError::Io(err)),
+        };
+        let succeeded = output.status.is_some_and(|status| status.success());
+        self.timings
+            .lock()
+            .expect("iac timings poisoned")
+            .push(IaCStepTiming {
+                step: step.clone(),
+                elapsed: output.elapsed,
+                succeeded,
+            });
+        span.in_scope(|| {
+            info!(
+                elapsed_ms = output.elapsed.as_millis() as u64,
+                succeeded, "IaC step finished"
+            )
+        });
+        match output.status {
+            None => Err(DeployerError::IaCTimeout {
+                tool: tool.to_string(),
+                step,
+                timeout_secs: self.step_timeout.map(|t| t.as_secs()).unwrap_or_default(),
+            }),
+            Some(_) if succeeded => Ok(output.stdout),
+            Some(status) => {
+                let stderr = output.stderr.trim().to_string();
+                debug!(tool = ?tool, step = %step, stderr = %stderr);
+                Err(DeployerError::IaCTool {
+                    tool: tool.to_string(),
+                    step,
+                    status: status.code(),
+                    stderr,
+                })
             }
         }
     }
+
+    fn timings(&self) -> Vec<IaCStepTiming> {
+        self.timings.lock().expect("iac timings poisoned").clone()
+    }
 }
 
-pub fn run_iac_plan_apply(
+struct StepOutput {
+    /// Exit status, or `None` when the step was killed after timing out.
+    status: Option<ExitStatus>,
+    stdout: String,
+    stderr: String,
+    elapsed: Duration,
+}
+
+fn run_streaming(
+    mut command: Command,
+    span: &Span,
+    echo: bool,
+    log: fs::File,
+    timeout: Option<Duration>,
+) -> std::io::Result<StepOutput> {
+    let started = Instant::now();
+    // The step leads its own process group so a timeout also reaches the providers and plugins
+    // it spawned, which would otherwise keep running and hold the output pipes open.
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let log = Arc::new(Mutex::new(log));
+    let stdout = child
+        .stdout
+        .take()
+        .map(|pipe| stream_lines(pipe, OutputStream::Stdout, echo, log.clone(), span.clone()));
+    let stderr = child
+        .stderr
+        .take()
+        .map(|pipe| stream_lines(pipe, OutputStream::Stderr, true, log.clone(), span.clone()));
+
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        if timeout.is_some_and(|limit| started.elapsed() >= limit) {
+            kill_step(&mut child)?;
+            break None;
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+    // After a kill, anything that escaped the process group may still hold a pipe open; give the
+    // readers a moment to drain and then leave them detached (the log file has their output).
+    let drain_deadline = Instant::now() + READER_DRAIN_GRACE;
+    let collect = |handle: Option<thread::JoinHandle<String>>| {
+        let Some(handle) = handle else {
+            return String::new();
+        };
+        while status.is_none() && !handle.is_finished() {
+            if Instant::now() >= drain_deadline {
+                return String::new();
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        handle.join().unwrap_or_default()
+    };
+    let stdout = collect(stdout);
+    let stderr = collect(stderr);
+    let elapsed = started.elapsed();
+
+    let mut log = log.lock().expect("iac log poisoned");
+    match status {
+        Some(status) => writeln!(
+            log,
+            "<== exit {:?} after {:.1}s",
+            status.code(),
+            elapsed.as_secs_f64()
+        )?,
+        None => writeln!(
+            log,
+            "<== killed after {:.1}s (timeout)",
+            elapsed.as_secs_f64()
+        )?,
+    }
+    Ok(StepOutput {
+        status,
+        stdout,
+        stderr,
+        elapsed,
+    })
+}
+
+/// Kill a timed-out step together with its process group (unix), then reap it.
+fn kill_step(child: &mut Child) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        let group = format!("-{}", child.id());
+        let killed = Command::new("kill")
+            .args(["-KILL", "--", &group])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+        if !killed.is_ok_and(|status| status.success()) {
+            child.kill()?;
+        }
+    }
+    #[cfg(not(unix))]
+    child.kill()?;
+    child.wait()?;
+    Ok(())
+}
+
+#[derive(Clone, Copy)]
+enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+fn stream_lines(
+    pipe: impl Read + Send + 'static,
+    stream: OutputStream,
+    echo: bool,
+    log: Arc<Mutex<fs::File>>,
+    span: Span,
+) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut captured = String::new();
+        for line in BufReader::new(pipe)
+            .lines()
+            .map_while(std::result::Result::ok)
+        {
+            // Both streams are echoed to stderr so command output on stdout stays parseable.
+            if echo {
+                eprintln!("{line}");
+            }
+            let label = match stream {
+                OutputStream::Stdout => "stdout",
+                OutputStream::Stderr => "stderr",
+            };
+            span.in_scope(|| debug!(stream = label, "{line}"));
+            if let Ok(mut log) = log.lock() {
+                let _ = writeln!(log, "[{label}] {line}");
+            }
+            captured.push_str(&line);
+            captured.push('\n');
+        }
+        captured
+    })
+}
+
+/// Run `init` and `plan`, then parse `show -json` of the saved plan into a change summary.
+pub fn run_iac_plan(
+    runner: &dyn IaCCommandRunner,
+    tool: IaCTool,
+    dir: &Path,
+) -> Result<PlanChangeSummary, DeployerError> {
+    let plan_out = format!("-out={PLAN_FILE}");
+    run_iac_init(runner, tool, dir)?;
+    runner.run(tool, dir, &["plan", "-input=false", &plan_out])?;
+    let json = runner.run(tool, dir, &["show", "-json", PLAN_FILE])?;
+    PlanChangeSummary::from_show_json(&json)
+}
+
+/// Apply the plan saved by [`run_iac_plan`].
+pub fn run_iac_apply(
     runner: &dyn IaCCommandRunner,
     tool: IaCTool,
     dir: &Path,
 ) -> Result<(), DeployerError> {
-    let commands = [
-        &["init", "-input=false"][..],
-        &["plan", "-input=false", "-out=plan.tfplan"][..],
-        &["apply", "-input=false", "-auto-approve", "plan.tfplan"][..],
-    ];
-    for command in commands {
-        runner.run(tool, dir, command)?;
-    }
+    runner.run(
+        tool,
+        dir,
+        &["apply", "-input=false", "-auto-approve", PLAN_FILE],
+    )?;
     Ok(())
 }
 
+/// Plan and apply without an intermediate confirmation, returning the applied change summary.
+pub fn run_iac_plan_apply(
+    runner: &dyn IaCCommandRunner,
+    tool: IaCTool,
+    dir: &Path,
+) -> Result<PlanChangeSummary, DeployerError> {
+    let summary = run_iac_plan(runner, tool, dir)?;
+    run_iac_apply(runner, tool, dir)?;
+    Ok(summary)
+}
+
 pub fn run_iac_destroy(
     runner: &dyn IaCCommandRunner,
     tool: IaCTool,
     dir: &Path,
 ) -> Result<(), DeployerError> {
-    let commands = [
-        &["init", "-input=false"][..],
-        &["destroy", "-input=false", "-auto-approve"][..],
+    run_iac_init(runner, tool, dir)?;
+    runner.run(tool, dir, &["destroy", "-input=false", "-auto-approve"])?;
+    Ok(())
+}
+
+/// Run a refresh-only plan with `-detailed-exitcode`. Exit code 2 means the real infrastructure
+/// drifted from state; the drifted resources are returned. `None` means no drift.
+pub fn run_iac_drift_check(
+    runner: &dyn IaCCommandRunner,
+    tool: IaCTool,
+    dir: &Path,
+) -> Result<Option<PlanChangeSummary>, DeployerError> {
+    run_iac_init(runner, tool, dir)?;
+    let plan_out = format!("-out={DRIFT_PLAN_FILE}");
+    let args = [
+        "plan",
+        "-refresh-only",
+        "-detailed-exitcode",
+        "-input=false",
+        plan_out.as_str(),
     ];
-    for command in commands {
-        runner.run(tool, dir, command)?;
+    match runner.run(tool, dir, &args) {
+        Ok(_) => return Ok(None),
+        Err(DeployerError::IaCTool {
+            status: Some(2), ..
+        }) => {}
+        Err(err) => return Err(err),
     }
+    let json = runner.run(tool, dir, &["show", "-json", DRIFT_PLAN_FILE])?;
+    PlanChangeSummary::drift_from_show_json(&json).map(Some)
+}
+
+/// `init`, passing the generated remote state settings when the directory has them.
+fn run_iac_init(
+    runner: &dyn IaCCommandRunner,
+    tool: IaCTool,
+    dir: &Path,
+) -> Result<(), DeployerError> {
+    let args = init_args(dir);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    runner.run(tool, dir, &args)?;
     Ok(())
 }
 
-pub fn dry_run_commands(destroy: bool) -> Vec<Vec<&'static str>> {
-    if destroy {
-        vec![
-            vec!["init", "-input=false"],
-            vec!["destroy", "-input=false", "-auto-approve"],
-        ]
+fn init_args(dir: &Path) -> Vec<String> {
+    let mut args = vec!["init".to_string(), "-input=false".to_string()];
+    if dir.join(BACKEND_CONFIG_FILE).is_file() {
+        args.push(format!("-backend-config={BACKEND_CONFIG_FILE}"));
+    }
+    args
+}
+
+/// Commands an apply (or destroy) would run inside `dir`, as listed by `--dry-run`.
+pub fn dry_run_commands(destroy: bool, dir: &Path) -> Vec<Vec<String>> {
+    let steps: &[&[&str]] = if destroy {
+        &[&["destroy", "-input=false", "-auto-approve"]]
     } else {
-        vec![
-            vec!["init", "-input=false"],
-            vec!["plan", "-input=false", "-out=plan.tfplan"],
-            vec!["apply", "-input=false", "-auto-approve", "plan.tfplan"],
+        &[
+            &["plan", "-input=false", "-out=plan.tfplan"],
+            &["show", "-json", "plan.tfplan"],
+            &["apply", "-input=false", "-auto-approve", "plan.tfplan"],
         ]
+    };
+    std::iter::once(init_args(dir))
+        .chain(
+            steps
+                .iter()
+                .map(|step| step.iter().map(|arg| arg.to_string()).collect()),
+        )
+        .collect()
+}
+
+/// Resource changes from a saved IaC plan, grouped by action (resource addresses).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanChangeSummary {
+    pub creates: Vec<String>,
+    pub updates: Vec<String>,
+    pub deletes: Vec<String>,
+    pub replaces: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ShowJson {
+    #[serde(default)]
+    resource_changes: Vec<ShowResourceChange>,
+    #[serde(default)]
+    resource_drift: Vec<ShowResourceChange>,
+}
+
+#[derive(Deserialize)]
+struct ShowResourceChange {
+    address: String,
+    change: ShowChange,
+}
+
+#[derive(Deserialize)]
+struct ShowChange {
+    actions: Vec<String>,
+}
+
+fn parse_show_json(json: &str) -> Result<ShowJson, DeployerError> {
+    serde_json::from_str(json)
+        .map_err(|err| DeployerError::Other(format!("failed to parse IaC plan JSON: {err}")))
+}
+
+impl PlanChangeSummary {
+    /// Parse the output of `terraform show -json <plan>` (or the OpenTofu equivalent).
+    pub fn from_show_json(json: &str) -> Result<Self, DeployerError> {
+        Ok(Self::from_changes(parse_show_json(json)?.resource_changes))
+    }
+
+    /// Parse the out-of-band changes (`resource_drift`) of a refresh-only plan.
+    pub fn drift_from_show_json(json: &str) -> Result<Self, DeployerError> {
+        Ok(Self::from_changes(parse_show_json(json)?.resource_drift))
+    }
+
+    fn from_changes(changes: Vec<ShowResourceChange>) -> Self {
+        let mut summary = Self::default();
+        for change in changes {
+            let actions: Vec<&str> = change.change.actions.iter().map(String::as_str).collect();
+            match actions.as_slice() {
+                ["create"] => summary.creates.push(change.address),
+                ["update"] => summary.updates.push(change.address),
+                ["delete"] => summary.deletes.push(change.address),
+                ["delete", "create"] | ["create", "delete"] => {
+                    summary.replaces.push(change.address)
+                }
+                _ => {}
+            }
+        }
+        summary
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.creates.is_empty()
+            && self.updates.is_empty()
+            && self.deletes.is_empty()
+            && self.replaces.is_empty()
+    }
+
+    /// Whether applying the plan deletes or replaces any resource.
+    pub fn is_destructive(&self) -> bool {
+        !self.deletes.is_empty() || !self.replaces.is_empty()
+    }
+
+    pub fn render(&self, format: OutputFormat) -> Result<String, DeployerError> {
+        match format {
+            OutputFormat::Json => Ok(serde_json::to_string_pretty(self)?),
+            OutputFormat::Yaml => {
+                serde_yaml_bw::to_string(self).map_err(|err| DeployerError::Other(err.to_string()))
+            }
+            OutputFormat::Text => {
+                if self.is_empty() {
+                    return Ok("IaC plan: no changes.\n".to_string());
+                }
+                let mut out = format!(
+                    "IaC plan: {} to create, {} to update, {} to delete, {} to replace\n",
+                    self.creates.len(),
+                    self.updates.len(),
+                    self.deletes.len(),
+                    self.replaces.len()
+                );
+                for (marker, addresses) in [
+                    ("+", &self.creates),
+                    ("~", &self.updates),
+                    ("-", &self.deletes),
+                    ("-/+", &self.replaces),
+                ] {
+                    for address in addresses {
+                        out.push_str(&format!("  {marker} {address}\n"));
+                    }
+                }
+                Ok(out)
+            }
+        }
     }
 }
 
@@ -204,35 +624,162 @@ mod tests {
         }
     }
 
+    const SHOW_JSON: &str = r#"{
+        "format_version": "1.2",
+        "resource_changes": [
+            {"address": "aws_s3_bucket.assets", "change": {"actions": ["create"]}},
+            {"address": "aws_ecs_service.runner", "change": {"actions": ["update"]}},
+            {"address": "aws_iam_role.old", "change": {"actions": ["delete"]}},
+            {"address": "aws_lb.ingress", "change": {"actions": ["delete", "create"]}},
+            {"address": "aws_vpc.main", "change": {"actions": ["no-op"]}}
+        ]
+    }"#;
+
     impl IaCCommandRunner for MockRunner {
-        fn run(&self, tool: IaCTool, _dir: &Path, args: &[&str]) -> Result<(), DeployerError> {
+        fn run(&self, tool: IaCTool, _dir: &Path, args: &[&str]) -> Result<String, DeployerError> {
             let mut guard = self.calls.lock().unwrap();
             guard.push((tool, args.iter().map(|arg| arg.to_string()).collect()));
-            Ok(())
+            if args.first() == Some(&"show") {
+                return Ok(SHOW_JSON.to_string());
+            }
+            Ok(String::new())
         }
     }
 
     #[test]
     fn apply_sequence_invokes_commands() {
         let runner = MockRunner::new();
-        run_iac_plan_apply(&runner, IaCTool::Terraform, Path::new("dummy")).unwrap();
-        let expected = vec![
-            vec!["init", "-input=false"],
-            vec!["plan", "-input=false", "-out=plan.tfplan"],
-            vec!["apply", "-input=false", "-auto-approve", "plan.tfplan"],
-        ];
+        let summary = run_iac_plan_apply(&runner, IaCTool::Terraform, Path::new("dummy")).unwrap();
+        assert_eq!(summary.creates, vec!["aws_s3_bucket.assets"]);
+        let expected = dry_run_commands(false, Path::new("dummy"));
         assert_eq!(
             runner.calls(),
             expected
                 .into_iter()
-                .map(|args| (
-                    IaCTool::Terraform,
-                    args.into_iter().map(String::from).collect()
-                ))
+                .map(|args| (IaCTool::Terraform, args))
                 .collect::<Vec<_>>()
         );
     }
 
+    #[test]
+    fn summarises_show_json_by_action() {
+        let summary = PlanChangeSummary::from_show_json(SHOW_JSON).unwrap();
+        assert_eq!(summary.updates, vec!["aws_ecs_service.runner"]);
+        assert_eq!(summary.deletes, vec!["aws_iam_role.old"]);
+        assert_eq!(summary.replaces, vec!["aws_lb.ingress"]);
+        assert!(summary.is_destructive());
+
+        let text = summary.render(OutputFormat::Text).unwrap();
+        assert!(text.contains("1 to create, 1 to update, 1 to delete, 1 to replace"));
+        assert!(text.contains("-/+ aws_lb.ingress"));
+        assert!(!text.contains("aws_vpc.main"));
+
+        let empty = PlanChangeSummary::from_show_json(r#"{"format_version":"1.2"}"#).unwrap();
+        assert!(empty.is_empty() && !empty.is_destructive());
+        assert!(PlanChangeSummary::from_show_json("not json").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn streams_output_into_log_and_captures_stdout() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_path = dir.path().join(IAC_LOG_FILE);
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo planned; echo warning >&2; exit 3"]);
+        let log = fs::File::create(&log_path).unwrap();
+        let output = run_streaming(command, &Span::none(), false, log, None).unwrap();
+        assert_eq!(output.status.unwrap().code(), Some(3));
+        assert_eq!(output.stdout, "planned\n");
+        assert_eq!(output.stderr, "warning\n");
+        let log = fs::read_to_string(&log_path).unwrap();
+        assert!(log.contains("[stdout] planned"));
+        assert!(log.contains("[stderr] warning"));
+        assert!(log.contains("<== exit Some(3)"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn kills_steps_that_exceed_the_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut command = Command::new("sleep");
+        command.arg("5");
+        let log = fs::File::create(dir.path().join(IAC_LOG_FILE)).unwrap();
+        let timeout = Some(Duration::from_millis(200));
+        let output = run_streaming(command, &Span::none(), false, log, timeout).unwrap();
+        assert!(output.status.is_none());
+        assert!(output.elapsed < Duration::from_secs(5));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn timeout_kills_processes_spawned_by_the_step() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut command = Command::new("sh");
+        // The background sleep inherits the output pipes and outlives a plain kill of `sh`.
+        command.args(["-c", "sleep 30 & sleep 30"]);
+        let log = fs::File::create(dir.path().join(IAC_LOG_FILE)).unwrap();
+        let timeout = Some(Duration::from_millis(200));
+        let output = run_streaming(command, &Span::none(), false, log, timeout).unwrap();
+        assert!(output.status.is_none());
+        assert!(
+            output.elapsed < Duration::from_secs(2),
+            "{:?}",
+            output.elapsed
+        );
+    }
+
+    #[test]
+    fn init_passes_backend_config_when_present() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(BACKEND_CONFIG_FILE), "bucket = \"state\"\n").unwrap();
+        let runner = MockRunner::new();
+        run_iac_destroy(&runner, IaCTool::Terraform, dir.path()).unwrap();
+        assert_eq!(
+            runner.calls()[0].1,
+            vec!["init", "-input=false", "-backend-config=backend.hcl"]
+        );
+        let listed: Vec<IaCInvocation> = dry_run_commands(true, dir.path())
+            .into_iter()
+            .map(|args| (IaCTool::Terraform, args))
+            .collect();
+        assert_eq!(runner.calls(), listed);
+    }
+
+    struct DriftRunner;
+
+    impl IaCCommandRunner for DriftRunner {
+        fn run(&self, tool: IaCTool, _dir: &Path, args: &[&str]) -> Result<String, DeployerError> {
+            match args.first().copied() {
+                Some("plan") => Err(DeployerError::IaCTool {
+                    tool: tool.to_string(),
+                    step: "plan".into(),
+                    status: Some(2),
+                    stderr: String::new(),
+                }),
+                Some("show") => Ok(r#"{"resource_drift": [
+                    {"address": "aws_ecs_service.runner", "change": {"actions": ["update"]}},
+                    {"address": "aws_s3_bucket.assets", "change": {"actions": ["delete"]}}
+                ]}"#
+                .to_string()),
+                _ => Ok(String::new()),
+            }
+        }
+    }
+
+    #[test]
+    fn drift_check_treats_exit_code_two_as_drift() {
+        let drift = run_iac_drift_check(&DriftRunner, IaCTool::Terraform, Path::new("dummy"))
+            .unwrap()
+            .expect("drift detected");
+        assert_eq!(drift.updates, vec!["aws_ecs_service.runner"]);
+        assert_eq!(drift.deletes, vec!["aws_s3_bucket.assets"]);
+
+        let runner = MockRunner::new();
+        let none = run_iac_drift_check(&runner, IaCTool::Terraform, Path::new("dummy")).unwrap();
+        assert!(none.is_none());
+        assert_eq!(runner.calls().len(), 2);
+    }
+
     #[test]
     fn destroy_sequence_invokes_commands() {
         let runner = MockRunner::new();
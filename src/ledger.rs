@@ -0,0 +1,651 @@
+//! Append-only deployment ledger stored per tenant/environment.
+//!
+//! Every plan/apply/destroy run appends one JSON line to
+//! `state_dir/ledger/<tenant>/<env>/records.jsonl` describing the pack that was deployed,
+//! the deployment pack that handled it, the artifacts produced and the outcome.
+//!
+//! Successful applies also snapshot their artifacts and plan into a content-addressed
+//! `blobs/` directory next to the records so `greentic-deployer rollback` can re-materialise
+//! them later.
+
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+use crate::config::{DeployerConfig, OutputFormat};
+use crate::error::{DeployerError, Result};
+use crate::plan::PlanContext;
+
+const RECORDS_FILE: &str = "records.jsonl";
+const BLOBS_DIR: &str = "blobs";
+
+/// Outcome recorded for a ledger entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerStatus {
+    /// The action ran to completion.
+    Succeeded,
+    /// The action failed; see `error`.
+    Failed,
+    /// The operator declined the confirmation prompt.
+    Cancelled,
+    /// `--preview` run; nothing was applied.
+    Preview,
+    /// `--dry-run` run; IaC commands were only printed.
+    DryRun,
+}
+
+impl LedgerStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LedgerStatus::Succeeded => "succeeded",
+            LedgerStatus::Failed => "failed",
+            LedgerStatus::Cancelled => "cancelled",
+            LedgerStatus::Preview => "preview",
+            LedgerStatus::DryRun => "dry_run",
+        }
+    }
+}
+
+/// Hash of a generated artifact relative to the provider output directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactHash {
+    pub path: String,
+    pub sha256: String,
+}
+
+/// Single ledger entry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LedgerRecord {
+    /// Sequence number within the tenant/environment ledger (1-based).
+    pub id: u64,
+    pub action: String,
+    pub tenant: String,
+    pub environment: String,
+    pub pack_id: String,
+    pub pack_version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pack_digest: Option<String>,
+    /// Local pack the plan was built from (absent for packs resolved from a registry).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pack_path: Option<String>,
+    pub provider: String,
+    pub strategy: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dispatch_pack_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dispatch_flow_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artifacts: Vec<ArtifactHash>,
+    pub status: LedgerStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Blob holding the serialized plan, present once the record has been snapshotted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan_sha256: Option<String>,
+    /// For `rollback` records, the ledger record whose artifacts were restored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rollback_of: Option<u64>,
+    pub started_at: u64,
+    pub finished_at: u64,
+}
+
+impl LedgerRecord {
+    /// Start a record for the given run; `id`, artifacts and outcome are filled on append.
+    pub fn begin(config: &DeployerConfig, plan: &PlanContext) -> Self {
+        Self {
+            id: 0,
+            action: config.action.as_str().to_string(),
+            tenant: config.tenant.clone(),
+            environment: config.environment.clone(),
+            pack_id: plan.plan.pack_id.clone(),
+            pack_version: plan.plan.pack_version.to_string(),
+            pack_digest: pack_digest(config),
+            // Canonical, so `promote` finds the pack from any working directory.
+            pack_path: config.pack_ref.is_none().then(|| {
+                config
+                    .pack_path
+                    .canonicalize()
+                    .unwrap_or_else(|_| config.pack_path.clone())
+                    .display()
+                    .to_string()
+            }),
+            provider: config.provider.as_str().to_string(),
+            strategy: config.strategy.clone(),
+            dispatch_pack_id: None,
+            dispatch_flow_id: None,
+            artifacts: Vec::new(),
+            status: LedgerStatus::Failed,
+            error: None,
+            plan_sha256: None,
+            rollback_of: None,
+            started_at: now_ts(),
+            finished_at: 0,
+        }
+    }
+
+    /// Whether this record left artifacts deployed (successful `apply` or `rollback`).
+    pub fn is_deployment(&self) -> bool {
+        self.status == LedgerStatus::Succeeded
+            && (self.action == "apply" || self.action == "rollback")
+    }
+
+    /// Record the final status, hash the artifacts under `deploy_dir`, and stamp the finish time.
+    pub fn finish(&mut self, status: LedgerStatus, error: Option<String>, deploy_dir: &Path) {
+        self.status = status;
+        self.error = error;
+        self.artifacts = hash_artifacts(deploy_dir);
+        self.finished_at = now_ts();
+    }
+}
+
+/// Ledger for a single tenant/environment.
+pub struct Ledger {
+    dir: PathBuf,
+}
+
+impl Ledger {
+    pub fn new(state_dir: &Path, tenant: &str, environment: &str) -> Self {
+        Self {
+            dir: state_dir.join("ledger").join(tenant).join(environment),
+        }
+    }
+
+    pub fn for_config(config: &DeployerConfig) -> Self {
+        Self::new(
+            &config.greentic.paths.state_dir,
+            &config.tenant,
+            &config.environment,
+        )
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn records_path(&self) -> PathBuf {
+        self.dir.join(RECORDS_FILE)
+    }
+
+    /// Load all records in append order. A missing ledger yields an empty list.
+    pub fn records(&self) -> Result<Vec<LedgerRecord>> {
+        let path = self.records_path();
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let reader = BufReader::new(fs::File::open(&path)?);
+        let mut records = Vec::new();
+        for (idx, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record = serde_json::from_str(&line).map_err(|err| {
+                DeployerError::Other(format!(
+                    "corrupt ledger entry at {}:{}: {err}",
+                    path.display(),
+                    idx + 1
+                ))
+            })?;
+            records.push(record);
+        }
+        Ok(records)
+    }
+
+    /// Find a record by id.
+    pub fn record(&self, id: u64) -> Result<LedgerRecord> {
+        self.records()?
+            .into_iter()
+            .find(|record| record.id == id)
+            .ok_or_else(|| {
+                DeployerError::Config(format!(
+                    "ledger record {id} not found in {}",
+                    self.records_path().display()
+                ))
+            })
+    }
+
+    /// Most recent successful apply or rollback, unless a later successful destroy tore it down.
+    pub fn current(&self) -> Result<Option<LedgerRecord>> {
+        let current = self.records()?.into_iter().rev().find(|record| {
+            record.is_deployment()
+                || (record.status == LedgerStatus::Succeeded && record.action == "destroy")
+        });
+        Ok(current.filter(LedgerRecord::is_deployment))
+    }
+
+    /// Record to roll back to: `to` when given, otherwise the snapshotted deployment that
+    /// preceded the current one.
+    pub fn rollback_target(&self, to: Option<u64>) -> Result<LedgerRecord> {
+        if let Some(id) = to {
+            let record = self.record(id)?;
+            if !record.is_deployment() {
+                return Err(DeployerError::Config(format!(
+                    "ledger record {id} is a {} {} and cannot be rolled back to",
+                    record.status.as_str(),
+                    record.action
+                )));
+            }
+            if record.plan_sha256.is_none() {
+                return Err(DeployerError::Config(format!(
+                    "ledger record {id} has no stored snapshot; only applies recorded by this \
+                     version of greentic-deployer can be rolled back to"
+                )));
+            }
+            return Ok(record);
+        }
+        let current = self.current()?.ok_or_else(|| {
+            DeployerError::Config(format!(
+                "nothing is currently deployed according to {}",
+                self.records_path().display()
+            ))
+        })?;
+        self.records()?
+            .into_iter()
+            .rev()
+            .filter(|record| record.id < current.id)
+            .find(|record| record.is_deployment() && record.plan_sha256.is_some())
+            .ok_or_else(|| {
+                DeployerError::Config(format!(
+                    "no earlier snapshotted deployment precedes record {}; pass --to <record>",
+                    current.id
+                ))
+            })
+    }
+
+    pub fn blobs_dir(&self) -> PathBuf {
+        self.dir.join(BLOBS_DIR)
+    }
+
+    /// Copy the record's artifacts from `deploy_dir` and the serialized plan into the blob
+    /// store, filling `plan_sha256`.
+    pub fn store_snapshot(
+        &self,
+        record: &mut LedgerRecord,
+        deploy_dir: &Path,
+        plan: &impl Serialize,
+    ) -> Result<()> {
+        for artifact in &mut record.artifacts {
+            let bytes = fs::read(deploy_dir.join(&artifact.path))?;
+            artifact.sha256 = self.write_blob(&bytes)?;
+        }
+        let plan_bytes = serde_json::to_vec_pretty(plan)?;
+        record.plan_sha256 = Some(self.write_blob(&plan_bytes)?);
+        Ok(())
+    }
+
+    /// Read a blob, verifying its content still matches the digest.
+    pub fn read_blob(&self, sha256: &str) -> Result<Vec<u8>> {
+        let path = self.blobs_dir().join(sha256);
+        let bytes = fs::read(&path).map_err(|err| {
+            DeployerError::Other(format!("missing ledger blob {}: {err}", path.display()))
+        })?;
+        if sha256_hex(&bytes) != sha256 {
+            return Err(DeployerError::Other(format!(
+                "ledger blob {} does not match its digest",
+                path.display()
+            )));
+        }
+        Ok(bytes)
+    }
+
+    fn write_blob(&self, bytes: &[u8]) -> Result<String> {
+        let digest = sha256_hex(bytes);
+        let path = self.blobs_dir().join(&digest);
+        if !path.exists() {
+            fs::create_dir_all(self.blobs_dir())?;
+            fs::write(&path, bytes)?;
+        }
+        Ok(digest)
+    }
+
+    /// Append a record, assigning the next sequence id. Returns the assigned id.
+    pub fn append(&self, record: &mut LedgerRecord) -> Result<u64> {
+        fs::create_dir_all(&self.dir)?;
+        let next = self.records()?.last().map(|last| last.id + 1).unwrap_or(1);
+        record.id = next;
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.records_path())?;
+        file.write_all(line.as_bytes())?;
+        Ok(next)
+    }
+}
+
+/// Render a list of records for `greentic-deployer history`.
+pub fn render_history(
+    ledger: &Ledger,
+    records: &[LedgerRecord],
+    current: Option<&LedgerRecord>,
+    format: OutputFormat,
+) -> Result<String> {
+    match format {
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(records)?),
+        OutputFormat::Yaml => {
+            serde_yaml_bw::to_string(records).map_err(|err| DeployerError::Other(err.to_string()))
+        }
+        OutputFormat::Text => {
+            let mut out = format!("Deployment ledger {}\n", ledger.records_path().display());
+            match current {
+                Some(record) => out.push_str(&format!(
+                    "Currently deployed: {}@{} (record #{}, {})\n",
+                    record.pack_id,
+                    record.pack_version,
+                    record.id,
+                    format_timestamp(record.finished_at)
+                )),
+                None => out.push_str("Currently deployed: none\n"),
+            }
+            if records.is_empty() {
+                out.push_str("No ledger records.\n");
+            }
+            for record in records {
+                out.push_str(&format!(
+                    "#{:<4} {}  {:<7} {:<9} {}@{}  {}/{}  digest={}  dispatch={}\n",
+                    record.id,
+                    format_timestamp(record.started_at),
+                    record.action,
+                    record.status.as_str(),
+                    record.pack_id,
+                    record.pack_version,
+                    record.provider,
+                    record.strategy,
+                    record.pack_digest.as_deref().unwrap_or("unknown"),
+                    dispatch_label(record),
+                ));
+            }
+            Ok(out)
+        }
+    }
+}
+
+/// Render a single record for `greentic-deployer history --record <id>`.
+pub fn render_record(record: &LedgerRecord, format: OutputFormat) -> Result<String> {
+    match format {
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(record)?),
+        OutputFormat::Yaml => {
+            serde_yaml_bw::to_string(record).map_err(|err| DeployerError::Other(err.to_string()))
+        }
+        OutputFormat::Text => {
+            let mut out = format!(
+                "record #{}\n- action: {}\n- status: {}\n- tenant/environment: {}/{}\n- pack: {}@{}\n- pack digest: {}\n- provider/strategy: {}/{}\n- dispatch: {}\n- started_at: {}\n- finished_at: {}\n",
+                record.id,
+                record.action,
+                record.status.as_str(),
+                record.tenant,
+                record.environment,
+                record.pack_id,
+                record.pack_version,
+                record.pack_digest.as_deref().unwrap_or("unknown"),
+                record.provider,
+                record.strategy,
+                dispatch_label(record),
+                format_timestamp(record.started_at),
+                format_timestamp(record.finished_at),
+            );
+            if let Some(error) = &record.error {
+                out.push_str(&format!("- error: {error}\n"));
+            }
+            if let Some(target) = record.rollback_of {
+                out.push_str(&format!("- rollback of: #{target}\n"));
+            }
+            out.push_str(&format!("- artifacts ({}):\n", record.artifacts.len()));
+            for artifact in &record.artifacts {
+                out.push_str(&format!(
+                    "  {}  sha256:{}\n",
+                    artifact.path, artifact.sha256
+                ));
+            }
+            Ok(out)
+        }
+    }
+}
+
+fn dispatch_label(record: &LedgerRecord) -> String {
+    match (&record.dispatch_pack_id, &record.dispatch_flow_id) {
+        (Some(pack), Some(flow)) => format!("{pack}::{flow}"),
+        _ => "unresolved".to_string(),
+    }
+}
+
+/// Digest of the application pack: the registry digest when resolved by reference, otherwise
+/// [`crate::platform::pack_digest`] (the `.gtpack` sha256, or for pack directories the digest over
+/// every file).
+pub fn pack_digest(config: &DeployerConfig) -> Option<String> {
+    if let Some(pack_ref) = &config.pack_ref {
+        return Some(pack_ref.digest.clone());
+    }
+    crate::platform::pack_digest(&config.pack_path).ok()
+}
+
+/// Hash generated artifacts, skipping IaC tool caches, state, saved plans, the IaC log and the
+/// state backend config (which may carry credentials such as a pg `conn_str`).
+pub fn hash_artifacts(deploy_dir: &Path) -> Vec<ArtifactHash> {
+    let mut artifacts = Vec::new();
+    let walker = WalkDir::new(deploy_dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != ".terraform");
+    for entry in walker.flatten() {
+        if !entry.file_type().is_file() || !is_tracked_artifact(entry.path()) {
+            continue;
+        }
+        let Ok(relative) = entry.path().strip_prefix(deploy_dir) else {
+            continue;
+        };
+        let Ok(bytes) = fs::read(entry.path()) else {
+            continue;
+        };
+        artifacts.push(ArtifactHash {
+            path: relative.to_string_lossy().replace('\\', "/"),
+            sha256: sha256_hex(&bytes),
+        });
+    }
+    artifacts
+}
+
+pub(crate) fn is_tracked_artifact(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    !(name.contains(".tfstate")
+        || name.ends_with(".tfplan")
+        || name == ".terraform.lock.hcl"
+        || name == crate::iac::IAC_LOG_FILE
+        || name == crate::state_backend::BACKEND_CONFIG_FILE)
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+    format!("{:x}", hasher.finalize())
+}
+
+pub(crate) fn now_ts() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Render a unix timestamp as an RFC 3339 UTC string (e.g. `2024-05-01T12:00:00Z`).
+pub fn format_timestamp(ts: u64) -> String {
+    let days = (ts / 86_400) as i64;
+    let secs = ts % 86_400;
+    // Civil-from-days (proleptic Gregorian calendar).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs / 3_600,
+        (secs % 3_600) / 60,
+        secs % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn sample_record() -> LedgerRecord {
+        LedgerRecord {
+            id: 0,
+            action: "apply".into(),
+            tenant: "acme".into(),
+            environment: "staging".into(),
+            pack_id: "dev.greentic.sample".into(),
+            pack_version: "0.1.0".into(),
+            pack_digest: Some("sha256:abc".into()),
+            pack_path: None,
+            provider: "aws".into(),
+            strategy: "iac-only".into(),
+            dispatch_pack_id: Some("greentic.deploy.aws".into()),
+            dispatch_flow_id: Some("deploy_aws_iac".into()),
+            artifacts: Vec::new(),
+            status: LedgerStatus::Succeeded,
+            error: None,
+            plan_sha256: None,
+            rollback_of: None,
+            started_at: 1,
+            finished_at: 2,
+        }
+    }
+
+    #[test]
+    fn appends_records_with_sequential_ids() {
+        let dir = tempdir().unwrap();
+        let ledger = Ledger::new(dir.path(), "acme", "staging");
+        assert!(ledger.records().unwrap().is_empty());
+
+        let mut first = sample_record();
+        let mut second = sample_record();
+        second.pack_version = "0.2.0".into();
+        assert_eq!(ledger.append(&mut first).unwrap(), 1);
+        assert_eq!(ledger.append(&mut second).unwrap(), 2);
+
+        let records = ledger.records().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].pack_version, "0.2.0");
+        assert_eq!(ledger.record(2).unwrap(), second);
+        assert!(ledger.record(3).is_err());
+        assert!(
+            dir.path()
+                .join("ledger/acme/staging/records.jsonl")
+                .is_file()
+        );
+    }
+
+    #[test]
+    fn current_tracks_latest_successful_apply() {
+        let dir = tempdir().unwrap();
+        let ledger = Ledger::new(dir.path(), "acme", "staging");
+        let mut applied = sample_record();
+        ledger.append(&mut applied).unwrap();
+        let mut failed = sample_record();
+        failed.pack_version = "0.3.0".into();
+        failed.status = LedgerStatus::Failed;
+        ledger.append(&mut failed).unwrap();
+        assert_eq!(ledger.current().unwrap().unwrap().id, applied.id);
+
+        let mut destroyed = sample_record();
+        destroyed.action = "destroy".into();
+        ledger.append(&mut destroyed).unwrap();
+        assert!(ledger.current().unwrap().is_none());
+
+        let text = render_history(
+            &ledger,
+            &ledger.records().unwrap(),
+            None,
+            OutputFormat::Text,
+        )
+        .unwrap();
+        assert!(text.contains("Currently deployed: none"));
+        assert!(text.contains("failed"));
+        assert!(text.contains("greentic.deploy.aws::deploy_aws_iac"));
+    }
+
+    #[test]
+    fn snapshots_artifacts_and_selects_rollback_target() {
+        let dir = tempdir().unwrap();
+        let deploy = dir.path().join("deploy");
+        fs::create_dir_all(&deploy).unwrap();
+        let ledger = Ledger::new(dir.path(), "acme", "staging");
+        let plan = serde_json::json!({ "plan": { "pack_id": "dev.greentic.sample" } });
+
+        fs::write(deploy.join("main.tf"), "v1").unwrap();
+        let mut first = sample_record();
+        first.artifacts = hash_artifacts(&deploy);
+        ledger.store_snapshot(&mut first, &deploy, &plan).unwrap();
+        ledger.append(&mut first).unwrap();
+
+        fs::write(deploy.join("main.tf"), "v2").unwrap();
+        let mut second = sample_record();
+        second.artifacts = hash_artifacts(&deploy);
+        ledger.store_snapshot(&mut second, &deploy, &plan).unwrap();
+        ledger.append(&mut second).unwrap();
+
+        let target = ledger.rollback_target(None).unwrap();
+        assert_eq!(target.id, first.id);
+        assert_eq!(
+            ledger.read_blob(&target.artifacts[0].sha256).unwrap(),
+            b"v1"
+        );
+        assert!(
+            ledger
+                .read_blob(target.plan_sha256.as_deref().unwrap())
+                .is_ok()
+        );
+        assert_eq!(ledger.rollback_target(Some(2)).unwrap().id, 2);
+
+        let mut failed = sample_record();
+        failed.status = LedgerStatus::Failed;
+        ledger.append(&mut failed).unwrap();
+        assert!(ledger.rollback_target(Some(3)).is_err());
+    }
+
+    #[test]
+    fn hashes_artifacts_but_skips_state_and_caches() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("main.tf"), "resource {}").unwrap();
+        fs::write(dir.path().join("terraform.tfstate"), "{}").unwrap();
+        fs::write(dir.path().join("plan.tfplan"), "bin").unwrap();
+        fs::write(
+            dir.path().join("backend.hcl"),
+            "conn_str = \"postgres://user:secret@db/state\"\n",
+        )
+        .unwrap();
+        fs::create_dir_all(dir.path().join(".terraform/providers")).unwrap();
+        fs::write(dir.path().join(".terraform/providers/x"), "bin").unwrap();
+        fs::create_dir_all(dir.path().join("modules")).unwrap();
+        fs::write(dir.path().join("modules/vars.tf"), "variable {}").unwrap();
+
+        let artifacts = hash_artifacts(dir.path());
+        let paths: Vec<&str> = artifacts.iter().map(|a| a.path.as_str()).collect();
+        assert_eq!(paths, vec!["main.tf", "modules/vars.tf"]);
+        assert_eq!(artifacts[0].sha256, sha256_hex(b"resource {}"));
+    }
+
+    #[test]
+    fn formats_timestamps_as_rfc3339() {
+        assert_eq!(format_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(format_timestamp(1_714_564_800), "2024-05-01T12:00:00Z");
+    }
+}
@@ -4,19 +4,26 @@
 use greentic_interfaces_host as _greentic_interfaces_host;
 
 pub mod apply;
+pub mod batch;
 pub mod bootstrap;
 pub mod config;
 pub mod deployment;
+pub mod diff;
+pub mod drift;
 pub mod error;
 pub mod iac;
+pub mod ledger;
 pub mod pack_introspect;
 pub mod path_safety;
 pub mod placeholder;
 pub mod plan;
 pub mod platform;
+pub mod promote;
 pub mod provider_onboarding;
 pub mod providers;
+pub mod rollback;
 pub mod secrets;
+pub mod state_backend;
 pub mod telemetry;
 
 pub use config::{Action, CliArgs, Command, DeployerConfig, OutputFormat, Provider};
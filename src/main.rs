@@ -1,40 +1,71 @@
-use clap::Parser;
-use std::io::Read;
+use clap::{Parser, ValueEnum};
+use std::io::{IsTerminal, Read};
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 
 use greentic_deployer::{
-    apply,
+    DeployerError, apply,
+    batch::{render_batch_report, resolve_targets, run_batch},
     bootstrap::{
         capabilities::build_host_capabilities,
         cli::{CliPromptAdapter, JsonPromptAdapter},
         config_patch::{
-            apply_config_patch, default_config_patch_path, restore_config, snapshot_config,
+            ConfigPlan, default_config_patch_path, diff_merge_patch, plan_config_patch,
+            restore_config, snapshot_config, write_config,
         },
-        flow_runner::run_bootstrap_flow,
+        encrypted_file::{KeySource, NEW_PASSPHRASE_ENV},
+        flow_runner::run_bootstrap_flow_with_installer,
         http_adapter::HttpPromptAdapter,
+        installer::{InstallerRuntime, UnavailableInstaller, WasmInstaller},
+        k8s_state::K8sStateTarget,
+        mqtt_adapter::MqttPromptAdapter,
+        mqtt_client::MqttBrokerOptions,
         network::{NetAllowList, NetworkPolicy},
         output::BootstrapOutput,
+        releases::{RollbackRef, archive_release, load_release},
         secrets::{
-            SecretsBackend, execute_writes, parse_backend, restore_backend, snapshot_backend,
+            SecretsBackend, execute_writes, parse_backend, restore_backend, rotate_key,
+            snapshot_backend,
         },
-        state::{BootstrapState, ensure_upgrade_allowed, load_state_backend, save_state_backend},
+        state::{BootstrapState, StateStore, ensure_upgrade_allowed},
     },
     config::{
-        BootstrapStateBackend, CliArgs, Command, DeployerConfig, GlobalArgs, InteractionMode,
-        PlatformActionArgs, PlatformArgs, PlatformCommand, ProviderArgs,
+        Action, ActionArgs, BatchArgs, CliArgs, Command, DeployerConfig, DiffArgs, DriftArgs,
+        GlobalArgs, HistoryArgs, InteractionMode, MqttProtocol, PackArgs, PlatformActionArgs,
+        PlatformArgs, PlatformBundleCommand, PlatformCacheCommand, PlatformCommand,
+        PlatformSecretsCommand, PlatformTrustCommand, PromoteArgs, Provider, ProviderArgs,
+        RollbackArgs, load_greentic_config,
     },
-    platform::oci::resolve_oci_pack,
+    deployment::{set_deployment_executor, wasm::WasmDeploymentExecutor},
+    diff::{diff_plans, load_plan_file, render_diff},
+    drift::{DriftReport, applied_record, check_drift, deploy_dir, render_drift},
+    iac::{DefaultIaCCommandRunner, resolve_iac_tool},
+    ledger::{Ledger, LedgerRecord, render_history, render_record},
+    pack_introspect,
+    platform::bundle::{BundlePackKind, BundleSource, export_bundle, import_bundle},
+    platform::cache::{PackCache, cache_base_for_state},
+    platform::oci::{docker_config_path, parse_oci_reference, push_oci_pack, resolve_oci_pack},
+    platform::trust::{TrustStore, TrustedKey, default_trust_store_path},
     platform::{self, VerificationPolicy},
+    promote::{promote, promotion_args, promotion_source},
     provider_onboarding::{self, OnboardRequest},
+    rollback::{RollbackRequest, rollback},
 };
 
 #[tokio::main]
 async fn main() {
     let CliArgs { global, command } = CliArgs::parse();
     match command {
-        Command::Platform(args) => handle_platform(args),
+        Command::Platform(args) => handle_platform(*args),
         Command::Provider { command } => handle_provider(command, &global),
+        Command::History(args) => handle_history(args, &global),
+        Command::Diff(args) => handle_diff(args, &global),
+        Command::Rollback(args) => handle_rollback(args, &global),
+        Command::Drift(args) => handle_drift(args, &global),
+        Command::Batch(args) => handle_batch(args, &global),
+        Command::Promote(args) => handle_promote(args, &global).await,
+        Command::Pack { command } => handle_pack(command),
         other => {
             let cli = CliArgs {
                 global,
@@ -47,7 +78,9 @@ async fn main() {
 
 fn handle_platform(args: PlatformArgs) {
     let backend = match parse_backend(&args.secrets_backend) {
-        Ok(backend) => backend,
+        Ok(backend) => backend
+            .with_k8s_options(args.k8s_kubeconfig.clone(), args.secrets_dry_run)
+            .with_key_file(args.secrets_key_file.clone()),
         Err(err) => {
             eprintln!("invalid secrets backend: {err}");
             std::process::exit(1);
@@ -60,12 +93,7 @@ fn handle_platform(args: PlatformArgs) {
         PlatformCommand::Install(cmd) => summarize_pack("install", &cmd, &context),
         PlatformCommand::Upgrade(cmd) => summarize_pack("upgrade", &cmd, &context),
         PlatformCommand::Status => {
-            match load_state_backend(
-                context.state_backend,
-                &context.state_path,
-                &context.k8s_namespace,
-                &context.k8s_state_name,
-            ) {
+            match context.state_store.load() {
                 Ok(Some(state)) => {
                     println!(
                         "bootstrap state:\n- version: {}\n- digest: {}\n- installed_at: {:?}\n- environment_kind: {}\n- last_upgrade_at: {:?}\n- rollback_ref: {}",
@@ -81,7 +109,7 @@ fn handle_platform(args: PlatformArgs) {
                 Err(err) => {
                     eprintln!(
                         "failed to read bootstrap state at {}: {err}",
-                        args.bootstrap_state.display()
+                        context.state_store.describe()
                     );
                     std::process::exit(1);
                 }
@@ -91,6 +119,230 @@ fn handle_platform(args: PlatformArgs) {
             );
             std::process::exit(0);
         }
+        PlatformCommand::Rollback => rollback_platform(&context),
+        PlatformCommand::Secrets {
+            command: PlatformSecretsCommand::RotateKey(cmd),
+        } => {
+            let new_key = match cmd.new_key_file {
+                Some(path) => KeySource::KeyFile(path),
+                None => KeySource::PassphraseEnv(NEW_PASSPHRASE_ENV.into()),
+            };
+            match rotate_key(&context.secrets_backend, &new_key) {
+                Ok(count) => println!("re-encrypted {count} secret(s) under the new key"),
+                Err(err) => {
+                    eprintln!("secrets key rotation failed: {err}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        PlatformCommand::Trust { command } => manage_trust(command, &context.trust_store),
+        PlatformCommand::Cache { command } => manage_cache(command, &context),
+        PlatformCommand::Bundle { command } => manage_bundle(command, &context),
+    }
+}
+
+fn manage_cache(command: PlatformCacheCommand, ctx: &PlatformContext) {
+    let cache = PackCache::new(&cache_base_for_state(&ctx.state_path));
+    match command {
+        PlatformCacheCommand::List => {
+            let entries = cache.list().unwrap_or_else(|err| {
+                eprintln!("failed to read pack cache: {err}");
+                std::process::exit(1);
+            });
+            if entries.is_empty() {
+                println!("pack cache {} is empty", cache.dir().display());
+                return;
+            }
+            println!("cached packs ({}):", cache.dir().display());
+            for entry in entries {
+                let size = entry
+                    .size
+                    .map(|size| format!("{size} bytes"))
+                    .unwrap_or_else(|| "missing".into());
+                println!("- {} -> {} ({size})", entry.reference, entry.digest);
+            }
+        }
+        PlatformCacheCommand::Prune { all } => match cache.prune(all) {
+            Ok(report) => println!(
+                "pruned {} index entr{} and {} file(s), freed {} bytes",
+                report.removed_entries.len(),
+                if report.removed_entries.len() == 1 {
+                    "y"
+                } else {
+                    "ies"
+                },
+                report.removed_files.len(),
+                report.freed_bytes
+            ),
+            Err(err) => {
+                eprintln!("failed to prune pack cache: {err}");
+                std::process::exit(1);
+            }
+        },
+        PlatformCacheCommand::Verify => match cache.verify() {
+            Ok(problems) if problems.is_empty() => {
+                println!("pack cache {} verified", cache.dir().display());
+            }
+            Ok(problems) => {
+                for problem in problems {
+                    eprintln!("- {problem}");
+                }
+                eprintln!(
+                    "pack cache verification failed; run `platform cache prune` and re-fetch"
+                );
+                std::process::exit(1);
+            }
+            Err(err) => {
+                eprintln!("failed to verify pack cache: {err}");
+                std::process::exit(1);
+            }
+        },
+    }
+}
+
+fn manage_bundle(command: PlatformBundleCommand, ctx: &PlatformContext) {
+    let cache_base = cache_base_for_state(&ctx.state_path);
+    match command {
+        PlatformBundleCommand::Export(args) => {
+            let sources: Vec<BundleSource> = args
+                .references
+                .into_iter()
+                .map(|source| BundleSource {
+                    kind: BundlePackKind::Platform,
+                    source,
+                })
+                .chain(
+                    args.deployment_packs
+                        .into_iter()
+                        .map(|source| BundleSource {
+                            kind: BundlePackKind::Deployment,
+                            source,
+                        }),
+                )
+                .collect();
+            let docker_config = args.docker_config.or_else(docker_config_path);
+            let exported = ctx.network_policy().and_then(|policy| {
+                export_bundle(
+                    &sources,
+                    &args.out,
+                    &cache_base,
+                    &policy,
+                    docker_config.as_deref(),
+                )
+            });
+            match exported {
+                Ok(index) => {
+                    println!(
+                        "wrote {} pack(s) to {}",
+                        index.packs.len(),
+                        args.out.display()
+                    );
+                    for pack in index.packs {
+                        println!(
+                            "- {}@{} {} ({})",
+                            pack.pack_id,
+                            pack.version,
+                            pack.digest,
+                            pack.reference.as_deref().unwrap_or("local deployment pack")
+                        );
+                    }
+                }
+                Err(err) => {
+                    eprintln!("bundle export failed: {err}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        PlatformBundleCommand::Import(args) => {
+            match import_bundle(&args.bundle, &cache_base, &args.packs_dir) {
+                Ok(report) => {
+                    for reference in &report.cached {
+                        println!("cached {reference}");
+                    }
+                    for path in &report.deployment_packs {
+                        println!("deployment pack {}", path.display());
+                    }
+                    println!(
+                        "imported {} (offline installs can now use the cached references)",
+                        args.bundle.display()
+                    );
+                }
+                Err(err) => {
+                    eprintln!("bundle import failed: {err}");
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+}
+
+fn manage_trust(command: PlatformTrustCommand, path: &std::path::Path) {
+    let mut store = TrustStore::load(path).unwrap_or_else(|err| {
+        eprintln!("failed to read trust store: {err}");
+        std::process::exit(1);
+    });
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    match command {
+        PlatformTrustCommand::Add(args) => {
+            let key = std::fs::read(&args.public_key)
+                .map_err(DeployerError::from)
+                .and_then(|bytes| TrustedKey::from_public_key(&args.key_id, &bytes));
+            let mut key = match key {
+                Ok(key) => key,
+                Err(err) => {
+                    eprintln!(
+                        "failed to load public key {}: {err}",
+                        args.public_key.display()
+                    );
+                    std::process::exit(1);
+                }
+            };
+            key.publisher = args.publisher;
+            key.added_at = Some(now);
+            key.expires_at = args.expires_in_days.map(|days| now + days * 86_400);
+            let algorithm = key.algorithm.as_str();
+            let replaced = store.add(key);
+            if let Err(err) = store.save(path) {
+                eprintln!("failed to write trust store {}: {err}", path.display());
+                std::process::exit(1);
+            }
+            let verb = if replaced { "replaced" } else { "added" };
+            println!("{verb} trusted {algorithm} key {}", args.key_id);
+        }
+        PlatformTrustCommand::List => {
+            if store.keys.is_empty() {
+                println!("no trusted keys in {}", path.display());
+                return;
+            }
+            println!("trusted keys ({}):", path.display());
+            for key in &store.keys {
+                let mut line = format!("- {} [{}]", key.key_id, key.algorithm.as_str());
+                if let Some(publisher) = &key.publisher {
+                    line.push_str(&format!(" publisher={publisher}"));
+                }
+                if let Some(expires_at) = key.expires_at {
+                    line.push_str(&format!(" expires_at={expires_at}"));
+                    if key.is_expired(now) {
+                        line.push_str(" (expired)");
+                    }
+                }
+                println!("{line}");
+            }
+        }
+        PlatformTrustCommand::Remove { key_id } => {
+            if !store.remove(&key_id) {
+                eprintln!("key {key_id} is not in the trust store {}", path.display());
+                std::process::exit(1);
+            }
+            if let Err(err) = store.save(path) {
+                eprintln!("failed to write trust store {}: {err}", path.display());
+                std::process::exit(1);
+            }
+            println!("removed trusted key {key_id}");
+        }
     }
 }
 
@@ -108,13 +360,36 @@ struct PlatformContext {
     output_path: Option<PathBuf>,
     config_out: Option<PathBuf>,
     state_path: PathBuf,
-    state_backend: BootstrapStateBackend,
-    k8s_namespace: String,
-    k8s_state_name: String,
+    state_store: Arc<StateStore>,
+    trust_store: PathBuf,
+    mqtt_broker: Option<String>,
+    mqtt_device_id: Option<String>,
+    mqtt_topic_prefix: String,
+    mqtt_protocol: MqttProtocol,
+    mqtt_username: Option<String>,
+    mqtt_password: Option<String>,
+    mqtt_ca_file: Option<PathBuf>,
+    http_tls_cert: Option<PathBuf>,
+    http_tls_key: Option<PathBuf>,
 }
 
 impl PlatformContext {
     fn from_args(args: &PlatformArgs, secrets_backend: SecretsBackend) -> Self {
+        let k8s_target = K8sStateTarget {
+            namespace: args.k8s_namespace.clone(),
+            name: args.k8s_state_name.clone(),
+            kind: args.k8s_state_kind,
+            kubeconfig: args.k8s_kubeconfig.clone(),
+        };
+        let state_store = StateStore::open(
+            args.bootstrap_state_backend,
+            &args.bootstrap_state,
+            &k8s_target,
+        )
+        .unwrap_or_else(|err| {
+            eprintln!("failed to open bootstrap state backend: {err}");
+            std::process::exit(1);
+        });
         Self {
             interaction: args.interaction,
             allow_listeners: args.allow_listeners,
@@ -128,9 +403,83 @@ impl PlatformContext {
             output_path: args.output.clone(),
             config_out: args.config_out.clone(),
             state_path: args.bootstrap_state.clone(),
-            state_backend: args.bootstrap_state_backend,
-            k8s_namespace: args.k8s_namespace.clone(),
-            k8s_state_name: args.k8s_state_name.clone(),
+            state_store: Arc::new(state_store),
+            trust_store: args
+                .trust_store
+                .clone()
+                .unwrap_or_else(|| default_trust_store_path(&args.bootstrap_state)),
+            mqtt_broker: args.mqtt_broker.clone(),
+            mqtt_device_id: args.mqtt_device_id.clone(),
+            mqtt_topic_prefix: args.mqtt_topic_prefix.clone(),
+            mqtt_protocol: args.mqtt_protocol,
+            mqtt_username: args.mqtt_username.clone(),
+            mqtt_password: args.mqtt_password.clone(),
+            mqtt_ca_file: args.mqtt_ca_file.clone(),
+            http_tls_cert: args.http_tls_cert.clone(),
+            http_tls_key: args.http_tls_key.clone(),
+        }
+    }
+
+    fn network_policy(&self) -> Result<NetworkPolicy, DeployerError> {
+        let net_allowlist = NetAllowList::parse(self.net_allowlist.as_deref())?;
+        Ok(NetworkPolicy::new(
+            self.allow_network,
+            self.offline_only,
+            net_allowlist,
+        ))
+    }
+
+    fn mqtt_options(&self, broker: &str, device_id: &str) -> MqttBrokerOptions {
+        MqttBrokerOptions {
+            protocol: self.mqtt_protocol,
+            username: self.mqtt_username.clone(),
+            password: self.mqtt_password.clone(),
+            ca_file: self.mqtt_ca_file.clone(),
+            ..MqttBrokerOptions::new(broker, format!("greentic-deployer-{device_id}"))
+        }
+    }
+}
+
+fn handle_pack(args: PackArgs) {
+    match args {
+        PackArgs::Push(cmd) => {
+            // Pushing is an explicit network operation: the target registry is always allowed.
+            let policy = parse_oci_reference(&cmd.reference).and_then(|reference| {
+                let allowlist = match &cmd.net_allowlist {
+                    Some(extra) => format!("{},{extra}", reference.host),
+                    None => reference.host,
+                };
+                Ok(NetworkPolicy::new(
+                    true,
+                    false,
+                    NetAllowList::parse(Some(&allowlist))?,
+                ))
+            });
+            let docker_config = cmd.docker_config.clone().or_else(docker_config_path);
+            let pushed = policy.and_then(|policy| {
+                push_oci_pack(
+                    &cmd.gtpack,
+                    &cmd.reference,
+                    &policy,
+                    docker_config.as_deref(),
+                )
+            });
+            match pushed {
+                Ok(pushed) => {
+                    println!(
+                        "pushed {} ({} bytes) to {}",
+                        cmd.gtpack.display(),
+                        pushed.size,
+                        pushed.reference
+                    );
+                    println!("digest: {}", pushed.manifest_digest);
+                    println!("pinned: {}", pushed.digest_reference);
+                }
+                Err(err) => {
+                    eprintln!("error: {err}");
+                    std::process::exit(1);
+                }
+            }
         }
     }
 }
@@ -169,6 +518,264 @@ fn handle_provider(args: ProviderArgs, global: &GlobalArgs) {
     }
 }
 
+fn handle_history(args: HistoryArgs, global: &GlobalArgs) {
+    let greentic = match load_greentic_config(global) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("configuration error: {err}");
+            std::process::exit(1);
+        }
+    };
+    let environment = args
+        .environment
+        .clone()
+        .unwrap_or_else(|| greentic.environment.env_id.to_string());
+    let ledger = Ledger::new(&greentic.paths.state_dir, &args.tenant, &environment);
+    let rendered = match args.record {
+        Some(id) => ledger
+            .record(id)
+            .and_then(|record| render_record(&record, args.output)),
+        None => ledger.records().and_then(|mut records| {
+            if let Some(limit) = args.limit {
+                let skip = records.len().saturating_sub(limit);
+                records.drain(..skip);
+            }
+            let current = ledger.current()?;
+            render_history(&ledger, &records, current.as_ref(), args.output)
+        }),
+    };
+    match rendered {
+        Ok(output) => print!("{output}"),
+        Err(err) => {
+            eprintln!("error: {err}");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn handle_rollback(args: RollbackArgs, global: &GlobalArgs) {
+    let greentic = match load_greentic_config(global) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("configuration error: {err}");
+            std::process::exit(1);
+        }
+    };
+    let iac_tool = match resolve_iac_tool(args.iac_tool, None) {
+        Ok(tool) => tool,
+        Err(err) => {
+            eprintln!("configuration error: {err}");
+            std::process::exit(1);
+        }
+    };
+    let request = RollbackRequest {
+        state_dir: greentic.paths.state_dir.clone(),
+        tenant: args.tenant.clone(),
+        environment: args
+            .environment
+            .clone()
+            .unwrap_or_else(|| greentic.environment.env_id.to_string()),
+        to: args.to,
+        iac_tool,
+        yes: args.yes,
+        dry_run: args.dry_run,
+        allow_destroy: args.allow_destroy,
+    };
+    let runner = DefaultIaCCommandRunner::new(args.iac_timeout.map(Duration::from_secs));
+    if let Err(err) = rollback(&request, &runner) {
+        eprintln!("rollback failed: {err}");
+        std::process::exit(1);
+    }
+}
+
+/// Exit status of `drift` when drift was detected (mirrors `-detailed-exitcode`).
+const DRIFT_EXIT_CODE: i32 = 2;
+
+fn handle_drift(args: DriftArgs, global: &GlobalArgs) {
+    let report = match build_drift_report(&args, global) {
+        Ok(report) => report,
+        Err(err) => {
+            eprintln!("error: {err}");
+            std::process::exit(1);
+        }
+    };
+    match render_drift(&report, args.output) {
+        Ok(output) => print!("{output}"),
+        Err(err) => {
+            eprintln!("error: {err}");
+            std::process::exit(1);
+        }
+    }
+    if report.has_drift() {
+        std::process::exit(DRIFT_EXIT_CODE);
+    }
+}
+
+fn build_drift_report(
+    args: &DriftArgs,
+    global: &GlobalArgs,
+) -> greentic_deployer::error::Result<DriftReport> {
+    let greentic = load_greentic_config(global)?;
+    let environment = args
+        .environment
+        .clone()
+        .unwrap_or_else(|| greentic.environment.env_id.to_string());
+    let state_dir = greentic.paths.state_dir.clone();
+    let ledger = Ledger::new(&state_dir, &args.tenant, &environment);
+    let record = applied_record(&ledger)?;
+    let candidate = match &args.pack {
+        Some(pack) => {
+            let provider = Provider::from_str(&record.provider, true).map_err(|err| {
+                DeployerError::Config(format!("unknown provider in ledger record: {err}"))
+            })?;
+            let action_args = ActionArgs::preview(
+                provider,
+                args.strategy
+                    .clone()
+                    .unwrap_or_else(|| record.strategy.clone()),
+                args.tenant.clone(),
+                Some(environment.clone()),
+                pack.clone(),
+                args.output,
+            );
+            let config = DeployerConfig::from_action_args(Action::Plan, action_args, global)?;
+            Some(pack_introspect::build_plan(&config)?)
+        }
+        None => None,
+    };
+    let runner = DefaultIaCCommandRunner::new(args.iac_timeout.map(Duration::from_secs));
+    check_drift(
+        &ledger,
+        &record,
+        &deploy_dir(&state_dir, &record),
+        resolve_iac_tool(args.iac_tool, None)?,
+        &runner,
+        candidate.as_ref(),
+    )
+}
+
+fn handle_diff(args: DiffArgs, global: &GlobalArgs) {
+    match build_diff(&args, global) {
+        Ok(output) => print!("{output}"),
+        Err(err) => {
+            eprintln!("error: {err}");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn build_diff(args: &DiffArgs, global: &GlobalArgs) -> greentic_deployer::error::Result<String> {
+    let (baseline, candidate) = match (&args.files, args.action_args()) {
+        (Some(files), _) => (load_plan_file(&files[0])?, load_plan_file(&files[1])?),
+        (None, Some(action_args)) => {
+            let config = DeployerConfig::from_action_args(Action::Plan, action_args, global)?;
+            let baseline_path = args
+                .against
+                .clone()
+                .unwrap_or_else(|| config.runtime_dir().join("plan.json"));
+            if !baseline_path.exists() {
+                return Err(DeployerError::Config(format!(
+                    "no persisted plan at {}; run `plan` first or pass --against <plan.json>",
+                    baseline_path.display()
+                )));
+            }
+            let baseline = load_plan_file(&baseline_path)?;
+            (baseline, pack_introspect::build_plan(&config)?)
+        }
+        (None, None) => {
+            return Err(DeployerError::Config(
+                "diff requires --files <FROM> <TO> or --provider/--tenant/--pack".into(),
+            ));
+        }
+    };
+    render_diff(&diff_plans(&baseline, &candidate), args.output)
+}
+
+fn handle_batch(args: BatchArgs, global: &GlobalArgs) {
+    let targets = match load_greentic_config(global)
+        .and_then(|greentic| resolve_targets(&args, greentic.environment.env_id.as_ref()))
+    {
+        Ok(targets) => targets,
+        Err(err) => {
+            eprintln!("configuration error: {err}");
+            std::process::exit(1);
+        }
+    };
+    match WasmDeploymentExecutor::new() {
+        Ok(executor) => set_deployment_executor(Arc::new(executor)),
+        Err(err) => {
+            eprintln!("error: {err}");
+            std::process::exit(1);
+        }
+    }
+    let report = run_batch(
+        args.action,
+        targets,
+        args.concurrency,
+        |target| {
+            let action_args = args.action_args(
+                target.provider,
+                target
+                    .strategy
+                    .clone()
+                    .unwrap_or_else(|| args.strategy.clone()),
+                target.tenant.clone(),
+                target.environment.clone(),
+                target.pack.clone().unwrap_or_default(),
+            );
+            let config = DeployerConfig::from_action_args(args.action, action_args, global)?;
+            for warning in &config.config_warnings {
+                eprintln!("configuration warning ({}): {warning}", config.tenant);
+            }
+            Ok(config)
+        },
+        |config| Box::new(DefaultIaCCommandRunner::new(config.iac_timeout)),
+    );
+    match render_batch_report(&report, args.output) {
+        Ok(output) => print!("{output}"),
+        Err(err) => {
+            eprintln!("error: {err}");
+            std::process::exit(1);
+        }
+    }
+    if report.has_failures() {
+        std::process::exit(1);
+    }
+}
+
+async fn handle_promote(args: PromoteArgs, global: &GlobalArgs) {
+    let (config, source) = match promotion_config(&args, global) {
+        Ok(resolved) => resolved,
+        Err(err) => {
+            eprintln!("configuration error: {err}");
+            std::process::exit(1);
+        }
+    };
+    match WasmDeploymentExecutor::new() {
+        Ok(executor) => set_deployment_executor(Arc::new(executor)),
+        Err(err) => {
+            eprintln!("error: {err}");
+            std::process::exit(1);
+        }
+    }
+    let runner = DefaultIaCCommandRunner::new(config.iac_timeout);
+    if let Err(err) = promote(config, &source, &runner).await {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    }
+}
+
+fn promotion_config(
+    args: &PromoteArgs,
+    global: &GlobalArgs,
+) -> greentic_deployer::error::Result<(DeployerConfig, LedgerRecord)> {
+    let greentic = load_greentic_config(global)?;
+    let source = promotion_source(&greentic.paths.state_dir, &args.tenant, &args.from)?;
+    let config =
+        DeployerConfig::from_action_args(Action::Apply, promotion_args(args, &source)?, global)?;
+    Ok((config, source))
+}
+
 async fn handle_standard(cli: CliArgs) {
     match DeployerConfig::from_env_and_args(cli) {
         Ok(config) => {
@@ -202,6 +809,13 @@ async fn handle_standard(cli: CliArgs) {
                 }
                 return;
             }
+            match WasmDeploymentExecutor::new() {
+                Ok(executor) => set_deployment_executor(Arc::new(executor)),
+                Err(err) => {
+                    eprintln!("error: {err}");
+                    std::process::exit(1);
+                }
+            }
             if let Err(err) = apply::run(config).await {
                 eprintln!("error: {err}");
                 std::process::exit(1);
@@ -215,19 +829,14 @@ async fn handle_standard(cli: CliArgs) {
 }
 
 fn summarize_pack(action: &str, cmd: &PlatformActionArgs, ctx: &PlatformContext) {
-    let net_allowlist = match NetAllowList::parse(ctx.net_allowlist.as_deref()) {
-        Ok(list) => list,
+    let network_policy = match ctx.network_policy() {
+        Ok(policy) => policy,
         Err(err) => {
             eprintln!("invalid network allowlist: {err}");
             std::process::exit(1);
         }
     };
-    let network_policy = NetworkPolicy::new(ctx.allow_network, ctx.offline_only, net_allowlist);
-    let cache_base = ctx
-        .state_path
-        .parent()
-        .map(PathBuf::from)
-        .unwrap_or_else(|| PathBuf::from("."));
+    let cache_base = cache_base_for_state(&ctx.state_path);
     let pack_path = if cmd.pack.starts_with("oci://") {
         match resolve_oci_pack(&cmd.pack, &cache_base, &network_policy) {
             Ok(path) => path,
@@ -245,21 +854,30 @@ fn summarize_pack(action: &str, cmd: &PlatformActionArgs, ctx: &PlatformContext)
                 verify: cmd.verify,
                 strict: cmd.strict_verify,
             };
-            if let Err(err) = platform::verify_platform_pack(&info, policy) {
-                eprintln!("platform {action} verification failed: {err}");
-                std::process::exit(1);
+            let trust = match TrustStore::load(&ctx.trust_store) {
+                Ok(trust) => trust,
+                Err(err) => {
+                    eprintln!("failed to read trust store: {err}");
+                    std::process::exit(1);
+                }
+            };
+            match platform::verify_platform_pack(&info, policy, &trust) {
+                Ok(outcome) => {
+                    for key_id in &outcome.verified_keys {
+                        println!("signature verified: {key_id}");
+                    }
+                }
+                Err(err) => {
+                    eprintln!("platform {action} verification failed: {err}");
+                    std::process::exit(1);
+                }
             }
-            let current_state = match load_state_backend(
-                ctx.state_backend,
-                &ctx.state_path,
-                &ctx.k8s_namespace,
-                &ctx.k8s_state_name,
-            ) {
+            let current_state = match ctx.state_store.load() {
                 Ok(state) => state,
                 Err(err) => {
                     eprintln!(
                         "failed to read bootstrap state at {}: {err}",
-                        ctx.state_path.display()
+                        ctx.state_store.describe()
                     );
                     std::process::exit(1);
                 }
@@ -296,12 +914,27 @@ fn summarize_pack(action: &str, cmd: &PlatformActionArgs, ctx: &PlatformContext)
                     println!("adapter disabled: {reason}");
                 }
             }
+            let mut installer: Box<dyn InstallerRuntime> = match WasmInstaller::from_pack(
+                &pack_path,
+                &info.manifest,
+                capabilities.clone(),
+                network_policy.clone(),
+            ) {
+                Ok(installer) => Box::new(installer),
+                Err(err) => {
+                    tracing::warn!("installer component unavailable: {err}");
+                    Box::new(UnavailableInstaller::new(err.to_string()))
+                }
+            };
+            let installer: Option<&mut dyn InstallerRuntime> = Some(installer.as_mut());
             let result = match ctx.interaction {
                 InteractionMode::Cli | InteractionMode::Auto => {
                     let stdin = std::io::stdin();
                     let stdout = std::io::stdout();
-                    let mut adapter = CliPromptAdapter::new(stdin.lock(), stdout);
-                    run_bootstrap_flow(&flow_bytes, &mut adapter)
+                    let masked = stdin.is_terminal();
+                    let mut adapter =
+                        CliPromptAdapter::new(stdin.lock(), stdout).mask_secrets(masked);
+                    run_bootstrap_flow_with_installer(&flow_bytes, &mut adapter, installer)
                 }
                 InteractionMode::Http => {
                     if network_policy.offline_only() {
@@ -318,13 +951,21 @@ fn summarize_pack(action: &str, cmd: &PlatformActionArgs, ctx: &PlatformContext)
                         .bind
                         .clone()
                         .unwrap_or_else(|| "127.0.0.1:0".to_string());
-                    let mut adapter = HttpPromptAdapter::bind(&bind_addr, ctx.interaction_timeout)
-                        .unwrap_or_else(|err| {
-                            eprintln!("failed to bind http adapter at {bind_addr}: {err}");
-                            std::process::exit(1);
-                        });
-                    println!("http adapter listening at http://{}", adapter.bound_addr());
-                    run_bootstrap_flow(&flow_bytes, &mut adapter)
+                    let bound = match (&ctx.http_tls_cert, &ctx.http_tls_key) {
+                        (Some(cert), Some(key)) => HttpPromptAdapter::bind_with_tls(
+                            &bind_addr,
+                            ctx.interaction_timeout,
+                            cert,
+                            key,
+                        ),
+                        _ => HttpPromptAdapter::bind(&bind_addr, ctx.interaction_timeout),
+                    };
+                    let mut adapter = bound.unwrap_or_else(|err| {
+                        eprintln!("failed to bind http adapter at {bind_addr}: {err}");
+                        std::process::exit(1);
+                    });
+                    println!("http wizard: {}", adapter.wizard_url());
+                    run_bootstrap_flow_with_installer(&flow_bytes, &mut adapter, installer)
                 }
                 InteractionMode::Json => {
                     let answers = match ctx.answers {
@@ -338,11 +979,43 @@ fn summarize_pack(action: &str, cmd: &PlatformActionArgs, ctx: &PlatformContext)
                         eprintln!("invalid answers payload: {err}");
                         std::process::exit(1);
                     });
-                    run_bootstrap_flow(&flow_bytes, &mut adapter)
+                    run_bootstrap_flow_with_installer(&flow_bytes, &mut adapter, installer)
                 }
                 InteractionMode::Mqtt => {
-                    eprintln!("mqtt interaction not yet wired into deployer runtime");
-                    std::process::exit(1);
+                    if network_policy.offline_only() {
+                        eprintln!("mqtt interaction not allowed in offline-only mode");
+                        std::process::exit(1);
+                    }
+                    if !ctx.allow_listeners || !network_policy.allow_network() {
+                        eprintln!(
+                            "mqtt interaction requires --allow-listeners and --allow-network"
+                        );
+                        std::process::exit(1);
+                    }
+                    let (Some(broker), Some(device_id)) = (&ctx.mqtt_broker, &ctx.mqtt_device_id)
+                    else {
+                        eprintln!(
+                            "--mqtt-broker and --mqtt-device-id are required when --interaction mqtt"
+                        );
+                        std::process::exit(1);
+                    };
+                    let mut adapter = MqttPromptAdapter::connect(
+                        &ctx.mqtt_options(broker, device_id),
+                        network_policy.clone(),
+                        device_id.clone(),
+                        ctx.mqtt_topic_prefix.clone(),
+                        ctx.interaction_timeout,
+                    )
+                    .unwrap_or_else(|err| {
+                        eprintln!("failed to connect mqtt adapter to {broker}: {err}");
+                        std::process::exit(1);
+                    });
+                    println!(
+                        "mqtt adapter connected to {broker}; questions on {}, answers on {}",
+                        adapter.questions_topic(),
+                        adapter.answers_topic()
+                    );
+                    run_bootstrap_flow_with_installer(&flow_bytes, &mut adapter, installer)
                 }
             };
             match result {
@@ -351,6 +1024,46 @@ fn summarize_pack(action: &str, cmd: &PlatformActionArgs, ctx: &PlatformContext)
                         .config_out
                         .clone()
                         .unwrap_or_else(|| default_config_patch_path(&ctx.state_path));
+                    let installed_config = current_state.as_ref().and_then(|state| {
+                        let release =
+                            RollbackRef::new(state.version.as_deref(), state.digest.as_deref());
+                        match load_release(&ctx.state_path, &release) {
+                            Ok(archived) => archived.applied_config,
+                            Err(err) => {
+                                eprintln!(
+                                    "warning: installed config unknown, edits since install are not detected: {err}"
+                                );
+                                None
+                            }
+                        }
+                    });
+                    let config_plan = plan_config_patch(
+                        &config_path,
+                        &result.output.config_patch,
+                        installed_config.as_ref(),
+                    )
+                    .unwrap_or_else(|err| {
+                        eprintln!(
+                            "failed to apply config patch to {}: {err}",
+                            config_path.display()
+                        );
+                        std::process::exit(1);
+                    });
+                    print_config_plan(&config_plan);
+                    if !config_plan.conflicts.is_empty() {
+                        if cmd.force_config {
+                            eprintln!("warning: overwriting config keys edited since install");
+                        } else {
+                            eprintln!(
+                                "config patch conflicts with edits made since install; re-run with --force-config to overwrite them"
+                            );
+                            std::process::exit(1);
+                        }
+                    }
+                    if cmd.config_preview {
+                        println!("config preview only; nothing was written");
+                        std::process::exit(0);
+                    }
                     let config_snapshot = snapshot_config(&config_path).unwrap_or_else(|err| {
                         eprintln!(
                             "failed to snapshot config at {}: {err}",
@@ -358,21 +1071,21 @@ fn summarize_pack(action: &str, cmd: &PlatformActionArgs, ctx: &PlatformContext)
                         );
                         std::process::exit(1);
                     });
-                    let secrets_snapshot = snapshot_backend(&ctx.secrets_backend);
+                    let secrets_snapshot =
+                        snapshot_backend(&ctx.secrets_backend, &result.output.secrets_writes)
+                            .unwrap_or_else(|err| {
+                                eprintln!("failed to snapshot secrets backend: {err}");
+                                std::process::exit(1);
+                            });
                     if let Err(err) =
                         execute_writes(&ctx.secrets_backend, &result.output.secrets_writes)
                     {
-                        if let Some(snapshot) = secrets_snapshot {
-                            let _ = restore_backend(&snapshot);
-                        }
+                        let _ = restore_backend(&secrets_snapshot);
                         eprintln!("secret write failed: {err}");
                         std::process::exit(1);
                     }
-                    if let Err(err) = apply_config_patch(&config_path, &result.output.config_patch)
-                    {
-                        if let Some(snapshot) = secrets_snapshot {
-                            let _ = restore_backend(&snapshot);
-                        }
+                    if let Err(err) = write_config(&config_plan) {
+                        let _ = restore_backend(&secrets_snapshot);
                         let _ = restore_config(&config_path, &config_snapshot);
                         eprintln!(
                             "failed to apply config patch to {}: {err}",
@@ -381,29 +1094,21 @@ fn summarize_pack(action: &str, cmd: &PlatformActionArgs, ctx: &PlatformContext)
                         std::process::exit(1);
                     }
                     if let Err(err) = run_install_plan(&pack_path) {
-                        if let Some(snapshot) = secrets_snapshot {
-                            let _ = restore_backend(&snapshot);
-                        }
+                        let _ = restore_backend(&secrets_snapshot);
                         let _ = restore_config(&config_path, &config_snapshot);
                         eprintln!("deploy plan failed: {err}");
                         std::process::exit(1);
                     }
-                    let state = if action == "upgrade" {
-                        let existing = load_state_backend(
-                            ctx.state_backend,
-                            &ctx.state_path,
-                            &ctx.k8s_namespace,
-                            &ctx.k8s_state_name,
+                    let state = if action == "upgrade"
+                        && let Some(existing) = &current_state
+                    {
+                        let rollback_ref = RollbackRef::new(
+                            existing.version.as_deref(),
+                            existing.digest.as_deref(),
                         )
-                        .unwrap_or(None)
-                        .expect("upgrade preflight ensures state exists");
-                        let rollback_ref = format!(
-                            "version={},digest={}",
-                            existing.version.as_deref().unwrap_or("unknown"),
-                            existing.digest.as_deref().unwrap_or("unknown")
-                        );
+                        .to_string();
                         BootstrapState::upgraded_from(
-                            &existing,
+                            existing,
                             Some(info.manifest.version.to_string()),
                             info.digest.clone(),
                             Some(rollback_ref),
@@ -414,23 +1119,28 @@ fn summarize_pack(action: &str, cmd: &PlatformActionArgs, ctx: &PlatformContext)
                             info.digest.clone(),
                         )
                     };
-                    if let Err(err) = save_state_backend(
-                        ctx.state_backend,
-                        &ctx.state_path,
-                        &ctx.k8s_namespace,
-                        &ctx.k8s_state_name,
-                        &state,
-                    ) {
-                        if let Some(snapshot) = secrets_snapshot {
-                            let _ = restore_backend(&snapshot);
-                        }
+                    if let Err(err) = ctx.state_store.save(&state) {
+                        let _ = restore_backend(&secrets_snapshot);
                         let _ = restore_config(&config_path, &config_snapshot);
                         eprintln!(
                             "failed to persist bootstrap state at {}: {err}",
-                            ctx.state_path.display()
+                            ctx.state_store.describe()
                         );
                         std::process::exit(1);
                     }
+                    let release =
+                        RollbackRef::new(state.version.as_deref(), state.digest.as_deref());
+                    if let Err(err) = archive_release(
+                        &ctx.state_path,
+                        &release,
+                        &pack_path,
+                        &result.output.config_patch,
+                        &config_plan.after,
+                    ) {
+                        eprintln!(
+                            "warning: failed to archive platform release for rollback: {err}"
+                        );
+                    }
                     render_bootstrap_output(&result.output);
                     if let Some(path) = ctx.output_path.as_ref()
                         && let Err(err) = write_output_file(path, &result.output)
@@ -456,6 +1166,31 @@ fn summarize_pack(action: &str, cmd: &PlatformActionArgs, ctx: &PlatformContext)
     }
 }
 
+fn print_config_plan(plan: &ConfigPlan) {
+    if plan.changes.is_empty() {
+        println!(
+            "config {} ({}) is unchanged",
+            plan.path.display(),
+            plan.format
+        );
+    } else {
+        println!(
+            "config changes for {} ({}):",
+            plan.path.display(),
+            plan.format
+        );
+        for change in &plan.changes {
+            println!("  {change}");
+        }
+    }
+    if !plan.conflicts.is_empty() {
+        eprintln!("config keys edited since install that the patch overwrites:");
+        for conflict in &plan.conflicts {
+            eprintln!("  {conflict}");
+        }
+    }
+}
+
 fn render_bootstrap_output(output: &BootstrapOutput) {
     let redacted = output.redacted();
     match serde_json::to_string_pretty(&redacted) {
@@ -495,6 +1230,99 @@ fn load_answers(source: &str) -> serde_json::Value {
     }
 }
 
+fn rollback_platform(ctx: &PlatformContext) {
+    let current = match ctx.state_store.load() {
+        Ok(Some(state)) => state,
+        Ok(None) => {
+            eprintln!("platform not installed; nothing to roll back");
+            std::process::exit(1);
+        }
+        Err(err) => {
+            eprintln!(
+                "failed to read bootstrap state at {}: {err}",
+                ctx.state_store.describe()
+            );
+            std::process::exit(1);
+        }
+    };
+    let Some(reference) = current.rollback_ref.as_deref() else {
+        eprintln!("bootstrap state has no rollback_ref; nothing to roll back to");
+        std::process::exit(1);
+    };
+    let target = RollbackRef::parse(reference).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        std::process::exit(1);
+    });
+    let release = load_release(&ctx.state_path, &target).unwrap_or_else(|err| {
+        eprintln!("platform rollback failed: {err}");
+        std::process::exit(1);
+    });
+    let config_path = ctx
+        .config_out
+        .clone()
+        .unwrap_or_else(|| default_config_patch_path(&ctx.state_path));
+    let config_snapshot = snapshot_config(&config_path).unwrap_or_else(|err| {
+        eprintln!(
+            "failed to snapshot config at {}: {err}",
+            config_path.display()
+        );
+        std::process::exit(1);
+    });
+    // Revert what the deployer changed between the two releases, leaving other operator edits;
+    // archives without the applied config fall back to re-applying the target's patch.
+    let current_config = load_release(
+        &ctx.state_path,
+        &RollbackRef::new(current.version.as_deref(), current.digest.as_deref()),
+    )
+    .ok()
+    .and_then(|release| release.applied_config);
+    let patch = match (&current_config, &release.applied_config) {
+        (Some(from), Some(to)) => diff_merge_patch(from, to),
+        _ => release.config_patch.clone(),
+    };
+    let config_plan = plan_config_patch(&config_path, &patch, current_config.as_ref())
+        .unwrap_or_else(|err| {
+            eprintln!(
+                "failed to restore config patch to {}: {err}",
+                config_path.display()
+            );
+            std::process::exit(1);
+        });
+    print_config_plan(&config_plan);
+    if let Err(err) = write_config(&config_plan) {
+        let _ = restore_config(&config_path, &config_snapshot);
+        eprintln!(
+            "failed to restore config patch to {}: {err}",
+            config_path.display()
+        );
+        std::process::exit(1);
+    }
+    if let Some(pack_path) = release.pack_path.as_ref()
+        && let Err(err) = run_install_plan(pack_path)
+    {
+        let _ = restore_config(&config_path, &config_snapshot);
+        eprintln!("deploy plan failed: {err}");
+        std::process::exit(1);
+    }
+    let state = BootstrapState::rolled_back_to(&current, &target);
+    if let Err(err) = ctx.state_store.save(&state) {
+        let _ = restore_config(&config_path, &config_snapshot);
+        eprintln!(
+            "failed to persist bootstrap state at {}: {err}",
+            ctx.state_store.describe()
+        );
+        std::process::exit(1);
+    }
+    println!(
+        "platform rolled back to version {} (digest {}) from {}\n- config patch: {}\n- release archive: {}",
+        target.version,
+        target.digest,
+        state.rollback_ref.as_deref().unwrap_or("unknown"),
+        config_path.display(),
+        release.dir.display()
+    );
+}
+
 /// Placeholder hook to reuse deployment machinery (will wire real apply later).
 fn run_install_plan(_pack_path: &std::path::Path) -> Result<(), String> {
     // The platform pack has already been validated and parsed; real deploy logic will
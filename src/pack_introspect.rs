@@ -19,6 +19,7 @@ use greentic_types::pack_manifest::{PackFlowEntry, PackKind, PackManifest};
 use greentic_types::secrets::{SecretRequirement, SecretScope};
 use semver::Version;
 use serde_json::{Value as JsonValue, json};
+use sha2::{Digest, Sha256};
 use tar::Archive;
 
 use crate::config::DeployerConfig;
@@ -28,6 +29,7 @@ use crate::plan::{
     ComponentRole, DeploymentHints, DeploymentProfile, InferenceNotes, InfraPlan, PlanContext,
     PlannedComponent, Target, assemble_plan,
 };
+use crate::platform::{empty_content_digest, pack_content_digest};
 
 /// Load a pack manifest from raw .gtpack bytes.
 ///
@@ -68,6 +70,9 @@ pub fn build_plan_with_source(
     config: &DeployerConfig,
 ) -> Result<PlanContext> {
     let manifest = source.read_manifest()?;
+    config
+        .pack_verification
+        .verify(&source.label(), &manifest, &source.content_digest()?)?;
     let deployment = build_deployment_hints(config);
     let base = plan_from_pack_kind(&manifest, config);
     let external_components: Vec<String> = external_facing_components(&manifest)
@@ -109,6 +114,23 @@ impl PackSource {
         Ok(Self::Registry { reference, source })
     }
 
+    fn label(&self) -> String {
+        match self {
+            PackSource::GtpackPath(path) | PackSource::Dir(path) => path.display().to_string(),
+            PackSource::Registry { reference, .. } => {
+                format!("{}@{}", reference.oci_url, reference.version)
+            }
+        }
+    }
+
+    /// Content digest covered by pack signatures; distributor packs carry only their manifest.
+    fn content_digest(&self) -> Result<String> {
+        match self {
+            PackSource::GtpackPath(path) | PackSource::Dir(path) => pack_content_digest(path),
+            PackSource::Registry { .. } => Ok(empty_content_digest()),
+        }
+    }
+
     fn read_manifest(&mut self) -> Result<PackManifest> {
         match self {
             PackSource::GtpackPath(path) => read_manifest_from_tar(path),
@@ -354,6 +376,15 @@ fn read_manifest_from_registry(
         DeployerError::Config(format!("invalid pack id '{}': {err}", reference.oci_url))
     })?;
     let bytes = source.fetch_pack(&pack_id, &reference.version)?;
+    // The reference digest pins the pack; check it against the bytes actually fetched.
+    let actual = format!("sha256:{:x}", Sha256::digest(&bytes));
+    if actual != reference.digest {
+        return Err(DeployerError::PackDigestMismatch {
+            pack: format!("{}@{}", reference.oci_url, reference.version),
+            expected: reference.digest.clone(),
+            actual,
+        });
+    }
     load_pack_manifest_from_bytes(&bytes)
 }
 
@@ -1179,10 +1210,26 @@ mod tests {
         let reference = PackRef::new(
             pack_id.to_string(),
             Version::new(0, 1, 0),
-            "sha256:deadbeef",
+            sample_manifest_digest(),
         );
         let decoded = read_manifest_from_registry(&source, &reference).expect("registry decode");
         assert_eq!(decoded.pack_id, manifest.pack_id);
+
+        let pinned_elsewhere = PackRef::new(
+            pack_id.to_string(),
+            Version::new(0, 1, 0),
+            format!("sha256:{}", "0".repeat(64)),
+        );
+        let err = read_manifest_from_registry(&source, &pinned_elsewhere).unwrap_err();
+        assert!(
+            matches!(err, DeployerError::PackDigestMismatch { .. }),
+            "{err}"
+        );
+    }
+
+    fn sample_manifest_digest() -> String {
+        let encoded = encode_pack_manifest(&sample_manifest()).expect("encode manifest");
+        format!("sha256:{:x}", Sha256::digest(&encoded))
     }
 
     #[test]
@@ -1198,6 +1245,22 @@ mod tests {
         assert_eq!(plan.plan.pack_id, manifest.pack_id.to_string());
     }
 
+    #[test]
+    fn strict_verification_rejects_unsigned_pack() {
+        let encoded = encode_pack_manifest(&sample_manifest()).expect("encode manifest");
+        set_distributor_source(Arc::new(MemorySource { bytes: encoded }));
+
+        let mut config = registry_config();
+        config.pack_verification.policy.strict = true;
+
+        let err = build_plan(&config).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("dev.greentic.sample@0.1.0: pack missing signatures"),
+            "{err}"
+        );
+    }
+
     fn registry_config() -> DeployerConfig {
         DeployerConfig {
             action: Action::Plan,
@@ -1212,13 +1275,16 @@ mod tests {
             pack_ref: Some(PackRef::new(
                 "dev.greentic.sample",
                 Version::new(0, 1, 0),
-                "sha256:deadbeef",
+                sample_manifest_digest(),
             )),
             distributor_url: None,
             distributor_token: None,
             yes: true,
             preview: false,
             dry_run: false,
+            allow_destroy: false,
+            iac_timeout: None,
+            state_backend: Default::default(),
             iac_tool: IaCTool::Terraform,
             output: OutputFormat::Text,
             greentic: greentic_config::ConfigResolver::new()
@@ -1230,6 +1296,8 @@ mod tests {
             explain_config: false,
             explain_config_json: false,
             allow_remote_in_offline: false,
+            pack_verification: Default::default(),
+            quiet: false,
         }
     }
 
@@ -1250,6 +1318,9 @@ mod tests {
             yes: true,
             preview: false,
             dry_run: false,
+            allow_destroy: false,
+            iac_timeout: None,
+            state_backend: Default::default(),
             iac_tool: IaCTool::Terraform,
             output: OutputFormat::Text,
             greentic: greentic_config::ConfigResolver::new()
@@ -1261,6 +1332,8 @@ mod tests {
             explain_config: false,
             explain_config_json: false,
             allow_remote_in_offline: false,
+            pack_verification: Default::default(),
+            quiet: false,
         }
     }
 }
@@ -1,16 +1,29 @@
-use std::path::Path;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::error::{DeployerError, Result};
 use crate::pack_introspect::{read_entry_from_gtpack, read_manifest_from_gtpack};
 use crate::platform::flow::resolve_bootstrap;
 use crate::platform::oci::compute_sha256;
+use crate::platform::trust::{DEFAULT_TRUST_STORE, TrustStore};
+use greentic_types::cbor::encode_pack_manifest;
 use greentic_types::pack_manifest::PackManifest;
+use sha2::{Digest, Sha256};
+use tar::Archive;
 use tracing::warn;
+use walkdir::WalkDir;
+
+/// Domain separator prefixed to every signed payload.
+const SIGNATURE_CONTEXT: &str = "greentic-pack-signature-v1";
 
 #[derive(Debug)]
 pub struct PlatformPackInfo {
     pub manifest: PackManifest,
     pub digest: Option<String>,
+    /// Digest over every archive entry except `manifest.cbor`; covered by pack signatures.
+    pub content_digest: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -19,13 +32,62 @@ pub struct VerificationPolicy {
     pub strict: bool,
 }
 
+/// Verification applied to the application and deployment packs loaded by plan/apply/destroy.
+#[derive(Debug, Clone)]
+pub struct PackVerification {
+    pub policy: VerificationPolicy,
+    pub trust_store: PathBuf,
+}
+
+impl Default for PackVerification {
+    fn default() -> Self {
+        Self {
+            policy: VerificationPolicy {
+                verify: true,
+                strict: false,
+            },
+            trust_store: PathBuf::from(DEFAULT_TRUST_STORE),
+        }
+    }
+}
+
+impl PackVerification {
+    /// Verify the signatures of the pack at `label` against the configured trust store.
+    pub fn verify(
+        &self,
+        label: &str,
+        manifest: &PackManifest,
+        content_digest: &str,
+    ) -> Result<VerificationOutcome> {
+        let trust = if self.policy.verify {
+            TrustStore::load(&self.trust_store)?
+        } else {
+            TrustStore::default()
+        };
+        let outcome = verify_pack_signatures(manifest, content_digest, self.policy, &trust)
+            .map_err(|err| match err {
+                DeployerError::Pack(msg) => DeployerError::Pack(format!("{label}: {msg}")),
+                other => other,
+            })?;
+        for w in &outcome.warnings {
+            warn!("{label}: {w}");
+        }
+        Ok(outcome)
+    }
+}
+
 #[derive(Debug)]
 pub struct VerificationOutcome {
     pub warnings: Vec<String>,
+    /// Key ids whose signatures verified against the trust store.
+    pub verified_keys: Vec<String>,
 }
 
+pub mod bundle;
+pub mod cache;
 pub mod flow;
 pub mod oci;
+pub mod trust;
 
 pub fn load_platform_pack(path: &Path) -> Result<PlatformPackInfo> {
     if !path.exists() {
@@ -43,8 +105,126 @@ pub fn load_platform_pack(path: &Path) -> Result<PlatformPackInfo> {
 
     let digest = compute_sha256(path)?;
     let manifest = read_manifest_from_gtpack(path)?;
+    let content_digest = Some(pack_content_digest(path)?);
+
+    Ok(PlatformPackInfo {
+        manifest,
+        digest,
+        content_digest,
+    })
+}
+
+/// Digest of the pack contents other than the manifest: sha256 over sorted
+/// `<sha256-hex>  <path>` lines, one per regular file in the `.gtpack` archive or pack directory.
+pub fn pack_content_digest(path: &Path) -> Result<String> {
+    let mut entries = entry_digests(path)?;
+    entries.remove("manifest.cbor");
+    Ok(digest_entries(&entries))
+}
+
+/// Digest pinning a whole pack: the sha256 of a `.gtpack` archive, or for a pack directory the
+/// content digest computed over every file including `manifest.cbor`.
+pub fn pack_digest(path: &Path) -> Result<String> {
+    if path.is_dir() {
+        Ok(digest_entries(&entry_digests(path)?))
+    } else {
+        compute_sha256(path)?
+            .ok_or_else(|| DeployerError::Pack(format!("no digest for {}", path.display())))
+    }
+}
+
+/// Content digest of a pack that consists of its manifest alone (e.g. served by a distributor).
+pub fn empty_content_digest() -> String {
+    digest_entries(&BTreeMap::new())
+}
+
+fn entry_digests(path: &Path) -> Result<BTreeMap<String, String>> {
+    let mut entries = BTreeMap::new();
+    if path.is_dir() {
+        for entry in WalkDir::new(path) {
+            let entry = entry.map_err(|err| DeployerError::Io(err.into()))?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let name = entry
+                .path()
+                .strip_prefix(path)
+                .unwrap_or(entry.path())
+                .to_string_lossy()
+                .replace('\\', "/");
+            let mut hasher = Sha256::new();
+            std::io::copy(&mut File::open(entry.path())?, &mut hasher)?;
+            entries.insert(name, format!("{:x}", hasher.finalize()));
+        }
+        return Ok(entries);
+    }
+    // Entry readers take the first entry with a matching name, so an archive carrying two entries
+    // for one path (or `./x` next to `x`) could hide content from the digest; reject those.
+    let mut seen = BTreeSet::new();
+    let mut archive = Archive::new(File::open(path)?);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_type = entry.header().entry_type();
+        if entry_type.is_dir() {
+            continue;
+        }
+        let raw = entry.path()?.into_owned();
+        let name = normalize_entry_name(&raw).ok_or_else(|| {
+            DeployerError::Pack(format!(
+                "pack {} has invalid entry path {}",
+                path.display(),
+                raw.display()
+            ))
+        })?;
+        if !seen.insert(name.clone()) {
+            return Err(DeployerError::Pack(format!(
+                "pack {} contains duplicate entry {name}",
+                path.display()
+            )));
+        }
+        if !entry_type.is_file() {
+            continue;
+        }
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut entry, &mut hasher)?;
+        entries.insert(name, format!("{:x}", hasher.finalize()));
+    }
+    Ok(entries)
+}
+
+/// `a/b` for `a/b`, `./a/b` or `a//b`; `None` for absolute paths or paths containing `..`.
+fn normalize_entry_name(path: &Path) -> Option<String> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+fn digest_entries(entries: &BTreeMap<String, String>) -> String {
+    let mut hasher = Sha256::new();
+    for (name, digest) in entries {
+        hasher.update(format!("{digest}  {name}\n"));
+    }
+    format!("sha256:{:x}", hasher.finalize())
+}
 
-    Ok(PlatformPackInfo { manifest, digest })
+/// Bytes covered by a pack signature: the manifest with its signatures removed (canonical CBOR,
+/// hashed) and the pack content digest.
+pub fn signing_payload(manifest: &PackManifest, content_digest: &str) -> Result<Vec<u8>> {
+    let mut unsigned = manifest.clone();
+    unsigned.signatures = Default::default();
+    let encoded = encode_pack_manifest(&unsigned)
+        .map_err(|err| DeployerError::Pack(format!("failed to encode manifest: {err}")))?;
+    Ok(format!(
+        "{SIGNATURE_CONTEXT}\nmanifest sha256:{:x}\ncontent {content_digest}\n",
+        Sha256::digest(&encoded)
+    )
+    .into_bytes())
 }
 
 pub fn load_bootstrap_flow(path: &Path, manifest: &PackManifest, install: bool) -> Result<Vec<u8>> {
@@ -59,17 +239,42 @@ pub fn load_bootstrap_flow(path: &Path, manifest: &PackManifest, install: bool)
     read_entry_from_gtpack(path, &entry)
 }
 
+/// Verify a platform pack's manifest signatures against `trust`.
 pub fn verify_platform_pack(
     info: &PlatformPackInfo,
     policy: VerificationPolicy,
+    trust: &TrustStore,
+) -> Result<VerificationOutcome> {
+    let content_digest = info
+        .content_digest
+        .as_deref()
+        .ok_or_else(|| DeployerError::Pack("pack content digest was not computed".into()))?;
+    let outcome = verify_pack_signatures(&info.manifest, content_digest, policy, trust)?;
+    for w in &outcome.warnings {
+        warn!("{w}");
+    }
+    Ok(outcome)
+}
+
+/// Verify the manifest signatures against `trust`. Unknown or expired keys and signatures that do
+/// not match the manifest and content digest are warnings, or errors under `policy.strict`.
+pub fn verify_pack_signatures(
+    manifest: &PackManifest,
+    content_digest: &str,
+    policy: VerificationPolicy,
+    trust: &TrustStore,
 ) -> Result<VerificationOutcome> {
     let mut warnings = Vec::new();
+    let mut verified_keys = Vec::new();
     if !policy.verify {
         warnings.push("verification skipped (--verify=false)".to_string());
-        return Ok(VerificationOutcome { warnings });
+        return Ok(VerificationOutcome {
+            warnings,
+            verified_keys,
+        });
     }
 
-    match info.manifest.signatures.signatures.as_slice() {
+    match manifest.signatures.signatures.as_slice() {
         [] => {
             let msg = "pack missing signatures";
             if policy.strict {
@@ -89,14 +294,37 @@ pub fn verify_platform_pack(
                     )));
                 }
             }
+            let payload = signing_payload(manifest, content_digest)?;
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            for sig in sigs {
+                let problem = match trust.get(&sig.key_id) {
+                    None => format!("signature key_id={} is not in the trust store", sig.key_id),
+                    Some(key) if key.is_expired(now) => format!(
+                        "trusted key {} expired at {}",
+                        sig.key_id,
+                        key.expires_at.unwrap_or_default()
+                    ),
+                    Some(key) => match key.verify(&sig.algorithm, &payload, &sig.signature) {
+                        Ok(()) => {
+                            verified_keys.push(sig.key_id.clone());
+                            continue;
+                        }
+                        Err(err) => format!("invalid signature for key_id={}: {err}", sig.key_id),
+                    },
+                };
+                if policy.strict {
+                    return Err(DeployerError::Pack(problem));
+                }
+                warnings.push(problem);
+            }
         }
     }
 
-    if !warnings.is_empty() {
-        for w in &warnings {
-            warn!("{w}");
-        }
-    }
-
-    Ok(VerificationOutcome { warnings })
+    Ok(VerificationOutcome {
+        warnings,
+        verified_keys,
+    })
 }
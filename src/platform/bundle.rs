@@ -0,0 +1,230 @@
+//! Offline bundles: a tarball carrying resolved `oci://` packs and deployment packs.
+//!
+//! `bundle.json` lists every pack with its kind, source reference, pack id/version and digest;
+//! the packs themselves are stored as `packs/sha256-<hex>.gtpack`. Importing verifies each digest,
+//! seeds the OCI pack cache for the recorded references (so `--offline-only` installs resolve
+//! them without network) and copies deployment packs into a packs directory where deployment
+//! pack discovery finds them.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::cache::PackCache;
+use super::oci::{compute_sha256, parse_oci_reference, resolve_oci_pack_with_auth};
+use crate::bootstrap::network::NetworkPolicy;
+use crate::error::{DeployerError, Result};
+use crate::pack_introspect::read_manifest_from_gtpack;
+
+/// Name of the bundle index inside the tarball.
+pub const BUNDLE_INDEX: &str = "bundle.json";
+const BUNDLE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BundlePackKind {
+    Platform,
+    Deployment,
+}
+
+/// A pack to put into a bundle: an `oci://` reference, or a local `.gtpack` for deployment packs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleSource {
+    pub kind: BundlePackKind,
+    pub source: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleIndex {
+    pub version: u32,
+    pub packs: Vec<BundleEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleEntry {
+    pub kind: BundlePackKind,
+    /// `oci://` reference the pack was resolved from; `None` for local deployment packs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    pub pack_id: String,
+    pub version: String,
+    pub digest: String,
+    /// Path of the pack inside the tarball.
+    pub path: String,
+}
+
+/// What [`import_bundle`] seeded.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ImportReport {
+    /// `oci://` references now resolvable from the cache.
+    pub cached: Vec<String>,
+    /// Deployment packs copied into the packs directory.
+    pub deployment_packs: Vec<PathBuf>,
+}
+
+/// Resolve `sources` (through the pack cache at `cache_base`) and write them with an index to
+/// the tarball `out`.
+pub fn export_bundle(
+    sources: &[BundleSource],
+    out: &Path,
+    cache_base: &Path,
+    network_policy: &NetworkPolicy,
+    auth_file: Option<&Path>,
+) -> Result<BundleIndex> {
+    let mut index = BundleIndex {
+        version: BUNDLE_VERSION,
+        packs: Vec::new(),
+    };
+    let mut files = Vec::new();
+    for source in sources {
+        let (path, reference) = if source.source.starts_with("oci://") {
+            let path =
+                resolve_oci_pack_with_auth(&source.source, cache_base, network_policy, auth_file)?;
+            (path, Some(source.source.clone()))
+        } else if source.kind == BundlePackKind::Deployment {
+            (PathBuf::from(&source.source), None)
+        } else {
+            return Err(DeployerError::Config(format!(
+                "platform pack {} must be an oci:// reference; copy local .gtpack files directly",
+                source.source
+            )));
+        };
+        if !path.is_file() {
+            return Err(DeployerError::Pack(format!(
+                "pack {} is not a .gtpack file",
+                path.display()
+            )));
+        }
+        let manifest = read_manifest_from_gtpack(&path)?;
+        let digest = compute_sha256(&path)?.unwrap_or_default();
+        let entry_path = format!("packs/{}.gtpack", digest.replace(':', "-"));
+        if !files.iter().any(|(name, _)| name == &entry_path) {
+            files.push((entry_path.clone(), path));
+        }
+        index.packs.push(BundleEntry {
+            kind: source.kind,
+            reference,
+            pack_id: manifest.pack_id.to_string(),
+            version: manifest.version.to_string(),
+            digest,
+            path: entry_path,
+        });
+    }
+
+    if let Some(parent) = out.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut builder = tar::Builder::new(fs::File::create(out)?);
+    append(
+        &mut builder,
+        BUNDLE_INDEX,
+        &serde_json::to_vec_pretty(&index)?,
+    )?;
+    for (name, path) in files {
+        append(&mut builder, &name, &fs::read(path)?)?;
+    }
+    builder.into_inner()?;
+    Ok(index)
+}
+
+/// Verify and unpack `bundle`: seed the pack cache at `cache_base` with every referenced pack and
+/// copy deployment packs into `packs_dir` as `<pack_id>-<version>.gtpack`.
+pub fn import_bundle(bundle: &Path, cache_base: &Path, packs_dir: &Path) -> Result<ImportReport> {
+    let cache = PackCache::new(cache_base);
+    fs::create_dir_all(cache.dir())?;
+    let mut archive = tar::Archive::new(fs::File::open(bundle)?);
+    let mut index: Option<BundleIndex> = None;
+    let mut unpacked = BTreeSet::new();
+    let mut staged = BTreeSet::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes)?;
+        if name == BUNDLE_INDEX {
+            index = Some(serde_json::from_slice(&bytes).map_err(|err| {
+                DeployerError::Pack(format!("invalid {BUNDLE_INDEX} in bundle: {err}"))
+            })?);
+            continue;
+        }
+        let digest = bundle_entry_digest(&name)
+            .ok_or_else(|| DeployerError::Pack(format!("unexpected entry {name} in bundle")))?;
+        let actual = format!("sha256:{:x}", Sha256::digest(&bytes));
+        if actual != digest {
+            return Err(DeployerError::Pack(format!(
+                "bundle entry {name} has digest {actual}, expected {digest}"
+            )));
+        }
+        let path = cache.blob_path(&digest);
+        if !path.exists() {
+            fs::write(&path, &bytes)?;
+            staged.insert(digest);
+        }
+        unpacked.insert(name);
+    }
+    let index =
+        index.ok_or_else(|| DeployerError::Pack(format!("bundle has no {BUNDLE_INDEX}")))?;
+    if index.version != BUNDLE_VERSION {
+        return Err(DeployerError::Pack(format!(
+            "unsupported bundle version {} (expected {BUNDLE_VERSION})",
+            index.version
+        )));
+    }
+
+    let mut report = ImportReport::default();
+    let mut referenced = BTreeSet::new();
+    for pack in &index.packs {
+        if !unpacked.contains(&pack.path)
+            || bundle_entry_digest(&pack.path).as_deref() != Some(pack.digest.as_str())
+        {
+            return Err(DeployerError::Pack(format!(
+                "bundle is missing {} for {}@{}",
+                pack.path, pack.pack_id, pack.version
+            )));
+        }
+        let cached = cache.blob_path(&pack.digest);
+        if let Some(raw) = &pack.reference {
+            cache.record(&parse_oci_reference(raw)?, &pack.digest)?;
+            referenced.insert(pack.digest.clone());
+            report.cached.push(raw.clone());
+        }
+        if pack.kind == BundlePackKind::Deployment {
+            fs::create_dir_all(packs_dir)?;
+            let target = packs_dir.join(format!(
+                "{}-{}.gtpack",
+                pack.pack_id.replace(['/', '\\'], "_"),
+                pack.version.replace(['/', '\\'], "_")
+            ));
+            fs::copy(&cached, &target)?;
+            report.deployment_packs.push(target);
+        }
+    }
+    // Local deployment packs only needed the staging copy in the cache directory.
+    for digest in staged.difference(&referenced) {
+        let _ = fs::remove_file(cache.blob_path(digest));
+    }
+    Ok(report)
+}
+
+/// `sha256:<hex>` for a `packs/sha256-<hex>.gtpack` bundle entry; `None` for any other name.
+fn bundle_entry_digest(name: &str) -> Option<String> {
+    let hex = name
+        .strip_prefix("packs/sha256-")?
+        .strip_suffix(".gtpack")?;
+    (hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+        .then(|| format!("sha256:{}", hex.to_ascii_lowercase()))
+}
+
+fn append(builder: &mut tar::Builder<fs::File>, name: &str, bytes: &[u8]) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(bytes.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(0);
+    header.set_cksum();
+    builder.append_data(&mut header, name, bytes)?;
+    Ok(())
+}
@@ -0,0 +1,317 @@
+//! On-disk cache of `.gtpack` files resolved from `oci://` references.
+//!
+//! Packs live in `<bootstrap dir>/cache/` named by layer digest (`sha256-<hex>.gtpack`), and
+//! `index.json` maps each resolved reference (`host/repo:tag` or `host/repo@sha256:...`) to the
+//! digest it resolved to. Several references may share one file.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::oci::{OciReference, compute_sha256};
+use crate::error::{DeployerError, Result};
+
+const INDEX_FILE: &str = "index.json";
+
+/// Cache root used for a bootstrap state file: the directory holding it.
+pub fn cache_base_for_state(state_path: &Path) -> PathBuf {
+    state_path
+        .parent()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheIndex {
+    entries: BTreeMap<String, String>,
+}
+
+/// One index entry of the pack cache.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheEntry {
+    /// Reference key (`host/repo:tag` or `host/repo@sha256:...`).
+    pub reference: String,
+    pub digest: String,
+    pub path: PathBuf,
+    /// File size, or `None` when the cached file is missing.
+    pub size: Option<u64>,
+}
+
+/// What [`PackCache::prune`] removed.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PruneReport {
+    pub removed_entries: Vec<String>,
+    pub removed_files: Vec<PathBuf>,
+    pub freed_bytes: u64,
+}
+
+/// Inconsistency found by [`PackCache::verify`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheProblem {
+    /// The index points at a file that does not exist.
+    Missing { reference: String, path: PathBuf },
+    /// The file contents do not hash to the digest in its name.
+    DigestMismatch {
+        path: PathBuf,
+        expected: String,
+        actual: String,
+    },
+}
+
+impl fmt::Display for CacheProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CacheProblem::Missing { reference, path } => {
+                write!(f, "{reference}: cached file {} is missing", path.display())
+            }
+            CacheProblem::DigestMismatch {
+                path,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{}: digest {actual} does not match {expected}",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// Handle on the pack cache below a cache root.
+#[derive(Debug, Clone)]
+pub struct PackCache {
+    dir: PathBuf,
+}
+
+impl PackCache {
+    pub fn new(cache_base: &Path) -> Self {
+        Self {
+            dir: cache_base.join("cache"),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Path of the cached file for `digest` (which need not exist).
+    pub fn blob_path(&self, digest: &str) -> PathBuf {
+        self.dir
+            .join(format!("{}.gtpack", digest.replace(':', "-")))
+    }
+
+    /// Cached file for `reference`, if the index has it and the file exists.
+    pub fn lookup(&self, reference: &OciReference) -> Result<Option<PathBuf>> {
+        let index = self.load_index()?;
+        Ok(index
+            .entries
+            .get(&cache_key(reference))
+            .map(|digest| self.blob_path(digest))
+            .filter(|path| path.exists()))
+    }
+
+    /// Store `bytes` (already verified against `digest`) and record `reference` -> `digest`.
+    pub fn insert(&self, reference: &OciReference, digest: &str, bytes: &[u8]) -> Result<PathBuf> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.blob_path(digest);
+        write_atomic(&path, bytes)?;
+        self.record(reference, digest)?;
+        Ok(path)
+    }
+
+    /// Point `reference` at the already cached file for `digest`.
+    pub fn record(&self, reference: &OciReference, digest: &str) -> Result<()> {
+        let mut index = self.load_index()?;
+        index
+            .entries
+            .insert(cache_key(reference), digest.to_string());
+        self.save_index(&index)
+    }
+
+    pub fn list(&self) -> Result<Vec<CacheEntry>> {
+        let index = self.load_index()?;
+        Ok(index
+            .entries
+            .into_iter()
+            .map(|(reference, digest)| {
+                let path = self.blob_path(&digest);
+                let size = fs::metadata(&path).ok().map(|meta| meta.len());
+                CacheEntry {
+                    reference,
+                    digest,
+                    path,
+                    size,
+                }
+            })
+            .collect())
+    }
+
+    /// Drop index entries whose file is missing and files no entry points at. With `all`, empty
+    /// the cache.
+    pub fn prune(&self, all: bool) -> Result<PruneReport> {
+        let mut report = PruneReport::default();
+        let mut index = self.load_index()?;
+        index.entries.retain(|reference, digest| {
+            let keep = !all && self.blob_path(digest).exists();
+            if !keep {
+                report.removed_entries.push(reference.clone());
+            }
+            keep
+        });
+        let referenced: BTreeSet<PathBuf> = index
+            .entries
+            .values()
+            .map(|digest| self.blob_path(digest))
+            .collect();
+        for path in self.cached_files()? {
+            if referenced.contains(&path) {
+                continue;
+            }
+            report.freed_bytes += fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+            fs::remove_file(&path)?;
+            report.removed_files.push(path);
+        }
+        if !report.removed_entries.is_empty() {
+            self.save_index(&index)?;
+        }
+        Ok(report)
+    }
+
+    /// Re-hash every cached file and check the index points at existing files.
+    pub fn verify(&self) -> Result<Vec<CacheProblem>> {
+        let mut problems = Vec::new();
+        for entry in self.list()? {
+            if entry.size.is_none() {
+                problems.push(CacheProblem::Missing {
+                    reference: entry.reference,
+                    path: entry.path,
+                });
+            }
+        }
+        for path in self.cached_files()? {
+            let Some(expected) = digest_from_file_name(&path) else {
+                continue;
+            };
+            let actual = compute_sha256(&path)?.unwrap_or_default();
+            if actual != expected {
+                problems.push(CacheProblem::DigestMismatch {
+                    path,
+                    expected,
+                    actual,
+                });
+            }
+        }
+        Ok(problems)
+    }
+
+    fn cached_files(&self) -> Result<Vec<PathBuf>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+        let mut files = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) == Some("gtpack") {
+                files.push(path);
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    fn load_index(&self) -> Result<CacheIndex> {
+        match fs::read(self.dir.join(INDEX_FILE)) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|err| DeployerError::Other(format!("cache index parse error: {err}"))),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(CacheIndex::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn save_index(&self, index: &CacheIndex) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let content = serde_json::to_string_pretty(index)
+            .map_err(|err| DeployerError::Other(format!("cache index encode error: {err}")))?;
+        write_atomic(&self.dir.join(INDEX_FILE), content.as_bytes())
+    }
+}
+
+/// Index key for `reference`: `host/repo@digest` when pinned, otherwise `host/repo:tag`.
+pub fn cache_key(reference: &OciReference) -> String {
+    match &reference.digest {
+        Some(digest) => format!("{}/{}@{}", reference.host, reference.repository, digest),
+        None => format!(
+            "{}/{}:{}",
+            reference.host, reference.repository, reference.tag
+        ),
+    }
+}
+
+/// `sha256:<hex>` for a cache file named `sha256-<hex>.gtpack`.
+fn digest_from_file_name(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_str()?;
+    let hex = stem.strip_prefix("sha256-")?;
+    (hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit())).then(|| format!("sha256:{hex}"))
+}
+
+fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    fs::write(&tmp, bytes)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::oci::parse_oci_reference;
+    use tempfile::tempdir;
+
+    fn digest(bytes: &[u8]) -> String {
+        use sha2::{Digest, Sha256};
+        format!("sha256:{:x}", Sha256::digest(bytes))
+    }
+
+    #[test]
+    fn prunes_dangling_entries_and_unreferenced_files_and_verifies_digests() {
+        let dir = tempdir().unwrap();
+        let cache = PackCache::new(dir.path());
+        let kept = parse_oci_reference("oci://r.local/platform:1.0").unwrap();
+        let dangling = parse_oci_reference("oci://r.local/platform:0.9").unwrap();
+        let path = cache.insert(&kept, &digest(b"pack"), b"pack").unwrap();
+        cache.record(&dangling, &digest(b"gone")).unwrap();
+        let orphan = cache.blob_path(&digest(b"orphan"));
+        fs::write(&orphan, b"orphan").unwrap();
+
+        assert_eq!(cache.lookup(&kept).unwrap(), Some(path.clone()));
+        assert_eq!(cache.lookup(&dangling).unwrap(), None);
+        let listed = cache.list().unwrap();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[1].size, Some(4));
+
+        let report = cache.prune(false).unwrap();
+        assert_eq!(report.removed_entries, vec!["r.local/platform:0.9"]);
+        assert_eq!(report.removed_files, vec![orphan]);
+        assert_eq!(report.freed_bytes, 6);
+        assert!(cache.verify().unwrap().is_empty());
+
+        fs::write(&path, b"tampered").unwrap();
+        let problems = cache.verify().unwrap();
+        assert!(matches!(
+            problems.as_slice(),
+            [CacheProblem::DigestMismatch { .. }]
+        ));
+
+        let report = cache.prune(true).unwrap();
+        assert_eq!(report.removed_entries, vec!["r.local/platform:1.0"]);
+        assert!(cache.list().unwrap().is_empty());
+        assert!(!path.exists());
+    }
+}
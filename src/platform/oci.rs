@@ -1,22 +1,52 @@
+//! Resolve `oci://` platform pack references into cached `.gtpack` files.
+//!
+//! References look like `oci://<host>/<repository>[:<tag>][@sha256:<hex>]`. Image indexes are
+//! followed to the entry carrying the greentic pack artifact type (or the host platform), and the
+//! pack layer is chosen by media type, artifact type or title annotation. Registries asking for
+//! credentials get them from the Docker `config.json`, including the bearer-token exchange
+//! announced by a `WWW-Authenticate` challenge.
+
 use std::collections::HashMap;
 use std::fs;
-use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use std::time::Duration;
 
-use reqwest::blocking::Client;
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE, LOCATION, WWW_AUTHENTICATE};
 use reqwest::redirect::Policy;
+use reqwest::{Method, StatusCode};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
+use super::cache::PackCache;
+use crate::bootstrap::blocking::off_runtime;
 use crate::bootstrap::network::NetworkPolicy;
 use crate::error::{DeployerError, Result};
+use crate::pack_introspect::read_manifest_from_gtpack;
+
+/// Media type (and OCI artifact type) of a greentic pack.
+pub const PACK_ARTIFACT_TYPE: &str = "application/vnd.greentic.pack.v1+gtpack";
 
-const OCI_ACCEPT: &str = "application/vnd.oci.image.manifest.v1+json,application/vnd.docker.distribution.manifest.v2+json";
-const PACK_MEDIA_TYPES: &[&str] = &[
-    "application/vnd.greentic.pack.v1+gtpack",
-    "application/octet-stream",
+const OCI_ACCEPT: &str = "application/vnd.oci.image.index.v1+json,application/vnd.docker.distribution.manifest.list.v2+json,application/vnd.oci.image.manifest.v1+json,application/vnd.docker.distribution.manifest.v2+json";
+const INDEX_MEDIA_TYPES: &[&str] = &[
+    "application/vnd.oci.image.index.v1+json",
+    "application/vnd.docker.distribution.manifest.list.v2+json",
 ];
+const TITLE_ANNOTATION: &str = "org.opencontainers.image.title";
+/// Registries that do not accept a connection within this are treated as unreachable.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Upper bound for a whole request, sized for multi-hundred-megabyte blob transfers.
+const TRANSFER_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+const IMAGE_MANIFEST_MEDIA_TYPE: &str = "application/vnd.oci.image.manifest.v1+json";
+/// Empty config blob (`{}`) used for artifact manifests.
+const EMPTY_CONFIG_MEDIA_TYPE: &str = "application/vnd.oci.empty.v1+json";
+const EMPTY_CONFIG: &[u8] = b"{}";
+/// Manifest annotations recording the pushed pack's identity.
+pub const PACK_ID_ANNOTATION: &str = "dev.greentic.pack.id";
+pub const PACK_VERSION_ANNOTATION: &str = "dev.greentic.pack.version";
+pub const PACK_PUBLISHER_ANNOTATION: &str = "dev.greentic.pack.publisher";
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct OciReference {
@@ -24,25 +54,62 @@ pub struct OciReference {
     pub host: String,
     pub repository: String,
     pub tag: String,
+    /// Manifest digest pinned with `@sha256:<hex>`; takes precedence over the tag.
+    pub digest: Option<String>,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
-struct CacheIndex {
-    entries: HashMap<String, String>,
+impl OciReference {
+    /// Tag or digest used to fetch the manifest.
+    pub fn manifest_reference(&self) -> &str {
+        self.digest.as_deref().unwrap_or(&self.tag)
+    }
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+/// Image manifest or image index; only the fields needed to locate the pack are kept.
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
 struct OciManifest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    schema_version: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    media_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    artifact_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    config: Option<OciDescriptor>,
     #[serde(default)]
-    layers: Vec<OciLayer>,
+    layers: Vec<OciDescriptor>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    manifests: Vec<OciDescriptor>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    annotations: HashMap<String, String>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
-struct OciLayer {
-    #[serde(rename = "mediaType")]
+#[serde(rename_all = "camelCase")]
+struct OciDescriptor {
     media_type: String,
     digest: String,
     size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    artifact_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    platform: Option<OciPlatform>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    annotations: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+struct OciPlatform {
+    architecture: String,
+    os: String,
+}
+
+/// Raw manifest response: body, `Content-Type` and `Docker-Content-Digest`.
+struct FetchedManifest {
+    bytes: Vec<u8>,
+    media_type: Option<String>,
+    digest: Option<String>,
 }
 
 pub fn parse_oci_reference(raw: &str) -> Result<OciReference> {
@@ -60,16 +127,36 @@ pub fn parse_oci_reference(raw: &str) -> Result<OciReference> {
             "oci reference missing host or repository".into(),
         ));
     }
+    let (path, digest) = match path.split_once('@') {
+        Some((path, digest)) => {
+            let valid = digest
+                .strip_prefix("sha256:")
+                .is_some_and(|hex| hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()));
+            if !valid {
+                return Err(DeployerError::Pack(format!(
+                    "oci reference digest must be sha256:<64 hex chars>, got {digest}"
+                )));
+            }
+            (path, Some(digest.to_ascii_lowercase()))
+        }
+        None => (path, None),
+    };
     let (repository, tag) = if let Some((repo, tag)) = path.rsplit_once(':') {
         (repo.to_string(), tag.to_string())
     } else {
         (path.to_string(), "latest".to_string())
     };
+    if repository.is_empty() || tag.is_empty() {
+        return Err(DeployerError::Pack(
+            "oci reference missing repository or tag".into(),
+        ));
+    }
     Ok(OciReference {
         raw: raw.to_string(),
         host: host.to_string(),
         repository,
         tag,
+        digest,
     })
 }
 
@@ -81,15 +168,144 @@ pub fn compute_sha256(path: &Path) -> Result<Option<String>> {
     Ok(Some(format!("sha256:{:x}", bytes)))
 }
 
+/// Resolve `raw` with credentials from the Docker config (see [`docker_config_path`]).
 pub fn resolve_oci_pack(
     raw: &str,
     cache_base: &Path,
     network_policy: &NetworkPolicy,
+) -> Result<PathBuf> {
+    resolve_oci_pack_with_auth(
+        raw,
+        cache_base,
+        network_policy,
+        docker_config_path().as_deref(),
+    )
+}
+
+/// Resolve `raw`, reading registry credentials from the Docker `config.json` at `auth_file`.
+pub fn resolve_oci_pack_with_auth(
+    raw: &str,
+    cache_base: &Path,
+    network_policy: &NetworkPolicy,
+    auth_file: Option<&Path>,
 ) -> Result<PathBuf> {
     let reference = parse_oci_reference(raw)?;
+    // A cached (or bundle-imported) pack needs no network, so offline-only installs can use it.
+    if let Some(cached) = PackCache::new(cache_base).lookup(&reference)? {
+        return Ok(cached);
+    }
     network_policy.enforce(&reference.host)?;
-    let fetcher = HttpOciFetcher::new()?;
-    resolve_with_fetcher_internal(reference, cache_base, &fetcher)
+    let credentials = match auth_file {
+        Some(path) => load_credentials(path, &reference.host)?,
+        None => None,
+    };
+    off_runtime(|| {
+        let fetcher = RegistryClient::new(network_policy.clone(), credentials)?;
+        resolve_with_fetcher_internal(reference, cache_base, &fetcher)
+    })
+}
+
+/// Result of [`push_oci_pack`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushedPack {
+    /// Tag reference the manifest was pushed to.
+    pub reference: String,
+    /// `oci://<host>/<repo>@<manifest digest>`, pinning this exact push.
+    pub digest_reference: String,
+    pub manifest_digest: String,
+    /// Digest of the `.gtpack` layer blob.
+    pub layer_digest: String,
+    pub size: u64,
+}
+
+/// Upload `gtpack` and an artifact manifest (artifact type and layer media type
+/// [`PACK_ARTIFACT_TYPE`], annotated with the pack id, version and publisher) to the tag `raw`.
+pub fn push_oci_pack(
+    gtpack: &Path,
+    raw: &str,
+    network_policy: &NetworkPolicy,
+    auth_file: Option<&Path>,
+) -> Result<PushedPack> {
+    let reference = parse_oci_reference(raw)?;
+    if reference.digest.is_some() {
+        return Err(DeployerError::Pack(format!(
+            "cannot push to digest reference {raw}; use oci://<host>/<repo>:<tag>"
+        )));
+    }
+    network_policy.enforce(&reference.host)?;
+    let manifest = read_manifest_from_gtpack(gtpack)?;
+    let pack = fs::read(gtpack)?;
+    let credentials = match auth_file {
+        Some(path) => load_credentials(path, &reference.host)?,
+        None => None,
+    };
+
+    let layer_digest = sha256_bytes(&pack);
+    let file_name = gtpack
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "pack.gtpack".to_string());
+    let layer = OciDescriptor {
+        media_type: PACK_ARTIFACT_TYPE.to_string(),
+        digest: layer_digest.clone(),
+        size: Some(pack.len() as u64),
+        artifact_type: None,
+        platform: None,
+        annotations: HashMap::from([(TITLE_ANNOTATION.to_string(), file_name)]),
+    };
+    let config = OciDescriptor {
+        media_type: EMPTY_CONFIG_MEDIA_TYPE.to_string(),
+        digest: sha256_bytes(EMPTY_CONFIG),
+        size: Some(EMPTY_CONFIG.len() as u64),
+        artifact_type: None,
+        platform: None,
+        annotations: HashMap::new(),
+    };
+    let oci_manifest = OciManifest {
+        schema_version: Some(2),
+        media_type: Some(IMAGE_MANIFEST_MEDIA_TYPE.to_string()),
+        artifact_type: Some(PACK_ARTIFACT_TYPE.to_string()),
+        config: Some(config.clone()),
+        layers: vec![layer.clone()],
+        manifests: Vec::new(),
+        annotations: HashMap::from([
+            (PACK_ID_ANNOTATION.to_string(), manifest.pack_id.to_string()),
+            (
+                PACK_VERSION_ANNOTATION.to_string(),
+                manifest.version.to_string(),
+            ),
+            (PACK_PUBLISHER_ANNOTATION.to_string(), manifest.publisher),
+        ]),
+    };
+    let manifest_bytes = serde_json::to_vec(&oci_manifest)?;
+
+    off_runtime(|| {
+        let client = RegistryClient::new(network_policy.clone(), credentials)?;
+        client.upload_blob(&reference, &config.digest, EMPTY_CONFIG.to_vec())?;
+        client.upload_blob(&reference, &layer.digest, pack)?;
+        client.put_manifest(&reference, manifest_bytes)
+    })
+    .map(|manifest_digest| PushedPack {
+        reference: format!(
+            "oci://{}/{}:{}",
+            reference.host, reference.repository, reference.tag
+        ),
+        digest_reference: format!(
+            "oci://{}/{}@{manifest_digest}",
+            reference.host, reference.repository
+        ),
+        manifest_digest,
+        layer_digest,
+        size: layer.size.unwrap_or_default(),
+    })
+}
+
+/// `$DOCKER_CONFIG/config.json`, falling back to `~/.docker/config.json`.
+pub fn docker_config_path() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("DOCKER_CONFIG") {
+        return Some(PathBuf::from(dir).join("config.json"));
+    }
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".docker/config.json"))
 }
 
 #[cfg(test)]
@@ -109,141 +325,567 @@ fn resolve_with_fetcher_internal<F: OciFetcher>(
     cache_base: &Path,
     fetcher: &F,
 ) -> Result<PathBuf> {
-    let cache_dir = cache_base.join("cache");
-    fs::create_dir_all(&cache_dir)?;
-    let index_path = cache_dir.join("index.json");
-    let mut index = load_index(&index_path)?;
-    let key = cache_key(&reference);
-
-    if let Some(digest) = index.entries.get(&key) {
-        let cached = cache_dir.join(format!("{}.gtpack", digest.replace(':', "-")));
-        if cached.exists() {
-            return Ok(cached);
-        }
+    let cache = PackCache::new(cache_base);
+    if let Some(cached) = cache.lookup(&reference)? {
+        return Ok(cached);
     }
 
-    let (manifest, manifest_digest, manifest_bytes) = fetcher.fetch_manifest(&reference)?;
-    if let Some(expected) = manifest_digest.as_deref() {
-        verify_digest(expected, &manifest_bytes, "manifest")?;
-    }
-    let layer = select_pack_layer(&manifest)
-        .ok_or_else(|| DeployerError::Other("oci manifest does not contain any layers".into()))?;
+    let manifest = fetch_pack_manifest(&reference, fetcher)?;
+    let layer = select_pack_layer(&manifest)?;
 
     let bytes = fetcher.fetch_blob(&reference, &layer.digest)?;
     verify_digest(&layer.digest, &bytes, "pack blob")?;
 
-    let digest = layer.digest.clone();
-    let file_name = format!("{}.gtpack", digest.replace(':', "-"));
-    let path = cache_dir.join(file_name);
-    fs::write(&path, bytes)?;
+    cache.insert(&reference, &layer.digest, &bytes)
+}
+
+/// Fetch and verify the manifest for `reference`, descending through an image index.
+fn fetch_pack_manifest<F: OciFetcher>(
+    reference: &OciReference,
+    fetcher: &F,
+) -> Result<OciManifest> {
+    let fetched = fetcher.fetch_manifest(reference, reference.manifest_reference())?;
+    if let Some(expected) = reference.digest.as_deref().or(fetched.digest.as_deref()) {
+        verify_digest(expected, &fetched.bytes, "manifest")?;
+    }
+    let manifest = parse_manifest(&fetched)?;
+    if !is_index(&manifest, &fetched) {
+        return Ok(manifest);
+    }
+
+    let entry = select_index_entry(&manifest)?;
+    let child = fetcher.fetch_manifest(reference, &entry.digest)?;
+    verify_digest(&entry.digest, &child.bytes, "manifest")?;
+    let child_manifest = parse_manifest(&child)?;
+    if is_index(&child_manifest, &child) {
+        return Err(DeployerError::Other(format!(
+            "oci index entry {} is itself an index; nested indexes are not supported",
+            entry.digest
+        )));
+    }
+    Ok(child_manifest)
+}
+
+fn parse_manifest(fetched: &FetchedManifest) -> Result<OciManifest> {
+    serde_json::from_slice(&fetched.bytes)
+        .map_err(|err| DeployerError::Other(format!("invalid OCI manifest: {err}")))
+}
+
+fn is_index(manifest: &OciManifest, fetched: &FetchedManifest) -> bool {
+    let media_type = manifest
+        .media_type
+        .as_deref()
+        .or(fetched.media_type.as_deref())
+        .unwrap_or_default();
+    INDEX_MEDIA_TYPES.iter().any(|t| media_type.starts_with(t))
+        || (!manifest.manifests.is_empty() && manifest.layers.is_empty())
+}
 
-    index.entries.insert(key, digest.clone());
-    save_index(&index_path, &index)?;
+/// Pick the index entry for the pack: the greentic artifact type first, then the host platform,
+/// then a platform-independent entry.
+fn select_index_entry(index: &OciManifest) -> Result<&OciDescriptor> {
+    let (os, arch) = host_platform();
+    index
+        .manifests
+        .iter()
+        .find(|entry| entry.artifact_type.as_deref() == Some(PACK_ARTIFACT_TYPE))
+        .or_else(|| {
+            index.manifests.iter().find(|entry| {
+                entry
+                    .platform
+                    .as_ref()
+                    .is_some_and(|p| p.os == os && p.architecture == arch)
+            })
+        })
+        .or_else(|| {
+            index
+                .manifests
+                .iter()
+                .find(|entry| entry.platform.is_none())
+        })
+        .ok_or_else(|| {
+            let platforms: Vec<String> = index
+                .manifests
+                .iter()
+                .filter_map(|entry| entry.platform.as_ref())
+                .map(|p| format!("{}/{}", p.os, p.architecture))
+                .collect();
+            DeployerError::Other(format!(
+                "oci index has no greentic pack entry or {os}/{arch} manifest (platforms: {})",
+                if platforms.is_empty() {
+                    "none".to_string()
+                } else {
+                    platforms.join(", ")
+                }
+            ))
+        })
+}
+
+/// OCI platform names for the running host.
+fn host_platform() -> (&'static str, &'static str) {
+    let os = match std::env::consts::OS {
+        "macos" => "darwin",
+        other => other,
+    };
+    let arch = match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        "x86" => "386",
+        other => other,
+    };
+    (os, arch)
+}
 
-    Ok(path)
+/// Pick the pack layer: the greentic pack media type, then a `.gtpack` title annotation, then
+/// the only layer of a manifest whose artifact (or config) type marks it as a pack.
+fn select_pack_layer(manifest: &OciManifest) -> Result<&OciDescriptor> {
+    if let Some(layer) = manifest
+        .layers
+        .iter()
+        .find(|layer| layer.media_type == PACK_ARTIFACT_TYPE)
+    {
+        return Ok(layer);
+    }
+    if let Some(layer) = manifest.layers.iter().find(|layer| {
+        layer
+            .annotations
+            .get(TITLE_ANNOTATION)
+            .is_some_and(|title| title.ends_with(".gtpack"))
+    }) {
+        return Ok(layer);
+    }
+    let pack_artifact = manifest.artifact_type.as_deref() == Some(PACK_ARTIFACT_TYPE)
+        || manifest
+            .config
+            .as_ref()
+            .is_some_and(|config| config.media_type == PACK_ARTIFACT_TYPE);
+    match manifest.layers.as_slice() {
+        [layer] if pack_artifact || layer.media_type == "application/octet-stream" => Ok(layer),
+        [] => Err(DeployerError::Other(
+            "oci manifest does not contain any layers".into(),
+        )),
+        layers => Err(DeployerError::Other(format!(
+            "oci manifest has no {PACK_ARTIFACT_TYPE} layer (layer media types: {})",
+            layers
+                .iter()
+                .map(|layer| layer.media_type.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))),
+    }
 }
 
 trait OciFetcher {
+    /// Fetch the manifest stored under `tag_or_digest` in `reference`'s repository.
     fn fetch_manifest(
         &self,
         reference: &OciReference,
-    ) -> Result<(OciManifest, Option<String>, Vec<u8>)>;
+        tag_or_digest: &str,
+    ) -> Result<FetchedManifest>;
     fn fetch_blob(&self, reference: &OciReference, digest: &str) -> Result<Vec<u8>>;
 }
 
-struct HttpOciFetcher {
+/// Registry credentials from a Docker `config.json` `auths` entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Credentials {
+    Basic {
+        username: String,
+        password: String,
+    },
+    /// `registrytoken`: sent as a bearer token as-is.
+    Token(String),
+}
+
+#[derive(Debug, Deserialize)]
+struct DockerConfig {
+    #[serde(default)]
+    auths: HashMap<String, DockerAuth>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DockerAuth {
+    auth: Option<String>,
+    username: Option<String>,
+    password: Option<String>,
+    registrytoken: Option<String>,
+}
+
+fn load_credentials(path: &Path, host: &str) -> Result<Option<Credentials>> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    let config: DockerConfig = serde_json::from_slice(&bytes).map_err(|err| {
+        DeployerError::Config(format!("invalid docker config {}: {err}", path.display()))
+    })?;
+    let Some(auth) = config
+        .auths
+        .iter()
+        .find(|(key, _)| auth_host(key) == docker_hub_alias(host))
+        .map(|(_, auth)| auth)
+    else {
+        return Ok(None);
+    };
+    if let Some(token) = &auth.registrytoken {
+        return Ok(Some(Credentials::Token(token.clone())));
+    }
+    if let Some(encoded) = &auth.auth {
+        let decoded = STANDARD
+            .decode(encoded.trim())
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .ok_or_else(|| {
+                DeployerError::Config(format!("invalid auth entry for {host} in docker config"))
+            })?;
+        let (username, password) = decoded.split_once(':').ok_or_else(|| {
+            DeployerError::Config(format!("auth entry for {host} is not user:password"))
+        })?;
+        return Ok(Some(Credentials::Basic {
+            username: username.to_string(),
+            password: password.to_string(),
+        }));
+    }
+    Ok(match (&auth.username, &auth.password) {
+        (Some(username), Some(password)) => Some(Credentials::Basic {
+            username: username.clone(),
+            password: password.clone(),
+        }),
+        _ => None,
+    })
+}
+
+/// Host part of a Docker config `auths` key (`https://index.docker.io/v1/` -> `index.docker.io`).
+fn auth_host(key: &str) -> &str {
+    let key = key.split_once("://").map_or(key, |(_, rest)| rest);
+    key.split('/').next().unwrap_or_default()
+}
+
+/// Docker Hub stores its credentials under `index.docker.io`.
+fn docker_hub_alias(host: &str) -> &str {
+    match host {
+        "docker.io" | "registry-1.docker.io" => "index.docker.io",
+        other => other,
+    }
+}
+
+struct RegistryClient {
     client: Client,
+    network_policy: NetworkPolicy,
+    credentials: Option<Credentials>,
+    /// `Authorization` value negotiated for this registry, reused for later requests.
+    authorization: Mutex<Option<String>>,
 }
 
-impl HttpOciFetcher {
-    fn new() -> Result<Self> {
+impl RegistryClient {
+    fn new(network_policy: NetworkPolicy, credentials: Option<Credentials>) -> Result<Self> {
         let client = Client::builder()
-            .timeout(Duration::from_secs(10))
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(TRANSFER_TIMEOUT)
             .redirect(Policy::none())
             .build()
             .map_err(|err| DeployerError::Other(err.to_string()))?;
-        Ok(Self { client })
+        let authorization = match &credentials {
+            Some(Credentials::Token(token)) => Some(format!("Bearer {token}")),
+            _ => None,
+        };
+        Ok(Self {
+            client,
+            network_policy,
+            credentials,
+            authorization: Mutex::new(authorization),
+        })
+    }
+
+    /// GET `url`, answering a 401 challenge once with Basic credentials or a bearer token.
+    fn get(&self, url: &str, accept: Option<&str>) -> Result<Response> {
+        self.send(Method::GET, url, |request| match accept {
+            Some(accept) => request.header(ACCEPT, accept),
+            None => request,
+        })
+    }
+
+    /// Send a request built by `build`, retrying once with credentials after a 401 challenge.
+    fn send(
+        &self,
+        method: Method,
+        url: &str,
+        build: impl Fn(RequestBuilder) -> RequestBuilder,
+    ) -> Result<Response> {
+        let send = |authorization: Option<&str>| {
+            let mut request = build(self.client.request(method.clone(), url));
+            if let Some(authorization) = authorization {
+                request = request.header(AUTHORIZATION, authorization);
+            }
+            request
+                .send()
+                .map_err(|err| DeployerError::Other(format!("{method} {url} failed: {err}")))
+        };
+        let cached = self.authorization.lock().unwrap().clone();
+        let response = send(cached.as_deref())?;
+        if response.status() != StatusCode::UNAUTHORIZED {
+            return Ok(response);
+        }
+        let challenge = response
+            .headers()
+            .get(WWW_AUTHENTICATE)
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| {
+                DeployerError::Other(format!("{url} returned 401 without an auth challenge"))
+            })?
+            .to_string();
+        let authorization = self.authorize(&challenge)?;
+        *self.authorization.lock().unwrap() = Some(authorization.clone());
+        send(Some(&authorization))
+    }
+
+    fn authorize(&self, challenge: &str) -> Result<String> {
+        let (scheme, params) = parse_challenge(challenge);
+        let basic = match &self.credentials {
+            Some(Credentials::Basic { username, password }) => Some((username, password)),
+            _ => None,
+        };
+        match scheme.to_ascii_lowercase().as_str() {
+            "basic" => {
+                let (username, password) = basic.ok_or_else(|| {
+                    DeployerError::Other(
+                        "registry requires credentials; add them to the docker config".into(),
+                    )
+                })?;
+                Ok(format!(
+                    "Basic {}",
+                    STANDARD.encode(format!("{username}:{password}"))
+                ))
+            }
+            "bearer" => {
+                let realm = params.get("realm").ok_or_else(|| {
+                    DeployerError::Other(format!("bearer challenge without realm: {challenge}"))
+                })?;
+                self.network_policy.enforce(realm)?;
+                let query: Vec<(&str, &String)> = ["service", "scope"]
+                    .into_iter()
+                    .filter_map(|key| params.get(key).map(|value| (key, value)))
+                    .collect();
+                let mut request = self.client.get(realm.as_str()).query(&query);
+                if let Some((username, password)) = basic {
+                    request = request.basic_auth(username, Some(password));
+                }
+                let response = request.send().map_err(|err| {
+                    DeployerError::Other(format!("token request to {realm} failed: {err}"))
+                })?;
+                if !response.status().is_success() {
+                    return Err(DeployerError::Other(format!(
+                        "token request to {realm} failed with status {}",
+                        response.status()
+                    )));
+                }
+                let token: TokenResponse = response.json().map_err(|err| {
+                    DeployerError::Other(format!("invalid token response from {realm}: {err}"))
+                })?;
+                let token = token.token.or(token.access_token).ok_or_else(|| {
+                    DeployerError::Other(format!("token response from {realm} has no token"))
+                })?;
+                Ok(format!("Bearer {token}"))
+            }
+            other => Err(DeployerError::Other(format!(
+                "unsupported registry auth scheme {other}"
+            ))),
+        }
     }
 }
 
-impl OciFetcher for HttpOciFetcher {
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    token: Option<String>,
+    access_token: Option<String>,
+}
+
+/// Split `Bearer realm="...",service="...",scope="..."` into its scheme and parameters.
+fn parse_challenge(challenge: &str) -> (String, HashMap<String, String>) {
+    let (scheme, rest) = challenge
+        .trim()
+        .split_once(' ')
+        .unwrap_or((challenge.trim(), ""));
+    let mut params = HashMap::new();
+    let mut chars = rest.chars().peekable();
+    loop {
+        let key: String = chars
+            .by_ref()
+            .skip_while(|c| *c == ',' || c.is_whitespace())
+            .take_while(|c| *c != '=')
+            .collect();
+        if key.is_empty() {
+            break;
+        }
+        let value: String = if chars.peek() == Some(&'"') {
+            chars.next();
+            chars.by_ref().take_while(|c| *c != '"').collect()
+        } else {
+            chars.by_ref().take_while(|c| *c != ',').collect()
+        };
+        params.insert(key.trim().to_ascii_lowercase(), value);
+    }
+    (scheme.to_string(), params)
+}
+
+impl OciFetcher for RegistryClient {
     fn fetch_manifest(
         &self,
         reference: &OciReference,
-    ) -> Result<(OciManifest, Option<String>, Vec<u8>)> {
-        fetch_manifest_http(reference, &self.client)
+        tag_or_digest: &str,
+    ) -> Result<FetchedManifest> {
+        let url = format!(
+            "{}/v2/{}/manifests/{}",
+            registry_base(reference),
+            reference.repository,
+            tag_or_digest
+        );
+        let response = self.get(&url, Some(OCI_ACCEPT))?;
+        if !response.status().is_success() {
+            return Err(DeployerError::Other(format!(
+                "fetch {url} failed with status {}",
+                response.status()
+            )));
+        }
+        let header = |name: &str| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+        let digest = header("docker-content-digest");
+        let media_type = header(CONTENT_TYPE.as_str());
+        let bytes = response
+            .bytes()
+            .map_err(|err| DeployerError::Other(format!("failed to read manifest body: {err}")))?
+            .to_vec();
+        Ok(FetchedManifest {
+            bytes,
+            media_type,
+            digest,
+        })
     }
 
     fn fetch_blob(&self, reference: &OciReference, digest: &str) -> Result<Vec<u8>> {
-        fetch_blob_http(reference, digest, &self.client)
+        let url = format!(
+            "{}/v2/{}/blobs/{}",
+            registry_base(reference),
+            reference.repository,
+            digest
+        );
+        let mut response = self.get(&url, None)?;
+        // Registries commonly redirect blob downloads to object storage; follow once without
+        // the registry credentials.
+        if response.status().is_redirection() {
+            let location = response
+                .headers()
+                .get(LOCATION)
+                .and_then(|v| v.to_str().ok())
+                .ok_or_else(|| {
+                    DeployerError::Other(format!("{url} redirected without Location"))
+                })?;
+            let target = response
+                .url()
+                .join(location)
+                .map_err(|err| DeployerError::Other(format!("invalid blob redirect: {err}")))?;
+            self.network_policy.enforce(target.as_str())?;
+            response = self.client.get(target.clone()).send().map_err(|err| {
+                DeployerError::Other(format!("failed to fetch OCI blob from {target}: {err}"))
+            })?;
+        }
+        if !response.status().is_success() {
+            return Err(DeployerError::Other(format!(
+                "fetch {url} failed with status {}",
+                response.status()
+            )));
+        }
+        response
+            .bytes()
+            .map(|b| b.to_vec())
+            .map_err(|err| DeployerError::Other(format!("failed to read blob body: {err}")))
     }
 }
 
-fn fetch_manifest_http(
-    reference: &OciReference,
-    client: &Client,
-) -> Result<(OciManifest, Option<String>, Vec<u8>)> {
-    let url = format!(
-        "{}/v2/{}/manifests/{}",
-        registry_base(reference),
-        reference.repository,
-        reference.tag
-    );
-    let response = client
-        .get(&url)
-        .header(reqwest::header::ACCEPT, OCI_ACCEPT)
-        .send()
-        .map_err(|err| {
-            DeployerError::Other(format!("failed to fetch OCI manifest from {url}: {err}"))
+impl RegistryClient {
+    /// Upload `bytes` as blob `digest` unless the registry already has it (monolithic upload).
+    fn upload_blob(&self, reference: &OciReference, digest: &str, bytes: Vec<u8>) -> Result<()> {
+        let base = registry_base(reference);
+        let blob_url = format!("{base}/v2/{}/blobs/{digest}", reference.repository);
+        if self
+            .send(Method::HEAD, &blob_url, |r| r)?
+            .status()
+            .is_success()
+        {
+            return Ok(());
+        }
+
+        let start_url = format!("{base}/v2/{}/blobs/uploads/", reference.repository);
+        let response = self.send(Method::POST, &start_url, |r| r)?;
+        if response.status() != StatusCode::ACCEPTED {
+            return Err(DeployerError::Other(format!(
+                "starting blob upload at {start_url} failed with status {}",
+                response.status()
+            )));
+        }
+        let location = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| {
+                DeployerError::Other(format!("blob upload at {start_url} returned no Location"))
+            })?;
+        let mut upload_url = response
+            .url()
+            .join(location)
+            .map_err(|err| DeployerError::Other(format!("invalid blob upload location: {err}")))?;
+        self.network_policy.enforce(upload_url.as_str())?;
+        upload_url.query_pairs_mut().append_pair("digest", digest);
+
+        let response = self.send(Method::PUT, upload_url.as_str(), |r| {
+            r.header(CONTENT_TYPE, "application/octet-stream")
+                .body(bytes.clone())
         })?;
-    if !response.status().is_success() {
-        return Err(DeployerError::Other(format!(
-            "fetch {url} failed with status {}",
-            response.status()
-        )));
+        if response.status() != StatusCode::CREATED {
+            return Err(DeployerError::Other(format!(
+                "uploading blob {digest} failed with status {}",
+                response.status()
+            )));
+        }
+        Ok(())
     }
-    let digest_header = response
-        .headers()
-        .get("docker-content-digest")
-        .and_then(|v| v.to_str().ok())
-        .map(str::to_string);
-    let bytes = response
-        .bytes()
-        .map_err(|err| DeployerError::Other(format!("failed to read manifest body: {err}")))?
-        .to_vec();
-    let manifest: OciManifest = serde_json::from_slice(&bytes)
-        .map_err(|err| DeployerError::Other(format!("invalid OCI manifest from {url}: {err}")))?;
-    Ok((manifest, digest_header, bytes))
-}
-
-fn select_pack_layer(manifest: &OciManifest) -> Option<&OciLayer> {
-    manifest
-        .layers
-        .iter()
-        .find(|layer| PACK_MEDIA_TYPES.contains(&layer.media_type.as_str()))
-        .or_else(|| manifest.layers.first())
-}
-
-fn fetch_blob_http(reference: &OciReference, digest: &str, client: &Client) -> Result<Vec<u8>> {
-    let url = format!(
-        "{}/v2/{}/blobs/{}",
-        registry_base(reference),
-        reference.repository,
-        digest
-    );
-    let response = client.get(&url).send().map_err(|err| {
-        DeployerError::Other(format!("failed to fetch OCI blob from {url}: {err}"))
-    })?;
-    if !response.status().is_success() {
-        return Err(DeployerError::Other(format!(
-            "fetch {url} failed with status {}",
-            response.status()
-        )));
+
+    /// Put an image manifest under `reference`'s tag and return its digest.
+    fn put_manifest(&self, reference: &OciReference, bytes: Vec<u8>) -> Result<String> {
+        let url = format!(
+            "{}/v2/{}/manifests/{}",
+            registry_base(reference),
+            reference.repository,
+            reference.tag
+        );
+        let digest = sha256_bytes(&bytes);
+        let response = self.send(Method::PUT, &url, |r| {
+            r.header(CONTENT_TYPE, IMAGE_MANIFEST_MEDIA_TYPE)
+                .body(bytes.clone())
+        })?;
+        if response.status() != StatusCode::CREATED {
+            return Err(DeployerError::Other(format!(
+                "pushing manifest to {url} failed with status {}",
+                response.status()
+            )));
+        }
+        if let Some(returned) = response
+            .headers()
+            .get("docker-content-digest")
+            .and_then(|v| v.to_str().ok())
+            && returned != digest
+        {
+            return Err(DeployerError::Other(format!(
+                "registry stored manifest as {returned}, expected {digest}"
+            )));
+        }
+        Ok(digest)
     }
-    response
-        .bytes()
-        .map(|b| b.to_vec())
-        .map_err(|err| DeployerError::Other(format!("failed to read blob body: {err}")))
 }
 
 fn registry_base(reference: &OciReference) -> String {
@@ -274,33 +916,10 @@ fn sha256_bytes(bytes: &[u8]) -> String {
     format!("sha256:{:x}", hasher.finalize())
 }
 
-fn cache_key(reference: &OciReference) -> String {
-    format!(
-        "{}/{}:{}",
-        reference.host, reference.repository, reference.tag
-    )
-}
-
-fn load_index(path: &Path) -> Result<CacheIndex> {
-    if !path.exists() {
-        return Ok(CacheIndex::default());
-    }
-    let mut buf = String::new();
-    fs::File::open(path)?.read_to_string(&mut buf)?;
-    serde_json::from_str(&buf)
-        .map_err(|err| DeployerError::Other(format!("cache index parse error: {err}")))
-}
-
-fn save_index(path: &Path, index: &CacheIndex) -> Result<()> {
-    let content = serde_json::to_string_pretty(index)
-        .map_err(|err| DeployerError::Other(format!("cache index encode error: {err}")))?;
-    fs::write(path, content)?;
-    Ok(())
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::platform::cache::cache_key;
     use greentic_types::ComponentId;
     use greentic_types::PackId;
     use greentic_types::component::{ComponentCapabilities, ComponentManifest, ComponentProfiles};
@@ -432,10 +1051,164 @@ mod tests {
         assert!(err.to_string().contains("digest mismatch"));
     }
 
+    #[test]
+    fn parse_accepts_digest_refs() {
+        let digest = format!("sha256:{}", "ab".repeat(32));
+        let r = parse_oci_reference(&format!("oci://r.local:5000/team/repo@{digest}")).unwrap();
+        assert_eq!(r.host, "r.local:5000");
+        assert_eq!(r.repository, "team/repo");
+        assert_eq!(r.tag, "latest");
+        assert_eq!(r.manifest_reference(), digest);
+        assert_eq!(cache_key(&r), format!("r.local:5000/team/repo@{digest}"));
+
+        let tagged = parse_oci_reference(&format!("oci://r.local/repo:1.0@{digest}")).unwrap();
+        assert_eq!(tagged.tag, "1.0");
+        assert_eq!(tagged.digest.as_deref(), Some(digest.as_str()));
+
+        assert!(parse_oci_reference("oci://r.local/repo@sha256:abc").is_err());
+        assert!(parse_oci_reference("oci://r.local/repo@md5:00").is_err());
+    }
+
+    #[test]
+    fn follows_index_to_pack_artifact() {
+        let dir = tempdir().unwrap();
+        let bytes = sample_gtpack_bytes();
+        let mut fetcher = StubFetcher::new(bytes.clone(), None, None);
+        let pack_manifest = fetcher.manifests.remove("latest").unwrap();
+        let image = OciManifest {
+            layers: vec![descriptor(
+                "application/vnd.oci.image.layer.v1.tar+gzip",
+                b"image",
+            )],
+            ..Default::default()
+        };
+        let image_bytes = serde_json::to_vec(&image).unwrap();
+        let mut image_entry =
+            descriptor("application/vnd.oci.image.manifest.v1+json", &image_bytes);
+        let (os, arch) = host_platform();
+        image_entry.platform = Some(OciPlatform {
+            architecture: arch.to_string(),
+            os: os.to_string(),
+        });
+        let mut pack_entry =
+            descriptor("application/vnd.oci.image.manifest.v1+json", &pack_manifest);
+        pack_entry.artifact_type = Some(PACK_ARTIFACT_TYPE.to_string());
+        let index = OciManifest {
+            media_type: Some(INDEX_MEDIA_TYPES[0].to_string()),
+            manifests: vec![image_entry.clone(), pack_entry.clone()],
+            ..Default::default()
+        };
+        fetcher
+            .manifests
+            .insert("latest".into(), serde_json::to_vec(&index).unwrap());
+        fetcher
+            .manifests
+            .insert(image_entry.digest.clone(), image_bytes);
+        fetcher
+            .manifests
+            .insert(pack_entry.digest.clone(), pack_manifest);
+        let allowlist =
+            crate::bootstrap::network::NetAllowList::parse(Some("registry.local")).unwrap();
+        let policy = NetworkPolicy::new(true, false, allowlist);
+
+        let path = resolve_with_fetcher("oci://registry.local/repo", dir.path(), &policy, &fetcher)
+            .unwrap();
+        assert_eq!(fs::read(path).unwrap(), bytes);
+    }
+
+    #[test]
+    fn selects_layer_by_artifact_type_and_title() {
+        let config = descriptor(PACK_ARTIFACT_TYPE, b"{}");
+        let layer = descriptor("application/vnd.oci.image.layer.v1.tar", b"pack");
+        let manifest = OciManifest {
+            config: Some(config),
+            layers: vec![layer.clone()],
+            ..Default::default()
+        };
+        assert_eq!(select_pack_layer(&manifest).unwrap().digest, layer.digest);
+
+        let mut titled = descriptor("application/vnd.oci.image.layer.v1.tar", b"titled");
+        titled
+            .annotations
+            .insert(TITLE_ANNOTATION.into(), "platform.gtpack".into());
+        let manifest = OciManifest {
+            layers: vec![layer.clone(), titled.clone()],
+            ..Default::default()
+        };
+        assert_eq!(select_pack_layer(&manifest).unwrap().digest, titled.digest);
+
+        let manifest = OciManifest {
+            layers: vec![layer.clone(), layer],
+            ..Default::default()
+        };
+        let err = select_pack_layer(&manifest).unwrap_err();
+        assert!(err.to_string().contains(PACK_ARTIFACT_TYPE));
+    }
+
+    #[test]
+    fn reads_docker_config_credentials() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        fs::write(
+            &path,
+            serde_json::json!({"auths": {
+                "https://index.docker.io/v1/": {"auth": STANDARD.encode("hub:pw")},
+                "registry.local:5000": {"username": "ci", "password": "secret"},
+                "token.local": {"registrytoken": "tkn"}
+            }})
+            .to_string(),
+        )
+        .unwrap();
+
+        let basic = |username: &str, password: &str| Credentials::Basic {
+            username: username.into(),
+            password: password.into(),
+        };
+        assert_eq!(
+            load_credentials(&path, "docker.io").unwrap(),
+            Some(basic("hub", "pw"))
+        );
+        assert_eq!(
+            load_credentials(&path, "registry.local:5000").unwrap(),
+            Some(basic("ci", "secret"))
+        );
+        assert_eq!(
+            load_credentials(&path, "token.local").unwrap(),
+            Some(Credentials::Token("tkn".into()))
+        );
+        assert_eq!(load_credentials(&path, "other.local").unwrap(), None);
+        assert_eq!(
+            load_credentials(&dir.path().join("missing.json"), "docker.io").unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn parses_bearer_challenge() {
+        let (scheme, params) = parse_challenge(
+            r#"Bearer realm="https://auth.local/token",service="registry.local",scope="repository:a/b:pull,push""#,
+        );
+        assert_eq!(scheme, "Bearer");
+        assert_eq!(params["realm"], "https://auth.local/token");
+        assert_eq!(params["service"], "registry.local");
+        assert_eq!(params["scope"], "repository:a/b:pull,push");
+    }
+
+    fn descriptor(media_type: &str, bytes: &[u8]) -> OciDescriptor {
+        OciDescriptor {
+            media_type: media_type.to_string(),
+            digest: sha256_bytes(bytes),
+            size: Some(bytes.len() as u64),
+            artifact_type: None,
+            platform: None,
+            annotations: HashMap::new(),
+        }
+    }
+
+    /// Serves manifests by tag or digest and a single blob.
     struct StubFetcher {
-        manifest: OciManifest,
+        manifests: HashMap<String, Vec<u8>>,
         manifest_digest: Option<String>,
-        manifest_bytes: Vec<u8>,
         blob: Vec<u8>,
     }
 
@@ -445,21 +1218,18 @@ mod tests {
             digest_override: Option<String>,
             manifest_digest: Option<String>,
         ) -> Self {
-            let digest = digest_override.unwrap_or_else(|| sha256_bytes(&blob));
-            let layer = OciLayer {
-                media_type: PACK_MEDIA_TYPES[0].to_string(),
-                digest: digest.clone(),
-                size: Some(blob.len() as u64),
-            };
+            let mut layer = descriptor(PACK_ARTIFACT_TYPE, &blob);
+            if let Some(digest) = digest_override {
+                layer.digest = digest;
+            }
             let manifest = OciManifest {
                 layers: vec![layer],
+                ..Default::default()
             };
             let manifest_bytes = serde_json::to_vec(&manifest).expect("serialize manifest");
-            let manifest_digest = manifest_digest.or_else(|| Some(sha256_bytes(&manifest_bytes)));
             Self {
-                manifest,
+                manifests: HashMap::from([("latest".to_string(), manifest_bytes)]),
                 manifest_digest,
-                manifest_bytes,
                 blob,
             }
         }
@@ -469,12 +1239,22 @@ mod tests {
         fn fetch_manifest(
             &self,
             _reference: &OciReference,
-        ) -> Result<(OciManifest, Option<String>, Vec<u8>)> {
-            Ok((
-                self.manifest.clone(),
-                self.manifest_digest.clone(),
-                self.manifest_bytes.clone(),
-            ))
+            tag_or_digest: &str,
+        ) -> Result<FetchedManifest> {
+            let bytes = self
+                .manifests
+                .get(tag_or_digest)
+                .cloned()
+                .ok_or_else(|| DeployerError::Other(format!("{tag_or_digest} not found")))?;
+            let digest = self
+                .manifest_digest
+                .clone()
+                .unwrap_or_else(|| sha256_bytes(&bytes));
+            Ok(FetchedManifest {
+                bytes,
+                media_type: None,
+                digest: Some(digest),
+            })
         }
 
         fn fetch_blob(&self, _reference: &OciReference, _digest: &str) -> Result<Vec<u8>> {
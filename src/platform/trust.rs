@@ -0,0 +1,306 @@
+//! Trust store of publisher keys used to verify platform pack signatures.
+//!
+//! The store is a JSON file (by default `trusted_keys.json` beside the bootstrap state) listing
+//! the public keys whose signatures `platform install/upgrade` accepts. Keys are stored raw and
+//! base64-encoded: 32 bytes for Ed25519, a 65-byte uncompressed SEC1 point for ECDSA P-256.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use greentic_types::pack::SignatureAlgorithm;
+use ring::signature::{self, UnparsedPublicKey};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{DeployerError, Result};
+
+/// File name of the trust store created beside the bootstrap state.
+pub const TRUST_STORE_FILE: &str = "trusted_keys.json";
+
+/// Trust store beside the default bootstrap state, used by plan/apply/destroy.
+pub const DEFAULT_TRUST_STORE: &str = "/var/lib/greentic/bootstrap/trusted_keys.json";
+
+/// DER SubjectPublicKeyInfo prefixes for the supported key types; the raw key follows.
+const ED25519_SPKI_PREFIX: &[u8] = &[
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+const P256_SPKI_PREFIX: &[u8] = &[
+    0x30, 0x59, 0x30, 0x13, 0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01, 0x06, 0x08, 0x2a,
+    0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07, 0x03, 0x42, 0x00,
+];
+
+/// Signature algorithms accepted for trusted keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeyAlgorithm {
+    Ed25519,
+    EcdsaP256,
+}
+
+impl KeyAlgorithm {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            KeyAlgorithm::Ed25519 => "ed25519",
+            KeyAlgorithm::EcdsaP256 => "ecdsa-p256",
+        }
+    }
+
+    /// Map a manifest signature algorithm onto a supported key algorithm.
+    pub fn for_signature(algorithm: &SignatureAlgorithm) -> Option<Self> {
+        match algorithm {
+            SignatureAlgorithm::Ed25519 => Some(KeyAlgorithm::Ed25519),
+            SignatureAlgorithm::Other(name) => match name.to_ascii_lowercase().as_str() {
+                "ecdsa-p256" | "ecdsa-p256-sha256" | "es256" => Some(KeyAlgorithm::EcdsaP256),
+                _ => None,
+            },
+        }
+    }
+
+    fn key_len(self) -> usize {
+        match self {
+            KeyAlgorithm::Ed25519 => 32,
+            KeyAlgorithm::EcdsaP256 => 65,
+        }
+    }
+}
+
+/// A publisher key trusted to sign platform packs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustedKey {
+    pub key_id: String,
+    pub algorithm: KeyAlgorithm,
+    /// Base64-encoded raw public key.
+    pub public_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub publisher: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub added_at: Option<u64>,
+    /// Unix timestamp after which signatures from this key are rejected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+}
+
+impl TrustedKey {
+    /// Build a trusted key from a PEM `PUBLIC KEY`, a DER SubjectPublicKeyInfo, or a raw key
+    /// (binary or base64). The algorithm is taken from the key encoding.
+    pub fn from_public_key(key_id: impl Into<String>, encoded: &[u8]) -> Result<Self> {
+        let (algorithm, raw) = parse_public_key(encoded)?;
+        Ok(Self {
+            key_id: key_id.into(),
+            algorithm,
+            public_key: STANDARD.encode(raw),
+            publisher: None,
+            added_at: None,
+            expires_at: None,
+        })
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// Check `sig` over `message`; the error says why the signature was rejected.
+    pub fn verify(
+        &self,
+        algorithm: &SignatureAlgorithm,
+        message: &[u8],
+        sig: &[u8],
+    ) -> std::result::Result<(), String> {
+        let requested = KeyAlgorithm::for_signature(algorithm)
+            .ok_or_else(|| format!("unsupported signature algorithm {algorithm:?}"))?;
+        if requested != self.algorithm {
+            return Err(format!(
+                "signature algorithm {} does not match trusted key algorithm {}",
+                requested.as_str(),
+                self.algorithm.as_str()
+            ));
+        }
+        let key = STANDARD
+            .decode(&self.public_key)
+            .map_err(|err| format!("trusted key {} is not base64: {err}", self.key_id))?;
+        let verified = match self.algorithm {
+            KeyAlgorithm::Ed25519 => UnparsedPublicKey::new(&signature::ED25519, &key)
+                .verify(message, sig)
+                .is_ok(),
+            // Accept both DER-encoded and fixed-width (r || s) ECDSA signatures.
+            KeyAlgorithm::EcdsaP256 => {
+                UnparsedPublicKey::new(&signature::ECDSA_P256_SHA256_ASN1, &key)
+                    .verify(message, sig)
+                    .is_ok()
+                    || UnparsedPublicKey::new(&signature::ECDSA_P256_SHA256_FIXED, &key)
+                        .verify(message, sig)
+                        .is_ok()
+            }
+        };
+        if verified {
+            Ok(())
+        } else {
+            Err("signature does not match the manifest and content digest".into())
+        }
+    }
+}
+
+/// Publisher keys trusted for platform pack verification.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustStore {
+    #[serde(default)]
+    pub keys: Vec<TrustedKey>,
+}
+
+impl TrustStore {
+    /// Load the store at `path`; a missing file is an empty store.
+    pub fn load(path: &Path) -> Result<Self> {
+        match fs::read(path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|err| {
+                DeployerError::Config(format!("invalid trust store {}: {err}", path.display()))
+            }),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    pub fn get(&self, key_id: &str) -> Option<&TrustedKey> {
+        self.keys.iter().find(|key| key.key_id == key_id)
+    }
+
+    /// Add `key`, replacing any key with the same id. Returns true when a key was replaced.
+    pub fn add(&mut self, key: TrustedKey) -> bool {
+        match self
+            .keys
+            .iter_mut()
+            .find(|existing| existing.key_id == key.key_id)
+        {
+            Some(existing) => {
+                *existing = key;
+                true
+            }
+            None => {
+                self.keys.push(key);
+                false
+            }
+        }
+    }
+
+    /// Remove the key with `key_id`. Returns false when no such key was trusted.
+    pub fn remove(&mut self, key_id: &str) -> bool {
+        let before = self.keys.len();
+        self.keys.retain(|key| key.key_id != key_id);
+        self.keys.len() != before
+    }
+}
+
+/// Trust store location used when `--trust-store` is not given: beside the bootstrap state.
+pub fn default_trust_store_path(state_path: &Path) -> PathBuf {
+    state_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(TRUST_STORE_FILE)
+}
+
+fn parse_public_key(encoded: &[u8]) -> Result<(KeyAlgorithm, Vec<u8>)> {
+    let text = std::str::from_utf8(encoded).map(str::trim).ok();
+    let der = match text {
+        Some(text) if text.starts_with("-----BEGIN PUBLIC KEY-----") => {
+            let body: String = text
+                .lines()
+                .filter(|line| !line.starts_with("-----"))
+                .collect();
+            STANDARD
+                .decode(body.trim())
+                .map_err(|err| DeployerError::Config(format!("invalid PEM public key: {err}")))?
+        }
+        Some(text) => STANDARD.decode(text).unwrap_or_else(|_| encoded.to_vec()),
+        None => encoded.to_vec(),
+    };
+    let (algorithm, raw) = if let Some(raw) = der.strip_prefix(ED25519_SPKI_PREFIX) {
+        (KeyAlgorithm::Ed25519, raw)
+    } else if let Some(raw) = der.strip_prefix(P256_SPKI_PREFIX) {
+        (KeyAlgorithm::EcdsaP256, raw)
+    } else if der.len() == KeyAlgorithm::Ed25519.key_len() {
+        (KeyAlgorithm::Ed25519, der.as_slice())
+    } else if der.len() == KeyAlgorithm::EcdsaP256.key_len() && der[0] == 0x04 {
+        (KeyAlgorithm::EcdsaP256, der.as_slice())
+    } else {
+        return Err(DeployerError::Config(
+            "unsupported public key; expected an Ed25519 or ECDSA P-256 key (PEM, DER or raw)"
+                .into(),
+        ));
+    };
+    if raw.len() != algorithm.key_len() {
+        return Err(DeployerError::Config(format!(
+            "{} public key must be {} bytes, got {}",
+            algorithm.as_str(),
+            algorithm.key_len(),
+            raw.len()
+        )));
+    }
+    Ok((algorithm, raw.to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::rand::SystemRandom;
+    use ring::signature::{EcdsaKeyPair, KeyPair};
+
+    #[test]
+    fn parses_pem_and_raw_keys_and_verifies_ecdsa() {
+        let rng = SystemRandom::new();
+        let pkcs8 =
+            EcdsaKeyPair::generate_pkcs8(&signature::ECDSA_P256_SHA256_ASN1_SIGNING, &rng).unwrap();
+        let pair = EcdsaKeyPair::from_pkcs8(
+            &signature::ECDSA_P256_SHA256_ASN1_SIGNING,
+            pkcs8.as_ref(),
+            &rng,
+        )
+        .unwrap();
+        let mut spki = P256_SPKI_PREFIX.to_vec();
+        spki.extend_from_slice(pair.public_key().as_ref());
+        let pem = format!(
+            "-----BEGIN PUBLIC KEY-----\n{}\n-----END PUBLIC KEY-----\n",
+            STANDARD.encode(&spki)
+        );
+
+        let key = TrustedKey::from_public_key("release", pem.as_bytes()).unwrap();
+        assert_eq!(key.algorithm, KeyAlgorithm::EcdsaP256);
+        let raw = TrustedKey::from_public_key("release", pair.public_key().as_ref()).unwrap();
+        assert_eq!(raw.public_key, key.public_key);
+
+        let algorithm = SignatureAlgorithm::Other("ecdsa-p256".into());
+        let sig = pair.sign(&rng, b"payload").unwrap();
+        key.verify(&algorithm, b"payload", sig.as_ref()).unwrap();
+        assert!(key.verify(&algorithm, b"tampered", sig.as_ref()).is_err());
+        assert!(
+            key.verify(&SignatureAlgorithm::Ed25519, b"payload", sig.as_ref())
+                .is_err()
+        );
+        assert!(TrustedKey::from_public_key("bad", b"not a key").is_err());
+    }
+
+    #[test]
+    fn add_replaces_and_remove_reports_missing() {
+        let mut store = TrustStore::default();
+        let key = TrustedKey::from_public_key("k1", &[7u8; 32]).unwrap();
+        assert!(!store.add(key.clone()));
+        assert!(store.add(TrustedKey {
+            expires_at: Some(10),
+            ..key
+        }));
+        assert_eq!(store.keys.len(), 1);
+        assert!(store.get("k1").unwrap().is_expired(10));
+        assert!(store.remove("k1"));
+        assert!(!store.remove("k1"));
+    }
+}
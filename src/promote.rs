@@ -0,0 +1,316 @@
+//! Promote the pack deployed in one environment to another.
+//!
+//! Promotion never rebuilds the pack: the target environment is planned from the exact pack
+//! digest recorded by the source environment's current ledger record, every secret requirement
+//! is resolved in the target scope before anything is applied, and the plan diff against the
+//! target's current deployment is shown ahead of the regular apply path.
+
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+
+use crate::apply::{self, resolve_secrets};
+use crate::config::{ActionArgs, DeployerConfig, PromoteArgs, Provider};
+use crate::diff::{diff_plans, render_diff};
+use crate::error::{DeployerError, Result};
+use crate::iac::IaCCommandRunner;
+use crate::ledger::{Ledger, LedgerRecord, pack_digest};
+use crate::pack_introspect;
+use crate::plan::PlanContext;
+use crate::secrets::SecretsContext;
+use crate::telemetry;
+
+/// Current deployment of the source environment, which must carry a pack digest.
+pub fn promotion_source(state_dir: &Path, tenant: &str, from: &str) -> Result<LedgerRecord> {
+    let ledger = Ledger::new(state_dir, tenant, from);
+    let record = ledger.current()?.ok_or_else(|| {
+        DeployerError::Config(format!(
+            "nothing is currently deployed to {tenant}/{from}; promote requires a successful apply"
+        ))
+    })?;
+    if record.pack_digest.is_none() {
+        return Err(DeployerError::Config(format!(
+            "ledger record #{} for {tenant}/{from} has no pack digest; re-apply before promoting",
+            record.id
+        )));
+    }
+    Ok(record)
+}
+
+/// Apply arguments for the target environment.
+///
+/// The pack comes from `--pack`, else the local pack recorded by the source record, else the
+/// record's pack id/version/digest resolved from the distributor.
+pub fn promotion_args(args: &PromoteArgs, source: &LedgerRecord) -> Result<ActionArgs> {
+    if args.from == args.to {
+        return Err(DeployerError::Config(
+            "--from and --to must name different environments".into(),
+        ));
+    }
+    let provider = Provider::from_str(&source.provider, true).map_err(|err| {
+        DeployerError::Config(format!("unknown provider in ledger record: {err}"))
+    })?;
+    let local_pack = args.pack.clone().or_else(|| {
+        source
+            .pack_path
+            .as_ref()
+            .map(PathBuf::from)
+            .filter(|path| path.exists())
+    });
+    let mut action_args = ActionArgs {
+        yes: args.yes,
+        preview: false,
+        dry_run: args.dry_run,
+        allow_destroy: args.allow_destroy,
+        iac_timeout: args.iac_timeout,
+        state_backend: args.state_backend.clone(),
+        iac_tool: args.iac_tool,
+        distributor_url: args.distributor_url.clone(),
+        distributor_token: args.distributor_token.clone(),
+        ..ActionArgs::preview(
+            provider,
+            source.strategy.clone(),
+            args.tenant.clone(),
+            Some(args.to.clone()),
+            local_pack.clone().unwrap_or_default(),
+            args.output,
+        )
+    };
+    if local_pack.is_none() {
+        action_args.pack_id = Some(source.pack_id.clone());
+        action_args.pack_version = Some(source.pack_version.clone());
+        action_args.pack_digest = source.pack_digest.clone();
+    }
+    Ok(action_args)
+}
+
+/// Ensure the pack the configuration points at is the one recorded for the source environment.
+///
+/// Local packs are hashed here; for registry packs the configured digest is the pin that
+/// [`pack_introspect::build_plan`] checks the fetched bytes against.
+pub fn verify_pack_digest(config: &DeployerConfig, source: &LedgerRecord) -> Result<()> {
+    let expected = source.pack_digest.as_deref().unwrap_or_default();
+    let actual = pack_digest(config).unwrap_or_else(|| "<unreadable>".into());
+    if actual != expected {
+        return Err(DeployerError::PackDigestMismatch {
+            pack: config.pack_path.display().to_string(),
+            expected: expected.to_string(),
+            actual,
+        });
+    }
+    Ok(())
+}
+
+/// Re-plan the source deployment's pack for the target environment, check its secrets, show
+/// the diff and apply it.
+pub async fn promote(
+    config: DeployerConfig,
+    source: &LedgerRecord,
+    runner: &dyn IaCCommandRunner,
+) -> Result<()> {
+    telemetry::init(&config)?;
+    verify_pack_digest(&config, source)?;
+    let plan = pack_introspect::build_plan(&config)?;
+    if plan.plan.pack_id != source.pack_id
+        || plan.plan.pack_version.to_string() != source.pack_version
+    {
+        return Err(DeployerError::Config(format!(
+            "pack {}@{} does not match {}@{} deployed to {}/{}",
+            plan.plan.pack_id,
+            plan.plan.pack_version,
+            source.pack_id,
+            source.pack_version,
+            source.tenant,
+            source.environment
+        )));
+    }
+    println!(
+        "Promoting {}@{} ({}) from {}/{} (record #{}) to {}/{}",
+        source.pack_id,
+        source.pack_version,
+        source.pack_digest.as_deref().unwrap_or_default(),
+        source.tenant,
+        source.environment,
+        source.id,
+        config.tenant,
+        config.environment
+    );
+
+    let secrets = SecretsContext::discover(&config).await?;
+    resolve_secrets(&secrets, &plan.secrets, &plan, &config).await?;
+    println!(
+        "All {} secret requirement(s) resolve in {}/{}",
+        plan.secrets.len(),
+        config.tenant,
+        config.environment
+    );
+
+    match deployed_plan(&Ledger::for_config(&config))? {
+        Some(baseline) => print!(
+            "{}",
+            render_diff(&diff_plans(&baseline, &plan), config.output)?
+        ),
+        None => println!(
+            "Nothing deployed to {}/{} yet; the promotion is its first deployment.",
+            config.tenant, config.environment
+        ),
+    }
+
+    apply::run_with_plan(config, plan, runner).await
+}
+
+/// Plan stored with the target environment's current deployment, if any.
+fn deployed_plan(ledger: &Ledger) -> Result<Option<PlanContext>> {
+    let Some(sha) = ledger.current()?.and_then(|record| record.plan_sha256) else {
+        return Ok(None);
+    };
+    Ok(Some(serde_json::from_slice(&ledger.read_blob(&sha)?)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use std::fs;
+    use tempfile::tempdir;
+
+    use crate::config::{CliArgs, Command};
+    use crate::ledger::LedgerStatus;
+
+    fn promote_args(extra: &[&str]) -> PromoteArgs {
+        let mut argv = vec![
+            "greentic-deployer",
+            "promote",
+            "--tenant",
+            "acme",
+            "--from",
+            "staging",
+            "--to",
+            "prod",
+        ];
+        argv.extend_from_slice(extra);
+        match CliArgs::parse_from(argv).command {
+            Command::Promote(args) => args,
+            other => panic!("unexpected command {other:?}"),
+        }
+    }
+
+    fn applied(pack_path: Option<&str>) -> LedgerRecord {
+        LedgerRecord {
+            id: 3,
+            action: "apply".into(),
+            tenant: "acme".into(),
+            environment: "staging".into(),
+            pack_id: "dev.greentic.sample".into(),
+            pack_version: "0.2.0".into(),
+            pack_digest: Some("sha256:abc".into()),
+            pack_path: pack_path.map(str::to_string),
+            provider: "aws".into(),
+            strategy: "iac-only".into(),
+            dispatch_pack_id: None,
+            dispatch_flow_id: None,
+            artifacts: Vec::new(),
+            status: LedgerStatus::Succeeded,
+            error: None,
+            plan_sha256: None,
+            rollback_of: None,
+            started_at: 1,
+            finished_at: 2,
+        }
+    }
+
+    #[test]
+    fn falls_back_to_registry_reference_without_local_pack() {
+        let args = promote_args(&["--yes"]);
+        let action_args = promotion_args(&args, &applied(Some("/nonexistent/app.gtpack"))).unwrap();
+        assert_eq!(action_args.provider, Provider::Aws);
+        assert_eq!(action_args.environment.as_deref(), Some("prod"));
+        assert_eq!(action_args.pack_id.as_deref(), Some("dev.greentic.sample"));
+        assert_eq!(action_args.pack_version.as_deref(), Some("0.2.0"));
+        assert_eq!(action_args.pack_digest.as_deref(), Some("sha256:abc"));
+        assert!(action_args.yes);
+        assert!(!action_args.preview);
+    }
+
+    #[test]
+    fn uses_explicit_pack_and_rejects_same_environment() {
+        let args = promote_args(&["--pack", "app.gtpack"]);
+        let action_args = promotion_args(&args, &applied(None)).unwrap();
+        assert_eq!(action_args.pack, PathBuf::from("app.gtpack"));
+        assert!(action_args.pack_id.is_none());
+
+        let mut same = promote_args(&[]);
+        same.to = "staging".into();
+        assert!(promotion_args(&same, &applied(None)).is_err());
+    }
+
+    #[test]
+    fn requires_a_deployed_source_with_digest() {
+        let state = tempdir().unwrap();
+        let err = promotion_source(state.path(), "acme", "staging").unwrap_err();
+        assert!(err.to_string().contains("nothing is currently deployed"));
+
+        let ledger = Ledger::new(state.path(), "acme", "staging");
+        let mut record = applied(None);
+        record.pack_digest = None;
+        ledger.append(&mut record).unwrap();
+        let err = promotion_source(state.path(), "acme", "staging").unwrap_err();
+        assert!(err.to_string().contains("has no pack digest"));
+    }
+
+    fn dir_config(pack_path: PathBuf) -> DeployerConfig {
+        DeployerConfig {
+            action: crate::config::Action::Apply,
+            provider: Provider::Aws,
+            strategy: "iac-only".into(),
+            tenant: "acme".into(),
+            environment: "prod".into(),
+            pack_path,
+            providers_dir: PathBuf::from("providers/deployer"),
+            packs_dir: PathBuf::from("packs"),
+            provider_pack: None,
+            pack_ref: None,
+            distributor_url: None,
+            distributor_token: None,
+            yes: true,
+            preview: false,
+            dry_run: false,
+            allow_destroy: false,
+            iac_timeout: None,
+            state_backend: Default::default(),
+            iac_tool: crate::iac::IaCTool::Terraform,
+            output: crate::config::OutputFormat::Text,
+            greentic: greentic_config::ConfigResolver::new()
+                .load()
+                .expect("load default config")
+                .config,
+            provenance: greentic_config::ProvenanceMap::new(),
+            config_warnings: Vec::new(),
+            explain_config: false,
+            explain_config_json: false,
+            allow_remote_in_offline: false,
+            pack_verification: Default::default(),
+            quiet: false,
+        }
+    }
+
+    #[test]
+    fn directory_packs_differing_only_in_a_component_do_not_match() {
+        let dir = tempdir().unwrap();
+        for (env, wasm) in [("staging", "v1"), ("prod", "v2")] {
+            let pack = dir.path().join(env);
+            fs::create_dir_all(pack.join("components")).unwrap();
+            fs::write(pack.join("manifest.cbor"), b"same manifest").unwrap();
+            fs::write(pack.join("components/app.wasm"), wasm).unwrap();
+        }
+        let mut source = applied(None);
+        source.pack_digest = pack_digest(&dir_config(dir.path().join("staging")));
+
+        verify_pack_digest(&dir_config(dir.path().join("staging")), &source).unwrap();
+        let err = verify_pack_digest(&dir_config(dir.path().join("prod")), &source).unwrap_err();
+        assert!(
+            matches!(err, DeployerError::PackDigestMismatch { .. }),
+            "{err}"
+        );
+    }
+}
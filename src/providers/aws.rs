@@ -10,6 +10,7 @@ use tracing::info;
 use crate::config::{DeployerConfig, Provider};
 use crate::error::Result;
 use crate::plan::PlanContext;
+use crate::state_backend::BACKEND_CONFIG_FILE;
 use greentic_types::deployment::RunnerPlan;
 use greentic_types::secrets::SecretRequirement;
 
@@ -62,11 +63,7 @@ impl AwsBackend {
             self.config.tenant, self.config.environment
         )
         .ok();
-        writeln!(
-            &mut buffer,
-            "terraform {{\n  backend \"local\" {{\n    path = \"terraform.tfstate\"\n  }}\n}}\n"
-        )
-        .ok();
+        writeln!(&mut buffer, "{}", self.config.state_backend.render_block()).ok();
         writeln!(
             &mut buffer,
             "provider \"aws\" {{\n  region = \"{}\"\n}}\n",
@@ -466,7 +463,7 @@ impl ProviderBackend for AwsBackend {
         let variables_tf = self.render_variables_tf();
         let plan_json = serde_json::to_string_pretty(&self.plan)?;
 
-        Ok(ProviderArtifacts::named(
+        let artifacts = ProviderArtifacts::named(
             Provider::Aws,
             format!(
                 "AWS deployment for tenant {} in {}",
@@ -476,7 +473,17 @@ impl ProviderBackend for AwsBackend {
         )
         .with_file("master.tf", main_tf)
         .with_file("variables.tf", variables_tf)
-        .with_file("plan.json", plan_json))
+        .with_file("plan.json", plan_json);
+        Ok(
+            match self.config.state_backend.render_backend_config(
+                Provider::Aws.as_str(),
+                &self.config.tenant,
+                &self.config.environment,
+            ) {
+                Some(backend_config) => artifacts.with_file(BACKEND_CONFIG_FILE, backend_config),
+                None => artifacts,
+            },
+        )
     }
 
     async fn apply(&self, artifacts: &ProviderArtifacts, secrets: &[ResolvedSecret]) -> Result<()> {
@@ -0,0 +1,425 @@
+//! Roll a tenant/environment back to the artifacts of a previous ledger record.
+//!
+//! Rollback rewrites the provider output directory from the ledger blob store (keeping IaC
+//! state and tool caches), restores the persisted `plan.json`, re-runs the IaC apply sequence
+//! for IaC-capable providers and appends a `rollback` record to the ledger. As with `apply`, the
+//! IaC plan is shown and destructive plans need `allow_destroy` before anything is applied.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use tracing::info;
+
+use crate::apply::{confirm_or_cancel, print_dry_run_commands, print_step_timings};
+use crate::config::OutputFormat;
+use crate::error::{DeployerError, Result};
+use crate::iac::{IaCCommandRunner, IaCTool, provider_runs_iac, run_iac_apply, run_iac_plan};
+use crate::ledger::{Ledger, LedgerRecord, LedgerStatus, hash_artifacts, now_ts};
+
+/// Inputs for a rollback run.
+#[derive(Debug, Clone)]
+pub struct RollbackRequest {
+    pub state_dir: PathBuf,
+    pub tenant: String,
+    pub environment: String,
+    /// Ledger record to restore; defaults to the deployment preceding the current one.
+    pub to: Option<u64>,
+    pub iac_tool: IaCTool,
+    pub yes: bool,
+    pub dry_run: bool,
+    /// Allow rollbacks whose IaC plan deletes or replaces resources.
+    pub allow_destroy: bool,
+}
+
+/// Result of a rollback run.
+#[derive(Debug, Clone)]
+pub struct RollbackOutcome {
+    /// Record that was restored.
+    pub target: LedgerRecord,
+    /// Record appended for this rollback (absent for dry runs and declined confirmations).
+    pub record: Option<LedgerRecord>,
+    pub deploy_dir: PathBuf,
+}
+
+/// Restore the artifacts of a previous deployment and re-apply them.
+pub fn rollback(
+    request: &RollbackRequest,
+    runner: &dyn IaCCommandRunner,
+) -> Result<RollbackOutcome> {
+    let ledger = Ledger::new(&request.state_dir, &request.tenant, &request.environment);
+    let target = ledger.rollback_target(request.to)?;
+    let deploy_dir = request
+        .state_dir
+        .join("deploy")
+        .join(&target.provider)
+        .join(&request.tenant)
+        .join(&request.environment);
+    let runs_iac = !target.artifacts.is_empty() && provider_runs_iac(&target.provider);
+
+    println!(
+        "Rolling back {}/{} to record #{} ({}@{}, {} artifact(s))",
+        request.tenant,
+        request.environment,
+        target.id,
+        target.pack_id,
+        target.pack_version,
+        target.artifacts.len()
+    );
+
+    if request.dry_run {
+        for artifact in &target.artifacts {
+            println!("would restore {}", artifact.path);
+        }
+        if runs_iac {
+            print_dry_run_commands(request.iac_tool, false, &deploy_dir);
+        }
+        return Ok(RollbackOutcome {
+            target,
+            record: None,
+            deploy_dir,
+        });
+    }
+
+    let snapshot = Snapshot::read(&ledger, &target)?;
+    // IaC plans run against the restored files; keep the current ones to put back if the
+    // rollback is refused or declined.
+    let current = if runs_iac {
+        let current = read_tree(&deploy_dir)?;
+        let planned = write_tree(&deploy_dir, &snapshot.files).and_then(|()| {
+            let changes = run_iac_plan(runner, request.iac_tool, &deploy_dir)?;
+            print!("{}", changes.render(OutputFormat::Text)?);
+            if changes.is_destructive() && !request.allow_destroy {
+                return Err(DeployerError::DestructivePlan {
+                    deletes: changes.deletes.len(),
+                    replaces: changes.replaces.len(),
+                });
+            }
+            Ok(())
+        });
+        if let Err(err) = planned {
+            write_tree(&deploy_dir, &current)?;
+            return Err(err);
+        }
+        Some(current)
+    } else {
+        None
+    };
+    if !(request.yes || confirm_or_cancel("rollback")?) {
+        if let Some(current) = &current {
+            write_tree(&deploy_dir, current)?;
+        }
+        return Ok(RollbackOutcome {
+            target,
+            record: None,
+            deploy_dir,
+        });
+    }
+
+    let mut record = LedgerRecord {
+        id: 0,
+        action: "rollback".into(),
+        tenant: request.tenant.clone(),
+        environment: request.environment.clone(),
+        pack_id: target.pack_id.clone(),
+        pack_version: target.pack_version.clone(),
+        pack_digest: target.pack_digest.clone(),
+        pack_path: target.pack_path.clone(),
+        provider: target.provider.clone(),
+        strategy: target.strategy.clone(),
+        dispatch_pack_id: target.dispatch_pack_id.clone(),
+        dispatch_flow_id: target.dispatch_flow_id.clone(),
+        artifacts: Vec::new(),
+        status: LedgerStatus::Failed,
+        error: None,
+        plan_sha256: target.plan_sha256.clone(),
+        rollback_of: Some(target.id),
+        started_at: now_ts(),
+        finished_at: 0,
+    };
+    let outcome = if runs_iac {
+        run_iac_apply(runner, request.iac_tool, &deploy_dir).map(|()| {
+            print_step_timings(runner, &deploy_dir);
+        })
+    } else {
+        write_tree(&deploy_dir, &snapshot.files).map(|()| {
+            info!(
+                "Skipping IaC apply for provider={} (no IaC-capable artifacts)",
+                target.provider
+            );
+        })
+    }
+    .and_then(|()| snapshot.restore_plan(&target, &request.state_dir));
+    let (status, error) = match &outcome {
+        Ok(()) => (LedgerStatus::Succeeded, None),
+        Err(err) => (LedgerStatus::Failed, Some(err.to_string())),
+    };
+    record.finish(status, error, &deploy_dir);
+    ledger.append(&mut record)?;
+    outcome?;
+    println!(
+        "Rolled back to record #{} (ledger record #{})",
+        target.id, record.id
+    );
+    Ok(RollbackOutcome {
+        target,
+        record: Some(record),
+        deploy_dir,
+    })
+}
+
+/// Artifacts and runtime plan of a ledger record, read from the blob store.
+struct Snapshot {
+    files: Vec<(String, Vec<u8>)>,
+    plan: Option<Vec<u8>>,
+}
+
+impl Snapshot {
+    /// Read every blob up front so a missing blob leaves the current deployment untouched.
+    fn read(ledger: &Ledger, target: &LedgerRecord) -> Result<Self> {
+        let mut files = Vec::with_capacity(target.artifacts.len());
+        for artifact in &target.artifacts {
+            files.push((artifact.path.clone(), ledger.read_blob(&artifact.sha256)?));
+        }
+        let plan = match &target.plan_sha256 {
+            Some(sha) => Some(ledger.read_blob(sha)?),
+            None => None,
+        };
+        Ok(Self { files, plan })
+    }
+
+    /// Write the runtime `plan.json`, if the record has one.
+    fn restore_plan(&self, target: &LedgerRecord, state_dir: &Path) -> Result<()> {
+        if let Some(plan) = &self.plan {
+            let runtime_dir = state_dir
+                .join("runtime")
+                .join(&target.tenant)
+                .join(&target.environment);
+            fs::create_dir_all(&runtime_dir)?;
+            fs::write(runtime_dir.join("plan.json"), plan)?;
+        }
+        Ok(())
+    }
+}
+
+/// Tracked artifacts currently in `deploy_dir`.
+fn read_tree(deploy_dir: &Path) -> Result<Vec<(String, Vec<u8>)>> {
+    hash_artifacts(deploy_dir)
+        .into_iter()
+        .map(|artifact| {
+            let bytes = fs::read(deploy_dir.join(&artifact.path))?;
+            Ok((artifact.path, bytes))
+        })
+        .collect()
+}
+
+/// Make the tracked artifacts in `deploy_dir` exactly `files`.
+fn write_tree(deploy_dir: &Path, files: &[(String, Vec<u8>)]) -> Result<()> {
+    let keep: BTreeSet<&str> = files.iter().map(|(path, _)| path.as_str()).collect();
+    for stale in hash_artifacts(deploy_dir) {
+        if !keep.contains(stale.path.as_str()) {
+            fs::remove_file(deploy_dir.join(&stale.path))?;
+        }
+    }
+    for (path, bytes) in files {
+        let dest = deploy_dir.join(path);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(dest, bytes)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use tempfile::tempdir;
+
+    #[derive(Default)]
+    struct RecordingRunner {
+        calls: Mutex<Vec<(PathBuf, Vec<String>)>>,
+        show_json: Option<String>,
+    }
+
+    impl IaCCommandRunner for RecordingRunner {
+        fn run(&self, _tool: IaCTool, dir: &Path, args: &[&str]) -> Result<String> {
+            self.calls.lock().unwrap().push((
+                dir.to_path_buf(),
+                args.iter().map(|arg| arg.to_string()).collect(),
+            ));
+            if args.first() == Some(&"show") {
+                return Ok(self
+                    .show_json
+                    .clone()
+                    .unwrap_or_else(|| r#"{"resource_changes":[]}"#.to_string()));
+            }
+            Ok(String::new())
+        }
+    }
+
+    fn applied(ledger: &Ledger, deploy_dir: &Path, version: &str) -> LedgerRecord {
+        let mut record = LedgerRecord {
+            id: 0,
+            action: "apply".into(),
+            tenant: "acme".into(),
+            environment: "staging".into(),
+            pack_id: "dev.greentic.sample".into(),
+            pack_version: version.into(),
+            pack_digest: None,
+            pack_path: None,
+            provider: "aws".into(),
+            strategy: "iac-only".into(),
+            dispatch_pack_id: None,
+            dispatch_flow_id: None,
+            artifacts: hash_artifacts(deploy_dir),
+            status: LedgerStatus::Succeeded,
+            error: None,
+            plan_sha256: None,
+            rollback_of: None,
+            started_at: 1,
+            finished_at: 2,
+        };
+        let plan = serde_json::json!({ "version": version });
+        ledger
+            .store_snapshot(&mut record, deploy_dir, &plan)
+            .unwrap();
+        ledger.append(&mut record).unwrap();
+        record
+    }
+
+    #[test]
+    fn restores_previous_artifacts_and_reapplies() {
+        let state = tempdir().unwrap();
+        let deploy_dir = state.path().join("deploy/aws/acme/staging");
+        fs::create_dir_all(&deploy_dir).unwrap();
+        let ledger = Ledger::new(state.path(), "acme", "staging");
+
+        fs::write(deploy_dir.join("main.tf"), "v1").unwrap();
+        applied(&ledger, &deploy_dir, "0.1.0");
+        fs::write(deploy_dir.join("main.tf"), "v2").unwrap();
+        fs::write(deploy_dir.join("extra.tf"), "new").unwrap();
+        fs::write(deploy_dir.join("terraform.tfstate"), "{}").unwrap();
+        applied(&ledger, &deploy_dir, "0.2.0");
+
+        let runner = RecordingRunner::default();
+        let request = RollbackRequest {
+            state_dir: state.path().to_path_buf(),
+            tenant: "acme".into(),
+            environment: "staging".into(),
+            to: None,
+            iac_tool: IaCTool::Terraform,
+            yes: true,
+            dry_run: false,
+            allow_destroy: false,
+        };
+        let outcome = rollback(&request, &runner).unwrap();
+
+        assert_eq!(outcome.target.id, 1);
+        assert_eq!(
+            fs::read_to_string(deploy_dir.join("main.tf")).unwrap(),
+            "v1"
+        );
+        assert!(!deploy_dir.join("extra.tf").exists());
+        assert!(deploy_dir.join("terraform.tfstate").exists());
+        let plan = fs::read_to_string(state.path().join("runtime/acme/staging/plan.json")).unwrap();
+        assert!(plan.contains("0.1.0"));
+
+        let calls = runner.calls.lock().unwrap();
+        assert_eq!(calls.len(), 4);
+        assert_eq!(calls[3].1[0], "apply");
+
+        let current = ledger.current().unwrap().unwrap();
+        assert_eq!(current.action, "rollback");
+        assert_eq!(current.rollback_of, Some(1));
+        assert_eq!(current.pack_version, "0.1.0");
+    }
+
+    #[test]
+    fn dry_run_leaves_deployment_untouched() {
+        let state = tempdir().unwrap();
+        let deploy_dir = state.path().join("deploy/aws/acme/staging");
+        fs::create_dir_all(&deploy_dir).unwrap();
+        let ledger = Ledger::new(state.path(), "acme", "staging");
+        fs::write(deploy_dir.join("main.tf"), "v1").unwrap();
+        applied(&ledger, &deploy_dir, "0.1.0");
+        fs::write(deploy_dir.join("main.tf"), "v2").unwrap();
+        applied(&ledger, &deploy_dir, "0.2.0");
+
+        let runner = RecordingRunner::default();
+        let request = RollbackRequest {
+            state_dir: state.path().to_path_buf(),
+            tenant: "acme".into(),
+            environment: "staging".into(),
+            to: Some(1),
+            iac_tool: IaCTool::Terraform,
+            yes: false,
+            dry_run: true,
+            allow_destroy: false,
+        };
+        let outcome = rollback(&request, &runner).unwrap();
+        assert!(outcome.record.is_none());
+        assert_eq!(
+            fs::read_to_string(deploy_dir.join("main.tf")).unwrap(),
+            "v2"
+        );
+        assert!(runner.calls.lock().unwrap().is_empty());
+        assert_eq!(ledger.records().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn destructive_plan_is_refused_without_allow_destroy() {
+        let state = tempdir().unwrap();
+        let deploy_dir = state.path().join("deploy/aws/acme/staging");
+        fs::create_dir_all(&deploy_dir).unwrap();
+        let ledger = Ledger::new(state.path(), "acme", "staging");
+        fs::write(deploy_dir.join("main.tf"), "v1").unwrap();
+        applied(&ledger, &deploy_dir, "0.1.0");
+        fs::write(deploy_dir.join("main.tf"), "v2").unwrap();
+        fs::write(deploy_dir.join("extra.tf"), "new").unwrap();
+        applied(&ledger, &deploy_dir, "0.2.0");
+
+        let runner = RecordingRunner {
+            show_json: Some(
+                r#"{"resource_changes":[{"address":"aws_s3_bucket.extra","change":{"actions":["delete"]}}]}"#
+                    .to_string(),
+            ),
+            ..Default::default()
+        };
+        let mut request = RollbackRequest {
+            state_dir: state.path().to_path_buf(),
+            tenant: "acme".into(),
+            environment: "staging".into(),
+            to: None,
+            iac_tool: IaCTool::Terraform,
+            yes: true,
+            dry_run: false,
+            allow_destroy: false,
+        };
+        let err = rollback(&request, &runner).unwrap_err();
+        assert!(matches!(
+            err,
+            DeployerError::DestructivePlan { deletes: 1, .. }
+        ));
+        assert_eq!(
+            fs::read_to_string(deploy_dir.join("main.tf")).unwrap(),
+            "v2"
+        );
+        assert!(deploy_dir.join("extra.tf").exists());
+        assert!(
+            runner
+                .calls
+                .lock()
+                .unwrap()
+                .iter()
+                .all(|(_, args)| args[0] != "apply")
+        );
+        assert_eq!(ledger.records().unwrap().len(), 2);
+
+        request.allow_destroy = true;
+        let outcome = rollback(&request, &runner).unwrap();
+        assert!(outcome.record.is_some());
+        assert!(!deploy_dir.join("extra.tf").exists());
+    }
+}
@@ -0,0 +1,303 @@
+//! Terraform/OpenTofu state backend configuration.
+//!
+//! The backend is loaded from a YAML/JSON document (`--state-backend <file>` or
+//! `GREENTIC_STATE_BACKEND`), rendered as a partial `backend` block into the generated IaC, and
+//! its settings are written to `backend.hcl` which `init` receives via `-backend-config`. Each
+//! `deploy/<provider>/<tenant>/<env>` directory gets its own state key.
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{DeployerError, Result};
+
+/// File holding the backend settings passed to `init -backend-config=<file>`.
+pub const BACKEND_CONFIG_FILE: &str = "backend.hcl";
+
+const STATE_FILE: &str = "terraform.tfstate";
+const DEFAULT_KEY_PREFIX: &str = "greentic";
+
+/// Remote (or local) state backend used by generated IaC.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum StateBackendConfig {
+    /// `terraform.tfstate` inside the deploy directory.
+    #[default]
+    Local,
+    /// S3 bucket with optional DynamoDB locking.
+    S3 {
+        bucket: String,
+        region: String,
+        #[serde(default)]
+        dynamodb_table: Option<String>,
+        #[serde(default)]
+        key_prefix: Option<String>,
+        #[serde(default)]
+        encrypt: Option<bool>,
+    },
+    /// Azure Storage blob container.
+    AzureRm {
+        resource_group_name: String,
+        storage_account_name: String,
+        container_name: String,
+        #[serde(default)]
+        key_prefix: Option<String>,
+    },
+    /// Google Cloud Storage bucket.
+    Gcs {
+        bucket: String,
+        #[serde(default)]
+        prefix: Option<String>,
+    },
+    /// Generic HTTP state endpoint; tenant/env path segments are appended to each address.
+    Http {
+        address: String,
+        #[serde(default)]
+        lock_address: Option<String>,
+        #[serde(default)]
+        unlock_address: Option<String>,
+    },
+    /// PostgreSQL; the connection string falls back to `PG_CONN_STR` when omitted.
+    Pg {
+        #[serde(default)]
+        conn_str: Option<String>,
+        #[serde(default)]
+        schema_prefix: Option<String>,
+    },
+}
+
+impl StateBackendConfig {
+    /// Load a backend document (YAML or JSON).
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = fs::read_to_string(path).map_err(|err| {
+            DeployerError::Config(format!(
+                "failed to read state backend config {}: {err}",
+                path.display()
+            ))
+        })?;
+        serde_yaml_bw::from_str(&data).map_err(|err| {
+            DeployerError::Config(format!(
+                "invalid state backend config {}: {err}",
+                path.display()
+            ))
+        })
+    }
+
+    /// Terraform backend type name.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            StateBackendConfig::Local => "local",
+            StateBackendConfig::S3 { .. } => "s3",
+            StateBackendConfig::AzureRm { .. } => "azurerm",
+            StateBackendConfig::Gcs { .. } => "gcs",
+            StateBackendConfig::Http { .. } => "http",
+            StateBackendConfig::Pg { .. } => "pg",
+        }
+    }
+
+    /// `terraform { backend ... }` block for the generated IaC. Remote backends are rendered as
+    /// partial configuration completed by [`BACKEND_CONFIG_FILE`].
+    pub fn render_block(&self) -> String {
+        match self {
+            StateBackendConfig::Local => format!(
+                "terraform {{\n  backend \"local\" {{\n    path = \"{STATE_FILE}\"\n  }}\n}}\n"
+            ),
+            other => format!("terraform {{\n  backend \"{}\" {{}}\n}}\n", other.kind()),
+        }
+    }
+
+    /// Backend settings for one provider/tenant/environment, with an isolated state key.
+    pub fn settings(
+        &self,
+        provider: &str,
+        tenant: &str,
+        environment: &str,
+    ) -> Vec<(String, String)> {
+        let scope = format!("{provider}/{tenant}/{environment}");
+        let keyed = |prefix: &Option<String>| {
+            format!(
+                "{}/{scope}/{STATE_FILE}",
+                prefix
+                    .as_deref()
+                    .unwrap_or(DEFAULT_KEY_PREFIX)
+                    .trim_end_matches('/')
+            )
+        };
+        let mut settings = Vec::new();
+        let mut set = |key: &str, value: String| settings.push((key.to_string(), value));
+        match self {
+            StateBackendConfig::Local => set("path", STATE_FILE.to_string()),
+            StateBackendConfig::S3 {
+                bucket,
+                region,
+                dynamodb_table,
+                key_prefix,
+                encrypt,
+            } => {
+                set("bucket", bucket.clone());
+                set("region", region.clone());
+                set("key", keyed(key_prefix));
+                if let Some(table) = dynamodb_table {
+                    set("dynamodb_table", table.clone());
+                }
+                set("encrypt", encrypt.unwrap_or(true).to_string());
+            }
+            StateBackendConfig::AzureRm {
+                resource_group_name,
+                storage_account_name,
+                container_name,
+                key_prefix,
+            } => {
+                set("resource_group_name", resource_group_name.clone());
+                set("storage_account_name", storage_account_name.clone());
+                set("container_name", container_name.clone());
+                set("key", keyed(key_prefix));
+            }
+            StateBackendConfig::Gcs { bucket, prefix } => {
+                set("bucket", bucket.clone());
+                set(
+                    "prefix",
+                    format!(
+                        "{}/{scope}",
+                        prefix
+                            .as_deref()
+                            .unwrap_or(DEFAULT_KEY_PREFIX)
+                            .trim_end_matches('/')
+                    ),
+                );
+            }
+            StateBackendConfig::Http {
+                address,
+                lock_address,
+                unlock_address,
+            } => {
+                let scoped = |base: &str| format!("{}/{scope}", base.trim_end_matches('/'));
+                set("address", scoped(address));
+                if let Some(lock) = lock_address {
+                    set("lock_address", scoped(lock));
+                }
+                if let Some(unlock) = unlock_address {
+                    set("unlock_address", scoped(unlock));
+                }
+            }
+            StateBackendConfig::Pg {
+                conn_str,
+                schema_prefix,
+            } => {
+                if let Some(conn_str) = conn_str {
+                    set("conn_str", conn_str.clone());
+                }
+                let schema = format!(
+                    "{}_{provider}_{tenant}_{environment}",
+                    schema_prefix.as_deref().unwrap_or(DEFAULT_KEY_PREFIX)
+                );
+                set("schema_name", sanitize_identifier(&schema));
+            }
+        }
+        settings
+    }
+
+    /// Contents of [`BACKEND_CONFIG_FILE`]; `None` for the local backend.
+    pub fn render_backend_config(
+        &self,
+        provider: &str,
+        tenant: &str,
+        environment: &str,
+    ) -> Option<String> {
+        if *self == StateBackendConfig::Local {
+            return None;
+        }
+        let mut out = String::new();
+        for (key, value) in self.settings(provider, tenant, environment) {
+            if value == "true" || value == "false" {
+                out.push_str(&format!("{key} = {value}\n"));
+            } else {
+                out.push_str(&format!("{key} = \"{}\"\n", escape_hcl(&value)));
+            }
+        }
+        Some(out)
+    }
+}
+
+fn escape_hcl(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn sanitize_identifier(value: &str) -> String {
+    value
+        .to_ascii_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_backend_keeps_state_in_deploy_dir() {
+        let backend = StateBackendConfig::default();
+        assert!(backend.render_block().contains("backend \"local\""));
+        assert!(backend.render_block().contains("terraform.tfstate"));
+        assert!(
+            backend
+                .render_backend_config("aws", "acme", "dev")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn s3_backend_derives_per_tenant_environment_key() {
+        let backend: StateBackendConfig = serde_yaml_bw::from_str(
+            "type: s3\nbucket: tf-state\nregion: eu-west-1\ndynamodb_table: tf-locks\n",
+        )
+        .unwrap();
+        assert_eq!(
+            backend.render_block(),
+            "terraform {\n  backend \"s3\" {}\n}\n"
+        );
+        let hcl = backend
+            .render_backend_config("aws", "acme", "staging")
+            .unwrap();
+        assert!(hcl.contains("bucket = \"tf-state\""));
+        assert!(hcl.contains("key = \"greentic/aws/acme/staging/terraform.tfstate\""));
+        assert!(hcl.contains("dynamodb_table = \"tf-locks\""));
+        assert!(hcl.contains("encrypt = true"));
+
+        let other = backend
+            .render_backend_config("aws", "acme", "prod")
+            .unwrap();
+        assert!(other.contains("greentic/aws/acme/prod/terraform.tfstate"));
+    }
+
+    #[test]
+    fn scopes_gcs_http_and_pg_backends() {
+        let gcs = StateBackendConfig::Gcs {
+            bucket: "state".into(),
+            prefix: Some("teams/".into()),
+        };
+        let settings = gcs.settings("gcp", "acme", "dev");
+        assert!(settings.contains(&("prefix".into(), "teams/gcp/acme/dev".into())));
+
+        let http = StateBackendConfig::Http {
+            address: "https://state.example.com/tf/".into(),
+            lock_address: Some("https://state.example.com/lock".into()),
+            unlock_address: None,
+        };
+        let hcl = http.render_backend_config("aws", "acme", "dev").unwrap();
+        assert!(hcl.contains("address = \"https://state.example.com/tf/aws/acme/dev\""));
+        assert!(hcl.contains("lock_address = \"https://state.example.com/lock/aws/acme/dev\""));
+
+        let pg = StateBackendConfig::Pg {
+            conn_str: None,
+            schema_prefix: None,
+        };
+        let settings = pg.settings("aws", "acme-corp", "dev");
+        assert_eq!(
+            settings,
+            vec![("schema_name".into(), "greentic_aws_acme_corp_dev".into())]
+        );
+    }
+}
@@ -186,6 +186,9 @@ fn default_config(pack_path: PathBuf) -> DeployerConfig {
         yes: true,
         preview: false,
         dry_run: false,
+        allow_destroy: false,
+        iac_timeout: None,
+        state_backend: Default::default(),
         iac_tool: IaCTool::Terraform,
         output: OutputFormat::Text,
         greentic: greentic_config::ConfigResolver::new()
@@ -197,6 +200,8 @@ fn default_config(pack_path: PathBuf) -> DeployerConfig {
         explain_config: false,
         explain_config_json: false,
         allow_remote_in_offline: false,
+        pack_verification: Default::default(),
+        quiet: false,
     }
 }
 
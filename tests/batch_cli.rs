@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use greentic_types::cbor::encode_pack_manifest;
+use greentic_types::flow::{
+    ComponentRef, Flow, FlowKind, FlowMetadata, InputMapping, Node, OutputMapping, Routing,
+};
+use greentic_types::pack_manifest::{PackFlowEntry, PackKind, PackManifest};
+use greentic_types::{ComponentId, FlowId, NodeId, PackId};
+use semver::Version;
+use serde_json::{Value, json};
+use tempfile::tempdir;
+
+/// Directory deployment pack whose single flow writes one file through the host
+/// `iac-write-files` component.
+fn write_deployment_pack(dir: &Path) {
+    fs::create_dir_all(dir).expect("create pack dir");
+    let node_id = NodeId::try_from("emit").unwrap();
+    let mut nodes = indexmap::IndexMap::default();
+    nodes.insert(
+        node_id.clone(),
+        Node {
+            id: node_id,
+            component: ComponentRef {
+                id: ComponentId::try_from("greentic.host.iac-write-files").unwrap(),
+                pack_alias: None,
+                operation: None,
+            },
+            input: InputMapping {
+                mapping: json!({"files": [{"path": "main.tf", "content": "# tf"}]}),
+            },
+            output: OutputMapping {
+                mapping: Value::Null,
+            },
+            routing: Routing::End,
+            telemetry: Default::default(),
+        },
+    );
+    let flow_id = FlowId::try_from("deploy_flow").unwrap();
+    let manifest = PackManifest {
+        schema_version: "pack-v1".to_string(),
+        pack_id: PackId::try_from("test.deploy").unwrap(),
+        version: Version::new(0, 1, 0),
+        kind: PackKind::Application,
+        publisher: "greentic".to_string(),
+        secret_requirements: Vec::new(),
+        components: Vec::new(),
+        flows: vec![PackFlowEntry {
+            id: flow_id.clone(),
+            kind: FlowKind::ComponentConfig,
+            flow: Flow {
+                schema_version: "flow-v1".into(),
+                id: flow_id,
+                kind: FlowKind::ComponentConfig,
+                entrypoints: BTreeMap::new(),
+                nodes,
+                metadata: FlowMetadata::default(),
+            },
+            tags: Vec::new(),
+            entrypoints: Vec::new(),
+        }],
+        dependencies: Vec::new(),
+        capabilities: Vec::new(),
+        signatures: Default::default(),
+        bootstrap: None,
+        extensions: None,
+    };
+    let bytes = encode_pack_manifest(&manifest).expect("encode manifest");
+    fs::write(dir.join("manifest.cbor"), bytes).expect("write manifest");
+}
+
+fn copy_dir(from: &Path, to: &Path) {
+    fs::create_dir_all(to).expect("create dir");
+    for entry in fs::read_dir(from).expect("read dir") {
+        let entry = entry.expect("dir entry");
+        let target = to.join(entry.file_name());
+        if entry.file_type().expect("file type").is_dir() {
+            copy_dir(&entry.path(), &target);
+        } else {
+            fs::copy(entry.path(), &target).expect("copy file");
+        }
+    }
+}
+
+#[test]
+fn batch_json_report_is_the_only_stdout() {
+    let temp = tempdir().expect("temp dir");
+    let state_dir = temp.path().join("state");
+    fs::create_dir_all(&state_dir).expect("create state dir");
+    let config_file = temp.path().join("config.toml");
+    fs::write(
+        &config_file,
+        format!("[paths]\nstate_dir = \"{}\"\n", state_dir.display()),
+    )
+    .expect("write config");
+    write_deployment_pack(&temp.path().join("providers/deployer/test-deploy"));
+    copy_dir(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/acme-pack"),
+        &temp.path().join("acme-pack"),
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_greentic-deployer"))
+        .current_dir(temp.path())
+        .env("DEPLOY_TARGET_LOCAL_IAC_ONLY_PACK_ID", "test.deploy")
+        .env("DEPLOY_TARGET_LOCAL_IAC_ONLY_FLOW_ID", "deploy_flow")
+        .args(["--config", config_file.to_str().unwrap()])
+        .args(["batch", "--action", "plan", "--output", "json"])
+        .args(["--tenant", "acme,beta", "--environment", "dev"])
+        .args(["--provider", "local", "--pack", "acme-pack"])
+        .output()
+        .expect("run greentic-deployer batch");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "batch failed\nstdout:\n{stdout}\nstderr:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let report: Value = serde_json::from_str(&stdout)
+        .unwrap_or_else(|err| panic!("stdout is not a JSON report ({err}):\n{stdout}"));
+    let targets = report["targets"].as_array().expect("targets array");
+    assert_eq!(targets.len(), 2);
+    for target in targets {
+        assert_eq!(target["status"], "succeeded");
+    }
+    assert!(state_dir.join("deploy/local/acme/dev/main.tf").is_file());
+    assert!(state_dir.join("deploy/local/beta/dev/main.tf").is_file());
+}
@@ -0,0 +1,52 @@
+use std::io::Cursor;
+
+use greentic_deployer::bootstrap::cli::CliPromptAdapter;
+use greentic_deployer::bootstrap::flow_runner::run_bootstrap_flow;
+use serde_json::json;
+
+#[test]
+fn cli_adapter_reprompts_until_answers_are_valid() {
+    let flow = json!({
+        "steps": [
+            {
+                "kind": "prompt",
+                "questions": [
+                    { "id": "port", "prompt": "Port", "type": "int", "min": 1024, "max": 65535,
+                      "help": "Listener port for the control plane" },
+                    { "id": "tls", "prompt": "Enable TLS?", "type": "bool", "default": false },
+                    { "id": "cert", "prompt": "Certificate password", "type": "secret",
+                      "when": { "answer": "tls" } }
+                ]
+            },
+            {
+                "kind": "installer_call",
+                "result": {
+                    "output_version": "v1",
+                    "config_patch": {"port": "{{port}}", "tls": "{{tls}}"},
+                    "secrets_writes": [],
+                    "warnings": [],
+                    "ready": true
+                }
+            }
+        ]
+    });
+    let bytes = serde_json::to_vec(&flow).unwrap();
+
+    let mut output = Vec::new();
+    let mut adapter = CliPromptAdapter::new(Cursor::new("80\nhttp\n8443\n\n"), &mut output);
+    let result = run_bootstrap_flow(&bytes, &mut adapter).expect("flow runs");
+    assert_eq!(result.output.config_patch["port"], json!(8443));
+    assert_eq!(result.output.config_patch["tls"], json!(false));
+
+    let transcript = String::from_utf8(output).unwrap();
+    assert!(transcript.contains("Listener port for the control plane"));
+    assert!(transcript.contains("'port': must be at least 1024"));
+    assert!(transcript.contains("'port': expected an integer"));
+    assert!(transcript.contains("Enable TLS? (y/n) [default: false]: "));
+    assert!(!transcript.contains("Certificate password"));
+
+    let mut output = Vec::new();
+    let mut adapter = CliPromptAdapter::new(Cursor::new("8443\ny\n"), &mut output);
+    let err = run_bootstrap_flow(&bytes, &mut adapter).unwrap_err();
+    assert!(err.to_string().contains("no input provided for cert"));
+}
@@ -0,0 +1,76 @@
+use std::fs;
+
+use greentic_deployer::bootstrap::config_patch::{
+    ConfigFormat, apply_config_patch, plan_config_patch, read_config, write_config,
+};
+use serde_json::json;
+use tempfile::tempdir;
+
+#[test]
+fn merges_patch_into_toml_config_keeping_operator_keys() {
+    let dir = tempdir().expect("temp dir");
+    let path = dir.path().join(".greentic/config.toml");
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(
+        &path,
+        "[telemetry]\nendpoint = \"https://otel.old\"\nsample_rate = 0.5\n\n[operator]\nnote = \"keep\"\n",
+    )
+    .unwrap();
+
+    let applied = apply_config_patch(
+        &path,
+        &json!({"telemetry": {"endpoint": "https://otel.new", "sample_rate": null}, "region": "eu"}),
+    )
+    .expect("apply patch");
+
+    let expected = json!({
+        "telemetry": {"endpoint": "https://otel.new"},
+        "operator": {"note": "keep"},
+        "region": "eu"
+    });
+    assert_eq!(applied, expected);
+    assert_eq!(read_config(&path).unwrap(), Some(expected));
+    assert!(toml::from_str::<toml::Value>(&fs::read_to_string(&path).unwrap()).is_ok());
+}
+
+#[test]
+fn reports_conflicts_with_operator_edits_since_install() {
+    let dir = tempdir().expect("temp dir");
+    let path = dir.path().join("platform.yaml");
+    let installed = json!({"port": 8080, "tls": false, "replicas": 2});
+    // The operator changed the port and added a key after the last install.
+    fs::write(&path, "port: 9090\ntls: false\nreplicas: 2\nextra: true\n").unwrap();
+
+    let plan = plan_config_patch(
+        &path,
+        &json!([
+            {"op": "replace", "path": "/port", "value": 8443},
+            {"op": "replace", "path": "/replicas", "value": 3}
+        ]),
+        Some(&installed),
+    )
+    .expect("plan patch");
+
+    assert_eq!(plan.format, ConfigFormat::Yaml);
+    let changes: Vec<String> = plan.changes.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        changes,
+        vec!["~ /port: 9090 -> 8443", "~ /replicas: 2 -> 3"]
+    );
+    assert_eq!(plan.conflicts.len(), 1);
+    assert_eq!(
+        plan.conflicts[0].to_string(),
+        "/port: changed from 8080 to 9090 since install; patch sets 8443"
+    );
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "port: 9090\ntls: false\nreplicas: 2\nextra: true\n",
+        "planning does not write"
+    );
+
+    write_config(&plan).expect("write config");
+    assert_eq!(
+        read_config(&path).unwrap(),
+        Some(json!({"port": 8443, "tls": false, "replicas": 3, "extra": true}))
+    );
+}
@@ -1,8 +1,14 @@
 use std::io::Write;
 use std::path::PathBuf;
 
+use greentic_deployer::bootstrap::capabilities::build_host_capabilities;
 use greentic_deployer::bootstrap::cli::DenyPromptAdapter;
-use greentic_deployer::bootstrap::flow_runner::run_bootstrap_flow;
+use greentic_deployer::bootstrap::flow_runner::{
+    run_bootstrap_flow, run_bootstrap_flow_with_installer,
+};
+use greentic_deployer::bootstrap::installer::WasmInstaller;
+use greentic_deployer::bootstrap::network::{NetAllowList, NetworkPolicy};
+use greentic_deployer::config::InteractionMode;
 use greentic_deployer::platform::{load_bootstrap_flow, load_platform_pack};
 use greentic_types::cbor::encode_pack_manifest;
 use greentic_types::component::{ComponentCapabilities, ComponentManifest, ComponentProfiles};
@@ -14,7 +20,77 @@ use serde_json::json;
 use tar::Builder;
 use tempfile::tempdir;
 
+/// Installer component that logs its input and returns a fixed, ready bootstrap output.
+const INSTALLER_WAT: &str = r#"
+(component
+  (import "greentic:platform-installer/host@1.0.0" (instance $host
+    (export "log" (func (param "message" string)))
+    (export "http-get" (func (param "url" string) (result (result string (error string)))))
+  ))
+  (core module $libc
+    (memory (export "memory") 1)
+    (global $heap (mut i32) (i32.const 4096))
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (local $ret i32)
+      global.get $heap
+      i32.const 7
+      i32.add
+      i32.const -8
+      i32.and
+      local.tee $ret
+      local.get 3
+      i32.add
+      global.set $heap
+      local.get $ret))
+  (core instance $libc (instantiate $libc))
+  (alias core export $libc "memory" (core memory $mem))
+  (alias core export $libc "realloc" (core func $realloc))
+  (core func $log (canon lower (func $host "log") (memory $mem) (realloc $realloc)))
+  (core module $main
+    (import "env" "memory" (memory 1))
+    (import "host" "log" (func $log (param i32 i32)))
+    (data (i32.const 1024) "{\"output_version\":\"v1\",\"config_patch\":{\"installed\":true},\"secrets_writes\":[],\"warnings\":[],\"ready\":true}")
+    (func (export "run-installer") (param i32 i32) (result i32)
+      local.get 0
+      local.get 1
+      call $log
+      i32.const 600
+      i32.const 0
+      i32.store8
+      i32.const 604
+      i32.const 1024
+      i32.store
+      i32.const 608
+      i32.const 104
+      i32.store
+      i32.const 600))
+  (core instance $main (instantiate $main
+    (with "env" (instance (export "memory" (memory $mem))))
+    (with "host" (instance (export "log" (func $log))))))
+  (func (export "run-installer") (param "input" string) (result (result string (error string)))
+    (canon lift (core func $main "run-installer") (memory $mem) (realloc $realloc)))
+)
+"#;
+
 fn write_stub_gtpack(path: &PathBuf) {
+    let flow = json!({
+        "steps": [
+            {
+                "kind": "installer_call",
+                "result": {
+                    "output_version": "v1",
+                    "config_patch": { "telemetry": { "endpoint": "https://otel" } },
+                    "secrets_writes": [],
+                    "warnings": [],
+                    "ready": true
+                }
+            }
+        ]
+    });
+    write_gtpack(path, "components/installer.wasm", b"stub-wasm", &flow);
+}
+
+fn write_gtpack(path: &PathBuf, installer_entry: &str, installer: &[u8], flow: &serde_json::Value) {
     let manifest = PackManifest {
         schema_version: "pack-v1".to_string(),
         pack_id: PackId::try_from("dev.greentic.platform").unwrap(),
@@ -59,36 +135,17 @@ fn write_stub_gtpack(path: &PathBuf) {
         .append_data(&mut header, "manifest.cbor", encoded_manifest.as_slice())
         .expect("append manifest");
 
-    // stub installer.wasm
-    let stub = b"stub-wasm";
+    // installer component
     let mut wasm_header = tar::Header::new_gnu();
-    wasm_header.set_size(stub.len() as u64);
+    wasm_header.set_size(installer.len() as u64);
     wasm_header.set_mode(0o644);
     wasm_header.set_cksum();
     builder
-        .append_data(
-            &mut wasm_header,
-            "components/installer.wasm",
-            stub.as_slice(),
-        )
+        .append_data(&mut wasm_header, installer_entry, installer)
         .expect("append installer");
 
     // flows
-    let flow_json = json!({
-        "steps": [
-            {
-                "kind": "installer_call",
-                "result": {
-                    "output_version": "v1",
-                    "config_patch": { "telemetry": { "endpoint": "https://otel" } },
-                    "secrets_writes": [],
-                    "warnings": [],
-                    "ready": true
-                }
-            }
-        ]
-    });
-    let flow_bytes = serde_json::to_vec(&flow_json).unwrap();
+    let flow_bytes = serde_json::to_vec(flow).unwrap();
     for name in &["platform_install", "platform_upgrade"] {
         let mut flow_header = tar::Header::new_gnu();
         flow_header.set_size(flow_bytes.len() as u64);
@@ -138,3 +195,62 @@ fn platform_install_invokes_stub_flow() {
         json!("https://otel")
     );
 }
+
+/// Flow whose only step leaves the output to the installer component.
+fn installer_flow() -> serde_json::Value {
+    json!({
+        "steps": [
+            { "kind": "installer_call", "input": { "mode": "install" } }
+        ]
+    })
+}
+
+#[test]
+fn installer_call_without_result_runs_pack_installer_component() {
+    let dir = tempdir().expect("temp dir");
+    let path = dir.path().join("platform.gtpack");
+    let wasm = wat::parse_str(INSTALLER_WAT).expect("installer wat");
+    write_gtpack(
+        &path,
+        "components/dev.greentic.platform.installer.wasm",
+        &wasm,
+        &installer_flow(),
+    );
+
+    let info = load_platform_pack(&path).expect("load pack");
+    let flow_bytes = load_bootstrap_flow(&path, &info.manifest, true).expect("load bootstrap flow");
+    let policy = NetworkPolicy::new(false, false, NetAllowList::parse(None).unwrap());
+    let capabilities = build_host_capabilities(InteractionMode::Json, false, &policy);
+    let mut installer =
+        WasmInstaller::from_pack(&path, &info.manifest, capabilities, policy).expect("installer");
+    let mut adapter = DenyPromptAdapter;
+    let result = run_bootstrap_flow_with_installer(&flow_bytes, &mut adapter, Some(&mut installer))
+        .expect("run bootstrap flow");
+
+    assert!(result.output.ready);
+    assert_eq!(result.output.config_patch["installed"], json!(true));
+    let logged: serde_json::Value = serde_json::from_str(&installer.logs[0]).unwrap();
+    assert_eq!(logged["input"]["mode"], json!("install"));
+}
+
+#[test]
+fn installer_call_without_result_or_installer_fails() {
+    let dir = tempdir().expect("temp dir");
+    let path = dir.path().join("platform.gtpack");
+    write_gtpack(
+        &path,
+        "components/installer.wasm",
+        b"stub-wasm",
+        &installer_flow(),
+    );
+
+    let info = load_platform_pack(&path).expect("load pack");
+    let flow_bytes = load_bootstrap_flow(&path, &info.manifest, true).expect("load bootstrap flow");
+    let mut adapter = DenyPromptAdapter;
+    let err = run_bootstrap_flow(&flow_bytes, &mut adapter).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("installer_call missing result and no installer component is available"),
+        "{err}"
+    );
+}
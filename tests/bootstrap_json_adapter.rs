@@ -31,5 +31,55 @@ fn json_adapter_supplies_answers() {
 
     let result = run_bootstrap_flow(&bytes, &mut adapter).expect("flow runs");
     assert!(result.output.ready);
-    assert_eq!(result.output.config_patch["region"], json!("{{region}}"));
+    assert_eq!(result.output.config_patch["region"], json!("eu-west-1"));
+}
+
+#[test]
+fn json_adapter_enforces_question_types_and_conditions() {
+    let flow = json!({
+        "steps": [
+            {
+                "kind": "prompt",
+                "questions": [
+                    { "id": "tier", "prompt": "Tier", "type": "enum", "options": ["edge", "cloud"] }
+                ]
+            },
+            {
+                "kind": "prompt",
+                "questions": [
+                    { "id": "replicas", "prompt": "Replicas", "type": "int", "min": 1, "max": 5,
+                      "when": { "answer": "tier", "equals": "cloud" } },
+                    { "id": "device", "prompt": "Device id", "pattern": "dev-[0-9]+",
+                      "when": { "answer": "tier", "equals": "edge" } }
+                ]
+            },
+            {
+                "kind": "installer_call",
+                "result": {
+                    "output_version": "v1",
+                    "config_patch": {"tier": "{{tier}}", "replicas": "{{replicas}}"},
+                    "secrets_writes": [],
+                    "warnings": [],
+                    "ready": true
+                }
+            }
+        ]
+    });
+    let bytes = serde_json::to_vec(&flow).unwrap();
+
+    let mut adapter =
+        JsonPromptAdapter::new(json!({"tier": "cloud", "replicas": "3"})).expect("valid answers");
+    let result = run_bootstrap_flow(&bytes, &mut adapter).expect("flow runs");
+    assert_eq!(result.output.config_patch["replicas"], json!(3));
+
+    let mut adapter =
+        JsonPromptAdapter::new(json!({"tier": "cloud", "replicas": 9})).expect("valid answers");
+    let err = run_bootstrap_flow(&bytes, &mut adapter).unwrap_err();
+    assert!(err.to_string().contains("'replicas': must be at most 5"));
+
+    // The device question is skipped for cloud but required (and validated) for edge.
+    let mut adapter =
+        JsonPromptAdapter::new(json!({"tier": "edge", "device": "gw-1"})).expect("valid answers");
+    let err = run_bootstrap_flow(&bytes, &mut adapter).unwrap_err();
+    assert!(err.to_string().contains("'device': must match pattern"));
 }
@@ -1,12 +1,38 @@
+mod common;
+
 use std::fs;
 
+use base64::Engine;
+use base64::engine::general_purpose;
+use common::{TOKEN, fake_api_server, fake_vault_server, write_kubeconfig};
+use greentic_deployer::bootstrap::encrypted_file::{KeySource, load_encrypted_file};
 use greentic_deployer::bootstrap::output::SecretWrite;
 use greentic_deployer::bootstrap::secrets::{
-    execute_writes, parse_backend, set_k8s_secret_dir_override,
+    SecretsBackend, SecretsSnapshot, execute_writes, parse_backend, restore_backend, rotate_key,
+    set_k8s_secret_dir_override, snapshot_backend,
 };
-use serde_json::json;
+use serde_json::{Value, json};
 use tempfile::tempdir;
 
+const SECRET_PATH: &str = "/api/v1/namespaces/dev/secrets/greentic-bootstrap";
+
+fn api_key_write() -> Vec<SecretWrite> {
+    vec![SecretWrite {
+        key: "api_key".into(),
+        value: Some("supersecret".into()),
+        scope: Some("dev".into()),
+        metadata: None,
+    }]
+}
+
+fn decode(value: &Value) -> String {
+    value
+        .as_str()
+        .and_then(|s| general_purpose::STANDARD.decode(s).ok())
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .unwrap()
+}
+
 #[test]
 fn writes_secrets_to_file_backend() {
     let dir = tempdir().expect("temp dir");
@@ -40,28 +66,283 @@ fn errors_on_unknown_backend() {
 }
 
 #[test]
-fn writes_secrets_to_k8s_backend_stub() {
+fn renders_k8s_secret_in_dry_run_mode() {
     let dir = tempdir().expect("temp dir");
     set_k8s_secret_dir_override(Some(dir.path().to_path_buf()));
-    let backend = parse_backend("k8s:dev/greentic-bootstrap").expect("parse k8s backend succeeds");
+    let backend = parse_backend("k8s:dev/greentic-bootstrap")
+        .expect("parse k8s backend succeeds")
+        .with_k8s_options(None, true);
+
+    execute_writes(&backend, &api_key_write()).expect("write secrets");
+
+    let secret_path = dir.path().join("dev").join("greentic-bootstrap.yaml");
+    let content = std::fs::read_to_string(secret_path).expect("secret file exists");
+    let parsed: serde_json::Value = serde_yaml_bw::from_str(&content).expect("yaml parses to json");
+    assert_eq!(decode(&parsed["data"]["dev.api_key"]), "supersecret");
+}
+
+#[test]
+fn k8s_backend_merges_into_existing_secret_and_restores_it() {
+    let Some((server, objects)) = fake_api_server() else {
+        return;
+    };
+    let dir = tempdir().expect("temp dir");
+    let kubeconfig = write_kubeconfig(dir.path(), &server);
+    objects.lock().unwrap().insert(
+        SECRET_PATH.into(),
+        json!({
+            "apiVersion": "v1",
+            "kind": "Secret",
+            "metadata": { "name": "greentic-bootstrap", "namespace": "dev", "resourceVersion": "7" },
+            "type": "Opaque",
+            "data": {
+                "db_password": general_purpose::STANDARD.encode("keep-me"),
+                "dev.api_key": general_purpose::STANDARD.encode("old")
+            }
+        }),
+    );
+    let backend = parse_backend("k8s:dev/greentic-bootstrap")
+        .unwrap()
+        .with_k8s_options(Some(kubeconfig), false);
+
+    let snapshot = snapshot_backend(&backend, &api_key_write()).expect("snapshot secret");
+    execute_writes(&backend, &api_key_write()).expect("write secrets");
+    {
+        let objects = objects.lock().unwrap();
+        let data = &objects[SECRET_PATH]["data"];
+        assert_eq!(decode(&data["dev.api_key"]), "supersecret");
+        assert_eq!(decode(&data["db_password"]), "keep-me");
+    }
+
+    restore_backend(&snapshot).expect("restore secret");
+    let objects = objects.lock().unwrap();
+    let data = &objects[SECRET_PATH]["data"];
+    assert_eq!(decode(&data["dev.api_key"]), "old");
+    assert_eq!(decode(&data["db_password"]), "keep-me");
+}
+
+#[test]
+fn k8s_backend_restore_removes_secret_it_created() {
+    let Some((server, objects)) = fake_api_server() else {
+        return;
+    };
+    let dir = tempdir().expect("temp dir");
+    let backend = parse_backend("k8s:namespace=dev,name=greentic-bootstrap")
+        .unwrap()
+        .with_k8s_options(Some(write_kubeconfig(dir.path(), &server)), false);
+
+    let snapshot = snapshot_backend(&backend, &api_key_write()).expect("snapshot missing secret");
+    assert_eq!(snapshot.content, None);
+    execute_writes(&backend, &api_key_write()).expect("create secret");
+    {
+        let objects = objects.lock().unwrap();
+        let secret = &objects[SECRET_PATH];
+        assert_eq!(secret["type"], json!("Opaque"));
+        assert_eq!(decode(&secret["data"]["dev.api_key"]), "supersecret");
+    }
+
+    restore_backend(&snapshot).expect("restore");
+    assert!(!objects.lock().unwrap().contains_key(SECRET_PATH));
+}
+
+#[test]
+fn greentic_backend_writes_scoped_secrets_and_restores_them() {
+    let backend = parse_backend("greentic:dev/acme").expect("parse greentic backend");
+    let writes = vec![
+        SecretWrite {
+            key: "api_key".into(),
+            value: Some("supersecret".into()),
+            scope: None,
+            metadata: None,
+        },
+        SecretWrite {
+            key: "db_password".into(),
+            value: Some("hunter2".into()),
+            scope: Some("staging/acme/ops".into()),
+            metadata: None,
+        },
+    ];
+    let uris = [
+        "secrets://dev/acme/_/configs/api_key",
+        "secrets://staging/acme/ops/configs/db_password",
+    ];
+
+    let contents = |snapshot: &SecretsSnapshot| -> Value {
+        serde_json::from_str(snapshot.content.as_deref().unwrap()).unwrap()
+    };
+
+    let snapshot = snapshot_backend(&backend, &writes).expect("snapshot");
+    assert_eq!(contents(&snapshot), json!({ uris[0]: null, uris[1]: null }));
+
+    execute_writes(&backend, &writes).expect("write secrets");
+    let written = snapshot_backend(&backend, &writes).expect("snapshot after write");
+    assert_eq!(
+        contents(&written),
+        json!({ uris[0]: "supersecret", uris[1]: "hunter2" })
+    );
+
+    restore_backend(&snapshot).expect("restore");
+    let restored = snapshot_backend(&backend, &writes).expect("snapshot after restore");
+    assert_eq!(contents(&restored), contents(&snapshot));
+}
+
+#[test]
+fn vault_backend_writes_kv2_secrets_and_restores_them() {
+    let Some((server, secrets)) = fake_vault_server() else {
+        return;
+    };
+    // SAFETY: no other test in this binary reads or writes the Vault environment.
+    unsafe { std::env::set_var("VAULT_TOKEN", TOKEN) };
+    secrets
+        .lock()
+        .unwrap()
+        .insert("kv/greentic/dev/api_key".into(), json!({ "value": "old" }));
+    // Soft-deleted latest version: reads 404, but the path and its history still exist.
+    secrets
+        .lock()
+        .unwrap()
+        .insert("kv/greentic/legacy_token".into(), Value::Null);
+    let backend = parse_backend(&format!("vault:{server}/kv/greentic")).expect("parse vault");
+    let writes = vec![
+        api_key_write().remove(0),
+        SecretWrite {
+            key: "db_password".into(),
+            value: Some("hunter2".into()),
+            scope: None,
+            metadata: Some(json!({ "source": "installer" })),
+        },
+        SecretWrite {
+            key: "legacy_token".into(),
+            value: Some("fresh".into()),
+            scope: None,
+            metadata: None,
+        },
+    ];
+
+    let snapshot = snapshot_backend(&backend, &writes).expect("snapshot");
+    execute_writes(&backend, &writes).expect("write secrets");
+    {
+        let secrets = secrets.lock().unwrap();
+        assert_eq!(
+            secrets["kv/greentic/dev/api_key"]["value"],
+            json!("supersecret")
+        );
+        assert_eq!(
+            secrets["kv/greentic/db_password"],
+            json!({ "value": "hunter2", "metadata": { "source": "installer" } })
+        );
+    }
+
+    restore_backend(&snapshot).expect("restore");
+    let secrets = secrets.lock().unwrap();
+    assert_eq!(
+        secrets["kv/greentic/dev/api_key"],
+        json!({ "value": "old" })
+    );
+    assert!(!secrets.contains_key("kv/greentic/db_password"));
+    assert_eq!(secrets["kv/greentic/legacy_token"], Value::Null);
+}
+
+#[test]
+fn parses_greentic_and_vault_backends() {
+    assert_eq!(
+        parse_backend("greentic:dev/acme/ops").unwrap(),
+        SecretsBackend::Greentic {
+            env: "dev".into(),
+            tenant: "acme".into(),
+            team: Some("ops".into()),
+        }
+    );
+    assert!(parse_backend("greentic:dev").is_err());
+    assert_eq!(
+        parse_backend("vault:secret").unwrap(),
+        SecretsBackend::Vault {
+            addr: None,
+            mount: "secret".into(),
+            prefix: None,
+        }
+    );
+    assert_eq!(
+        parse_backend("vault:https://vault.internal:8200/kv/greentic/edge").unwrap(),
+        SecretsBackend::Vault {
+            addr: Some("https://vault.internal:8200".into()),
+            mount: "kv".into(),
+            prefix: Some("greentic/edge".into()),
+        }
+    );
+}
+
+#[test]
+fn encrypted_file_backend_keeps_values_sealed_and_restores_them() {
+    let dir = tempdir().expect("temp dir");
+    let path = dir.path().join("secrets.enc");
+    let key_file = dir.path().join("secrets.key");
+    fs::write(&key_file, [42u8; 32]).unwrap();
+    let backend = parse_backend(&format!("file+enc:{}", path.display()))
+        .expect("parse encrypted file backend")
+        .with_key_file(Some(key_file.clone()));
+    let key = KeySource::KeyFile(key_file);
+
+    execute_writes(&backend, &api_key_write()).expect("write secrets");
+    let raw = fs::read_to_string(&path).expect("encrypted file exists");
+    assert!(!raw.contains("supersecret"));
+    let stored: Value = serde_json::from_str(&raw).unwrap();
+    assert_eq!(stored["encryption"]["kdf"]["name"], json!("hkdf-sha256"));
+    assert_eq!(stored["secrets"]["dev/api_key"]["scope"], json!("dev"));
+    let records = load_encrypted_file(&path, &key).expect("decrypt");
+    assert_eq!(records["dev/api_key"]["value"], json!("supersecret"));
+
     let writes = vec![SecretWrite {
-        key: "api_key".into(),
-        value: Some("supersecret".into()),
+        key: "db_password".into(),
+        value: Some("hunter2".into()),
         scope: Some("dev".into()),
         metadata: None,
     }];
+    let snapshot = snapshot_backend(&backend, &writes).expect("snapshot");
+    execute_writes(&backend, &writes).expect("second write");
+    assert_eq!(load_encrypted_file(&path, &key).unwrap().len(), 2);
+    restore_backend(&snapshot).expect("restore");
+    let records = load_encrypted_file(&path, &key).unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records["dev/api_key"]["value"], json!("supersecret"));
 
-    execute_writes(&backend, &writes).expect("write secrets");
+    let wrong_key = dir.path().join("wrong.key");
+    fs::write(&wrong_key, [1u8; 32]).unwrap();
+    let wrong = parse_backend(&format!("file+enc:{}", path.display()))
+        .unwrap()
+        .with_key_file(Some(wrong_key));
+    let err = snapshot_backend(&wrong, &writes).expect_err("wrong key is rejected up front");
+    assert!(err.to_string().contains("does not decrypt"));
+}
 
-    let secret_path = dir.path().join("dev").join("greentic-bootstrap.yaml");
-    let content = std::fs::read_to_string(secret_path).expect("secret file exists");
-    let parsed: serde_json::Value = serde_yaml_bw::from_str(&content).expect("yaml parses to json");
-    use base64::Engine;
-    use base64::engine::general_purpose;
-    let decoded = parsed["data"]["dev/api_key"]
-        .as_str()
-        .and_then(|s| general_purpose::STANDARD.decode(s).ok())
-        .and_then(|bytes| String::from_utf8(bytes).ok())
-        .unwrap();
-    assert_eq!(decoded, "supersecret");
+#[test]
+fn rotate_key_re_encrypts_encrypted_file_backend() {
+    let dir = tempdir().expect("temp dir");
+    let path = dir.path().join("secrets.enc");
+    let old_key = dir.path().join("old.key");
+    fs::write(&old_key, [3u8; 32]).unwrap();
+    let uri = format!("file+enc:{}", path.display());
+    let backend = parse_backend(&uri)
+        .unwrap()
+        .with_key_file(Some(old_key.clone()));
+    execute_writes(&backend, &api_key_write()).expect("write secrets");
+
+    // SAFETY: no other test in this binary reads or writes this variable.
+    unsafe {
+        std::env::set_var(
+            "GREENTIC_TEST_NEW_PASSPHRASE",
+            "correct horse battery staple",
+        )
+    };
+    let new_key = KeySource::PassphraseEnv("GREENTIC_TEST_NEW_PASSPHRASE".into());
+    assert_eq!(rotate_key(&backend, &new_key).expect("rotate"), 1);
+
+    assert!(load_encrypted_file(&path, &KeySource::KeyFile(old_key)).is_err());
+    let records = load_encrypted_file(&path, &new_key).expect("new key opens the file");
+    assert_eq!(records["dev/api_key"]["value"], json!("supersecret"));
+    let stored: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(stored["encryption"]["kdf"]["name"], json!("pbkdf2-sha256"));
+
+    let plain = parse_backend(&format!("file:{}", dir.path().join("plain.db").display())).unwrap();
+    assert!(rotate_key(&plain, &new_key).is_err());
 }
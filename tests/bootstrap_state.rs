@@ -1,5 +1,6 @@
 use std::path::PathBuf;
 
+use greentic_deployer::bootstrap::releases::RollbackRef;
 use greentic_deployer::bootstrap::state::{
     BootstrapState, ensure_upgrade_allowed, load_state, save_state,
 };
@@ -58,3 +59,23 @@ fn upgrade_state_sets_last_upgrade_timestamp() {
     assert_eq!(upgraded.digest.as_deref(), Some("def"));
     assert_eq!(upgraded.rollback_ref.as_deref(), Some("rollback"));
 }
+
+#[test]
+fn rollback_state_restores_prior_release_and_points_forward() {
+    let state = BootstrapState::installed_now(Some("1.0.0".into()), Some("abc".into()));
+    let upgraded = BootstrapState::upgraded_from(
+        &state,
+        Some("1.1.0".into()),
+        Some("def".into()),
+        Some(RollbackRef::new(Some("1.0.0"), Some("abc")).to_string()),
+    );
+    let target = RollbackRef::parse(upgraded.rollback_ref.as_deref().unwrap()).unwrap();
+    let rolled_back = BootstrapState::rolled_back_to(&upgraded, &target);
+    assert_eq!(rolled_back.version.as_deref(), Some("1.0.0"));
+    assert_eq!(rolled_back.digest.as_deref(), Some("abc"));
+    assert_eq!(rolled_back.installed_at, state.installed_at);
+    assert_eq!(
+        rolled_back.rollback_ref.as_deref(),
+        Some("version=1.1.0,digest=def")
+    );
+}
@@ -1,7 +1,32 @@
-use greentic_deployer::bootstrap::state::{BootstrapState, load_state_backend, save_state_backend};
-use greentic_deployer::config::BootstrapStateBackend;
+mod common;
+
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD;
+use common::{TOKEN, fake_api_server};
+use greentic_deployer::bootstrap::k8s_client::K8sConnection;
+use greentic_deployer::bootstrap::k8s_state::{K8sStateStore, K8sStateTarget};
+use greentic_deployer::bootstrap::state::{
+    BootstrapState, StateStore, load_state_backend, save_state_backend,
+};
+use greentic_deployer::config::{BootstrapStateBackend, K8sStateKind};
+use serde_json::json;
 use tempfile::tempdir;
 
+fn k8s_store(server: &str, kind: K8sStateKind) -> StateStore {
+    let connection = K8sConnection {
+        server: server.to_string(),
+        token: Some(TOKEN.into()),
+        ..K8sConnection::default()
+    };
+    let target = K8sStateTarget {
+        namespace: "greentic-system".into(),
+        name: "greentic-bootstrap".into(),
+        kind,
+        kubeconfig: None,
+    };
+    StateStore::K8s(K8sStateStore::new(connection, &target))
+}
+
 #[test]
 fn file_backend_reads_and_writes() {
     let dir = tempdir().expect("temp dir");
@@ -17,10 +42,106 @@ fn file_backend_reads_and_writes() {
 }
 
 #[test]
-fn k8s_backend_not_available_errors() {
-    let dir = tempdir().expect("temp dir");
-    let path = dir.path().join("state.json");
-    let err = load_state_backend(BootstrapStateBackend::K8s, &path, "ns", "name")
-        .expect_err("k8s backend should error");
-    assert!(format!("{err}").contains("not available"));
+fn k8s_backend_round_trips_state_through_a_configmap() {
+    let Some((server, objects)) = fake_api_server() else {
+        return;
+    };
+    let store = k8s_store(&server, K8sStateKind::ConfigMap);
+    assert_eq!(
+        store.describe(),
+        "configmap greentic-system/greentic-bootstrap"
+    );
+    assert_eq!(store.load().expect("load missing state"), None);
+
+    let state = BootstrapState::installed_now(Some("1.2.3".into()), Some("sha256:abc".into()));
+    store.save(&state).expect("create state");
+    let upgraded = BootstrapState::upgraded_from(&state, Some("1.3.0".into()), None, None);
+    store.save(&upgraded).expect("update state");
+
+    let fresh = k8s_store(&server, K8sStateKind::ConfigMap);
+    assert_eq!(fresh.load().expect("load state"), Some(upgraded));
+    let objects = objects.lock().unwrap();
+    let object = &objects["/api/v1/namespaces/greentic-system/configmaps/greentic-bootstrap"];
+    assert_eq!(object["kind"], json!("ConfigMap"));
+    assert_eq!(object["metadata"]["resourceVersion"], json!("2"));
+    assert!(
+        object["data"]["state.json"]
+            .as_str()
+            .unwrap()
+            .contains("1.3.0")
+    );
+}
+
+#[test]
+fn k8s_backend_stores_base64_state_in_a_secret() {
+    let Some((server, objects)) = fake_api_server() else {
+        return;
+    };
+    let store = k8s_store(&server, K8sStateKind::Secret);
+    let state = BootstrapState::installed_now(Some("2.0.0".into()), None);
+    store.save(&state).expect("save state");
+    assert_eq!(store.load().expect("load state"), Some(state));
+
+    let objects = objects.lock().unwrap();
+    let object = &objects["/api/v1/namespaces/greentic-system/secrets/greentic-bootstrap"];
+    assert_eq!(object["type"], json!("Opaque"));
+    let decoded = STANDARD
+        .decode(object["data"]["state.json"].as_str().unwrap())
+        .unwrap();
+    assert!(String::from_utf8(decoded).unwrap().contains("2.0.0"));
+}
+
+#[test]
+fn k8s_backend_rejects_writes_based_on_stale_state() {
+    let Some((server, _objects)) = fake_api_server() else {
+        return;
+    };
+    let first = k8s_store(&server, K8sStateKind::ConfigMap);
+    let second = k8s_store(&server, K8sStateKind::ConfigMap);
+    let state = BootstrapState::installed_now(Some("1.0.0".into()), None);
+
+    // Both installers see "not installed"; only the first create wins.
+    assert_eq!(first.load().unwrap(), None);
+    assert_eq!(second.load().unwrap(), None);
+    first.save(&state).expect("first install");
+    let err = second.save(&state).expect_err("concurrent install");
+    assert!(err.to_string().contains("changed by another installer"));
+
+    // Same for updates: a reader that lost the race must reload before writing.
+    let stale = k8s_store(&server, K8sStateKind::ConfigMap);
+    stale.load().unwrap();
+    first
+        .save(&BootstrapState::upgraded_from(
+            &state,
+            Some("1.1.0".into()),
+            None,
+            None,
+        ))
+        .expect("first upgrade");
+    let err = stale.save(&state).expect_err("stale upgrade");
+    assert!(err.to_string().contains("changed by another installer"));
+    stale.load().unwrap();
+    stale.save(&state).expect("save after reload");
+}
+
+#[test]
+fn k8s_backend_reports_api_errors() {
+    let Some((server, _objects)) = fake_api_server() else {
+        return;
+    };
+    let connection = K8sConnection {
+        server,
+        token: Some("wrong".into()),
+        ..K8sConnection::default()
+    };
+    let target = K8sStateTarget {
+        namespace: "greentic-system".into(),
+        name: "greentic-bootstrap".into(),
+        kind: K8sStateKind::ConfigMap,
+        kubeconfig: None,
+    };
+    let err = K8sStateStore::new(connection, &target)
+        .load()
+        .expect_err("unauthorized");
+    assert!(err.to_string().contains("HTTP 401: Unauthorized"));
 }
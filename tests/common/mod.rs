@@ -0,0 +1,366 @@
+//! Shared helpers for integration tests that talk to a fake Kubernetes API, Vault server or OCI
+//! registry.
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+pub const TOKEN: &str = "sa-token";
+
+/// Objects stored by the fake API server, keyed by request path.
+pub type Objects = Arc<Mutex<HashMap<String, Value>>>;
+
+/// Registry login accepted by the fake OCI registry's token endpoint.
+pub const REGISTRY_USER: &str = "ci";
+pub const REGISTRY_PASSWORD: &str = "secret";
+
+/// Content served by the fake OCI registry: `(content type, body)` keyed by request path.
+pub type Blobs = Arc<Mutex<HashMap<String, (String, Vec<u8>)>>>;
+
+/// Minimal stand-in for the Kubernetes core/v1 API: GET/POST/PUT/DELETE on namespaced objects
+/// with bearer auth and resourceVersion conflict checks.
+pub fn fake_api_server() -> Option<(String, Objects)> {
+    let listener = match TcpListener::bind("127.0.0.1:0") {
+        Ok(listener) => listener,
+        Err(err) => {
+            // Some environments disallow binding listeners; skip in that case.
+            eprintln!("fake api server bind skipped: {err}");
+            return None;
+        }
+    };
+    let server = format!("http://{}", listener.local_addr().unwrap());
+    let objects: Objects = Arc::default();
+    let store = objects.clone();
+    thread::spawn(move || {
+        let mut version = 0u64;
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let Some((method, path, headers, body)) = read_request(&mut stream) else {
+                continue;
+            };
+            let mut objects = store.lock().unwrap();
+            let (status, reply) = if headers.get("authorization")
+                != Some(&format!("Bearer {TOKEN}"))
+            {
+                (401, json!({"message": "Unauthorized"}))
+            } else {
+                match method.as_str() {
+                    "GET" => match objects.get(&path) {
+                        Some(object) => (200, object.clone()),
+                        None => (404, json!({"message": "not found"})),
+                    },
+                    "POST" => {
+                        let mut object = body;
+                        let key =
+                            format!("{path}/{}", object["metadata"]["name"].as_str().unwrap());
+                        match objects.entry(key) {
+                            Entry::Occupied(_) => (409, json!({"message": "already exists"})),
+                            Entry::Vacant(slot) => {
+                                version += 1;
+                                object["metadata"]["resourceVersion"] = json!(version.to_string());
+                                slot.insert(object.clone());
+                                (201, object)
+                            }
+                        }
+                    }
+                    "PUT" => {
+                        let mut object = body;
+                        let current = objects
+                            .get(&path)
+                            .map(|existing| existing["metadata"]["resourceVersion"].clone());
+                        match current {
+                            None => (404, json!({"message": "not found"})),
+                            Some(current) if current != object["metadata"]["resourceVersion"] => {
+                                (409, json!({"message": "the object has been modified"}))
+                            }
+                            Some(_) => {
+                                version += 1;
+                                object["metadata"]["resourceVersion"] = json!(version.to_string());
+                                objects.insert(path, object.clone());
+                                (200, object)
+                            }
+                        }
+                    }
+                    "DELETE" => match objects.remove(&path) {
+                        Some(_) => (200, json!({"status": "Success"})),
+                        None => (404, json!({"message": "not found"})),
+                    },
+                    _ => (405, json!({"message": "method not allowed"})),
+                }
+            };
+            drop(objects);
+            respond(&mut stream, status, &reply);
+        }
+    });
+    Some((server, objects))
+}
+
+/// Minimal stand-in for a Vault KV v2 engine: secret data keyed by `<mount>/<path>`, requests
+/// authenticated with `X-Vault-Token: TOKEN`. A `null` value is a path whose latest version is
+/// soft-deleted (data endpoint `DELETE`); metadata `DELETE` removes the path entirely.
+pub fn fake_vault_server() -> Option<(String, Objects)> {
+    let listener = match TcpListener::bind("127.0.0.1:0") {
+        Ok(listener) => listener,
+        Err(err) => {
+            eprintln!("fake vault server bind skipped: {err}");
+            return None;
+        }
+    };
+    let server = format!("http://{}", listener.local_addr().unwrap());
+    let secrets: Objects = Arc::default();
+    let store = secrets.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let Some((method, path, headers, body)) = read_request(&mut stream) else {
+                continue;
+            };
+            let mut secrets = store.lock().unwrap();
+            let route = path.strip_prefix("/v1/").and_then(|rest| {
+                let (mount, rest) = rest.split_once('/')?;
+                let (kind, path) = rest.split_once('/')?;
+                Some((kind.to_string(), format!("{mount}/{path}")))
+            });
+            let (status, reply) = if headers.get("x-vault-token").map(String::as_str) != Some(TOKEN)
+            {
+                (403, json!({"errors": ["permission denied"]}))
+            } else {
+                match (method.as_str(), route) {
+                    ("GET", Some((kind, key))) if kind == "data" => match secrets.get(&key) {
+                        Some(data) if !data.is_null() => (200, json!({"data": {"data": data}})),
+                        _ => (404, json!({"errors": []})),
+                    },
+                    ("GET", Some((kind, key))) if kind == "metadata" => {
+                        if secrets.contains_key(&key) {
+                            (200, json!({"data": {"current_version": 1}}))
+                        } else {
+                            (404, json!({"errors": []}))
+                        }
+                    }
+                    ("DELETE", Some((kind, key))) if kind == "data" => {
+                        if let Some(data) = secrets.get_mut(&key) {
+                            *data = Value::Null;
+                        }
+                        (204, Value::Null)
+                    }
+                    ("POST", Some((kind, key))) if kind == "data" => {
+                        secrets.insert(key, body["data"].clone());
+                        (200, json!({"data": {"version": 1}}))
+                    }
+                    ("DELETE", Some((kind, key))) if kind == "metadata" => {
+                        secrets.remove(&key);
+                        (204, Value::Null)
+                    }
+                    _ => (405, json!({"errors": ["unsupported request"]})),
+                }
+            };
+            drop(secrets);
+            respond(&mut stream, status, &reply);
+        }
+    });
+    Some((server, secrets))
+}
+
+/// Minimal stand-in for an OCI distribution registry behind a bearer-token challenge. Requests
+/// to `/v2/...` need the token that `/token` issues for `REGISTRY_USER:REGISTRY_PASSWORD`.
+/// Supports manifest/blob `GET`/`HEAD`, monolithic blob uploads and manifest `PUT`s.
+pub fn fake_oci_registry() -> Option<(String, Blobs)> {
+    let listener = match TcpListener::bind("127.0.0.1:0") {
+        Ok(listener) => listener,
+        Err(err) => {
+            eprintln!("fake oci registry bind skipped: {err}");
+            return None;
+        }
+    };
+    let addr = listener.local_addr().unwrap();
+    let blobs: Blobs = Arc::default();
+    let store = blobs.clone();
+    let registry_token = "registry-token";
+    let login = format!(
+        "Basic {}",
+        STANDARD.encode(format!("{REGISTRY_USER}:{REGISTRY_PASSWORD}"))
+    );
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let Some((method, path, headers, body)) = read_raw_request(&mut stream) else {
+                continue;
+            };
+            let authorization = headers.get("authorization").map(String::as_str);
+            if path.starts_with("/token") {
+                let (status, reply) = if authorization == Some(login.as_str()) {
+                    (200, json!({"token": registry_token}))
+                } else {
+                    (401, json!({"errors": [{"code": "UNAUTHORIZED"}]}))
+                };
+                respond(&mut stream, status, &reply);
+                continue;
+            }
+            if authorization != Some(&format!("Bearer {registry_token}")) {
+                let challenge = format!(
+                    "WWW-Authenticate: Bearer realm=\"http://{addr}/token\",service=\"{addr}\",scope=\"repository:pull\""
+                );
+                let body = json!({"errors": [{"code": "UNAUTHORIZED"}]}).to_string();
+                respond_bytes(
+                    &mut stream,
+                    401,
+                    &["Content-Type: application/json", &challenge],
+                    body.as_bytes(),
+                );
+                continue;
+            }
+            let digest = |bytes: &[u8]| format!("sha256:{:x}", Sha256::digest(bytes));
+            let mut blobs = store.lock().unwrap();
+            match method.as_str() {
+                "GET" | "HEAD" => match blobs.get(&path) {
+                    Some((content_type, body)) => {
+                        let content_type = format!("Content-Type: {content_type}");
+                        let digest = format!("Docker-Content-Digest: {}", digest(body));
+                        let body: &[u8] = if method == "HEAD" { &[] } else { body };
+                        respond_bytes(&mut stream, 200, &[&content_type, &digest], body);
+                    }
+                    None => respond(
+                        &mut stream,
+                        404,
+                        &json!({"errors": [{"code": "MANIFEST_UNKNOWN"}]}),
+                    ),
+                },
+                "POST" if path.ends_with("/blobs/uploads/") => {
+                    let location = format!("Location: {path}upload-1");
+                    respond_bytes(&mut stream, 202, &[&location], &[]);
+                }
+                "PUT" if path.contains("/blobs/uploads/") => {
+                    let (upload, query) = path.split_once('?').unwrap_or((&path, ""));
+                    let expected = query
+                        .split('&')
+                        .find_map(|pair| pair.strip_prefix("digest="))
+                        .map(|value| value.replace("%3A", ":"))
+                        .unwrap_or_default();
+                    if expected != digest(&body) {
+                        respond(
+                            &mut stream,
+                            400,
+                            &json!({"errors": [{"code": "DIGEST_INVALID"}]}),
+                        );
+                        continue;
+                    }
+                    let repo = upload.split("/blobs/uploads/").next().unwrap_or_default();
+                    let location = format!("Location: {repo}/blobs/{expected}");
+                    blobs.insert(
+                        format!("{repo}/blobs/{expected}"),
+                        ("application/octet-stream".into(), body),
+                    );
+                    respond_bytes(&mut stream, 201, &[&location], &[]);
+                }
+                "PUT" if path.contains("/manifests/") => {
+                    let content_type = headers.get("content-type").cloned().unwrap_or_default();
+                    let manifest_digest = digest(&body);
+                    let (repo, _) = path.split_once("/manifests/").unwrap();
+                    blobs.insert(
+                        format!("{repo}/manifests/{manifest_digest}"),
+                        (content_type.clone(), body.clone()),
+                    );
+                    blobs.insert(path.clone(), (content_type, body));
+                    let header = format!("Docker-Content-Digest: {manifest_digest}");
+                    respond_bytes(&mut stream, 201, &[&header], &[]);
+                }
+                _ => respond(
+                    &mut stream,
+                    405,
+                    &json!({"errors": [{"code": "UNSUPPORTED"}]}),
+                ),
+            }
+        }
+    });
+    Some((addr.to_string(), blobs))
+}
+
+fn respond(stream: &mut TcpStream, status: u16, reply: &Value) {
+    let body = reply.to_string();
+    respond_bytes(
+        stream,
+        status,
+        &["Content-Type: application/json"],
+        body.as_bytes(),
+    );
+}
+
+fn respond_bytes(stream: &mut TcpStream, status: u16, headers: &[&str], body: &[u8]) {
+    let mut head = format!("HTTP/1.1 {status} X\r\n");
+    for header in headers {
+        head.push_str(header);
+        head.push_str("\r\n");
+    }
+    head.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    ));
+    let _ = stream.write_all(head.as_bytes());
+    let _ = stream.write_all(body);
+}
+
+/// Method, path, lower-cased headers and JSON body of one request.
+fn read_request(
+    stream: &mut TcpStream,
+) -> Option<(String, String, HashMap<String, String>, Value)> {
+    let (method, path, headers, body) = read_raw_request(stream)?;
+    let body = serde_json::from_slice(&body).unwrap_or(Value::Null);
+    Some((method, path, headers, body))
+}
+
+/// Method, path, lower-cased headers and raw body of one request.
+type RawRequest = (String, String, HashMap<String, String>, Vec<u8>);
+
+/// Read one request without decoding its body.
+fn read_raw_request(stream: &mut TcpStream) -> Option<RawRequest> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let header_end = loop {
+        if let Some(pos) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        let n = stream.read(&mut chunk).ok().filter(|n| *n > 0)?;
+        buffer.extend_from_slice(&chunk[..n]);
+    };
+    let head = String::from_utf8_lossy(&buffer[..header_end]).into_owned();
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+    let length = headers
+        .get("content-length")
+        .and_then(|length| length.parse().ok())
+        .unwrap_or(0);
+    let mut body = buffer[header_end..].to_vec();
+    while body.len() < length {
+        let n = stream.read(&mut chunk).ok().filter(|n| *n > 0)?;
+        body.extend_from_slice(&chunk[..n]);
+    }
+    Some((method, path, headers, body))
+}
+
+/// Kubeconfig pointing at `server` with the fake server's token.
+pub fn write_kubeconfig(dir: &Path, server: &str) -> PathBuf {
+    let path = dir.join("kubeconfig");
+    std::fs::write(
+        &path,
+        format!(
+            "apiVersion: v1\ncurrent-context: test\ncontexts:\n  - name: test\n    context: {{ cluster: fake, user: installer }}\nclusters:\n  - name: fake\n    cluster: {{ server: \"{server}\" }}\nusers:\n  - name: installer\n    user: {{ token: {TOKEN} }}\n"
+        ),
+    )
+    .expect("write kubeconfig");
+    path
+}
@@ -3,16 +3,18 @@ use std::time::Duration;
 
 use greentic_deployer::bootstrap::flow_runner::run_bootstrap_flow;
 use greentic_deployer::bootstrap::http_adapter::HttpPromptAdapter;
-use serde_json::json;
+use reqwest::blocking::Client;
+use reqwest::redirect::Policy;
+use serde_json::{Value, json};
 
-#[test]
-fn http_adapter_serves_schema_and_accepts_answers() {
-    let flow = json!({
+fn region_flow() -> Vec<u8> {
+    serde_json::to_vec(&json!({
         "steps": [
             {
                 "kind": "prompt",
                 "questions": [
-                    { "id": "region", "prompt": "Region?", "default": "us-east-1" }
+                    { "id": "region", "prompt": "Region?", "default": "us-east-1",
+                      "pattern": "[a-z]{2}-[a-z]+-[0-9]" }
                 ]
             },
             {
@@ -26,43 +28,193 @@ fn http_adapter_serves_schema_and_accepts_answers() {
                 }
             }
         ]
-    });
-    let bytes = serde_json::to_vec(&flow).unwrap();
+    }))
+    .unwrap()
+}
 
-    let mut adapter = match HttpPromptAdapter::bind("127.0.0.1:0", Duration::from_secs(5)) {
-        Ok(adapter) => adapter,
+fn bind() -> Option<HttpPromptAdapter> {
+    match HttpPromptAdapter::bind("127.0.0.1:0", Duration::from_secs(5)) {
+        Ok(adapter) => Some(adapter),
         Err(err) => {
             // Some environments disallow binding listeners; skip in that case.
             eprintln!("http adapter bind skipped: {err}");
-            return;
+            None
         }
-    };
-    let addr = adapter.bound_addr();
-
-    thread::spawn(move || {
-        // give server a moment to start
-        std::thread::sleep(Duration::from_millis(50));
-        let client = reqwest::blocking::Client::new();
-        let schema_url = format!("http://{}/schema", addr);
-        let answers_url = format!("http://{}/answers", addr);
+    }
+}
 
+/// Poll `/schema` until the flow is waiting on its prompt step.
+fn wait_for_questions(client: &Client, base: &str, token: &str) -> Value {
+    for _ in 0..100 {
         let schema = client
-            .get(&schema_url)
+            .get(format!("{base}/schema"))
+            .bearer_auth(token)
             .send()
             .expect("schema request")
-            .json::<serde_json::Value>()
+            .json::<Value>()
             .expect("schema json");
+        if schema["waiting"] == json!(true) {
+            return schema;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    panic!("wizard never started waiting for answers");
+}
+
+#[test]
+fn http_adapter_serves_schema_and_accepts_answers() {
+    let Some(mut adapter) = bind() else {
+        return;
+    };
+    let base = format!("http://{}", adapter.bound_addr());
+    let token = adapter.access_token().to_string();
+
+    thread::spawn(move || {
+        let client = Client::new();
+        let answers_url = format!("{base}/answers");
+
+        let schema = wait_for_questions(&client, &base, &token);
+        assert_eq!(schema["step"], json!(1));
         assert_eq!(schema["questions"][0]["id"], json!("region"));
 
         let resp = client
             .post(&answers_url)
+            .bearer_auth(&token)
+            .body(r#"{"region":"mars-1"}"#)
+            .send()
+            .expect("post invalid answers");
+        assert_eq!(resp.status().as_u16(), 422);
+        let errors = resp.json::<Value>().expect("errors json");
+        assert!(
+            errors["errors"][0]
+                .as_str()
+                .unwrap()
+                .contains("must match pattern")
+        );
+
+        let resp = client
+            .post(&answers_url)
+            .bearer_auth(&token)
             .body(r#"{"region":"eu-west-1"}"#)
             .send()
             .expect("post answers");
         assert!(resp.status().is_success());
     });
 
-    let result = run_bootstrap_flow(&bytes, &mut adapter).expect("run flow");
-    assert_eq!(result.output.config_patch["region"], json!("{{region}}"));
+    let result = run_bootstrap_flow(&region_flow(), &mut adapter).expect("run flow");
+    assert_eq!(result.output.config_patch["region"], json!("eu-west-1"));
     assert!(result.output.ready);
 }
+
+#[test]
+fn http_wizard_requires_token_and_csrf() {
+    let Some(mut adapter) = bind() else {
+        return;
+    };
+    let base = format!("http://{}", adapter.bound_addr());
+    let token = adapter.access_token().to_string();
+
+    let client = thread::spawn(move || {
+        let client = Client::builder()
+            .redirect(Policy::none())
+            .build()
+            .expect("client");
+        wait_for_questions(&client, &base, &token);
+
+        for url in ["/", "/schema", "/status"] {
+            let resp = client.get(format!("{base}{url}")).send().expect("get");
+            assert_eq!(resp.status().as_u16(), 401, "{url} without token");
+        }
+        let resp = client
+            .post(format!("{base}/answers"))
+            .bearer_auth("wrong")
+            .body(r#"{"region":"eu-west-1"}"#)
+            .send()
+            .expect("post with wrong token");
+        assert_eq!(resp.status().as_u16(), 401);
+
+        // The token in the wizard URL is traded for a session cookie.
+        let resp = client
+            .get(format!("{base}/?token={token}"))
+            .send()
+            .expect("token exchange");
+        assert_eq!(resp.status().as_u16(), 303);
+        let cookie = resp.headers()["set-cookie"].to_str().unwrap().to_string();
+        assert!(cookie.contains("HttpOnly") && cookie.contains("SameSite=Strict"));
+        assert!(
+            !cookie.contains("Secure"),
+            "plain HTTP cookie marked Secure"
+        );
+        let session = cookie.split(';').next().unwrap().to_string();
+        assert!(
+            !session.contains(&token),
+            "session cookie must not carry the access token"
+        );
+
+        // The wizard URL works once; a leaked URL cannot open a second session.
+        let resp = client
+            .get(format!("{base}/?token={token}"))
+            .send()
+            .expect("second token exchange");
+        assert_eq!(resp.status().as_u16(), 403);
+        let resp = client
+            .get(format!("{base}/"))
+            .header("Cookie", format!("greentic_wizard={token}"))
+            .send()
+            .expect("token as cookie");
+        assert_eq!(resp.status().as_u16(), 401);
+
+        let page = client
+            .get(format!("{base}/"))
+            .header("Cookie", &session)
+            .send()
+            .expect("wizard page")
+            .text()
+            .unwrap();
+        assert!(page.contains("name=\"region\""));
+        let csrf = page
+            .split("name=\"csrf\" value=\"")
+            .nth(1)
+            .and_then(|rest| rest.split('"').next())
+            .expect("csrf field")
+            .to_string();
+
+        // A cross-site form post carries the cookie but cannot know the CSRF token.
+        let resp = client
+            .post(format!("{base}/answers"))
+            .header("Cookie", &session)
+            .form(&[("region", "eu-west-1")])
+            .send()
+            .expect("post without csrf");
+        assert_eq!(resp.status().as_u16(), 403);
+        let resp = client
+            .post(format!("{base}/answers"))
+            .header("Cookie", &session)
+            .header("Origin", "https://evil.example")
+            .form(&[("csrf", csrf.as_str()), ("region", "eu-west-1")])
+            .send()
+            .expect("post from another origin");
+        assert_eq!(resp.status().as_u16(), 403);
+
+        let resp = client
+            .post(format!("{base}/answers"))
+            .header("Cookie", &session)
+            .form(&[("csrf", csrf.as_str()), ("region", "eu-west-2")])
+            .send()
+            .expect("form post");
+        assert_eq!(resp.status().as_u16(), 303);
+
+        let status = client
+            .get(format!("{base}/status"))
+            .header("Cookie", &session)
+            .send()
+            .expect("status")
+            .json::<Value>()
+            .unwrap();
+        assert_eq!(status["status_history"][0], json!("waiting_for_answers"));
+    });
+
+    let result = run_bootstrap_flow(&region_flow(), &mut adapter).expect("run flow");
+    client.join().expect("client thread");
+    assert_eq!(result.output.config_patch["region"], json!("eu-west-2"));
+}
@@ -41,7 +41,7 @@ fn mqtt_mock_adapter_exchanges_schema_and_answers() {
     .with_timeout(Duration::from_secs(2));
 
     // Subscribe before running to capture schema/status.
-    let schema_rx = broker.subscribe("greentic/install/device-123/schema");
+    let schema_rx = broker.subscribe("greentic/install/device-123/questions");
     let status_rx = broker.subscribe("greentic/install/device-123/status");
 
     let bytes = flow_bytes();
@@ -62,7 +62,7 @@ fn mqtt_mock_adapter_exchanges_schema_and_answers() {
     let result = run_bootstrap_flow(&bytes, &mut adapter).expect("run flow");
     assert_eq!(
         result.output.config_patch["device"]["name"],
-        json!("{{device_name}}")
+        json!("edge-west")
     );
     assert!(result.output.ready);
 
@@ -108,7 +108,7 @@ fn mqtt_adapter_allows_allowlisted_broker() {
     .with_timeout(Duration::from_secs(2));
 
     // Pre-subscribe to see schema/status.
-    let schema_rx = broker.subscribe("greentic/install/device-allow/schema");
+    let schema_rx = broker.subscribe("greentic/install/device-allow/questions");
     let answers_rx = broker.subscribe("greentic/install/device-allow/status");
 
     let bytes = flow_bytes();
@@ -0,0 +1,280 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use greentic_deployer::bootstrap::flow_runner::run_bootstrap_flow;
+use greentic_deployer::bootstrap::mqtt_adapter::MqttPromptAdapter;
+use greentic_deployer::bootstrap::mqtt_client::MqttBrokerOptions;
+use greentic_deployer::bootstrap::network::{NetAllowList, NetworkPolicy};
+use serde_json::{Value, json};
+
+const PREFIX: &str = "greentic/bootstrap/edge-07";
+
+/// What the broker stand-in saw from the deployer.
+#[derive(Debug, Default)]
+struct Session {
+    client_id: String,
+    username: Option<String>,
+    password: Option<String>,
+    subscriptions: Vec<String>,
+    published: Vec<(String, Value)>,
+}
+
+/// Single-connection MQTT 3.1.1 broker stand-in that also plays the device: it answers the
+/// questions with an invalid payload first and corrects it once the deployer reports the errors.
+fn broker_stand_in(password: &'static str) -> Option<(String, JoinHandle<Session>)> {
+    let listener = match TcpListener::bind("127.0.0.1:0") {
+        Ok(listener) => listener,
+        Err(err) => {
+            // Some environments disallow binding listeners; skip in that case.
+            eprintln!("mqtt broker stand-in bind skipped: {err}");
+            return None;
+        }
+    };
+    let url = format!("mqtt://{}", listener.local_addr().unwrap());
+    let handle = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().expect("accept");
+        stream
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+        let mut session = Session::default();
+        while let Some((header, body)) = read_packet(&mut stream) {
+            let mut pos = 0;
+            match header >> 4 {
+                1 => {
+                    read_str(&body, &mut pos); // protocol name
+                    let flags = body[pos + 1];
+                    pos += 4; // level, flags, keep alive
+                    session.client_id = read_str(&body, &mut pos);
+                    if flags & 0x80 != 0 {
+                        session.username = Some(read_str(&body, &mut pos));
+                    }
+                    if flags & 0x40 != 0 {
+                        session.password = Some(read_str(&body, &mut pos));
+                    }
+                    let code = if session.password.as_deref() == Some(password) {
+                        0
+                    } else {
+                        5
+                    };
+                    write_packet(&mut stream, 0x20, &[0, code]);
+                    if code != 0 {
+                        break;
+                    }
+                }
+                8 => {
+                    let pkid = &body[..2];
+                    pos = 2;
+                    let mut granted = pkid.to_vec();
+                    while pos < body.len() {
+                        session.subscriptions.push(read_str(&body, &mut pos));
+                        granted.push(body[pos]);
+                        pos += 1;
+                    }
+                    write_packet(&mut stream, 0x90, &granted);
+                }
+                3 => {
+                    let topic = read_str(&body, &mut pos);
+                    if (header >> 1) & 0x03 > 0 {
+                        write_packet(&mut stream, 0x40, &body[pos..pos + 2]);
+                        pos += 2;
+                    }
+                    let payload: Value = serde_json::from_slice(&body[pos..]).expect("json");
+                    let reply = match (topic.rsplit('/').next(), payload["status"].as_str()) {
+                        (Some("questions"), _) => Some(json!({"device_name": "Edge 7"})),
+                        (Some("status"), Some("invalid_answers")) => {
+                            Some(json!({"device_name": "edge-seven"}))
+                        }
+                        _ => None,
+                    };
+                    session.published.push((topic, payload));
+                    if let Some(reply) = reply {
+                        let answers = format!("{PREFIX}/answers");
+                        let mut publish = encode_str(&answers);
+                        publish.extend(serde_json::to_vec(&reply).unwrap());
+                        write_packet(&mut stream, 0x30, &publish);
+                    }
+                }
+                12 => write_packet(&mut stream, 0xD0, &[]),
+                14 => break,
+                _ => {}
+            }
+        }
+        session
+    });
+    Some((url, handle))
+}
+
+fn read_packet(stream: &mut TcpStream) -> Option<(u8, Vec<u8>)> {
+    let mut byte = [0u8; 1];
+    stream.read_exact(&mut byte).ok()?;
+    let header = byte[0];
+    let (mut len, mut shift) = (0usize, 0);
+    loop {
+        stream.read_exact(&mut byte).ok()?;
+        len |= ((byte[0] & 0x7F) as usize) << shift;
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+    let mut body = vec![0u8; len];
+    stream.read_exact(&mut body).ok()?;
+    Some((header, body))
+}
+
+fn write_packet(stream: &mut TcpStream, header: u8, body: &[u8]) {
+    let mut packet = vec![header];
+    let mut len = body.len();
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if len == 0 {
+            break;
+        }
+    }
+    packet.extend_from_slice(body);
+    stream.write_all(&packet).expect("write packet");
+}
+
+fn read_str(body: &[u8], pos: &mut usize) -> String {
+    let len = u16::from_be_bytes([body[*pos], body[*pos + 1]]) as usize;
+    let value = String::from_utf8(body[*pos + 2..*pos + 2 + len].to_vec()).unwrap();
+    *pos += 2 + len;
+    value
+}
+
+fn encode_str(value: &str) -> Vec<u8> {
+    let mut out = (value.len() as u16).to_be_bytes().to_vec();
+    out.extend_from_slice(value.as_bytes());
+    out
+}
+
+fn flow_bytes() -> Vec<u8> {
+    serde_json::to_vec(&json!({
+        "steps": [
+            {
+                "kind": "prompt",
+                "questions": [
+                    { "id": "device_name", "prompt": "Device name?", "pattern": "edge-[a-z]+" }
+                ]
+            },
+            {
+                "kind": "installer_call",
+                "result": {
+                    "output_version": "v1",
+                    "config_patch": {"device": {"name": "{{device_name}}"}},
+                    "secrets_writes": [],
+                    "warnings": [],
+                    "ready": true
+                }
+            }
+        ]
+    }))
+    .unwrap()
+}
+
+fn local_policy() -> NetworkPolicy {
+    NetworkPolicy::new(
+        true,
+        false,
+        NetAllowList::parse(Some("127.0.0.1")).expect("allowlist"),
+    )
+}
+
+fn options(url: &str, password: &str) -> MqttBrokerOptions {
+    MqttBrokerOptions {
+        username: Some("installer".into()),
+        password: Some(password.into()),
+        ..MqttBrokerOptions::new(url, "greentic-deployer-edge-07")
+    }
+}
+
+#[test]
+fn broker_transport_exchanges_questions_and_answers() {
+    let Some((url, broker)) = broker_stand_in("s3cret") else {
+        return;
+    };
+    let mut adapter = MqttPromptAdapter::connect(
+        &options(&url, "s3cret"),
+        local_policy(),
+        "edge-07".into(),
+        "greentic/bootstrap".into(),
+        Duration::from_secs(5),
+    )
+    .expect("connect to stand-in broker");
+
+    let result = run_bootstrap_flow(&flow_bytes(), &mut adapter).expect("run flow");
+    assert_eq!(
+        result.output.config_patch["device"]["name"],
+        json!("edge-seven")
+    );
+    drop(adapter);
+
+    let session = broker.join().expect("broker thread");
+    assert_eq!(session.client_id, "greentic-deployer-edge-07");
+    assert_eq!(session.username.as_deref(), Some("installer"));
+    assert_eq!(session.subscriptions, vec![format!("{PREFIX}/answers")]);
+    let topics: Vec<&str> = session
+        .published
+        .iter()
+        .map(|(topic, _)| topic.as_str())
+        .collect();
+    assert_eq!(
+        topics,
+        vec![
+            format!("{PREFIX}/questions"),
+            format!("{PREFIX}/status"),
+            format!("{PREFIX}/status"),
+        ]
+    );
+    assert_eq!(
+        session.published[0].1["questions"][0]["id"],
+        json!("device_name")
+    );
+    assert_eq!(session.published[1].1["status"], json!("invalid_answers"));
+    assert_eq!(session.published[2].1["status"], json!("answers_received"));
+}
+
+#[test]
+fn broker_transport_reports_rejected_credentials() {
+    let Some((url, broker)) = broker_stand_in("s3cret") else {
+        return;
+    };
+    let err = MqttPromptAdapter::connect(
+        &options(&url, "wrong"),
+        local_policy(),
+        "edge-07".into(),
+        "greentic/bootstrap".into(),
+        Duration::from_secs(5),
+    )
+    .err()
+    .expect("credentials rejected");
+    assert!(err.to_string().contains("MQTT connection to 127.0.0.1"));
+    broker.join().expect("broker thread");
+}
+
+#[test]
+fn broker_host_must_be_allowlisted_before_connecting() {
+    let policy = NetworkPolicy::new(
+        true,
+        false,
+        NetAllowList::parse(Some("mqtt.example.com")).expect("allowlist"),
+    );
+    // Nothing listens on this port: the policy has to fail before any connection attempt.
+    let err = MqttPromptAdapter::connect(
+        &options("mqtt://127.0.0.1:9", "s3cret"),
+        policy,
+        "edge-07".into(),
+        "greentic/bootstrap".into(),
+        Duration::from_secs(5),
+    )
+    .err()
+    .expect("blocked broker");
+    assert!(err.to_string().contains("allowlist"));
+}
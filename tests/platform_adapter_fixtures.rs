@@ -18,10 +18,12 @@ fn wizard_flow_executes_with_json_answers() {
     let result = run_bootstrap_flow(&bytes, &mut adapter).expect("flow executes");
     assert!(result.output.ready);
     assert_eq!(result.output.secrets_writes[0].key, "platform/admin/token");
-    // Placeholder values are preserved for installer templating.
+    let control_plane = &result.output.config_patch["platform"]["control_plane"];
+    assert_eq!(control_plane["region"], json!("eu-west-1"));
+    assert_eq!(control_plane["cluster"], json!("greentic-edge"));
     assert_eq!(
-        result.output.config_patch["platform"]["control_plane"]["region"],
-        json!("{{region}}")
+        control_plane["alerts"]["email"],
+        json!("alerts@example.com")
     );
 }
 
@@ -46,7 +48,10 @@ fn mqtt_fixture_loads_and_returns_topics() {
     let result = run_bootstrap_flow(&bytes, &mut adapter).expect("flow executes");
     assert!(result.output.ready);
     let topics = &result.output.config_patch["interaction"]["topics"];
-    assert_eq!(topics["schema"], json!("greentic/bootstrap/edge-01/schema"));
+    assert_eq!(
+        topics["questions"],
+        json!("greentic/bootstrap/edge-01/questions")
+    );
     assert_eq!(
         topics["answers"],
         json!("greentic/bootstrap/edge-01/answers")
@@ -0,0 +1,307 @@
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use common::{Blobs, REGISTRY_PASSWORD, REGISTRY_USER, fake_oci_registry};
+use greentic_deployer::bootstrap::network::{NetAllowList, NetworkPolicy};
+use greentic_deployer::platform::bundle::{
+    BundlePackKind, BundleSource, export_bundle, import_bundle,
+};
+use greentic_deployer::platform::oci::{
+    PACK_ARTIFACT_TYPE, PACK_ID_ANNOTATION, PACK_PUBLISHER_ANNOTATION, PACK_VERSION_ANNOTATION,
+    push_oci_pack, resolve_oci_pack_with_auth,
+};
+use greentic_types::PackId;
+use greentic_types::cbor::encode_pack_manifest;
+use greentic_types::pack_manifest::{PackKind, PackManifest};
+use semver::Version;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use tempfile::tempdir;
+
+const PACK: &[u8] = b"platform pack bytes";
+
+fn sha256(bytes: &[u8]) -> String {
+    format!("sha256:{:x}", Sha256::digest(bytes))
+}
+
+fn put(blobs: &Blobs, path: String, content_type: &str, body: Vec<u8>) {
+    blobs
+        .lock()
+        .unwrap()
+        .insert(path, (content_type.to_string(), body));
+}
+
+/// Publish `PACK` as an artifact manifest plus an image index that also lists an unrelated
+/// linux/s390x image. Returns the digest of the pack manifest.
+fn publish(blobs: &Blobs, repo: &str, tag: &str) -> String {
+    let manifest_type = "application/vnd.oci.image.manifest.v1+json";
+    put(
+        blobs,
+        format!("/v2/{repo}/blobs/{}", sha256(PACK)),
+        "application/octet-stream",
+        PACK.to_vec(),
+    );
+    let manifest = serde_json::to_vec(&json!({
+        "schemaVersion": 2,
+        "mediaType": manifest_type,
+        "artifactType": PACK_ARTIFACT_TYPE,
+        "config": {
+            "mediaType": "application/vnd.oci.empty.v1+json",
+            "digest": sha256(b"{}"),
+            "size": 2
+        },
+        "layers": [{
+            "mediaType": PACK_ARTIFACT_TYPE,
+            "digest": sha256(PACK),
+            "size": PACK.len()
+        }]
+    }))
+    .unwrap();
+    let manifest_digest = sha256(&manifest);
+    put(
+        blobs,
+        format!("/v2/{repo}/manifests/{manifest_digest}"),
+        manifest_type,
+        manifest.clone(),
+    );
+    let index_type = "application/vnd.oci.image.index.v1+json";
+    let index = serde_json::to_vec(&json!({
+        "schemaVersion": 2,
+        "mediaType": index_type,
+        "manifests": [
+            {
+                "mediaType": manifest_type,
+                "digest": sha256(b"other image"),
+                "size": 11,
+                "platform": {"architecture": "s390x", "os": "linux"}
+            },
+            {
+                "mediaType": manifest_type,
+                "digest": manifest_digest,
+                "size": manifest.len(),
+                "artifactType": PACK_ARTIFACT_TYPE
+            }
+        ]
+    }))
+    .unwrap();
+    put(
+        blobs,
+        format!("/v2/{repo}/manifests/{tag}"),
+        index_type,
+        index,
+    );
+    manifest_digest
+}
+
+fn docker_config(dir: &Path, registry: &str, password: &str) -> PathBuf {
+    let path = dir.join("config.json");
+    fs::write(
+        &path,
+        json!({"auths": {registry: {"username": REGISTRY_USER, "password": password}}}).to_string(),
+    )
+    .unwrap();
+    path
+}
+
+fn policy() -> NetworkPolicy {
+    NetworkPolicy::new(true, false, NetAllowList::parse(Some("127.0.0.1")).unwrap())
+}
+
+#[test]
+fn pulls_pack_through_index_with_bearer_token_auth() {
+    let Some((registry, blobs)) = fake_oci_registry() else {
+        return;
+    };
+    publish(&blobs, "greentic/platform", "1.0.0");
+    let dir = tempdir().unwrap();
+    let auth = docker_config(dir.path(), &registry, REGISTRY_PASSWORD);
+
+    let path = resolve_oci_pack_with_auth(
+        &format!("oci://{registry}/greentic/platform:1.0.0"),
+        dir.path(),
+        &policy(),
+        Some(&auth),
+    )
+    .expect("resolve by tag");
+
+    assert_eq!(fs::read(&path).unwrap(), PACK);
+    assert_eq!(
+        path.file_name().unwrap().to_str().unwrap(),
+        format!("{}.gtpack", sha256(PACK).replace(':', "-"))
+    );
+}
+
+#[test]
+fn pulls_pack_pinned_by_manifest_digest() {
+    let Some((registry, blobs)) = fake_oci_registry() else {
+        return;
+    };
+    let digest = publish(&blobs, "greentic/platform", "1.0.0");
+    // Moving the tag must not affect a digest reference.
+    blobs
+        .lock()
+        .unwrap()
+        .remove("/v2/greentic/platform/manifests/1.0.0");
+    let dir = tempdir().unwrap();
+    let auth = docker_config(dir.path(), &format!("http://{registry}"), REGISTRY_PASSWORD);
+
+    let path = resolve_oci_pack_with_auth(
+        &format!("oci://{registry}/greentic/platform@{digest}"),
+        dir.path(),
+        &policy(),
+        Some(&auth),
+    )
+    .expect("resolve by digest");
+
+    assert_eq!(fs::read(path).unwrap(), PACK);
+    let index = fs::read_to_string(dir.path().join("cache/index.json")).unwrap();
+    assert!(index.contains(&format!("{registry}/greentic/platform@{digest}")));
+}
+
+#[test]
+fn rejects_bad_registry_credentials() {
+    let Some((registry, blobs)) = fake_oci_registry() else {
+        return;
+    };
+    publish(&blobs, "greentic/platform", "1.0.0");
+    let dir = tempdir().unwrap();
+    let auth = docker_config(dir.path(), &registry, "wrong");
+
+    let err = resolve_oci_pack_with_auth(
+        &format!("oci://{registry}/greentic/platform:1.0.0"),
+        dir.path(),
+        &policy(),
+        Some(&auth),
+    )
+    .unwrap_err();
+
+    assert!(err.to_string().contains("token request"), "{err}");
+}
+
+fn write_gtpack(path: &Path) {
+    write_gtpack_for(path, "dev.greentic.platform");
+}
+
+fn write_gtpack_for(path: &Path, pack_id: &str) {
+    let manifest = PackManifest {
+        schema_version: "pack-v1".to_string(),
+        pack_id: PackId::try_from(pack_id).unwrap(),
+        version: Version::new(1, 2, 0),
+        kind: PackKind::Application,
+        publisher: "greentic".to_string(),
+        components: Vec::new(),
+        flows: Vec::new(),
+        dependencies: Vec::new(),
+        capabilities: Vec::new(),
+        secret_requirements: Vec::new(),
+        signatures: Default::default(),
+        bootstrap: None,
+        extensions: None,
+    };
+    let encoded = encode_pack_manifest(&manifest).unwrap();
+    let mut builder = tar::Builder::new(fs::File::create(path).unwrap());
+    let mut header = tar::Header::new_gnu();
+    header.set_size(encoded.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder
+        .append_data(&mut header, "manifest.cbor", encoded.as_slice())
+        .unwrap();
+    builder.finish().unwrap();
+}
+
+#[test]
+fn pushed_pack_round_trips_through_registry() {
+    let Some((registry, blobs)) = fake_oci_registry() else {
+        return;
+    };
+    let dir = tempdir().unwrap();
+    let gtpack = dir.path().join("platform.gtpack");
+    write_gtpack(&gtpack);
+    let auth = docker_config(dir.path(), &registry, REGISTRY_PASSWORD);
+    let reference = format!("oci://{registry}/greentic/platform:1.2.0");
+
+    let pushed = push_oci_pack(&gtpack, &reference, &policy(), Some(&auth)).expect("push");
+
+    assert_eq!(pushed.reference, reference);
+    assert_eq!(pushed.layer_digest, sha256(&fs::read(&gtpack).unwrap()));
+    let (content_type, stored) =
+        blobs.lock().unwrap()["/v2/greentic/platform/manifests/1.2.0"].clone();
+    assert_eq!(content_type, "application/vnd.oci.image.manifest.v1+json");
+    assert_eq!(pushed.manifest_digest, sha256(&stored));
+    let stored: Value = serde_json::from_slice(&stored).unwrap();
+    assert_eq!(stored["artifactType"], PACK_ARTIFACT_TYPE);
+    assert_eq!(stored["layers"][0]["mediaType"], PACK_ARTIFACT_TYPE);
+    assert_eq!(
+        stored["annotations"][PACK_ID_ANNOTATION],
+        "dev.greentic.platform"
+    );
+    assert_eq!(stored["annotations"][PACK_VERSION_ANNOTATION], "1.2.0");
+    assert_eq!(stored["annotations"][PACK_PUBLISHER_ANNOTATION], "greentic");
+
+    let cache = dir.path().join("pull");
+    let pulled =
+        resolve_oci_pack_with_auth(&pushed.digest_reference, &cache, &policy(), Some(&auth))
+            .expect("pull pushed pack");
+    assert_eq!(fs::read(pulled).unwrap(), fs::read(&gtpack).unwrap());
+
+    let err = push_oci_pack(&gtpack, &pushed.digest_reference, &policy(), Some(&auth)).unwrap_err();
+    assert!(
+        err.to_string().contains("cannot push to digest reference"),
+        "{err}"
+    );
+}
+
+#[test]
+fn bundle_seeds_cache_for_offline_install() {
+    let Some((registry, _blobs)) = fake_oci_registry() else {
+        return;
+    };
+    let online = tempdir().unwrap();
+    let gtpack = online.path().join("platform.gtpack");
+    write_gtpack(&gtpack);
+    let deployment = online.path().join("deploy-k8s.gtpack");
+    write_gtpack_for(&deployment, "dev.greentic.deploy.k8s");
+    let auth = docker_config(online.path(), &registry, REGISTRY_PASSWORD);
+    let reference = format!("oci://{registry}/greentic/platform:1.2.0");
+    push_oci_pack(&gtpack, &reference, &policy(), Some(&auth)).expect("push");
+
+    let bundle = online.path().join("usb/bundle.tar");
+    let sources = [
+        BundleSource {
+            kind: BundlePackKind::Platform,
+            source: reference.clone(),
+        },
+        BundleSource {
+            kind: BundlePackKind::Deployment,
+            source: deployment.display().to_string(),
+        },
+    ];
+    let index = export_bundle(&sources, &bundle, online.path(), &policy(), Some(&auth))
+        .expect("export bundle");
+    assert_eq!(index.packs.len(), 2);
+    assert_eq!(index.packs[1].pack_id, "dev.greentic.deploy.k8s");
+
+    let offline_host = tempdir().unwrap();
+    let packs_dir = offline_host.path().join("packs");
+    let report = import_bundle(&bundle, offline_host.path(), &packs_dir).expect("import bundle");
+    assert_eq!(report.cached, vec![reference.clone()]);
+    assert_eq!(
+        report.deployment_packs,
+        vec![packs_dir.join("dev.greentic.deploy.k8s-1.2.0.gtpack")]
+    );
+    assert_eq!(
+        fs::read(&report.deployment_packs[0]).unwrap(),
+        fs::read(&deployment).unwrap()
+    );
+
+    let offline = NetworkPolicy::new(false, true, NetAllowList::default());
+    let resolved = resolve_oci_pack_with_auth(&reference, offline_host.path(), &offline, None)
+        .expect("resolve from seeded cache");
+    assert_eq!(fs::read(resolved).unwrap(), fs::read(&gtpack).unwrap());
+    let other = format!("oci://{registry}/greentic/platform:9.9.9");
+    let err = resolve_oci_pack_with_auth(&other, offline_host.path(), &offline, None).unwrap_err();
+    assert!(err.to_string().contains("offline-only"), "{err}");
+}
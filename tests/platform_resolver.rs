@@ -12,10 +12,24 @@ use semver::Version;
 use tar::Builder;
 use tempfile::tempdir;
 
-use greentic_deployer::platform::{VerificationPolicy, verify_platform_pack};
+use greentic_deployer::platform::trust::{TrustStore, TrustedKey};
+use greentic_deployer::platform::{
+    VerificationPolicy, pack_content_digest, signing_payload, verify_platform_pack,
+};
 use greentic_types::pack::{Signature, SignatureAlgorithm};
+use ring::rand::SystemRandom;
+use ring::signature::{Ed25519KeyPair, KeyPair};
+
+const STRICT: VerificationPolicy = VerificationPolicy {
+    verify: true,
+    strict: true,
+};
 
 fn write_gtpack(manifest: &PackManifest, path: &PathBuf) {
+    write_gtpack_with(manifest, path, &[]);
+}
+
+fn write_gtpack_with(manifest: &PackManifest, path: &PathBuf, entries: &[(&str, &[u8])]) {
     let encoded = encode_pack_manifest(manifest).expect("encode manifest");
     let mut builder = Builder::new(Vec::new());
 
@@ -26,6 +40,15 @@ fn write_gtpack(manifest: &PackManifest, path: &PathBuf) {
     builder
         .append_data(&mut header, "manifest.cbor", encoded.as_slice())
         .expect("append manifest");
+    for (name, data) in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, name, *data)
+            .expect("append entry");
+    }
 
     let bytes = builder.into_inner().expect("tar bytes");
     let mut file = File::create(path).expect("create gtpack");
@@ -98,6 +121,7 @@ fn missing_signatures_warns_when_non_strict() {
             verify: true,
             strict: false,
         },
+        &TrustStore::default(),
     )
     .expect("non-strict verification should pass");
     assert!(
@@ -123,6 +147,7 @@ fn missing_signatures_fails_when_strict() {
             verify: true,
             strict: true,
         },
+        &TrustStore::default(),
     )
     .unwrap_err();
     assert!(
@@ -151,6 +176,7 @@ fn invalid_signature_fails() {
             verify: true,
             strict: true,
         },
+        &TrustStore::default(),
     )
     .unwrap_err();
     assert!(
@@ -158,3 +184,124 @@ fn invalid_signature_fails() {
         "expected invalid signature failure"
     );
 }
+
+const FLOW: (&str, &[u8]) = ("flows/install.ygtc", b"id: install\n");
+
+/// Write a pack containing `FLOW` whose manifest is signed by `pair` under `key_id`.
+fn write_signed_gtpack(path: &PathBuf, key_id: &str, pair: &Ed25519KeyPair) {
+    let manifest = sample_manifest();
+    write_gtpack_with(&manifest, path, &[FLOW]);
+    let content_digest = pack_content_digest(path).expect("content digest");
+    let payload = signing_payload(&manifest, &content_digest).expect("payload");
+    let mut signed = manifest;
+    signed.signatures.signatures.push(Signature {
+        key_id: key_id.to_string(),
+        algorithm: SignatureAlgorithm::Ed25519,
+        signature: pair.sign(&payload).as_ref().to_vec(),
+    });
+    write_gtpack_with(&signed, path, &[FLOW]);
+}
+
+fn generate_key() -> Ed25519KeyPair {
+    let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).expect("generate key");
+    Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).expect("parse key")
+}
+
+fn trust(key_id: &str, pair: &Ed25519KeyPair, expires_at: Option<u64>) -> TrustStore {
+    let mut store = TrustStore::default();
+    let mut key = TrustedKey::from_public_key(key_id, pair.public_key().as_ref()).unwrap();
+    key.expires_at = expires_at;
+    store.add(key);
+    store
+}
+
+#[test]
+fn signed_pack_verifies_against_trusted_key() {
+    let pair = generate_key();
+    let dir = tempdir().expect("temp dir");
+    let path = dir.path().join("platform.gtpack");
+    write_signed_gtpack(&path, "release", &pair);
+
+    let info = load_platform_pack(&path).expect("load platform pack");
+    let outcome = verify_platform_pack(&info, STRICT, &trust("release", &pair, None))
+        .expect("signature should verify");
+    assert_eq!(outcome.verified_keys, vec!["release".to_string()]);
+    assert!(outcome.warnings.is_empty());
+}
+
+#[test]
+fn unknown_key_warns_unless_strict() {
+    let pair = generate_key();
+    let dir = tempdir().expect("temp dir");
+    let path = dir.path().join("platform.gtpack");
+    write_signed_gtpack(&path, "release", &pair);
+    let info = load_platform_pack(&path).expect("load platform pack");
+    let store = trust("other", &pair, None);
+
+    let outcome = verify_platform_pack(
+        &info,
+        VerificationPolicy {
+            verify: true,
+            strict: false,
+        },
+        &store,
+    )
+    .expect("non-strict verification should pass");
+    assert!(outcome.verified_keys.is_empty());
+    assert!(outcome.warnings[0].contains("not in the trust store"));
+
+    let err = verify_platform_pack(&info, STRICT, &store).unwrap_err();
+    assert!(format!("{err}").contains("not in the trust store"), "{err}");
+}
+
+#[test]
+fn expired_key_fails_when_strict() {
+    let pair = generate_key();
+    let dir = tempdir().expect("temp dir");
+    let path = dir.path().join("platform.gtpack");
+    write_signed_gtpack(&path, "release", &pair);
+    let info = load_platform_pack(&path).expect("load platform pack");
+
+    let err = verify_platform_pack(&info, STRICT, &trust("release", &pair, Some(1))).unwrap_err();
+    assert!(format!("{err}").contains("expired"), "{err}");
+}
+
+#[test]
+fn tampered_content_fails_digest_check() {
+    let pair = generate_key();
+    let dir = tempdir().expect("temp dir");
+    let path = dir.path().join("platform.gtpack");
+    write_signed_gtpack(&path, "release", &pair);
+    // Keep the signed manifest but swap the flow for different content.
+    let signed = load_platform_pack(&path)
+        .expect("load platform pack")
+        .manifest;
+    write_gtpack_with(&signed, &path, &[("flows/install.ygtc", b"id: evil\n")]);
+
+    let info = load_platform_pack(&path).expect("load platform pack");
+    let err = verify_platform_pack(&info, STRICT, &trust("release", &pair, None)).unwrap_err();
+    assert!(
+        format!("{err}").contains("invalid signature for key_id=release"),
+        "{err}"
+    );
+}
+
+#[test]
+fn duplicate_entry_in_signed_pack_is_rejected() {
+    let pair = generate_key();
+    let dir = tempdir().expect("temp dir");
+    let path = dir.path().join("platform.gtpack");
+    write_signed_gtpack(&path, "release", &pair);
+    let signed = load_platform_pack(&path)
+        .expect("load platform pack")
+        .manifest;
+    // Readers take the first copy of a path, so a prepended entry would replace the signed flow.
+    for evil in ["flows/install.ygtc", "./flows/install.ygtc"] {
+        write_gtpack_with(&signed, &path, &[(evil, b"id: evil\n"), FLOW]);
+        let err = load_platform_pack(&path).unwrap_err();
+        assert!(
+            format!("{err}").contains("duplicate entry flows/install.ygtc"),
+            "{err}"
+        );
+    }
+}